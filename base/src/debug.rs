@@ -1,27 +1,57 @@
 //! Debugging utilities.
 
 use std::borrow::Cow;
+use std::panic::Location;
 
+use bevy::app::{self, App};
 use bevy::ecs::bundle;
+use bevy::ecs::component::Component;
+
+pub mod audit;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod invariant;
+
+/// Installs debugging checks.
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) { app.add_plugins(invariant::Plugin); }
+}
 
 /// Debug info for an entity.
 #[derive(bundle::Bundle)]
 pub struct Bundle {
     #[cfg(feature = "entity-names")]
-    name: bevy::core::Name,
+    name:       bevy::core::Name,
+    #[cfg(debug_assertions)]
+    provenance: Provenance,
 }
 
 impl Bundle {
     /// Provide name info of an entity.
     #[must_use]
+    #[track_caller]
     pub fn new(name: &'static str) -> Bundle { Self::new_with(|| name) }
 
     /// Provide name info of an entity on demand.
     #[must_use]
+    #[track_caller]
     pub fn new_with<Name: Into<Cow<'static, str>>>(_name: impl FnOnce() -> Name) -> Bundle {
         Bundle {
             #[cfg(feature = "entity-names")]
             name:                                  bevy::core::Name::new(_name()),
+            #[cfg(debug_assertions)]
+            provenance:                            Provenance { location: Location::caller() },
         }
     }
 }
+
+/// The source location that constructed the [`Bundle`] of an entity.
+///
+/// Only present in debug builds.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Provenance {
+    /// The caller of [`Bundle::new`] or [`Bundle::new_with`].
+    pub location: &'static Location<'static>,
+}
@@ -0,0 +1,140 @@
+//! Audits the system ordering of the [`Update`](app::Update) schedule at startup.
+//!
+//! Bevy only logs ambiguities, which are easily lost in the startup output.
+//! This audit panics with a report listing
+//! - pairs of systems with conflicting data access but no ordering between them, and
+//! - systems writing or reading a [partitioned event](crate::partition::AppExt)
+//!   outside its [`EventWriterSystemSet`](crate::EventWriterSystemSet)
+//!   or [`EventReaderSystemSet`](crate::EventReaderSystemSet).
+//!
+//! [Chaos interceptors](super::chaos) are exempt from the partitioning check.
+//! Exclusive systems are not checked for partitioning either,
+//! since their access to event resources is not declared.
+//!
+//! Install [`Plugin`] only in development builds;
+//! the audit builds the schedule once more and is not free.
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use bevy::app::{self, App};
+use bevy::ecs::schedule::{NodeId, SystemSet};
+use bevy::ecs::system::BoxedSystem;
+use bevy::ecs::world::World;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::partition::Declarations;
+
+#[cfg(test)]
+mod tests;
+
+/// Panics at startup if the [`Update`](app::Update) schedule fails the audit.
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) { app.add_systems(app::PreStartup, audit_system); }
+}
+
+fn audit_system(world: &mut World) {
+    if let Some(report) = audit(world) {
+        panic!("Schedule audit failed:\n{report}");
+    }
+}
+
+/// Audits the [`Update`](app::Update) schedule, returning a report if any violation is found.
+#[must_use]
+pub fn audit(world: &mut World) -> Option<String> {
+    let mut report = String::new();
+
+    world.schedule_scope(app::Update, |world, schedule| {
+        if let Err(err) = schedule.initialize(world) {
+            _ = writeln!(report, "Schedule cannot be built: {err}");
+            return;
+        }
+        // Systems are moved out of the graph into the executable schedule during initialization.
+        let systems: HashMap<NodeId, &BoxedSystem> =
+            schedule.systems().expect("schedule has been initialized").collect();
+        let graph = schedule.graph();
+        let system_name =
+            |node: &NodeId| systems.get(node).map_or(Cow::Borrowed("?"), |system| system.name());
+
+        for (first, second, components) in graph.conflicting_systems() {
+            let mut components: Vec<_> = components
+                .iter()
+                .map(|&component| world.components().get_name(component).unwrap_or("?"))
+                .collect();
+            if components.is_empty() {
+                components.push("World"); // one of the systems is exclusive
+            }
+            _ = writeln!(
+                report,
+                "Unordered systems with conflicting access to [{}]:\n  {}\n  {}",
+                components.join(", "),
+                system_name(first),
+                system_name(second),
+            );
+        }
+
+        let Some(declarations) = world.get_resource::<Declarations>() else { return };
+
+        let mut parents: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for (parent, child, ()) in graph.hierarchy().graph().all_edges() {
+            parents.entry(child).or_default().push(parent);
+        }
+        let set_nodes: Vec<(NodeId, &dyn SystemSet)> =
+            graph.system_sets().map(|(node, set, _)| (node, set)).collect();
+        let find_set = |target: &dyn SystemSet| {
+            set_nodes.iter().find(|(_, set)| *set == target).map(|&(node, _)| node)
+        };
+
+        #[cfg(feature = "chaos")]
+        let intercept_set = find_set(&super::chaos::InterceptSystemSet);
+
+        for (&system_node, system) in &systems {
+            let ancestors = ancestors(&parents, system_node);
+            #[cfg(feature = "chaos")]
+            if intercept_set.is_some_and(|node| ancestors.contains(&node)) {
+                continue;
+            }
+            let access = system.component_access();
+
+            for decl in &declarations.0 {
+                let in_set = |set| find_set(set).is_some_and(|node| ancestors.contains(&node));
+
+                if access.has_write(decl.events) {
+                    if !in_set(&*decl.writer) {
+                        _ = writeln!(
+                            report,
+                            "{} writes {} outside EventWriterSystemSet",
+                            system.name(),
+                            decl.name,
+                        );
+                    }
+                } else if access.has_read(decl.events) && !in_set(&*decl.reader) {
+                    _ = writeln!(
+                        report,
+                        "{} reads {} outside EventReaderSystemSet",
+                        system.name(),
+                        decl.name,
+                    );
+                }
+            }
+        }
+    });
+
+    (!report.is_empty()).then_some(report)
+}
+
+/// Collects all system sets containing `node`, directly or transitively.
+fn ancestors(parents: &HashMap<NodeId, Vec<NodeId>>, node: NodeId) -> HashSet<NodeId> {
+    let mut output = HashSet::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        for &parent in parents.get(&node).into_iter().flatten() {
+            if output.insert(parent) {
+                stack.push(parent);
+            }
+        }
+    }
+    output
+}
@@ -0,0 +1,91 @@
+use bevy::app::{self, App};
+use bevy::ecs::event::{Event, EventReader, EventWriter};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Res, ResMut, Resource};
+
+use crate::partition::{AppExt, EventReaderSystemSet, EventWriterSystemSet};
+
+#[derive(Event)]
+struct Ping;
+
+#[derive(Default, Resource)]
+struct Counter(u32);
+
+fn send_system(mut writer: EventWriter<Ping>) { writer.send(Ping); }
+
+fn receive_system(mut reader: EventReader<Ping>) { reader.clear(); }
+
+fn increment_system(mut counter: ResMut<Counter>) { counter.0 += 1; }
+
+fn observe_system(counter: Res<Counter>) { _ = counter.0; }
+
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_partitioned_event::<Ping>();
+    app.init_resource::<Counter>();
+    app
+}
+
+#[test]
+fn clean_schedule() {
+    let mut app = new_app();
+    app.add_systems(
+        app::Update,
+        (
+            send_system.in_set(EventWriterSystemSet::<Ping>::default()),
+            receive_system.in_set(EventReaderSystemSet::<Ping>::default()),
+            (increment_system, observe_system).chain(),
+        ),
+    );
+
+    let report = super::audit(app.world_mut());
+    assert_eq!(report, None);
+}
+
+#[test]
+fn report_writer_outside_set() {
+    let mut app = new_app();
+    app.add_systems(
+        app::Update,
+        (send_system, receive_system.in_set(EventReaderSystemSet::<Ping>::default())),
+    );
+
+    let report = super::audit(app.world_mut()).expect("audit should fail");
+    assert!(report.contains("send_system writes"), "{report}");
+    assert!(report.contains("outside EventWriterSystemSet"), "{report}");
+}
+
+#[test]
+fn report_reader_outside_set() {
+    let mut app = new_app();
+    app.add_systems(
+        app::Update,
+        (send_system.in_set(EventWriterSystemSet::<Ping>::default()), receive_system),
+    );
+
+    let report = super::audit(app.world_mut()).expect("audit should fail");
+    assert!(report.contains("receive_system reads"), "{report}");
+    assert!(report.contains("outside EventReaderSystemSet"), "{report}");
+}
+
+#[test]
+fn report_conflict() {
+    let mut app = new_app();
+    app.add_systems(app::Update, (increment_system, observe_system));
+
+    let report = super::audit(app.world_mut()).expect("audit should fail");
+    assert!(report.contains("Unordered systems with conflicting access"), "{report}");
+    assert!(report.contains("Counter"), "{report}");
+    assert!(report.contains("increment_system"), "{report}");
+    assert!(report.contains("observe_system"), "{report}");
+}
+
+#[test]
+fn audit_after_update() {
+    let mut app = new_app();
+    app.add_systems(app::Update, send_system);
+    app.update();
+
+    let report = super::audit(app.world_mut()).expect("audit should fail");
+    assert!(report.contains("send_system writes"), "{report}");
+}
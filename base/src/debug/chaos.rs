@@ -0,0 +1,175 @@
+//! Fault injection for testing recovery from lost, late and imprecise events.
+//!
+//! Only compiled with the `chaos` feature, which must not be enabled in release builds.
+//! [`intercept`] registers a [partitioned event](crate::partition::AppExt) type
+//! whose new events are intercepted between its writers and readers in every update.
+//! Each event is dropped with probability [`Config::drop_ratio`]
+//! and otherwise delayed by up to [`Config::max_delay`] updates.
+//! [`intercept_perturbed`] additionally scales the floating-point fields of each event
+//! by a random factor within [`Config::tolerance`].
+//!
+//! Only intercept events that receivers are expected to recover from,
+//! e.g. periodic metric updates and viewable moves.
+//! [`Plugin`] panics at the end of any update
+//! in which the world violates an [invariant](super::invariant),
+//! so that tests reveal failures to recover.
+//! The random sequence is determined by [`Config::seed`] to reproduce failures.
+
+use std::mem;
+
+use bevy::app::{self, App};
+use bevy::ecs::event::{Event, Events, ManualEventReader};
+use bevy::ecs::schedule::{self, IntoSystemConfigs};
+use bevy::ecs::system::{Local, Res, ResMut, Resource};
+use bevy::ecs::world::World;
+
+use super::invariant;
+use crate::partition::{EventReaderSystemSet, EventWriterSystemSet};
+
+#[cfg(test)]
+mod tests;
+
+/// Initializes the random source for interceptors and asserts invariants after each update.
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let seed = app.world_mut().get_resource_or_insert_with(Config::default).seed;
+        app.insert_resource(Rng::new(seed));
+        app.add_systems(app::Last, assert_invariants_system);
+    }
+}
+
+/// Parameters of fault injection.
+///
+/// Insert before adding [`Plugin`] to override the defaults.
+#[derive(Debug, Clone, Resource)]
+pub struct Config {
+    /// Seed of the random sequence.
+    pub seed:       u64,
+    /// Probability of dropping each intercepted event.
+    pub drop_ratio: f32,
+    /// Maximum number of updates by which an event is delayed.
+    pub max_delay:  u32,
+    /// Maximum relative error added to perturbed floating-point fields.
+    pub tolerance:  f32,
+}
+
+impl Default for Config {
+    fn default() -> Self { Self { seed: 0, drop_ratio: 0.05, max_delay: 3, tolerance: 1e-4 } }
+}
+
+/// Systems intercepting events.
+///
+/// Interceptors necessarily write events outside the writer set,
+/// so the [schedule audit](super::audit) skips systems in this set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, schedule::SystemSet)]
+pub struct InterceptSystemSet;
+
+/// Intercepts events of type `T` with random drops and delays.
+pub fn intercept<T: Event>(app: &mut App) { intercept_perturbed::<T>(app, |_, _| {}); }
+
+/// Intercepts events of type `T` with random drops and delays,
+/// passing the fields to perturb in each event to [`Perturbation::apply`].
+pub fn intercept_perturbed<T: Event>(app: &mut App, perturb: fn(&mut T, &mut Perturbation)) {
+    app.insert_resource(Delayed::<T> { pending: Vec::new(), perturb });
+    app.add_systems(
+        app::Update,
+        intercept_system::<T>
+            .in_set(InterceptSystemSet)
+            .after(EventWriterSystemSet::<T>::default())
+            .before(EventReaderSystemSet::<T>::default()),
+    );
+}
+
+/// A xorshift64* generator, sufficient for fault injection.
+#[derive(Resource)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a uniform sample in `[0, 1)`.
+    #[allow(clippy::cast_precision_loss)] // 24 bits fit in the mantissa
+    fn next_f32(&mut self) -> f32 { (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 }
+
+    /// Returns a uniform sample in `[-1, 1)`.
+    fn next_signed(&mut self) -> f32 { self.next_f32() * 2. - 1. }
+
+    /// Returns a uniform sample in `[0, bound]`.
+    #[allow(clippy::cast_possible_truncation)] // the remainder is at most `bound`
+    fn up_to(&mut self, bound: u32) -> u32 { (self.next_u64() % (u64::from(bound) + 1)) as u32 }
+}
+
+/// Perturbs floating-point fields of an intercepted event.
+pub struct Perturbation<'a> {
+    rng:       &'a mut Rng,
+    tolerance: f32,
+}
+
+impl Perturbation<'_> {
+    /// Scales `value` by a random factor in `[1 - tolerance, 1 + tolerance)`.
+    pub fn apply(&mut self, value: &mut f32) {
+        *value *= 1. + self.tolerance * self.rng.next_signed();
+    }
+}
+
+#[derive(Resource)]
+struct Delayed<T> {
+    /// Withheld events with the number of updates remaining.
+    pending: Vec<(u32, T)>,
+    /// Perturbs the fields of each intercepted event.
+    perturb: fn(&mut T, &mut Perturbation),
+}
+
+fn intercept_system<T: Event>(
+    config: Res<Config>,
+    mut rng: ResMut<Rng>,
+    mut events: ResMut<Events<T>>,
+    mut delayed: ResMut<Delayed<T>>,
+    mut reader: Local<ManualEventReader<T>>,
+) {
+    let new = reader.len(&events);
+
+    // Events before the new ones were delivered in the previous update,
+    // so removing them only expires them early.
+    let drained: Vec<T> = events.drain().collect();
+    let delivered = drained.len() - new;
+    for mut event in drained.into_iter().skip(delivered) {
+        if rng.next_f32() < config.drop_ratio {
+            continue;
+        }
+        (delayed.perturb)(
+            &mut event,
+            &mut Perturbation { rng: &mut rng, tolerance: config.tolerance },
+        );
+        let delay = rng.up_to(config.max_delay);
+        delayed.pending.push((delay, event));
+    }
+
+    for (delay, event) in mem::take(&mut delayed.pending) {
+        if delay == 0 {
+            events.send(event);
+        } else {
+            delayed.pending.push((delay - 1, event));
+        }
+    }
+
+    // Released events are not new in the next update.
+    reader.clear(&events);
+}
+
+fn assert_invariants_system(world: &mut World) {
+    let violations = invariant::check(world);
+    assert!(violations.is_empty(), "invariants violated under fault injection: {violations:#?}");
+}
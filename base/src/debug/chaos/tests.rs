@@ -0,0 +1,102 @@
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::event::{Event, EventReader, EventWriter};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Local, ResMut, Resource};
+
+use super::{Config, Perturbation};
+use crate::debug::invariant;
+use crate::partition::{AppExt, EventReaderSystemSet, EventWriterSystemSet};
+
+#[derive(Event)]
+struct Ping(u32, f32);
+
+#[derive(Default, Resource)]
+struct Received(Vec<(u32, f32)>);
+
+fn run(config: Config, updates: u32) -> Vec<u32> {
+    run_perturbed(config, updates, |_, _| {}).into_iter().map(|(id, _)| id).collect()
+}
+
+fn run_perturbed(
+    config: Config,
+    updates: u32,
+    perturb: fn(&mut Ping, &mut Perturbation),
+) -> Vec<(u32, f32)> {
+    fn send_system(mut writer: EventWriter<Ping>, mut next: Local<u32>) {
+        writer.send(Ping(*next, 1.));
+        *next += 1;
+    }
+
+    fn receive_system(mut reader: EventReader<Ping>, mut received: ResMut<Received>) {
+        received.0.extend(reader.read().map(|&Ping(id, value)| (id, value)));
+    }
+
+    let mut app = App::new();
+    app.insert_resource(config);
+    app.add_plugins(super::Plugin);
+    app.add_partitioned_event::<Ping>();
+    app.init_resource::<Received>();
+    app.add_systems(
+        app::Update,
+        (
+            send_system.in_set(EventWriterSystemSet::<Ping>::default()),
+            receive_system.in_set(EventReaderSystemSet::<Ping>::default()),
+        ),
+    );
+    super::intercept_perturbed::<Ping>(&mut app, perturb);
+
+    for _ in 0..updates {
+        app.update();
+    }
+
+    app.world_mut().remove_resource::<Received>().unwrap().0
+}
+
+#[test]
+fn passthrough() {
+    let received = run(Config { seed: 0, drop_ratio: 0., max_delay: 0, tolerance: 0. }, 10);
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn drop_all() {
+    let received = run(Config { seed: 0, drop_ratio: 1., max_delay: 0, tolerance: 0. }, 10);
+    assert_eq!(received, Vec::<u32>::new());
+}
+
+#[test]
+fn delay_without_duplicates() {
+    let mut received = run(Config { seed: 42, drop_ratio: 0., max_delay: 3, tolerance: 0. }, 100);
+    let len = received.len();
+    received.sort_unstable();
+    received.dedup();
+    assert_eq!(received.len(), len, "events must not be delivered twice");
+    assert!(len >= 97, "only events delayed beyond the last update may be missing");
+}
+
+#[test]
+fn perturb_within_tolerance() {
+    let config = Config { seed: 7, drop_ratio: 0., max_delay: 0, tolerance: 0.01 };
+    let received = run_perturbed(config, 100, |ping, perturbation| perturbation.apply(&mut ping.1));
+    assert_eq!(received.len(), 100);
+    assert!(received.iter().all(|&(_, value)| (value - 1.).abs() <= 0.01), "{received:?}");
+    assert!(received.iter().any(|&(_, value)| (value - 1.).abs() > 0.), "{received:?}");
+}
+
+#[test]
+#[should_panic(expected = "invariants violated")]
+fn assert_invariants() {
+    #[derive(Component)]
+    struct Marker;
+    #[derive(Component)]
+    struct Required;
+
+    let mut app = App::new();
+    app.add_plugins((crate::debug::Plugin, super::Plugin));
+    invariant::require::<Marker, (Required,)>(&mut app);
+    app.update();
+
+    app.world_mut().spawn(Marker);
+    app.update();
+}
@@ -0,0 +1,161 @@
+//! Runtime checks that marked entities have their required components.
+//!
+//! Plugins declare that entities with a marker component must also have other components
+//! through [`require`], e.g. every `building::Marker` entity must have a `FacilityList`.
+//! In debug builds, all archetypes are scanned after each update,
+//! and entities violating a declaration are reported with their [`Provenance`].
+//!
+//! Tests can call [`check`] directly to assert that a world is consistent.
+
+use std::any::type_name;
+
+use bevy::app::{self, App};
+use bevy::ecs::archetype::ArchetypeId;
+use bevy::ecs::component::{Component, ComponentId};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::World;
+use bevy::utils::HashSet;
+
+use super::Provenance;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rules>();
+        if cfg!(debug_assertions) {
+            app.add_systems(app::Last, check_system);
+        }
+    }
+}
+
+/// Declares that every entity with the marker `M` must have all components in `R`.
+pub fn require<M: Component, R: Required>(app: &mut App) {
+    let world = app.world_mut();
+    let rule = Rule {
+        marker_name: type_name::<M>(),
+        marker:      world.init_component::<M>(),
+        required:    R::init(world),
+    };
+    world.get_resource_or_insert_with(Rules::default).rules.push(rule);
+}
+
+/// A set of required components.
+///
+/// Implemented by tuples of components.
+pub trait Required {
+    /// Registers the components and returns their IDs.
+    fn init(world: &mut World) -> Vec<ComponentId>;
+}
+
+macro_rules! impl_required {
+    ($($T:ident),*) => {
+        impl<$($T: Component),*> Required for ($($T,)*) {
+            fn init(world: &mut World) -> Vec<ComponentId> {
+                vec![$(world.init_component::<$T>()),*]
+            }
+        }
+    }
+}
+
+bevy::utils::all_tuples!(impl_required, 1, 15, T);
+
+struct Rule {
+    marker_name: &'static str,
+    marker:      ComponentId,
+    required:    Vec<ComponentId>,
+}
+
+/// Declared rules and the archetypes already reported.
+#[derive(Default, Resource)]
+struct Rules {
+    rules:    Vec<Rule>,
+    /// Pairs of archetype and rule index that have been reported,
+    /// to avoid repeating the same report every frame.
+    reported: HashSet<(ArchetypeId, usize)>,
+}
+
+/// An entity missing components required by its marker.
+#[derive(Debug)]
+pub struct Violation {
+    /// The offending entity.
+    pub entity:     Entity,
+    /// The type name of the marker component.
+    pub marker:     &'static str,
+    /// Names of the missing components.
+    pub missing:    Vec<String>,
+    /// Where the entity was constructed, if known.
+    pub provenance: Option<Provenance>,
+}
+
+/// Scans all archetypes for entities violating the declarations.
+#[must_use]
+pub fn check(world: &World) -> Vec<Violation> {
+    let Some(rules) = world.get_resource::<Rules>() else { return Vec::new() };
+    scan(world, rules).into_iter().map(|(_, _, violation)| violation).collect()
+}
+
+fn scan(world: &World, rules: &Rules) -> Vec<(ArchetypeId, usize, Violation)> {
+    let mut violations = Vec::new();
+
+    for archetype in world.archetypes().iter() {
+        for (rule_index, rule) in rules.rules.iter().enumerate() {
+            if !archetype.contains(rule.marker) {
+                continue;
+            }
+
+            let missing: Vec<String> = rule
+                .required
+                .iter()
+                .filter(|&&id| !archetype.contains(id))
+                .map(|&id| {
+                    world
+                        .components()
+                        .get_info(id)
+                        .map_or_else(|| format!("{id:?}"), |info| info.name().to_string())
+                })
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+
+            for entity in archetype.entities() {
+                let entity = entity.id();
+                violations.push((
+                    archetype.id(),
+                    rule_index,
+                    Violation {
+                        entity,
+                        marker: rule.marker_name,
+                        missing: missing.clone(),
+                        provenance: world.get::<Provenance>(entity).copied(),
+                    },
+                ));
+            }
+        }
+    }
+
+    violations
+}
+
+fn check_system(world: &mut World) {
+    let violations = scan(world, world.resource::<Rules>());
+    let mut rules = world.resource_mut::<Rules>();
+
+    for (archetype, rule, violation) in violations {
+        if !rules.reported.insert((archetype, rule)) {
+            continue;
+        }
+
+        let location = violation
+            .provenance
+            .map_or_else(|| "unknown spawn site".to_string(), |p| p.location.to_string());
+        bevy::log::error!(
+            "{:?} with {} is missing {} (constructed at {location})",
+            violation.entity,
+            violation.marker,
+            violation.missing.join(", "),
+        );
+    }
+}
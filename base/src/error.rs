@@ -0,0 +1,171 @@
+//! Structured errors for rejected commands.
+//!
+//! Commands that cannot be applied report an [`Error`] through [`reject`],
+//! which logs the error and emits a [`RejectedEvent`] for clients to render.
+//! Each error carries an i18n message key and the entities it refers to,
+//! so that clients can highlight the offending structures
+//! instead of displaying an opaque log message.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, Events};
+use bevy::ecs::world::World;
+use serde::{Deserialize, Serialize};
+
+use crate::partition::AppExt;
+
+/// Registers [`RejectedEvent`].
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) { app.add_partitioned_event::<RejectedEvent>(); }
+}
+
+/// The reason a command was rejected.
+#[derive(Debug, Clone, thiserror::Error, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "detail")]
+pub enum Error {
+    /// The command arguments are invalid for the current world state.
+    #[error("{0}")]
+    Validation(Detail),
+    /// A referenced entity does not exist or is not of the expected type.
+    #[error("{0}")]
+    NotFound(Detail),
+    /// The issuer is not allowed to perform the command.
+    #[error("{0}")]
+    Permission(Detail),
+    /// The command would exceed a limit.
+    #[error("{0}")]
+    Capacity(Detail),
+    /// An unexpected failure unrelated to the command arguments.
+    #[error("{0}")]
+    Internal(Detail),
+}
+
+impl Error {
+    /// Constructs a [validation](Error::Validation) error.
+    pub fn validation(key: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self::Validation(Detail::new(key, message))
+    }
+
+    /// Constructs a [not-found](Error::NotFound) error.
+    pub fn not_found(key: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self::NotFound(Detail::new(key, message))
+    }
+
+    /// Constructs a [permission](Error::Permission) error.
+    pub fn permission(key: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self::Permission(Detail::new(key, message))
+    }
+
+    /// Constructs a [capacity](Error::Capacity) error.
+    pub fn capacity(key: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self::Capacity(Detail::new(key, message))
+    }
+
+    /// Constructs an [internal](Error::Internal) error.
+    pub fn internal(key: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self::Internal(Detail::new(key, message))
+    }
+
+    /// Appends an entity referenced by the error.
+    #[must_use]
+    pub fn with_entity(mut self, entity: Entity) -> Self {
+        self.detail_mut().entities.push(entity);
+        self
+    }
+
+    /// Returns the details common to all error kinds.
+    #[must_use]
+    pub fn detail(&self) -> &Detail {
+        match self {
+            Self::Validation(detail)
+            | Self::NotFound(detail)
+            | Self::Permission(detail)
+            | Self::Capacity(detail)
+            | Self::Internal(detail) => detail,
+        }
+    }
+
+    fn detail_mut(&mut self) -> &mut Detail {
+        match self {
+            Self::Validation(detail)
+            | Self::NotFound(detail)
+            | Self::Permission(detail)
+            | Self::Capacity(detail)
+            | Self::Internal(detail) => detail,
+        }
+    }
+}
+
+/// Describes an [`Error`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Detail {
+    /// The i18n key of the message, e.g. `graph.placement.overlap`.
+    ///
+    /// Message arguments are the [`entities`](Detail::entities) in order.
+    pub key:      Cow<'static, str>,
+    /// Entities referenced by the error, e.g. the target of the command
+    /// or the structure blocking it.
+    #[serde(with = "entity_bits")]
+    pub entities: Vec<Entity>,
+    /// An English message for logs and clients without the key.
+    pub message:  String,
+}
+
+impl Detail {
+    fn new(key: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self { key: key.into(), entities: Vec::new(), message: message.into() }
+    }
+}
+
+impl fmt::Display for Detail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.message) }
+}
+
+/// A command was rejected.
+#[derive(Debug, Event)]
+pub struct RejectedEvent {
+    /// The reason for rejection.
+    pub error: Error,
+}
+
+/// Reports that a command was rejected.
+///
+/// The event is dropped if [`Plugin`] is not installed,
+/// e.g. in tests that only exercise part of the simulation.
+pub fn reject(world: &mut World, error: Error) {
+    bevy::log::warn!("command rejected: {error}");
+    if let Some(mut events) = world.get_resource_mut::<Events<RejectedEvent>>() {
+        events.send(RejectedEvent { error });
+    }
+}
+
+/// Serializes entities by their bit representation,
+/// since the `serialize` feature of bevy is not enabled.
+mod entity_bits {
+    use bevy::ecs::entity::Entity;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        entities: &[Entity],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        entities.iter().map(|entity| entity.to_bits()).collect::<Vec<_>>().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Entity>, D::Error> {
+        let bits = Vec::<u64>::deserialize(deserializer)?;
+        bits.into_iter()
+            .map(|bits| {
+                Entity::try_from_bits(bits)
+                    .map_err(|_| serde::de::Error::custom(format_args!("invalid entity {bits}")))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,147 @@
+//! Typed entity handles for cross-crate references.
+//!
+//! A [`Ref<T>`] is an [`Entity`] expected to have the marker component `T`,
+//! e.g. `Ref<building::Marker>`.
+//! Public APIs accept `Ref` instead of plain entities
+//! so that passing a container where a building is expected fails to compile.
+//!
+//! The marker is not guaranteed to be present:
+//! the entity may have been despawned after the handle was created.
+//! Accessors such as [`Ref::get`] and [`Ref::resolve`] validate the marker on access.
+
+use std::any::type_name;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::{EntityRef, EntityWorldMut, World};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error;
+
+/// An entity expected to have the marker component `T`.
+pub struct Ref<T> {
+    entity: Entity,
+    _ph:    PhantomData<fn() -> T>,
+}
+
+impl<T: Component> Ref<T> {
+    /// Wraps an entity known to have `T`, e.g. from a query filtered by `With<T>`.
+    #[must_use]
+    pub fn new_unchecked(entity: Entity) -> Self { Self { entity, _ph: PhantomData } }
+
+    /// Wraps an entity if it exists and has `T`.
+    #[must_use]
+    pub fn try_new(world: &World, entity: Entity) -> Option<Self> {
+        world.get::<T>(entity).is_some().then(|| Self::new_unchecked(entity))
+    }
+
+    /// Returns the underlying entity.
+    #[must_use]
+    pub fn entity(self) -> Entity { self.entity }
+
+    /// Checks whether the entity still exists with `T`.
+    #[must_use]
+    pub fn is_valid(self, world: &World) -> bool { world.get::<T>(self.entity).is_some() }
+
+    /// Returns the entity if it still exists with `T`.
+    #[must_use]
+    pub fn get(self, world: &World) -> Option<EntityRef<'_>> {
+        world.get_entity(self.entity).filter(EntityRef::contains::<T>)
+    }
+
+    /// Returns the entity mutably if it still exists with `T`.
+    #[must_use]
+    pub fn get_mut(self, world: &mut World) -> Option<EntityWorldMut<'_>> {
+        world.get_entity_mut(self.entity).filter(EntityWorldMut::contains::<T>)
+    }
+
+    /// Returns the entity, or a [not-found](error::Error::NotFound) error
+    /// suitable for rejecting a command.
+    ///
+    /// # Errors
+    /// Returns an error if the entity has been despawned or does not have `T`.
+    pub fn resolve(self, world: &World) -> Result<EntityRef<'_>, error::Error> {
+        self.get(world).ok_or_else(|| self.not_found())
+    }
+
+    /// Returns the entity mutably, or a [not-found](error::Error::NotFound) error
+    /// suitable for rejecting a command.
+    ///
+    /// # Errors
+    /// Returns an error if the entity has been despawned or does not have `T`.
+    pub fn resolve_mut(self, world: &mut World) -> Result<EntityWorldMut<'_>, error::Error> {
+        let err = self.not_found();
+        self.get_mut(world).ok_or(err)
+    }
+
+    /// Converts into a handle of another marker if the entity has it.
+    #[must_use]
+    pub fn cast<U: Component>(self, world: &World) -> Option<Ref<U>> {
+        Ref::try_new(world, self.entity)
+    }
+
+    fn not_found(self) -> error::Error {
+        let message = format!("{:?} is not a {}", self.entity, type_name::<T>());
+        error::Error::not_found("base.ref.not_found", message).with_entity(self.entity)
+    }
+}
+
+impl<T> From<Ref<T>> for Entity {
+    fn from(value: Ref<T>) -> Self { value.entity }
+}
+
+impl<T> Clone for Ref<T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<T> Copy for Ref<T> {}
+
+impl<T> PartialEq for Ref<T> {
+    fn eq(&self, other: &Self) -> bool { self.entity == other.entity }
+}
+
+impl<T> Eq for Ref<T> {}
+
+impl<T> PartialOrd for Ref<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<T> Ord for Ref<T> {
+    fn cmp(&self, other: &Self) -> Ordering { self.entity.cmp(&other.entity) }
+}
+
+impl<T> Hash for Ref<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.entity.hash(state); }
+}
+
+impl<T> fmt::Debug for Ref<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = type_name::<T>();
+        let name = name.rsplit_once("::").map_or(name, |(_, name)| name);
+        write!(f, "Ref<{name}>({:?})", self.entity)
+    }
+}
+
+/// Serialized as the entity bits, since the `serialize` feature of bevy is not enabled.
+///
+/// Only meaningful within the same world, e.g. in protocol messages.
+/// Save files reference entities through [`save::Id`](crate::save::Id) instead;
+/// `Ref` can be used as [`Def::Runtime`](crate::save::Def::Runtime).
+impl<T> Serialize for Ref<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.entity.to_bits().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Ref<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bits = u64::deserialize(deserializer)?;
+        let entity = Entity::try_from_bits(bits)
+            .map_err(|_| serde::de::Error::custom(format_args!("invalid entity {bits}")))?;
+        Ok(Self { entity, _ph: PhantomData })
+    }
+}
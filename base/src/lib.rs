@@ -7,3 +7,7 @@ pub use state::EmptyState;
 pub mod partition;
 pub use partition::{EventReaderSystemSet, EventWriterSystemSet};
 pub mod debug;
+pub mod error;
+pub mod handle;
+pub use handle::Ref;
+pub mod tasks;
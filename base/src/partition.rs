@@ -1,8 +1,12 @@
 //! Generic system ordering management utils.
 
+use std::any::type_name;
+
 use bevy::app::{self, App};
-use bevy::ecs::event::Event;
-use bevy::ecs::schedule::IntoSystemSetConfigs;
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::event::{Event, Events};
+use bevy::ecs::schedule::{InternedSystemSet, IntoSystemSetConfigs, SystemSet};
+use bevy::ecs::system::Resource;
 
 /// Declares a generic system set that takes a type argument.
 #[macro_export]
@@ -64,5 +68,31 @@ impl AppExt for App {
             app::Update,
             EventReaderSystemSet::<T>::default().after(EventWriterSystemSet::<T>::default()),
         );
+
+        let events = self
+            .world()
+            .components()
+            .resource_id::<Events<T>>()
+            .expect("Events resource was initialized by add_event");
+        self.world_mut().get_resource_or_insert_with(Declarations::default).0.push(Declaration {
+            name: type_name::<T>(),
+            events,
+            reader: EventReaderSystemSet::<T>::default().intern(),
+            writer: EventWriterSystemSet::<T>::default().intern(),
+        });
     }
 }
+
+/// Events registered through [`AppExt::add_partitioned_event`],
+/// used by the [schedule audit](crate::debug::audit).
+#[derive(Default, Resource)]
+pub(crate) struct Declarations(pub(crate) Vec<Declaration>);
+
+pub(crate) struct Declaration {
+    /// Type name of the event.
+    pub(crate) name:   &'static str,
+    /// The `Events<T>` resource.
+    pub(crate) events: ComponentId,
+    pub(crate) reader: InternedSystemSet,
+    pub(crate) writer: InternedSystemSet,
+}
@@ -5,12 +5,17 @@
 //!
 //! To add a new persisted type, implement [`Def`] and add a new [`add_def`] definition.
 //!
+//! New worlds can be generated without a running world through [`JsonBuilder`].
+//!
 //! # Save format
 //! There are two formats, msgpack and JSON.
 //!
 //! ## Msgpack
 //! Msgpack is the normal save format to persist a world.
-//! It starts with the [`MSGPACK_HEADER`], followed by a DEFLATE-encoded Msgpack buffer.
+//! It starts with the [`MSGPACK_COMPRESSED_HEADER`],
+//! followed by a byte identifying the [`Compression`] and the compressed Msgpack buffer.
+//! Older saves start with the [`MSGPACK_HEADER`] followed by a DEFLATE-encoded Msgpack buffer,
+//! which are still accepted by the loader.
 //! The data for each definition are stored as a separate Msgpack-encoded byte array
 //! that deserializes to `Vec<Def>` of a fixed type.
 //!
@@ -21,8 +26,6 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use std::any::type_name;
-use std::borrow::Cow;
 use std::fmt;
 use std::hash::Hash;
 use std::marker::PhantomData;
@@ -31,9 +34,16 @@ use bevy::app::{self, App};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-/// Header bytes for Msgpack saves.
+/// Header bytes for legacy Msgpack saves, which are always DEFLATE-encoded.
 pub const MSGPACK_HEADER: &[u8] = b"\xFFtraffloat.github.io/save.msgpack\n";
 
+/// Header bytes for Msgpack saves followed by a compression byte.
+pub const MSGPACK_COMPRESSED_HEADER: &[u8] = b"\xFFtraffloat.github.io/save.msgpack.v2\n";
+
+/// Header bytes of save files from the legion-based engine,
+/// which must be converted by `traffloat-legacy` before loading.
+pub const LEGACY_TFSAVE_HEADER: &[u8] = b"\xFFTSV";
+
 /// Registers a new definition type to the app.
 pub fn add_def<D: Def>(app: &mut App) {
     store::add_def::<D>(app);
@@ -43,11 +53,37 @@ pub fn add_def<D: Def>(app: &mut App) {
     schema::add_def::<D>(app);
 }
 
+/// Registers a new definition type whose loading may be deferred.
+///
+/// [`DeferredLoadCommand`] loads deferred types, and all types depending on them,
+/// one type per frame after the rest of the world is ready.
+/// Use this for bulky data that the world remains consistent without,
+/// since systems may run before the deferred types are loaded.
+pub fn add_deferred_def<D: Def>(app: &mut App) {
+    add_def::<D>(app);
+    load::mark_deferred::<D>(app);
+}
+
 #[cfg(feature = "schema")]
 pub mod schema;
 
 mod load;
-pub use load::{Depend as LoadDepend, LoadCommand, LoadFn, LoadOnce, LoadResult};
+pub use load::{
+    decode_untyped, DeferredLoadCommand, Depend as LoadDepend, Error as LoadError,
+    FinishDeferredLoadCommand, LoadCommand, LoadFn, LoadOnce, LoadResult,
+};
+
+mod build;
+pub use build::JsonBuilder;
+
+pub mod archive;
+pub mod asset_packs;
+pub mod file;
+
+pub mod rng;
+pub mod sandbox;
+pub mod seed;
+pub mod tunables;
 
 mod store;
 use serde_json::value::RawValue;
@@ -64,9 +100,21 @@ pub struct Plugin;
 
 impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((store::Plugin, load::Plugin));
+        // the schema store must exist before the built-in defs below are registered
         #[cfg(feature = "schema")]
         app.add_plugins(schema::Plugin);
+        app.add_plugins((
+            store::Plugin,
+            load::Plugin,
+            file::Plugin,
+            tunables::Plugin,
+            asset_packs::Plugin,
+            seed::Plugin,
+            rng::Plugin,
+        ));
+        if !app.is_plugin_added::<crate::tasks::Plugin>() {
+            app.add_plugins(crate::tasks::Plugin);
+        }
     }
 }
 
@@ -77,7 +125,10 @@ pub trait Def: Serialize + DeserializeOwned + cfg_schema::Bound + Send + Sync +
 
     /// The runtime type that maps to this definition,
     /// e.g. an `Entity` referencing the entity saved by this entry.
-    type Runtime: fmt::Debug + Copy + PartialEq + Eq + Hash + Send + Sync;
+    ///
+    /// Entries written together are sorted by their runtime value,
+    /// so that the save output does not depend on query iteration order.
+    type Runtime: fmt::Debug + Copy + Ord + Hash + Send + Sync;
 
     /// Returns a system that converts world entities and resources into save data.
     ///
@@ -109,9 +160,16 @@ mod cfg_schema {
 /// `D` must be stored/loaded before the current type.
 /// If self-dependency or cyclic dependency is required,
 /// separate the logic to another save entry type instead.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id<D: Def>(u32, PhantomData<fn() -> D>);
 
+// implemented manually because the derive would require `D: Copy`
+impl<D: Def> Clone for Id<D> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<D: Def> Copy for Id<D> {}
+
 impl<D: Def> Serialize for Id<D> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -139,6 +197,9 @@ impl<'de, D: Def> Deserialize<'de> for Id<D> {
 
 #[cfg(feature = "schema")]
 const _: () = {
+    use std::any::type_name;
+    use std::borrow::Cow;
+
     use schemars::gen::SchemaGenerator;
     use schemars::schema::Schema;
 
@@ -187,7 +248,45 @@ struct MsgpackTypedData {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
     /// The Msgpack save format.
-    Msgpack,
+    Msgpack(Compression),
     /// The JSON save format.
     Json,
 }
+
+/// Compression applied to Msgpack saves.
+///
+/// The loader detects the compression from the save header,
+/// so the choice only affects the speed and size of storing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// No compression, for short-lived in-memory saves.
+    None,
+    /// DEFLATE with a level from 0 (fastest) to 9 (smallest).
+    Deflate {
+        /// The compression level.
+        level: u32,
+    },
+    /// Zstandard with a level from 1 (fastest) to 22 (smallest).
+    Zstd {
+        /// The compression level.
+        level: i32,
+    },
+}
+
+impl Default for Compression {
+    fn default() -> Self { Self::Zstd { level: 3 } }
+}
+
+impl Compression {
+    const TAG_NONE: u8 = 0;
+    const TAG_DEFLATE: u8 = 1;
+    const TAG_ZSTD: u8 = 2;
+
+    fn tag(self) -> u8 {
+        match self {
+            Self::None => Self::TAG_NONE,
+            Self::Deflate { .. } => Self::TAG_DEFLATE,
+            Self::Zstd { .. } => Self::TAG_ZSTD,
+        }
+    }
+}
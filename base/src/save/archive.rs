@@ -0,0 +1,205 @@
+//! Cold-storage archives of finished worlds.
+//!
+//! An archive bundles the final save of a world with arbitrary attachments,
+//! e.g. screenshots, into a single file.
+//! The file starts with the [`ARCHIVE_HEADER`], followed by the byte length of the [`Index`]
+//! as a little-endian `u64`, the uncompressed JSON index,
+//! and the Zstandard-compressed entries in the order listed in the index.
+//!
+//! The index contains a [`Summary`] of the world,
+//! so that browsers of past games can call [`read_index`] on the start of the file
+//! without decompressing any entries.
+//! Individual entries are extracted with [`read_entry`].
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use serde::{Deserialize, Serialize};
+
+use super::{seed, Def, LoadError};
+
+#[cfg(test)]
+mod tests;
+
+/// Header bytes of archive files.
+pub const ARCHIVE_HEADER: &[u8] = b"\xFFtraffloat.github.io/archive.v1\n";
+
+/// Name of the entry containing the final save.
+pub const SAVE_ENTRY: &str = "save";
+
+/// Zstandard level of archived entries.
+///
+/// Archives are written once and rarely read, so the ratio is preferred over speed.
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// Upper bound of the index length, to reject corrupt files before allocating.
+const MAX_INDEX_LEN: u64 = 16 << 20;
+
+/// The table of contents of an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Index {
+    /// Summary of the archived world.
+    pub summary: Summary,
+    /// Entries in the order they are stored.
+    pub entries: Vec<EntryInfo>,
+}
+
+/// Summary of an archived world, readable without decompressing entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary {
+    /// Display title of the game.
+    pub title:       String,
+    /// Time of archival in seconds since the Unix epoch.
+    pub archived_at: u64,
+    /// The [seed](seed::WorldSeed) of the world, if it was generated.
+    pub seed:        Option<u64>,
+    /// Number of definitions of each type in the final save.
+    pub definitions: BTreeMap<String, usize>,
+}
+
+/// Location of an entry in an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryInfo {
+    /// Unique name of the entry.
+    pub name:   String,
+    /// Byte offset of the compressed entry from the end of the index.
+    pub offset: u64,
+    /// Byte length of the compressed entry.
+    pub len:    u64,
+}
+
+/// Creates an archive from the final save of a world and additional attachments.
+///
+/// # Errors
+/// Returns an error if the save cannot be decoded,
+/// if an attachment is named [`SAVE_ENTRY`] or duplicated, or if compression fails.
+pub fn create(
+    title: String,
+    archived_at: u64,
+    save: &[u8],
+    attachments: &[(&str, &[u8])],
+) -> Result<Vec<u8>, Error> {
+    let types = super::decode_untyped(save).map_err(Error::Save)?;
+    let seed = types
+        .get(seed::Save::TYPE)
+        .and_then(|defs| defs.first())
+        .and_then(|def| def.get("seed"))
+        .and_then(serde_json::Value::as_u64);
+    let definitions = types.into_iter().map(|(ty, defs)| (ty, defs.len())).collect();
+
+    let mut entries = Vec::new();
+    let mut data = Vec::new();
+    for (name, bytes) in [(SAVE_ENTRY, save)].into_iter().chain(attachments.iter().copied()) {
+        if entries.iter().any(|entry: &EntryInfo| entry.name == name) {
+            return Err(Error::DuplicateEntry(name.to_owned()));
+        }
+
+        let compressed = zstd::encode_all(bytes, COMPRESSION_LEVEL).map_err(Error::Io)?;
+        entries.push(EntryInfo {
+            name:   name.to_owned(),
+            offset: data.len() as u64,
+            len:    compressed.len() as u64,
+        });
+        data.extend_from_slice(&compressed);
+    }
+
+    let index = Index { summary: Summary { title, archived_at, seed, definitions }, entries };
+    let index = serde_json::to_vec(&index).map_err(Error::Index)?;
+
+    let mut output = Vec::with_capacity(ARCHIVE_HEADER.len() + 8 + index.len() + data.len());
+    output.extend_from_slice(ARCHIVE_HEADER);
+    output.extend_from_slice(&(index.len() as u64).to_le_bytes());
+    output.extend_from_slice(&index);
+    output.extend_from_slice(&data);
+    Ok(output)
+}
+
+/// Reads the index from the start of an archive.
+///
+/// Only the header and the index are read from `reader`.
+///
+/// # Errors
+/// Returns an error if the reader does not start with a valid archive index.
+pub fn read_index(mut reader: impl Read) -> Result<Index, Error> {
+    read_index_with_len(&mut reader).map(|(index, _)| index)
+}
+
+/// Reads the index and returns it with the number of bytes preceding the entries.
+fn read_index_with_len(reader: &mut impl Read) -> Result<(Index, usize), Error> {
+    let mut header = vec![0; ARCHIVE_HEADER.len()];
+    match reader.read_exact(&mut header) {
+        Ok(()) if header == ARCHIVE_HEADER => {}
+        Ok(()) => return Err(Error::NotArchive),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Err(Error::NotArchive),
+        Err(err) => return Err(Error::Io(err)),
+    }
+
+    let mut len = [0; 8];
+    reader.read_exact(&mut len).map_err(Error::Io)?;
+    let len = u64::from_le_bytes(len);
+    if len > MAX_INDEX_LEN {
+        return Err(Error::IndexTooLarge(len));
+    }
+    let len = usize::try_from(len).expect("MAX_INDEX_LEN fits in usize");
+
+    let mut index = vec![0; len];
+    reader.read_exact(&mut index).map_err(Error::Io)?;
+    let index = serde_json::from_slice(&index).map_err(Error::Index)?;
+
+    Ok((index, ARCHIVE_HEADER.len() + 8 + len))
+}
+
+/// Extracts and decompresses an entry of an archive.
+///
+/// # Errors
+/// Returns an error if the archive is invalid or does not contain the entry.
+pub fn read_entry(archive: &[u8], name: &str) -> Result<Vec<u8>, Error> {
+    let (index, data_start) = read_index_with_len(&mut &archive[..])?;
+    let entry = index
+        .entries
+        .iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| Error::MissingEntry(name.to_owned()))?;
+
+    let truncated = || Error::Truncated(name.to_owned());
+    let start = usize::try_from(entry.offset)
+        .ok()
+        .and_then(|offset| data_start.checked_add(offset))
+        .ok_or_else(truncated)?;
+    let end = usize::try_from(entry.len)
+        .ok()
+        .and_then(|len| start.checked_add(len))
+        .ok_or_else(truncated)?;
+    let compressed = archive.get(start..end).ok_or_else(truncated)?;
+
+    zstd::decode_all(compressed).map_err(Error::Io)
+}
+
+/// Error types of archives.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The file does not start with the archive header.
+    #[error("not a Traffloat archive")]
+    NotArchive,
+    /// Reading, compressing or decompressing failed.
+    #[error("archive IO: {0}")]
+    Io(io::Error),
+    /// The index could not be encoded or decoded.
+    #[error("archive index: {0}")]
+    Index(serde_json::Error),
+    /// The declared index length exceeds the limit, usually due to corruption.
+    #[error("archive index is too large ({0} bytes)")]
+    IndexTooLarge(u64),
+    /// The archived save could not be decoded.
+    #[error("decoding archived save: {0}")]
+    Save(LoadError),
+    /// Two entries have the same name.
+    #[error("duplicate archive entry {0:?}")]
+    DuplicateEntry(String),
+    /// The requested entry is not in the archive.
+    #[error("archive has no entry {0:?}")]
+    MissingEntry(String),
+    /// The entry extends beyond the end of the archive.
+    #[error("archive entry {0:?} is truncated")]
+    Truncated(String),
+}
@@ -0,0 +1,51 @@
+use super::{Error, SAVE_ENTRY};
+use crate::save::{seed, JsonBuilder};
+
+fn new_save() -> Vec<u8> {
+    let mut builder = JsonBuilder::default();
+    builder.add(seed::Save { seed: 42 }).unwrap();
+    builder.build().unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let save = new_save();
+    let archive =
+        super::create("Test".into(), 1000, &save, &[("screenshot.png", &b"not a png"[..])])
+            .unwrap();
+
+    let index = super::read_index(&archive[..]).unwrap();
+    assert_eq!(index.summary.title, "Test");
+    assert_eq!(index.summary.archived_at, 1000);
+    assert_eq!(index.summary.seed, Some(42));
+    assert_eq!(index.summary.definitions.get("traffloat.save.WorldSeed"), Some(&1));
+    let names: Vec<_> = index.entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, [SAVE_ENTRY, "screenshot.png"]);
+
+    assert_eq!(super::read_entry(&archive, SAVE_ENTRY).unwrap(), save);
+    assert_eq!(super::read_entry(&archive, "screenshot.png").unwrap(), b"not a png");
+    assert!(matches!(super::read_entry(&archive, "missing"), Err(Error::MissingEntry(_))));
+}
+
+#[test]
+fn index_from_prefix() {
+    let archive = super::create("Test".into(), 0, &new_save(), &[]).unwrap();
+    let index = super::read_index(&archive[..]).unwrap();
+
+    // the index is readable from a prefix of the file
+    let data_start = archive.len() - usize::try_from(index.entries[0].len).unwrap();
+    assert!(super::read_index(&archive[..data_start]).is_ok());
+}
+
+#[test]
+fn reject_duplicate_save() {
+    let save = new_save();
+    let result = super::create("Test".into(), 0, &save, &[(SAVE_ENTRY, &save[..])]);
+    assert!(matches!(result, Err(Error::DuplicateEntry(_))));
+}
+
+#[test]
+fn reject_non_archive() {
+    assert!(matches!(super::read_index(&new_save()[..]), Err(Error::NotArchive)));
+    assert!(matches!(super::read_index(&b""[..]), Err(Error::NotArchive)));
+}
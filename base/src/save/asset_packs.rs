@@ -0,0 +1,104 @@
+//! Shared asset packs that a scenario depends on.
+//!
+//! Scenarios reference large communal asset packs, e.g. textures and sounds,
+//! by URL and content hash instead of embedding the files.
+//! The [`Save`] entry declares the packs, and the loaded declarations are available
+//! in the [`AssetPacks`] resource for clients to resolve.
+//! Clients must verify the SHA-256 digest of a fetched pack against [`Pack::sha256`]
+//! before using its contents,
+//! so that a pack cannot be altered after the scenario was published.
+
+use anyhow::Context;
+use bevy::app::{self, App};
+use bevy::ecs::system::{Res, Resource};
+use bevy::ecs::world::World;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetPacks>();
+        save::add_def::<Save>(app);
+    }
+}
+
+/// A dependency on an asset pack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Pack {
+    /// Human-readable name of the pack, used in diagnostics.
+    pub name:   String,
+    /// The location to download the pack archive from.
+    pub url:    String,
+    /// The lowercase hexadecimal SHA-256 digest of the pack archive.
+    pub sha256: String,
+}
+
+impl Pack {
+    /// Checks that the digest is 64 lowercase hexadecimal digits.
+    ///
+    /// Clients use the digest as a cache directory name,
+    /// so this also ensures that it is a safe path component.
+    ///
+    /// # Errors
+    /// Returns an error if the digest is malformed.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let valid = self.sha256.len() == 64
+            && self.sha256.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'));
+        anyhow::ensure!(valid, "asset pack {:?} has malformed sha256 {:?}", self.name, self.sha256);
+        Ok(())
+    }
+}
+
+/// The asset packs required by the loaded scenario.
+#[derive(Debug, Default, Resource)]
+pub struct AssetPacks {
+    /// Declared dependencies in declaration order.
+    pub packs: Vec<Pack>,
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// Asset packs required by the scenario.
+    pub packs: Vec<Pack>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.AssetPacks";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(mut writer: save::Writer<Save>, (): (), packs: Res<AssetPacks>) {
+            if !packs.packs.is_empty() {
+                writer.write((), Save { packs: packs.packs.clone() });
+            }
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            for pack in &def.packs {
+                pack.validate().context("invalid asset pack dependency")?;
+            }
+
+            let mut packs = world.resource_mut::<AssetPacks>();
+            for pack in def.packs {
+                if !packs.packs.contains(&pack) {
+                    packs.packs.push(pack);
+                }
+            }
+
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use serde_json::value::RawValue;
+
+use super::{Def, Id, JsonFile, JsonTypedData};
+
+/// Builds a JSON save file from definitions directly,
+/// without storing them from a world.
+///
+/// This is used to generate new worlds, e.g. scenarios and random maps.
+/// The output is deterministic for the same sequence of definitions.
+#[derive(Default)]
+pub struct JsonBuilder {
+    types: BTreeMap<&'static str, Vec<Box<RawValue>>>,
+}
+
+impl JsonBuilder {
+    /// Appends a definition, returning its ID to be referenced by subsequent definitions.
+    ///
+    /// # Errors
+    /// Returns an error if the definition cannot be serialized.
+    pub fn add<D: Def>(&mut self, def: D) -> Result<Id<D>, serde_json::Error> {
+        let defs = self.types.entry(D::TYPE).or_default();
+        let id = u32::try_from(defs.len()).expect("too many definitions of the same type");
+        defs.push(serde_json::value::to_raw_value(&def)?);
+        Ok(Id(id, PhantomData))
+    }
+
+    /// Encodes the definitions as a JSON save file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be encoded.
+    pub fn build(self) -> Result<Vec<u8>, serde_json::Error> {
+        let types = self
+            .types
+            .into_iter()
+            .map(|(ty, defs)| {
+                Ok(JsonTypedData {
+                    r#type: ty.to_string(),
+                    defs:   serde_json::value::to_raw_value(&defs)?,
+                })
+            })
+            .collect::<Result<_, serde_json::Error>>()?;
+        serde_json::to_vec(&JsonFile { types })
+    }
+}
@@ -0,0 +1,141 @@
+//! Stores saves to files without blocking the main thread.
+//!
+//! Only collecting entries from the world runs on the main thread.
+//! Encoding, compression and file IO run on the [`IoTaskPool`],
+//! reporting each [stage](Stage) through [`StoreFileProgressEvent`].
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::{fs, mem};
+
+use bevy::app::{self, App};
+use bevy::ecs::event::Event;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::{Command, World};
+use bevy::tasks::IoTaskPool;
+
+use super::{store, Format};
+use crate::partition::{AppExt, EventWriterSystemSet};
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingWrites>();
+        app.add_partitioned_event::<StoreFileProgressEvent>();
+        app.add_systems(
+            app::Update,
+            poll_writes_system.in_set(EventWriterSystemSet::<StoreFileProgressEvent>::default()),
+        );
+    }
+}
+
+/// Stores the world into a file.
+pub struct StoreFileCommand {
+    /// The file to write.
+    pub path:   PathBuf,
+    /// The format to serialize into.
+    pub format: Format,
+}
+
+/// A stage of a [`StoreFileCommand`].
+#[derive(Debug, Clone)]
+pub enum Stage {
+    /// Entries have been collected from the world.
+    Collected,
+    /// The save file has been encoded and compressed.
+    Encoded {
+        /// Size of the encoded file in bytes.
+        size: usize,
+    },
+    /// The save file has been written.
+    Written,
+    /// The save failed and no further stages follow.
+    Failed {
+        /// Description of the error.
+        error: String,
+    },
+}
+
+/// Reports the progress of a [`StoreFileCommand`].
+#[derive(Debug, Event)]
+pub struct StoreFileProgressEvent {
+    /// The file being written.
+    pub path:  PathBuf,
+    /// The stage reached.
+    pub stage: Stage,
+}
+
+impl Command for StoreFileCommand {
+    fn apply(self, world: &mut World) {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+
+        let encoder = match store::collect(world, self.format) {
+            Ok(encoder) => encoder,
+            Err(err) => {
+                world.send_event(StoreFileProgressEvent {
+                    path:  self.path,
+                    stage: Stage::Failed { error: err.to_string() },
+                });
+                return;
+            }
+        };
+        world.send_event(StoreFileProgressEvent {
+            path:  self.path.clone(),
+            stage: Stage::Collected,
+        });
+
+        // The task is detached because the single-threaded task pool cannot return a result;
+        // completion is observed through the final stage instead.
+        IoTaskPool::get_or_init(<_>::default)
+            .spawn({
+                let path = self.path.clone();
+                let updates = Arc::clone(&updates);
+                async move {
+                    let push = |stage| updates.lock().expect("poisoned mutex").push(stage);
+
+                    let data = match encoder.encode() {
+                        Ok(data) => data,
+                        Err(err) => return push(Stage::Failed { error: err.to_string() }),
+                    };
+                    push(Stage::Encoded { size: data.len() });
+
+                    match fs::write(&path, data) {
+                        Ok(()) => push(Stage::Written),
+                        Err(err) => push(Stage::Failed { error: err.to_string() }),
+                    }
+                }
+            })
+            .detach();
+
+        world.resource_mut::<PendingWrites>().0.push(PendingWrite { path: self.path, updates });
+    }
+}
+
+struct PendingWrite {
+    path:    PathBuf,
+    updates: Arc<Mutex<Vec<Stage>>>,
+}
+
+/// File writes that have not completed yet.
+#[derive(Default, Resource)]
+struct PendingWrites(Vec<PendingWrite>);
+
+fn poll_writes_system(world: &mut World) {
+    let mut events = Vec::new();
+    world.resource_mut::<PendingWrites>().0.retain(|write| {
+        let stages = mem::take(&mut *write.updates.lock().expect("poisoned mutex"));
+        let finished = stages
+            .last()
+            .is_some_and(|stage| matches!(stage, Stage::Written | Stage::Failed { .. }));
+        events.extend(
+            stages
+                .into_iter()
+                .map(|stage| StoreFileProgressEvent { path: write.path.clone(), stage }),
+        );
+        !finished
+    });
+
+    world.send_event_batch(events);
+}
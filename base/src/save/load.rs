@@ -1,21 +1,24 @@
 use std::any::{type_name, Any, TypeId};
 use std::collections::BTreeMap;
-use std::hash::Hash;
+use std::io;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
 use bevy::app::{self, App};
 use bevy::ecs::system::Resource;
 use bevy::ecs::world::{Command, World};
-use bevy::utils::{hashbrown, HashMap};
+use bevy::utils::{HashMap, HashSet};
 use serde_json::value::RawValue;
 
-use super::{Def, Id, JsonFile, MsgpackFile};
+use super::{Compression, Def, Id, JsonFile, MsgpackFile};
 
 pub(super) struct Plugin;
 
 impl app::Plugin for Plugin {
-    fn build(&self, app: &mut App) { app.init_resource::<LoaderMap>(); }
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoaderMap>();
+        app.add_systems(app::Update, deferred_load_system);
+    }
 }
 
 pub(super) fn add_def<D: Def>(app: &mut App) {
@@ -97,74 +100,227 @@ pub struct LoadCommand {
     pub on_complete: Box<dyn FnOnce(&mut World, LoadResult) + Send>,
 }
 
-fn process_file(buf: &[u8], world: &mut World) -> Result<(), Error> {
-    fn process_step<K: Eq + Hash, T>(
-        world: &mut World,
-        depends: &mut DependSource,
-        ty: &str,
-        types: &mut HashMap<K, T>,
-        load_fn: impl FnOnce(&mut World, &mut DependSource, LoaderVtable, T) -> Result<(), Error>,
-    ) -> Result<(), Error>
-    where
-        str: hashbrown::Equivalent<K>,
+/// Raw definitions of a single type, not yet decoded.
+enum RawDefs {
+    Msgpack(Vec<u8>),
+    Json(Box<RawValue>),
+}
+
+fn parse_file(buf: &[u8]) -> Result<HashMap<String, RawDefs>, Error> {
+    if buf.starts_with(super::LEGACY_TFSAVE_HEADER) {
+        return Err(Error::LegacyFormat);
+    }
+
+    let msgpack_reader: Option<Box<dyn io::Read + '_>> = if let Some(tagged) =
+        buf.strip_prefix(super::MSGPACK_COMPRESSED_HEADER)
     {
-        let &loader =
-            world.resource::<LoaderMap>().map.get(ty).expect("exec_order has nonexistent type");
-        if let Some(entry) = types.remove(ty) {
-            load_fn(world, depends, loader, entry)
-        } else {
+        let (&tag, compressed) = tagged.split_first().ok_or(Error::UnknownCompression(None))?;
+        Some(match tag {
+            Compression::TAG_NONE => Box::new(compressed),
+            Compression::TAG_DEFLATE => Box::new(flate2::bufread::DeflateDecoder::new(compressed)),
+            Compression::TAG_ZSTD => Box::new(
+                zstd::stream::read::Decoder::with_buffer(compressed).map_err(Error::Decompress)?,
+            ),
+            _ => return Err(Error::UnknownCompression(Some(tag))),
+        })
+    } else {
+        buf.strip_prefix(super::MSGPACK_HEADER).map(|compressed| {
+            Box::new(flate2::bufread::DeflateDecoder::new(compressed)) as Box<dyn io::Read>
+        })
+    };
+
+    if let Some(reader) = msgpack_reader {
+        let file: MsgpackFile = rmp_serde::from_read(reader).map_err(Error::MsgpackDecodeFile)?;
+        Ok(file
+            .types
+            .into_iter()
+            .map(|entry| (entry.r#type, RawDefs::Msgpack(entry.defs)))
+            .collect())
+    } else {
+        let file: JsonFile = serde_json::from_slice(buf).map_err(Error::JsonDecodeFile)?;
+        Ok(file.types.into_iter().map(|entry| (entry.r#type, RawDefs::Json(entry.defs))).collect())
+    }
+}
+
+/// Decodes a save file into untyped values, grouped by definition type.
+///
+/// The value at index `i` of each type is the definition with save ID `i`.
+/// Useful for tools that inspect saves without constructing a world.
+///
+/// # Errors
+/// Returns an error if the file or any type cannot be decoded.
+pub fn decode_untyped(buf: &[u8]) -> Result<BTreeMap<String, Vec<serde_json::Value>>, Error> {
+    parse_file(buf)?
+        .into_iter()
+        .map(|(ty, defs)| {
+            let values = match defs {
+                RawDefs::Msgpack(bytes) => rmp_serde::from_slice(&bytes)
+                    .map_err(|err| Error::MsgpackDecodeUntyped(ty.clone(), err))?,
+                RawDefs::Json(raw) => serde_json::from_str(raw.get())
+                    .map_err(|err| Error::JsonDecodeUntyped(ty.clone(), err))?,
+            };
+            Ok((ty, values))
+        })
+        .collect()
+}
+
+fn load_type(
+    world: &mut World,
+    depends: &mut DependSource,
+    ty: &str,
+    types: &mut HashMap<String, RawDefs>,
+) -> Result<(), Error> {
+    let &loader =
+        world.resource::<LoaderMap>().map.get(ty).expect("exec_order has nonexistent type");
+    match types.remove(ty) {
+        Some(RawDefs::Msgpack(defs)) => (loader.load_msgpack)(world, defs, depends),
+        Some(RawDefs::Json(defs)) => (loader.load_json)(world, &defs, depends),
+        None => {
             // type does not exist in entry file, just populate DependSource directly.
             (loader.init_depend_source)(depends);
             Ok(())
         }
     }
+}
 
+fn process_file(buf: &[u8], world: &mut World) -> Result<(), Error> {
+    let mut types = parse_file(buf)?;
     let exec_order = world.resource::<LoaderMap>().toposorted_types();
     let mut depends = DependSource(HashMap::new());
 
-    if let Some(compressed) = buf.strip_prefix(super::MSGPACK_HEADER) {
-        let file: MsgpackFile =
-            rmp_serde::from_read(flate2::bufread::DeflateDecoder::new(compressed))
-                .map_err(Error::MsgpackDecodeFile)?;
-        let mut types: HashMap<_, _> =
-            file.types.into_iter().map(|entry| (entry.r#type.clone(), entry)).collect();
-
-        for ty in exec_order {
-            process_step(world, &mut depends, ty, &mut types, |world, depends, loader, entry| {
-                (loader.load_msgpack)(world, entry.defs, depends)
-            })?;
-        }
+    for ty in exec_order {
+        load_type(world, &mut depends, ty, &mut types)?;
+    }
 
-        Ok(())
-    } else {
-        let file: JsonFile = serde_json::from_slice(buf).map_err(Error::JsonDecodeFile)?;
-        let mut types: HashMap<_, _> =
-            file.types.into_iter().map(|entry| (entry.r#type.clone(), entry)).collect();
+    Ok(())
+}
+
+impl Command for LoadCommand {
+    fn apply(self, world: &mut World) {
+        let result = process_file(&self.data, world);
+        (self.on_complete)(world, result);
+    }
+}
 
-        for ty in exec_order {
-            process_step(world, &mut depends, ty, &mut types, |world, depends, loader, entry| {
-                (loader.load_json)(world, &entry.defs, depends)
-            })?;
+/// Load the save file in `data` into the world,
+/// deferring types registered with [`add_deferred_def`](super::add_deferred_def).
+///
+/// All other types are loaded immediately, after which `on_ready` is invoked.
+/// Deferred types are then loaded one type per frame in dependency order,
+/// after which `on_complete` is invoked.
+/// `on_complete` is not invoked if `on_ready` receives an error.
+///
+/// This only spreads the construction of deferred definitions over later frames.
+/// The whole file is still read, decompressed and split into type sections immediately,
+/// and each deferred section is decoded in full in the frame that loads its type.
+///
+/// Starting another deferred load before `on_complete` abandons the remaining deferred types
+/// without invoking the previous `on_complete`.
+pub struct DeferredLoadCommand {
+    /// Bytes of the save file.
+    pub data:        Vec<u8>,
+    /// Closure to be invoked when all non-deferred types have been loaded.
+    pub on_ready:    Box<dyn FnOnce(&mut World, LoadResult) + Send>,
+    /// Closure to be invoked when all deferred types have been loaded.
+    pub on_complete: Box<dyn FnOnce(&mut World, LoadResult) + Send + Sync>,
+}
+
+impl Command for DeferredLoadCommand {
+    fn apply(self, world: &mut World) {
+        let mut types = match parse_file(&self.data) {
+            Ok(types) => types,
+            Err(err) => return (self.on_ready)(world, Err(err)),
+        };
+
+        let (eager, deferred) = world.resource::<LoaderMap>().split_deferred();
+        let mut depends = DependSource(HashMap::new());
+
+        for ty in eager {
+            if let Err(err) = load_type(world, &mut depends, ty, &mut types) {
+                return (self.on_ready)(world, Err(err));
+            }
         }
 
-        Ok(())
+        world.insert_resource(DeferredLoad {
+            remaining: deferred.into_iter().rev().collect(),
+            types,
+            depends,
+            on_complete: self.on_complete,
+        });
+        (self.on_ready)(world, Ok(()));
     }
 }
 
-impl Command for LoadCommand {
+/// Loads all remaining deferred types of the current [`DeferredLoadCommand`] immediately,
+/// e.g. when the deferred data are required before proceeding.
+pub struct FinishDeferredLoadCommand;
+
+impl Command for FinishDeferredLoadCommand {
     fn apply(self, world: &mut World) {
-        let result = process_file(&self.data, world);
-        (self.on_complete)(world, result);
+        while world.contains_resource::<DeferredLoad>() {
+            deferred_load_system(world);
+        }
+    }
+}
+
+/// Types of a [`DeferredLoadCommand`] that have not been loaded yet.
+#[derive(Resource)]
+struct DeferredLoad {
+    /// Types to load, in reverse execution order.
+    remaining:   Vec<&'static str>,
+    types:       HashMap<String, RawDefs>,
+    depends:     DependSource,
+    on_complete: Box<dyn FnOnce(&mut World, LoadResult) + Send + Sync>,
+}
+
+fn deferred_load_system(world: &mut World) {
+    let Some(mut load) = world.remove_resource::<DeferredLoad>() else { return };
+
+    let result = match load.remaining.pop() {
+        Some(ty) => load_type(world, &mut load.depends, ty, &mut load.types),
+        None => return (load.on_complete)(world, Ok(())),
+    };
+
+    match result {
+        Ok(()) if load.remaining.is_empty() => (load.on_complete)(world, Ok(())),
+        Ok(()) => world.insert_resource(load),
+        Err(err) => (load.on_complete)(world, Err(err)),
     }
 }
 
+pub(super) fn mark_deferred<D: Def>(app: &mut App) {
+    app.world_mut().resource_mut::<LoaderMap>().deferred.insert(D::TYPE);
+}
+
 #[derive(Default, Resource)]
 struct LoaderMap {
-    deps: HashMap<&'static str, Vec<&'static str>>,
-    map:  HashMap<&'static str, LoaderVtable>,
+    deps:     HashMap<&'static str, Vec<&'static str>>,
+    map:      HashMap<&'static str, LoaderVtable>,
+    /// Types loaded after the rest of the world in a [`DeferredLoadCommand`].
+    deferred: HashSet<&'static str>,
 }
 
 impl LoaderMap {
+    /// Splits the execution order into eagerly loaded and deferred types.
+    ///
+    /// Types depending on a deferred type are also deferred.
+    fn split_deferred(&self) -> (Vec<&'static str>, Vec<&'static str>) {
+        let mut deferred = self.deferred.clone();
+        let (mut eager_order, mut deferred_order) = (Vec::new(), Vec::new());
+
+        for ty in self.toposorted_types() {
+            let deps = self.deps.get(ty).expect("toposorted types must have deps");
+            if deferred.contains(ty) || deps.iter().any(|dep| deferred.contains(dep)) {
+                deferred.insert(ty);
+                deferred_order.push(ty);
+            } else {
+                eager_order.push(ty);
+            }
+        }
+
+        (eager_order, deferred_order)
+    }
+
     fn toposorted_types(&self) -> Vec<&'static str> {
         #[derive(Debug, Clone, Copy)]
         enum UnvisitedState {
@@ -390,20 +546,43 @@ pub type LoadResult = Result<(), Error>;
 /// Error types during loading.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// The file is a save from the legacy legion-based engine.
+    #[error("legacy .tfsave files from the legion-based engine must be imported before loading")]
+    LegacyFormat,
+    /// The msgpack save uses an unknown compression scheme.
+    #[error("unknown save compression {0:?}")]
+    UnknownCompression(Option<u8>),
+    /// The compressed save could not be decompressed.
+    #[error("decompressing save file: {0}")]
+    Decompress(io::Error),
+    /// The msgpack save file is malformed.
     #[error("msgpack file decode: {0}")]
     MsgpackDecodeFile(rmp_serde::decode::Error),
+    /// The msgpack definitions of a type do not match its schema.
     #[error("msgpack type {0} decode: {1}")]
     MsgpackDecodeType(&'static str, rmp_serde::decode::Error),
+    /// The JSON save file is malformed.
     #[error("json file decode: {0}")]
     JsonDecodeFile(serde_json::Error),
+    /// The JSON definitions of a type do not match its schema.
     #[error("json value {0} decode: {1}")]
     JsonDecodeType(&'static str, serde_json::Error),
+    /// The msgpack definitions of a type could not be decoded as generic values.
+    #[error("msgpack type {0} untyped decode: {1}")]
+    MsgpackDecodeUntyped(String, rmp_serde::decode::Error),
+    /// The JSON definitions of a type could not be decoded as generic values.
+    #[error("json type {0} untyped decode: {1}")]
+    JsonDecodeUntyped(String, serde_json::Error),
+    /// The save contains a definition type that is not registered.
     #[error("unsupported def entry {0:?}")]
     UnsupportedType(String),
+    /// A definition was rejected by its loader.
     #[error("processing value {0}#{1}: {2:?}")]
     Validation(&'static str, usize, anyhow::Error),
+    /// A definition refers to a definition that does not exist.
     #[error("unresolved reference to {0}#{1}")]
     UnresolvedReference(&'static str, u32),
+    /// A type has more definitions than can be referenced.
     #[error("too many defs of type {0}")]
     RegistryOverflow(&'static str),
 }
@@ -0,0 +1,201 @@
+//! Seeded simulation randomness with a recorded history of random events.
+//!
+//! Simulation systems draw randomness from the [`SimRng`] resource
+//! instead of thread-local generators,
+//! so that a loaded world replays the same random events.
+//! Each call to [`SimRng::event`] records the event type, the update tick
+//! and the stream position at which the event started drawing,
+//! so a bug report about an "unfair" event can be reproduced exactly
+//! from the [world seed](super::seed::WorldSeed) and the recorded position.
+//!
+//! The stream is a counter-based splitmix64 sequence,
+//! so the generator state is fully described by the seed and the stream position.
+//! The history is saved with the world in the [`Save`] entry.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use bevy::app::{self, App};
+use bevy::ecs::system::{Res, ResMut, Resource};
+use bevy::ecs::world::World;
+use rand_core::{impls, RngCore};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+#[cfg(test)]
+mod tests;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimRng>();
+        app.add_systems(app::First, advance_tick_system);
+        save::add_def::<Save>(app);
+    }
+}
+
+/// The maximum number of random events kept in the history.
+///
+/// Older events are dropped first.
+/// Their draws remain reproducible from the seed and their stream position.
+pub const HISTORY_LIMIT: usize = 4096;
+
+/// A random event recorded in the [`SimRng`] history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Draw {
+    /// The type of event, namespaced by the drawing crate, e.g. `view.cluster.noise`.
+    pub kind:     Cow<'static, str>,
+    /// The update tick in which the event was drawn.
+    pub tick:     u64,
+    /// The stream position of the first value drawn for the event.
+    pub position: u64,
+}
+
+/// The seeded random number generator of the simulation.
+#[derive(Debug, Default, Resource)]
+pub struct SimRng {
+    seed:     u64,
+    position: u64,
+    tick:     u64,
+    history:  VecDeque<Draw>,
+}
+
+impl SimRng {
+    /// Restarts the stream from `seed`, clearing the history.
+    pub fn reseed(&mut self, seed: u64) { *self = Self { seed, ..Self::default() }; }
+
+    /// Sets the seed of a loaded stream, keeping its position and history.
+    pub(super) fn set_seed(&mut self, seed: u64) { self.seed = seed; }
+
+    /// The current update tick.
+    #[must_use]
+    pub fn tick(&self) -> u64 { self.tick }
+
+    /// The number of values drawn since the stream was seeded.
+    #[must_use]
+    pub fn position(&self) -> u64 { self.position }
+
+    /// Starts a random event of type `kind`, recording it in the history.
+    ///
+    /// All randomness for the event should be drawn from the returned generator.
+    pub fn event(&mut self, kind: impl Into<Cow<'static, str>>) -> EventRng<'_> {
+        if self.history.len() >= HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(Draw {
+            kind:     kind.into(),
+            tick:     self.tick,
+            position: self.position,
+        });
+        EventRng { rng: self }
+    }
+
+    /// Iterates over the recorded events, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &Draw> { self.history.iter() }
+
+    /// Iterates over the recorded events of type `kind` drawn within `ticks`.
+    pub fn query<'a>(
+        &'a self,
+        kind: &'a str,
+        ticks: impl std::ops::RangeBounds<u64> + 'a,
+    ) -> impl Iterator<Item = &'a Draw> {
+        self.history.iter().filter(move |draw| draw.kind == kind && ticks.contains(&draw.tick))
+    }
+
+    /// Returns a generator replaying the draws of an event from its stream position,
+    /// without affecting the simulation stream.
+    #[must_use]
+    pub fn replay(&self, draw: &Draw) -> Self {
+        Self { seed: self.seed, position: draw.position, ..Self::default() }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.position += 1;
+        let mut z = self.seed.wrapping_add(self.position.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl RngCore for SimRng {
+    #[allow(clippy::cast_possible_truncation)] // the upper half is taken
+    fn next_u32(&mut self) -> u32 { (self.next() >> 32) as u32 }
+
+    fn next_u64(&mut self) -> u64 { self.next() }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) { impls::fill_bytes_via_next(self, dest) }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Draws the randomness of one event started by [`SimRng::event`].
+pub struct EventRng<'a> {
+    rng: &'a mut SimRng,
+}
+
+impl RngCore for EventRng<'_> {
+    fn next_u32(&mut self) -> u32 { self.rng.next_u32() }
+
+    fn next_u64(&mut self) -> u64 { self.rng.next_u64() }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) { self.rng.fill_bytes(dest) }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+fn advance_tick_system(mut rng: ResMut<SimRng>) { rng.tick += 1; }
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The stream position.
+    pub position: u64,
+    /// The current update tick.
+    pub tick:     u64,
+    /// The recorded random events, oldest first.
+    pub history:  Vec<Draw>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.SimRng";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(mut writer: save::Writer<Save>, (): (), rng: Res<SimRng>) {
+            writer.write(
+                (),
+                Save {
+                    position: rng.position,
+                    tick:     rng.tick,
+                    history:  rng.history.iter().cloned().collect(),
+                },
+            );
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            // the seed is restored separately from `seed::Save`
+            let mut rng = world.resource_mut::<SimRng>();
+            rng.position = def.position;
+            rng.tick = def.tick;
+            rng.history = def.history.into();
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
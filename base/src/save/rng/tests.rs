@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::app::App;
+use bevy::ecs::world::Command;
+use rand_core::RngCore;
+
+use super::{Draw, SimRng, HISTORY_LIMIT};
+use crate::save;
+use crate::save::seed::WorldSeed;
+
+#[test]
+fn replay_event() {
+    let mut rng = SimRng::default();
+    rng.reseed(42);
+
+    let _ = rng.event("test.first").next_u64();
+    let drawn = {
+        let mut event = rng.event("test.second");
+        [event.next_u64(), event.next_u64()]
+    };
+
+    let mut other = SimRng::default();
+    other.reseed(42);
+    let _ = other.event("test.first").next_u64();
+    assert_eq!(other.event("test.second").next_u64(), drawn[0]);
+
+    let draw = rng.history().nth(1).expect("two events were recorded").clone();
+    assert_eq!(draw, Draw { kind: "test.second".into(), tick: 0, position: 1 });
+    let mut replay = rng.replay(&draw);
+    assert_eq!([replay.next_u64(), replay.next_u64()], drawn);
+    assert_eq!(rng.position(), 3, "replay should not advance the simulation stream");
+}
+
+#[test]
+fn query_history() {
+    let mut app = App::new();
+    app.add_plugins(save::Plugin);
+
+    for _ in 0..3 {
+        app.update();
+        let mut rng = app.world_mut().resource_mut::<SimRng>();
+        let _ = rng.event("test.a").next_u32();
+        let _ = rng.event("test.b").next_u32();
+    }
+
+    let rng = app.world().resource::<SimRng>();
+    assert_eq!(rng.tick(), 3);
+    let ticks: Vec<_> = rng.query("test.a", 2..).map(|draw| draw.tick).collect();
+    assert_eq!(ticks, [2, 3]);
+    assert_eq!(rng.query("test.b", ..).count(), 3);
+    assert_eq!(rng.query("test.c", ..).count(), 0);
+
+    let mut rng = SimRng::default();
+    for _ in 0..=HISTORY_LIMIT {
+        let _ = rng.event("test.a").next_u32();
+    }
+    assert_eq!(rng.history().count(), HISTORY_LIMIT);
+    assert_eq!(rng.history().next().map(|draw| draw.position), Some(1));
+}
+
+#[test]
+fn save_and_load() {
+    let mut app = App::new();
+    app.add_plugins(save::Plugin);
+    app.world_mut().resource_mut::<WorldSeed>().seed = Some(7);
+    app.world_mut().resource_mut::<SimRng>().reseed(7);
+    app.update();
+    let _ = app.world_mut().resource_mut::<SimRng>().event("test.a").next_u64();
+
+    let data = Arc::new(Mutex::new(None));
+    save::StoreCommand {
+        format:      save::Format::Json,
+        on_complete: Box::new({
+            let data = Arc::clone(&data);
+            move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+        }),
+    }
+    .apply(app.world_mut());
+    let data = data.lock().unwrap().take().expect("StoreCommand completes synchronously");
+
+    let mut loaded = App::new();
+    loaded.add_plugins(save::Plugin);
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(loaded.world_mut());
+
+    let loaded_rng = loaded.world().resource::<SimRng>();
+    assert_eq!(loaded_rng.tick(), 1);
+    assert_eq!(loaded_rng.position(), 1);
+    assert_eq!(
+        loaded_rng.history().collect::<Vec<_>>(),
+        app.world().resource::<SimRng>().history().collect::<Vec<_>>()
+    );
+
+    let expected = app.world_mut().resource_mut::<SimRng>().event("test.b").next_u64();
+    let actual = loaded.world_mut().resource_mut::<SimRng>().event("test.b").next_u64();
+    assert_eq!(actual, expected);
+}
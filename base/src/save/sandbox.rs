@@ -0,0 +1,111 @@
+//! Sandboxes run hypothetical forward simulations on a copy of the world.
+//!
+//! The live world is [stored](super::StoreCommand) into a buffer,
+//! which is [loaded](super::LoadCommand) into a new headless app on a background thread.
+//! Hypothetical changes are applied to the sandbox world,
+//! which is then updated for a number of ticks while sampling its state after each tick.
+//! The live world is never modified.
+//!
+//! The simulation runs as a [background task](crate::tasks),
+//! reporting the proportion of simulated ticks and stopping early when cancelled.
+
+use std::sync::Arc;
+
+use bevy::app::App;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::{Command, World};
+
+use super::{Compression, Format, LoadCommand, StoreCommand};
+use crate::tasks;
+
+/// Simulates the world forward in a sandbox.
+pub struct RunCommand<T> {
+    /// Token to report progress and cancel the simulation.
+    pub token:       tasks::Token,
+    /// Installs the simulation plugins in the sandbox app.
+    ///
+    /// This should install the same save definitions and simulation systems as the live app,
+    /// but without rendering or input plugins.
+    pub setup:       Arc<dyn Fn(&mut App) + Send + Sync>,
+    /// Applies hypothetical changes to the sandbox world after it is loaded.
+    pub apply:       Box<dyn FnOnce(&mut World) + Send>,
+    /// Number of updates to simulate.
+    pub ticks:       u32,
+    /// Samples the sandbox world after each update.
+    pub sample:      Box<dyn FnMut(&mut World) -> T + Send>,
+    /// Invoked in the live world with the samples of each tick.
+    ///
+    /// If the simulation is cancelled, an error is passed instead.
+    pub on_complete: Box<dyn FnOnce(&mut World, anyhow::Result<Vec<T>>) + Send>,
+}
+
+impl<T: Send + 'static> Command for RunCommand<T> {
+    fn apply(self, world: &mut World) {
+        let Self { token, setup, apply, ticks, mut sample, on_complete } = self;
+
+        StoreCommand {
+            format:      Format::Msgpack(Compression::None),
+            on_complete: Box::new(move |world, result| {
+                let data = match result {
+                    Ok(data) => data,
+                    Err(err) => {
+                        on_complete(world, Err(err.into()));
+                        return;
+                    }
+                };
+
+                world.resource_mut::<tasks::Tasks>().spawn(
+                    token.clone(),
+                    tasks::Pool::Compute,
+                    async move { simulate(&token, &*setup, data, apply, ticks, &mut *sample) },
+                    move |world, result| {
+                        let result =
+                            result.unwrap_or_else(|| Err(anyhow::anyhow!("sandbox cancelled")));
+                        on_complete(world, result);
+                    },
+                );
+            }),
+        }
+        .apply(world);
+    }
+}
+
+fn simulate<T>(
+    token: &tasks::Token,
+    setup: &(dyn Fn(&mut App) + Send + Sync),
+    data: Vec<u8>,
+    apply: Box<dyn FnOnce(&mut World) + Send>,
+    ticks: u32,
+    sample: &mut (dyn FnMut(&mut World) -> T + Send),
+) -> anyhow::Result<Vec<T>> {
+    let mut app = App::new();
+    setup(&mut app);
+    app.finish();
+    app.cleanup();
+
+    LoadCommand {
+        data,
+        on_complete: Box::new(|world, result| world.insert_resource(LoadResultSlot(result))),
+    }
+    .apply(app.world_mut());
+    let LoadResultSlot(load_result) =
+        app.world_mut().remove_resource().expect("LoadCommand always invokes on_complete");
+    load_result?;
+
+    apply(app.world_mut());
+
+    let mut samples = Vec::new();
+    for tick in 0..ticks {
+        if token.is_cancelled() {
+            break;
+        }
+        app.update();
+        samples.push(sample(app.world_mut()));
+        #[allow(clippy::cast_precision_loss)]
+        token.report((tick + 1) as f32 / ticks as f32, "simulating");
+    }
+    Ok(samples)
+}
+
+#[derive(Resource)]
+struct LoadResultSlot(super::LoadResult);
@@ -0,0 +1,65 @@
+//! The seed from which a world was generated.
+//!
+//! Generated worlds record their generator seed in the [`Save`] entry,
+//! so that a world can be regenerated exactly when reproducing a bug report.
+//! Hand-written scenarios have no seed.
+//! The seed of the loaded world is available in the [`WorldSeed`] resource,
+//! and also seeds the [simulation randomness](save::rng::SimRng).
+
+use bevy::app::{self, App};
+use bevy::ecs::system::{Res, Resource};
+use bevy::ecs::world::World;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldSeed>();
+        save::add_def::<Save>(app);
+    }
+}
+
+/// The seed of the loaded world.
+#[derive(Debug, Default, Resource)]
+pub struct WorldSeed {
+    /// The generator seed, or `None` if the world was not generated.
+    pub seed: Option<u64>,
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The generator seed.
+    pub seed: u64,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.WorldSeed";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(mut writer: save::Writer<Save>, (): (), world_seed: Res<WorldSeed>) {
+            if let Some(seed) = world_seed.seed {
+                writer.write((), Save { seed });
+            }
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            world.resource_mut::<WorldSeed>().seed = Some(def.seed);
+            world.resource_mut::<save::rng::SimRng>().set_seed(def.seed);
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
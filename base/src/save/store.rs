@@ -1,4 +1,5 @@
 use std::any::{type_name, TypeId};
+use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::{iter, mem};
 
@@ -11,7 +12,7 @@ use bevy::ecs::system::{IntoSystem, Res, ResMut, Resource, SystemParam};
 use bevy::ecs::world::{Command, World};
 use bevy::utils::HashMap;
 
-use super::{Def, Format, Id, JsonFile, JsonTypedData, MsgpackFile, MsgpackTypedData};
+use super::{Compression, Def, Format, Id, JsonFile, JsonTypedData, MsgpackFile, MsgpackTypedData};
 
 pub(super) struct Plugin;
 
@@ -60,20 +61,28 @@ pub type StoreResult = Result<Vec<u8>, Error>;
 
 impl Command for StoreCommand {
     fn apply(self, world: &mut World) {
-        *world.resource_mut::<GlobalWriter>() = match self.format {
-            Format::Json => GlobalWriter::JsonWriter { data: Vec::new(), errs: Vec::new() },
-            Format::Msgpack => GlobalWriter::MsgpackWriter { data: Vec::new(), errs: Vec::new() },
-        };
-
-        world.run_schedule(Schedule::Store);
-        world.run_schedule(Schedule::PostStore);
+        let output = collect(world, self.format).and_then(Encoder::encode);
+        (self.on_complete)(world, output);
+    }
+}
 
-        let writer = mem::replace(&mut *world.resource_mut::<GlobalWriter>(), GlobalWriter::Uninit);
+/// Runs all store systems and collects their entries.
+///
+/// The returned encoder does not borrow the world,
+/// so the expensive encoding and compression can run on another thread.
+pub(super) fn collect(world: &mut World, format: Format) -> Result<Encoder, Error> {
+    *world.resource_mut::<GlobalWriter>() = match format {
+        Format::Json => GlobalWriter::JsonWriter { data: Vec::new(), errs: Vec::new() },
+        Format::Msgpack(compression) => {
+            GlobalWriter::MsgpackWriter { data: Vec::new(), errs: Vec::new(), compression }
+        }
+    };
 
-        let output = writer.output();
+    world.run_schedule(Schedule::Store);
+    world.run_schedule(Schedule::PostStore);
 
-        (self.on_complete)(world, output);
-    }
+    let writer = mem::replace(&mut *world.resource_mut::<GlobalWriter>(), GlobalWriter::Uninit);
+    writer.finish()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
@@ -141,7 +150,7 @@ pub struct Writer<'w, D: Def> {
     id_registry: ResMut<'w, IdRegistry<D>>,
 }
 
-impl<'w, D: Def> Writer<'w, D> {
+impl<D: Def> Writer<'_, D> {
     /// Writes a save entry to the output.
     ///
     /// `entity` is required for dependent types to resolve.
@@ -152,18 +161,24 @@ impl<'w, D: Def> Writer<'w, D> {
     }
 
     /// Writes an iterator of save entries to the output.
+    ///
+    /// Entries are sorted by their runtime value
+    /// so that saving the same world always produces the same output.
     pub fn write_all(&mut self, iter: impl IntoIterator<Item = (D::Runtime, D)>) {
         struct MutExtend<'a, T>(&'a mut T);
 
-        impl<'a, A, T: Extend<A>> Extend<A> for MutExtend<'a, T> {
+        impl<A, T: Extend<A>> Extend<A> for MutExtend<'_, T> {
             fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) { self.0.extend(iter) }
             // fn extend_one(&mut self, item: A) { self.0.extend_one(item) }
             // fn extend_reserve(&mut self, additional: usize) { self.0.extend_reserve(additional) }
         }
 
+        let mut entries: Vec<_> = iter.into_iter().collect();
+        entries.sort_by_key(|&(rt, _)| rt);
+
         let initial_save_id = self.write_buf.0.len();
 
-        let extend = iter::zip(iter, initial_save_id..)
+        let extend = iter::zip(entries, initial_save_id..)
             .map(|((entity, def), save_id)| (def, (entity, save_id)));
 
         (MutExtend(&mut self.write_buf.0), MutExtend(&mut *self.id_registry)).extend(extend);
@@ -197,7 +212,7 @@ pub struct Depend<'w, D: Def> {
     id_registry: Res<'w, IdRegistry<D>>,
 }
 
-impl<'w, D: Def> Depend<'w, D> {
+impl<D: Def> Depend<'_, D> {
     /// Gets the save ID of an entity.
     ///
     /// Returns `None` if this entity did not get saved as an instance of `D`.
@@ -258,8 +273,15 @@ struct StoreSystemSet(TypeId);
 #[derive(Resource)]
 enum GlobalWriter {
     Uninit,
-    JsonWriter { data: Vec<JsonTypedData>, errs: Vec<serde_json::Error> },
-    MsgpackWriter { data: Vec<MsgpackTypedData>, errs: Vec<rmp_serde::encode::Error> },
+    JsonWriter {
+        data: Vec<JsonTypedData>,
+        errs: Vec<serde_json::Error>,
+    },
+    MsgpackWriter {
+        data:        Vec<MsgpackTypedData>,
+        errs:        Vec<rmp_serde::encode::Error>,
+        compression: Compression,
+    },
 }
 
 impl GlobalWriter {
@@ -270,36 +292,67 @@ impl GlobalWriter {
                 Ok(defs) => data.push(JsonTypedData { r#type: D::TYPE.into(), defs }),
                 Err(err) => errs.push(err),
             },
-            Self::MsgpackWriter { data, errs } => match rmp_serde::to_vec_named(&objects) {
+            Self::MsgpackWriter { data, errs, .. } => match rmp_serde::to_vec_named(&objects) {
                 Ok(defs) => data.push(MsgpackTypedData { r#type: D::TYPE.into(), defs }),
                 Err(err) => errs.push(err),
             },
         }
     }
 
-    fn output(self) -> Result<Vec<u8>, Error> {
+    /// Completes the file, ordering types by name
+    /// since store systems of independent types run in arbitrary order.
+    fn finish(self) -> Result<Encoder, Error> {
         match self {
-            Self::Uninit => panic!("output should not be called when world is not saving"),
-            Self::JsonWriter { data, errs } => {
+            Self::Uninit => panic!("finish should not be called when world is not saving"),
+            Self::JsonWriter { mut data, errs } => {
+                data.sort_by(|a, b| a.r#type.cmp(&b.r#type));
                 if !errs.is_empty() {
                     return Err(Error::JsonDefToValue(errs));
                 }
-
-                let buf = serde_json::to_vec(&JsonFile { types: data })
-                    .map_err(Error::JsonEncodeValue)?;
-                Ok(buf)
+                Ok(Encoder::Json(JsonFile { types: data }))
             }
-            Self::MsgpackWriter { data, errs } => {
+            Self::MsgpackWriter { mut data, errs, compression } => {
+                data.sort_by(|a, b| a.r#type.cmp(&b.r#type));
                 if !errs.is_empty() {
                     return Err(Error::MsgpackEncodeDef(errs));
                 }
+                Ok(Encoder::Msgpack(MsgpackFile { types: data }, compression))
+            }
+        }
+    }
+}
+
+/// Collected save entries pending file-level encoding.
+pub(super) enum Encoder {
+    Json(JsonFile),
+    Msgpack(MsgpackFile, Compression),
+}
 
-                let mut buf = Vec::from(super::MSGPACK_HEADER);
-                rmp_serde::encode::write_named(
-                    &mut flate2::write::DeflateEncoder::new(&mut buf, flate2::Compression::best()),
-                    &MsgpackFile { types: data },
-                )
-                .map_err(Error::MsgpackEncodeFile)?;
+impl Encoder {
+    /// Encodes and compresses the save file.
+    pub(super) fn encode(self) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Json(file) => serde_json::to_vec(&file).map_err(Error::JsonEncodeValue),
+            Self::Msgpack(file, compression) => {
+                let raw = rmp_serde::to_vec_named(&file).map_err(Error::MsgpackEncodeFile)?;
+
+                let mut buf = Vec::from(super::MSGPACK_COMPRESSED_HEADER);
+                buf.push(compression.tag());
+                match compression {
+                    Compression::None => buf.extend_from_slice(&raw),
+                    Compression::Deflate { level } => {
+                        let mut encoder = flate2::write::DeflateEncoder::new(
+                            &mut buf,
+                            flate2::Compression::new(level),
+                        );
+                        encoder.write_all(&raw).map_err(Error::Compress)?;
+                        encoder.finish().map_err(Error::Compress)?;
+                    }
+                    Compression::Zstd { level } => {
+                        zstd::stream::copy_encode(&raw[..], &mut buf, level)
+                            .map_err(Error::Compress)?;
+                    }
+                }
 
                 Ok(buf)
             }
@@ -318,4 +371,6 @@ pub enum Error {
     MsgpackEncodeDef(Vec<rmp_serde::encode::Error>),
     #[error("producing msgpack file: {0}")]
     MsgpackEncodeFile(rmp_serde::encode::Error),
+    #[error("compressing save file: {0}")]
+    Compress(io::Error),
 }
@@ -1,6 +1,10 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
 use bevy::app::App;
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
 use bevy::ecs::system::Query;
 use bevy::ecs::world::{Command, World};
 use serde::{Deserialize, Serialize};
@@ -11,7 +15,13 @@ use crate::save;
 fn e2e_json() { e2e(save::Format::Json); }
 
 #[test]
-fn e2e_msgpack() { e2e(save::Format::Msgpack); }
+fn e2e_msgpack() { e2e(save::Format::Msgpack(save::Compression::None)); }
+
+#[test]
+fn e2e_msgpack_deflate() { e2e(save::Format::Msgpack(save::Compression::Deflate { level: 6 })); }
+
+#[test]
+fn e2e_msgpack_zstd() { e2e(save::Format::Msgpack(save::Compression::Zstd { level: 3 })); }
 
 fn e2e(format: save::Format) {
     fn init() -> App {
@@ -56,6 +66,121 @@ fn e2e(format: save::Format) {
     .apply(app.world_mut());
 }
 
+#[test]
+fn deferred_load() {
+    let mut app = App::new();
+    app.add_plugins(save::Plugin);
+    save::add_def::<Parent>(&mut app);
+    save::add_deferred_def::<Child>(&mut app);
+
+    let parent = app.world_mut().spawn((ParentName("Parent".into()),)).id();
+    app.world_mut().spawn((ChildParent(parent), ChildLabel("Child".into())));
+
+    let data = Arc::new(Mutex::new(None));
+    save::StoreCommand {
+        format:      save::Format::Json,
+        on_complete: Box::new({
+            let data = Arc::clone(&data);
+            move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+        }),
+    }
+    .apply(app.world_mut());
+    let data = data.lock().unwrap().take().unwrap();
+
+    let mut app = App::new();
+    app.add_plugins(save::Plugin);
+    save::add_def::<Parent>(&mut app);
+    save::add_deferred_def::<Child>(&mut app);
+
+    let completed = Arc::new(AtomicBool::new(false));
+    save::DeferredLoadCommand {
+        data,
+        on_ready: Box::new(|world, result| {
+            result.unwrap();
+
+            assert_eq!(world.query::<&ParentName>().single(world).0, "Parent");
+            assert_eq!(world.query::<&ChildLabel>().iter(world).count(), 0);
+        }),
+        on_complete: Box::new({
+            let completed = Arc::clone(&completed);
+            move |_, result| {
+                result.unwrap();
+                completed.store(true, Ordering::SeqCst);
+            }
+        }),
+    }
+    .apply(app.world_mut());
+    assert!(!completed.load(Ordering::SeqCst));
+
+    app.update();
+    assert!(completed.load(Ordering::SeqCst));
+
+    let world = app.world_mut();
+    let parent_entity = world.query_filtered::<Entity, With<ParentName>>().single(world);
+    let (child_parent, child_label) = world.query::<(&ChildParent, &ChildLabel)>().single(world);
+    assert_eq!(child_parent.0, parent_entity);
+    assert_eq!(child_label.0, "Child");
+}
+
+#[test]
+fn deterministic_json() { deterministic(save::Format::Json); }
+
+#[test]
+fn deterministic_msgpack() { deterministic(save::Format::Msgpack(save::Compression::None)); }
+
+fn deterministic(format: save::Format) {
+    fn init() -> App {
+        let mut app = App::new();
+        app.add_plugins(save::Plugin);
+        save::add_def::<Parent>(&mut app);
+        save::add_def::<Child>(&mut app);
+        app
+    }
+
+    fn store(world: &mut World, format: save::Format) -> Vec<u8> {
+        let data = Arc::new(Mutex::new(None));
+        save::StoreCommand {
+            format,
+            on_complete: Box::new({
+                let data = Arc::clone(&data);
+                move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+            }),
+        }
+        .apply(world);
+        Arc::into_inner(data).unwrap().into_inner().unwrap().unwrap()
+    }
+
+    let mut app = init();
+
+    let parents: Vec<_> =
+        (0..8).map(|i| app.world_mut().spawn(ParentName(format!("Parent {i}"))).id()).collect();
+    // despawn and respawn some parents so that query order differs from entity order
+    for &parent in parents.iter().step_by(3) {
+        app.world_mut().despawn(parent);
+    }
+    for i in 8..12 {
+        app.world_mut().spawn(ParentName(format!("Parent {i}")));
+    }
+    let parents: Vec<_> =
+        app.world_mut().query_filtered::<Entity, With<ParentName>>().iter(app.world()).collect();
+    for (i, &parent) in parents.iter().enumerate().rev() {
+        app.world_mut().spawn((ChildParent(parent), ChildLabel(format!("Child {i}"))));
+    }
+
+    let first = store(app.world_mut(), format);
+    let second = store(app.world_mut(), format);
+    assert_eq!(first, second, "saving the same world twice must produce identical output");
+
+    let mut app = init();
+    save::LoadCommand {
+        data:        first.clone(),
+        on_complete: Box::new(|_, result| result.unwrap()),
+    }
+    .apply(app.world_mut());
+    let reloaded = store(app.world_mut(), format);
+    assert_eq!(first, reloaded, "saving a loaded world must reproduce the loaded save");
+}
+
 #[derive(Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct Parent {
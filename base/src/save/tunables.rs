@@ -0,0 +1,115 @@
+//! Named scalar parameters that scenarios can override.
+//!
+//! Plugins declare each balance parameter as a [`Tunable`] constant with a default value,
+//! [register](register) it when the plugin is built,
+//! and read the current value through the [`Tunables`] resource.
+//! Scenarios override values by name through the [`Save`] entry,
+//! so balance can be adjusted without recompiling.
+
+use std::collections::BTreeMap;
+
+use bevy::app::{self, App};
+use bevy::ecs::system::{Res, Resource};
+use bevy::ecs::world::World;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tunables>();
+        save::add_def::<Save>(app);
+    }
+}
+
+/// Declares a tunable parameter.
+#[derive(Debug, Clone, Copy)]
+pub struct Tunable {
+    /// The name used in scenarios, namespaced by the declaring crate,
+    /// e.g. `fluid.pipe.transfer_rate`.
+    pub name:    &'static str,
+    /// The value used when the scenario does not override it.
+    pub default: f32,
+}
+
+/// Registers a tunable so that scenario overrides of it are recognized.
+pub fn register(app: &mut App, tunable: Tunable) {
+    let mut tunables = app.world_mut().get_resource_or_insert_with(Tunables::default);
+    tunables.known.insert(tunable.name, tunable.default);
+}
+
+/// The current values of all tunables.
+#[derive(Debug, Default, Resource)]
+pub struct Tunables {
+    /// Defaults of the registered tunables.
+    known:     BTreeMap<&'static str, f32>,
+    /// Values overridden by the scenario.
+    overrides: BTreeMap<String, f32>,
+}
+
+impl Tunables {
+    /// Returns the current value of a tunable.
+    #[must_use]
+    pub fn get(&self, tunable: Tunable) -> f32 {
+        self.overrides.get(tunable.name).copied().unwrap_or(tunable.default)
+    }
+
+    /// Overrides the value of a tunable.
+    pub fn set(&mut self, tunable: Tunable, value: f32) {
+        self.overrides.insert(tunable.name.to_string(), value);
+    }
+
+    /// Restores the default value of a tunable.
+    pub fn reset(&mut self, tunable: Tunable) { self.overrides.remove(tunable.name); }
+
+    /// Iterates over the registered tunables with their current values.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, f32)> + '_ {
+        self.known
+            .iter()
+            .map(|(&name, &default)| (name, self.overrides.get(name).copied().unwrap_or(default)))
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// Overridden values by tunable name.
+    pub values: BTreeMap<String, f32>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.Tunables";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(mut writer: save::Writer<Save>, (): (), tunables: Res<Tunables>) {
+            if !tunables.overrides.is_empty() {
+                writer.write((), Save { values: tunables.overrides.clone() });
+            }
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            let mut tunables = world.resource_mut::<Tunables>();
+            for (name, value) in def.values {
+                if !tunables.known.contains_key(name.as_str()) {
+                    // kept so that the value survives a round trip through this build
+                    bevy::log::warn!("scenario overrides unknown tunable {name:?}");
+                }
+                tunables.overrides.insert(name, value);
+            }
+
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,212 @@
+//! Long-running operations on background task pools.
+//!
+//! [`Tasks::spawn`] runs a future on the [IO](Pool::Io) or [compute](Pool::Compute) task pool
+//! and invokes its completion callback in the world once the future resolves.
+//! The future reports progress and observes cancellation through a shared [`Token`].
+//! Progress reports are forwarded as [`ProgressEvent`]s
+//! and completion is announced with a [`FinishedEvent`].
+//!
+//! Features with background work should use this module
+//! instead of polling their own [`Task`](bevy::tasks::Task) handles.
+
+use std::mem;
+use std::sync::atomic::{self, AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex};
+
+use bevy::app::{self, App};
+use bevy::ecs::event::Event;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::World;
+use bevy::tasks::{AsyncComputeTaskPool, IoTaskPool, TaskPool};
+use bevy::utils::ConditionalSendFuture;
+
+use crate::partition::{AppExt, EventWriterSystemSet};
+
+/// Polls pending tasks and forwards their progress.
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tasks>();
+        app.add_partitioned_event::<ProgressEvent>();
+        app.add_partitioned_event::<FinishedEvent>();
+        app.add_systems(
+            app::Update,
+            poll_tasks_system
+                .in_set(EventWriterSystemSet::<ProgressEvent>::default())
+                .in_set(EventWriterSystemSet::<FinishedEvent>::default()),
+        );
+    }
+}
+
+/// Identifies a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id(u64);
+
+/// A progress report of a task.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// Completed proportion in the range `[0, 1]`.
+    pub fraction: f32,
+    /// Description of the current step.
+    pub message:  String,
+}
+
+/// Shared state between a task and its owner.
+///
+/// Clones refer to the same task.
+#[derive(Clone)]
+pub struct Token(Arc<Shared>);
+
+struct Shared {
+    id:        Id,
+    cancelled: AtomicBool,
+    progress:  Mutex<Option<Progress>>,
+}
+
+impl Default for Token {
+    fn default() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        Self(Arc::new(Shared {
+            id:        Id(NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed)),
+            cancelled: AtomicBool::new(false),
+            progress:  Mutex::new(None),
+        }))
+    }
+}
+
+impl Token {
+    /// Creates a token for a new task.
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns the ID of the task.
+    #[must_use]
+    pub fn id(&self) -> Id { self.0.id }
+
+    /// Requests the task to stop.
+    ///
+    /// The task stops at its next cancellation check,
+    /// and its completion callback receives `None`.
+    pub fn cancel(&self) { self.0.cancelled.store(true, atomic::Ordering::Relaxed); }
+
+    /// Whether cancellation has been requested.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool { self.0.cancelled.load(atomic::Ordering::Relaxed) }
+
+    /// Reports the progress of the task.
+    ///
+    /// Only the latest report since the last update is forwarded as a [`ProgressEvent`].
+    pub fn report(&self, fraction: f32, message: impl Into<String>) {
+        *self.0.progress.lock().expect("poisoned mutex") =
+            Some(Progress { fraction, message: message.into() });
+    }
+}
+
+/// The task pool to run a task on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pool {
+    /// For tasks mostly waiting on files, dialogs or the network.
+    Io,
+    /// For CPU-bound tasks.
+    Compute,
+}
+
+/// Reports the progress of a task.
+#[derive(Debug, Event)]
+pub struct ProgressEvent {
+    /// The task.
+    pub id:       Id,
+    /// The latest progress report.
+    pub progress: Progress,
+}
+
+/// A task has completed and its callback has been invoked.
+#[derive(Debug, Event)]
+pub struct FinishedEvent {
+    /// The task.
+    pub id:        Id,
+    /// Whether the task was cancelled.
+    pub cancelled: bool,
+}
+
+type Callback = Box<dyn FnOnce(&mut World) + Send>;
+
+struct PendingTask {
+    token:  Token,
+    /// Set by the task on completion.
+    ///
+    /// The task handle itself is detached
+    /// because the single-threaded task pool cannot return a result.
+    output: Arc<Mutex<Option<(bool, Callback)>>>,
+}
+
+/// Background tasks that have not completed yet.
+#[derive(Default, Resource)]
+pub struct Tasks(Vec<PendingTask>);
+
+impl Tasks {
+    /// Runs a future on a background task pool.
+    ///
+    /// `on_complete` is invoked in the world with the output of the future,
+    /// or `None` if the task was cancelled through `token`.
+    pub fn spawn<T: Send + 'static>(
+        &mut self,
+        token: Token,
+        pool: Pool,
+        job: impl ConditionalSendFuture<Output = T> + 'static,
+        on_complete: impl FnOnce(&mut World, Option<T>) + Send + 'static,
+    ) {
+        let pool: &TaskPool = match pool {
+            Pool::Io => IoTaskPool::get_or_init(<_>::default),
+            Pool::Compute => AsyncComputeTaskPool::get_or_init(<_>::default),
+        };
+        let output = Arc::new(Mutex::new(None));
+        pool.spawn({
+            let token = token.clone();
+            let output = Arc::clone(&output);
+            async move {
+                let result = job.await;
+                let cancelled = token.is_cancelled();
+                let result = (!cancelled).then_some(result);
+                let callback: Callback = Box::new(move |world| on_complete(world, result));
+                *output.lock().expect("poisoned mutex") = Some((cancelled, callback));
+            }
+        })
+        .detach();
+
+        self.0.push(PendingTask { token, output });
+    }
+}
+
+fn poll_tasks_system(world: &mut World) {
+    let mut progress_events = Vec::new();
+    let mut completed = Vec::new();
+
+    world.resource_mut::<Tasks>().0.retain_mut(|pending| {
+        // take the output before draining progress so that no report pushed before completion is missed
+        let completion = pending.output.lock().expect("poisoned mutex").take();
+
+        let progress = mem::take(&mut *pending.token.0.progress.lock().expect("poisoned mutex"));
+        if let Some(progress) = progress {
+            progress_events.push(ProgressEvent { id: pending.token.id(), progress });
+        }
+
+        match completion {
+            Some((cancelled, callback)) => {
+                completed.push((pending.token.id(), cancelled, callback));
+                false
+            }
+            None => true,
+        }
+    });
+
+    world.send_event_batch(progress_events);
+
+    for (id, cancelled, callback) in completed {
+        callback(world);
+        world.send_event(FinishedEvent { id, cancelled });
+    }
+}
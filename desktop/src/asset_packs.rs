@@ -0,0 +1,196 @@
+//! Fetches the asset packs required by the loaded scenario.
+//!
+//! Packs are zip archives declared in [`AssetPacks`] by URL and SHA-256 digest.
+//! An archive is only extracted after its digest matches the declaration,
+//! into `asset-packs/<sha256>/` under the cache directory,
+//! so a pack shared by multiple scenarios is fetched once.
+//! `file://` URLs are read from the local filesystem.
+//!
+//! Files missing from the asset directory are looked up in the extracted packs,
+//! so scenarios reference pack contents with the same paths as bundled assets.
+//! Viewables shown before their pack is fetched
+//! render without the missing assets until they are shown again.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use bevy::app::{self, App};
+use bevy::asset::io::{
+    AssetReader, AssetReaderError, AssetSource, AssetSourceId, ErasedAssetReader, PathStream,
+    Reader, VecReader,
+};
+use bevy::asset::AssetApp;
+use bevy::ecs::schedule::common_conditions::resource_changed;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Res, ResMut, Resource};
+use bevy::utils::HashSet;
+use sha2::{Digest, Sha256};
+use traffloat_base::error::{self, Error};
+use traffloat_base::save::asset_packs::{AssetPacks, Pack};
+use traffloat_base::tasks;
+
+use crate::paths::Paths;
+
+/// Fetches asset packs when a scenario declares them.
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pending>();
+        app.add_systems(app::Update, fetch_packs_system.run_if(resource_changed::<AssetPacks>));
+    }
+}
+
+/// Subdirectory of the cache directory containing extracted packs.
+const PACKS_DIR: &str = "asset-packs";
+
+/// Upper bound on the size of a downloaded pack archive.
+const MAX_ARCHIVE_SIZE: u64 = 1 << 30;
+
+/// Replaces the default asset source with one that falls back to extracted packs.
+///
+/// Must be called before `AssetPlugin` is added.
+pub fn register_asset_source(app: &mut App, asset_dir: String, paths: &Paths) {
+    let packs_root = paths.cache.join(PACKS_DIR);
+    let mut base_reader = AssetSource::get_default_reader(asset_dir);
+
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSource::build().with_reader(move || {
+            Box::new(PackedAssetReader {
+                base:       base_reader(),
+                packs_root: packs_root.clone(),
+            })
+        }),
+    );
+}
+
+struct PackedAssetReader {
+    base:       Box<dyn ErasedAssetReader>,
+    packs_root: PathBuf,
+}
+
+impl PackedAssetReader {
+    fn find_in_packs(&self, path: &Path) -> Option<Vec<u8>> {
+        fs::read_dir(&self.packs_root)
+            .ok()?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_none()) // skip partial extractions
+            .find_map(|entry| fs::read(entry.path().join(path)).ok())
+    }
+}
+
+impl AssetReader for PackedAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<Box<Reader<'a>>, AssetReaderError> {
+        match self.base.read(path).await {
+            Err(AssetReaderError::NotFound(not_found)) => match self.find_in_packs(path) {
+                Some(bytes) => {
+                    let reader: Box<Reader<'a>> = Box::new(VecReader::new(bytes));
+                    Ok(reader)
+                }
+                None => Err(AssetReaderError::NotFound(not_found)),
+            },
+            result => result,
+        }
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<Box<Reader<'a>>, AssetReaderError> {
+        self.base.read_meta(path).await
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        self.base.read_directory(path).await
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        self.base.is_directory(path).await
+    }
+}
+
+/// Digests of packs being fetched.
+#[derive(Default, Resource)]
+struct Pending(HashSet<String>);
+
+fn fetch_packs_system(
+    packs: Res<AssetPacks>,
+    paths: Res<Paths>,
+    mut pending: ResMut<Pending>,
+    mut tasks: ResMut<tasks::Tasks>,
+) {
+    let root = paths.cache.join(PACKS_DIR);
+
+    for pack in &packs.packs {
+        if root.join(&pack.sha256).is_dir() || !pending.0.insert(pack.sha256.clone()) {
+            continue;
+        }
+
+        let pack = pack.clone();
+        let root = root.clone();
+        tasks.spawn(
+            tasks::Token::new(),
+            tasks::Pool::Io,
+            async move {
+                let result = fetch(&pack, &root);
+                (pack, result)
+            },
+            |world, output| {
+                let Some((pack, result)) = output else { return };
+                world.resource_mut::<Pending>().0.remove(&pack.sha256);
+
+                match result {
+                    Ok(()) => bevy::log::info!("fetched asset pack {}", pack.name),
+                    Err(err) => error::reject(
+                        world,
+                        Error::internal(
+                            "asset-pack.fetch",
+                            format!("Cannot fetch asset pack {}: {err}", pack.name),
+                        ),
+                    ),
+                }
+            },
+        );
+    }
+}
+
+fn fetch(pack: &Pack, root: &Path) -> Result<(), String> {
+    let bytes = download(&pack.url)?;
+
+    let digest = hex::encode(Sha256::digest(&bytes));
+    if digest != pack.sha256 {
+        return Err(format!("digest mismatch, expected {} but got {digest}", pack.sha256));
+    }
+
+    let partial = root.join(format!("{}.partial", pack.sha256));
+    if partial.exists() {
+        fs::remove_dir_all(&partial)
+            .map_err(|err| format!("cannot clean {}: {err}", partial.display()))?;
+    }
+    fs::create_dir_all(&partial)
+        .map_err(|err| format!("cannot create {}: {err}", partial.display()))?;
+
+    let mut archive = zip::ZipArchive::new(io::Cursor::new(bytes))
+        .map_err(|err| format!("invalid archive: {err}"))?;
+    archive.extract(&partial).map_err(|err| format!("cannot extract archive: {err}"))?;
+
+    fs::rename(&partial, root.join(&pack.sha256))
+        .map_err(|err| format!("cannot move extracted pack into cache: {err}"))
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return fs::read(path).map_err(|err| format!("cannot read {path}: {err}"));
+    }
+
+    let response = ureq::get(url).call().map_err(|err| format!("cannot download {url}: {err}"))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_ARCHIVE_SIZE)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("cannot download {url}: {err}"))?;
+    Ok(bytes)
+}
@@ -10,8 +10,11 @@ use bevy::winit::WinitSettings;
 use bevy_mod_picking::DefaultPickingPlugins;
 use options::Options;
 
+#[cfg(not(target_family = "wasm"))]
+mod asset_packs;
 mod main_menu;
 mod options;
+mod paths;
 mod util;
 mod view;
 
@@ -31,46 +34,93 @@ fn main() -> AppExit {
         }
     };
 
-    App::new()
-        .add_plugins((
-            bevy::DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        name: Some("Traffloat".into()),
-                        title: "Traffloat".into(),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                })
-                .set(AssetPlugin {
-                    file_path: if let Some(asset_dir) = options.asset_dir.to_str() {
-                        String::from(asset_dir)
-                    } else {
-                        eprintln!("Asset path is not UTF-8");
-                        return AppExit::error();
-                    },
+    let audit_schedules = options.audit_schedules;
+
+    let mut app = App::new();
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let paths = match paths::Paths::resolve(options.data_dir.as_deref())
+            .and_then(|paths| paths.create_saves_dir().map(|()| paths))
+        {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("{err}");
+                return AppExit::error();
+            }
+        };
+        asset_packs::register_asset_source(
+            &mut app,
+            options.asset_dir.to_string_lossy().into_owned(),
+            &paths,
+        );
+        app.insert_resource(paths);
+        app.add_plugins(asset_packs::Plugin);
+    }
+
+    app.add_plugins((
+        bevy::DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    name: Some("Traffloat".into()),
+                    title: "Traffloat".into(),
                     ..Default::default()
                 }),
-            DefaultPickingPlugins,
-            traffloat_base::save::Plugin,
-            traffloat_view::Plugin,
-            traffloat_graph::Plugin,
-            traffloat_fluid::Plugin(AppState::GameView),
-        ))
-        .insert_resource(options) // inserted the earliest to allow plugins to read during build
-        .init_resource::<WinitSettings>()
-        .init_state::<AppState>()
-        .add_plugins((
-            #[cfg(feature = "inspector")]
-            bevy_inspector_egui::quick::WorldInspectorPlugin::new(),
-        ))
-        .add_plugins(main_menu::Plugin)
-        .add_plugins(view::Plugin)
-        .edit_schedule(app::Update, |schedule| {
-            schedule.set_build_settings(ScheduleBuildSettings {
-                ambiguity_detection: schedule::LogLevel::Warn,
                 ..Default::default()
-            });
-        })
-        .run()
+            })
+            .set(AssetPlugin {
+                file_path: if let Some(asset_dir) = options.asset_dir.to_str() {
+                    String::from(asset_dir)
+                } else {
+                    eprintln!("Asset path is not UTF-8");
+                    return AppExit::error();
+                },
+                ..Default::default()
+            }),
+        DefaultPickingPlugins,
+        traffloat_base::save::Plugin,
+        traffloat_base::error::Plugin,
+        traffloat_base::debug::Plugin,
+        traffloat_view::Plugin,
+        traffloat_graph::Plugin,
+        traffloat_fluid::Plugin(AppState::GameView),
+    ))
+    .insert_resource(options) // inserted the earliest to allow plugins to read during build
+    .init_resource::<WinitSettings>()
+    .init_state::<AppState>()
+    .add_plugins((
+        #[cfg(feature = "inspector")]
+        bevy_inspector_egui::quick::WorldInspectorPlugin::new(),
+    ))
+    .add_plugins(util::ui_style::Plugin)
+    .add_plugins(main_menu::Plugin)
+    .add_plugins(view::Plugin)
+    .edit_schedule(app::Update, |schedule| {
+        schedule.set_build_settings(ScheduleBuildSettings {
+            ambiguity_detection: schedule::LogLevel::Warn,
+            ..Default::default()
+        });
+    });
+    if audit_schedules {
+        app.add_plugins(traffloat_base::debug::audit::Plugin);
+    }
+    #[cfg(feature = "chaos")]
+    {
+        use traffloat_base::debug::chaos;
+        app.add_plugins(chaos::Plugin);
+        chaos::intercept_perturbed::<traffloat_view::viewable::MoveEvent>(
+            &mut app,
+            |event, perturbation| {
+                let position = &mut event.transform.position;
+                for coord in [&mut position.x, &mut position.y, &mut position.z] {
+                    perturbation.apply(coord);
+                }
+            },
+        );
+        chaos::intercept_perturbed::<traffloat_view::metrics::UpdateMetricEvent>(
+            &mut app,
+            |event, perturbation| perturbation.apply(&mut event.magnitude),
+        );
+    }
+    app.run()
 }
@@ -1,7 +1,6 @@
 use std::time::Duration;
 
 use bevy::app::{self, App};
-use bevy::color::Color;
 use bevy::core_pipeline::core_2d::Camera2dBundle;
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
@@ -18,8 +17,10 @@ use bevy::winit::{self, WinitSettings};
 use traffloat_base::EventReaderSystemSet;
 
 use crate::util::button;
+use crate::util::ui_style::{ThemeColor, Themed};
 use crate::AppState;
 
+mod new_game;
 mod select_load;
 
 pub struct Plugin;
@@ -44,6 +45,7 @@ struct Owned;
 
 #[derive(Debug, Clone, Event)]
 enum ClickEvent {
+    NewGame,
     Load,
 }
 
@@ -64,9 +66,9 @@ fn setup(mut commands: Commands, mut winit_settings: ResMut<WinitSettings>) {
                     align_content: ui::AlignContent::Center,
                     ..Default::default()
                 },
-                background_color: ui::BackgroundColor(Color::hsl(0., 0., 0.05)),
                 ..Default::default()
             },
+            Themed::background(ThemeColor::Background),
             Owned,
         ))
         .with_children(|builder| {
@@ -80,54 +82,69 @@ fn setup(mut commands: Commands, mut winit_settings: ResMut<WinitSettings>) {
                     ..Default::default()
                 })
                 .with_children(|builder| {
-                    builder.spawn(TextBundle {
-                        text: Text::from_section(
-                            "Traffloat",
-                            TextStyle { font_size: 48., ..Default::default() },
-                        )
-                        .with_justify(JustifyText::Center),
-                        style: Style {
-                            bottom: ui::Val::Px(24.),
-                            justify_content: ui::JustifyContent::Center,
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    });
-                    builder.spawn(TextBundle {
-                        text: Text::from_section(
-                            traffloat_version::VERSION,
-                            TextStyle { font_size: 12., ..Default::default() },
-                        )
-                        .with_justify(JustifyText::Center),
-                        style: Style {
-                            bottom: ui::Val::Px(24.),
-                            justify_content: ui::JustifyContent::Center,
+                    builder.spawn((
+                        Themed::text(),
+                        TextBundle {
+                            text: Text::from_section(
+                                "Traffloat",
+                                TextStyle { font_size: 48., ..Default::default() },
+                            )
+                            .with_justify(JustifyText::Center),
+                            style: Style {
+                                bottom: ui::Val::Px(24.),
+                                justify_content: ui::JustifyContent::Center,
+                                ..Default::default()
+                            },
                             ..Default::default()
                         },
-                        ..Default::default()
-                    });
-                    builder.spawn(button::Bundle::new(ClickEvent::Load)).with_children(|builder| {
-                        builder.spawn(TextBundle {
-                            text: Text::from_section("Load", TextStyle::default())
-                                .with_justify(JustifyText::Center),
+                    ));
+                    builder.spawn((
+                        Themed::text(),
+                        TextBundle {
+                            text: Text::from_section(
+                                traffloat_version::VERSION,
+                                TextStyle { font_size: 12., ..Default::default() },
+                            )
+                            .with_justify(JustifyText::Center),
                             style: Style {
-                                width: ui::Val::Percent(100.),
+                                bottom: ui::Val::Px(24.),
                                 justify_content: ui::JustifyContent::Center,
                                 ..Default::default()
                             },
                             ..Default::default()
+                        },
+                    ));
+                    for (event, label) in
+                        [(ClickEvent::NewGame, "New game"), (ClickEvent::Load, "Load")]
+                    {
+                        builder.spawn(button::Bundle::new(event)).with_children(|builder| {
+                            builder.spawn((
+                                Themed::text(),
+                                TextBundle {
+                                    text: Text::from_section(label, TextStyle::default())
+                                        .with_justify(JustifyText::Center),
+                                    style: Style {
+                                        width: ui::Val::Percent(100.),
+                                        justify_content: ui::JustifyContent::Center,
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                            ));
                         });
-                    });
+                    }
                 });
         });
 }
 
 fn handle_click(
+    mut commands: Commands,
     mut events: EventReader<ClickEvent>,
     mut next_load_active_state: ResMut<NextState<select_load::ActiveState>>,
 ) {
     for event in events.read() {
         match event {
+            ClickEvent::NewGame => new_game::start(&mut commands),
             ClickEvent::Load => {
                 next_load_active_state.set(select_load::ActiveState::Active);
             }
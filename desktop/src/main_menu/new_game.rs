@@ -0,0 +1,48 @@
+use bevy::ecs::system::Commands;
+use bevy::ecs::world::Command;
+use bevy::state::state::NextState;
+use traffloat_base::save;
+
+use super::select_load::ErrorButtons;
+use crate::util::{modal, ui_style};
+use crate::AppState;
+
+/// Generates a new world with a random seed and loads it.
+pub(super) fn start(commands: &mut Commands) {
+    let seed = rand::random();
+    let params = traffloat_mapgen::Params::builder().seed(seed).build();
+
+    let data = match traffloat_mapgen::generate(&params) {
+        Ok(data) => data,
+        Err(err) => {
+            bevy::log::error!("map generation error: {err:?}");
+            commands.push(
+                modal::DisplayCommand::<ErrorButtons>::builder()
+                    .background_color(ui_style::ThemeColor::Error)
+                    .title("New game error")
+                    .text(err.to_string())
+                    .build(),
+            );
+            return;
+        }
+    };
+    bevy::log::info!("generated map from seed {seed} with {} bytes", data.len());
+
+    commands.push(save::LoadCommand {
+        data,
+        on_complete: Box::new(|world, result| match result {
+            Ok(()) => {
+                world.resource_mut::<NextState<AppState>>().set(AppState::GameView);
+            }
+            Err(err) => {
+                bevy::log::error!("load error: {err:?}");
+                modal::DisplayCommand::<ErrorButtons>::builder()
+                    .background_color(ui_style::ThemeColor::Error)
+                    .title("New game error")
+                    .text(err.to_string())
+                    .build()
+                    .apply(world);
+            }
+        }),
+    });
+}
@@ -2,16 +2,14 @@ use std::fs;
 use std::path::PathBuf;
 
 use bevy::app::{self, App};
-use bevy::ecs::schedule::IntoSystemConfigs;
-use bevy::ecs::system::{Commands, ResMut, Resource};
-use bevy::ecs::world::Command;
+use bevy::ecs::system::{Res, ResMut, Resource};
+use bevy::ecs::world::{Command, World};
 use bevy::state::app::AppExtStates;
-use bevy::state::condition::in_state;
 use bevy::state::state::{self, NextState, States};
-use bevy::tasks::{block_on, poll_once, IoTaskPool, Task};
-use traffloat_base::save;
+use traffloat_base::{save, tasks};
 
 use crate::options::Options;
+use crate::paths::Paths;
 use crate::util::{modal, ui_style};
 use crate::AppState;
 
@@ -36,59 +34,50 @@ impl app::Plugin for Plugin {
 
         app.add_plugins(modal::Plugin::<ErrorButtons>::default());
         app.add_systems(state::OnEnter(ActiveState::Active), setup);
-        app.add_systems(
-            app::Update,
-            poll_task.ambiguous_with(super::handle_click).run_if(in_state(ActiveState::Active)),
-        );
-        app.init_resource::<SelectFileTask>();
     }
 }
 
 #[derive(Resource)]
 struct PreSelectedFile(Option<PathBuf>);
 
-#[derive(Default, Resource)]
-struct SelectFileTask(Option<Task<Option<FileSelection>>>);
-
 struct FileSelection {
     path:     PathBuf,
     contents: std::io::Result<Vec<u8>>,
 }
 
-fn setup(mut task_res: ResMut<SelectFileTask>, mut pre_selected_file: ResMut<PreSelectedFile>) {
+fn setup(
+    mut tasks: ResMut<tasks::Tasks>,
+    mut pre_selected_file: ResMut<PreSelectedFile>,
+    paths: Option<Res<Paths>>,
+) {
     let pre_selected_file = pre_selected_file.0.take();
-    let pool = IoTaskPool::get_or_init(<_>::default);
-    let task = pool.spawn(async {
+    let saves_dir = paths.map(|paths| paths.saves.clone());
+    let job = async {
         if let Some(path) = pre_selected_file {
             let contents = fs::read(&path);
 
             Some(FileSelection { path, contents })
         } else {
-            let handle = rfd::AsyncFileDialog::new()
-                .add_filter("Traffloat save files", &["tfsave"])
-                .pick_file()
-                .await?;
+            let mut dialog =
+                rfd::AsyncFileDialog::new().add_filter("Traffloat save files", &["tfsave"]);
+            if let Some(saves_dir) = saves_dir {
+                dialog = dialog.set_directory(saves_dir);
+            }
+            let handle = dialog.pick_file().await?;
 
             let path = handle.path().to_path_buf();
             let contents = handle.read().await;
             Some(FileSelection { path, contents: Ok(contents) })
         }
+    };
+    tasks.spawn(tasks::Token::new(), tasks::Pool::Io, job, |world, result| {
+        on_selected(world, result.flatten());
     });
-    task_res.0 = Some(task);
 }
 
-fn poll_task(
-    mut task_res: ResMut<SelectFileTask>,
-    mut active_state: ResMut<NextState<ActiveState>>,
-    mut commands: Commands,
-) {
-    let Some(task) = task_res.0.as_mut() else { return };
-    let Some(result) = block_on(poll_once(task)) else { return };
-
-    task_res.0 = None;
-
+fn on_selected(world: &mut World, result: Option<FileSelection>) {
     let Some(result) = result else {
-        active_state.set(ActiveState::Inactive);
+        world.resource_mut::<NextState<ActiveState>>().set(ActiveState::Inactive);
         return;
     };
 
@@ -96,7 +85,20 @@ fn poll_task(
         Ok(contents) => {
             bevy::log::info!("loaded {:?} with {} bytes", result.path, contents.len());
 
-            commands.push(save::LoadCommand {
+            let contents = if traffloat_legacy::is_legacy(&contents) {
+                match traffloat_legacy::import(&contents) {
+                    Ok(imported) => imported,
+                    Err(err) => {
+                        bevy::log::error!("import error: {err:?}");
+                        display_error(world, err.to_string());
+                        return;
+                    }
+                }
+            } else {
+                contents
+            };
+
+            save::LoadCommand {
                 data:        contents,
                 on_complete: Box::new(|world, result| match result {
                     Ok(()) => {
@@ -104,33 +106,31 @@ fn poll_task(
                     }
                     Err(err) => {
                         bevy::log::error!("load error: {err:?}");
-                        world.resource_mut::<NextState<ActiveState>>().set(ActiveState::Inactive);
-                        modal::DisplayCommand::<ErrorButtons>::builder()
-                            .background_color(ui_style::ERROR_COLOR)
-                            .title("Load error")
-                            .text(err.to_string())
-                            .build()
-                            .apply(world);
+                        display_error(world, err.to_string());
                     }
                 }),
-            });
+            }
+            .apply(world);
         }
         Err(err) => {
             bevy::log::error!("read error: {err:?}");
-            active_state.set(ActiveState::Inactive);
-            commands.push(
-                modal::DisplayCommand::<ErrorButtons>::builder()
-                    .background_color(ui_style::ERROR_COLOR)
-                    .title("Load error")
-                    .text(format!("Error reading {}: {err}", result.path.display()))
-                    .build(),
-            );
+            display_error(world, format!("Error reading {}: {err}", result.path.display()));
         }
     }
 }
 
+fn display_error(world: &mut World, text: String) {
+    world.resource_mut::<NextState<ActiveState>>().set(ActiveState::Inactive);
+    modal::DisplayCommand::<ErrorButtons>::builder()
+        .background_color(ui_style::ThemeColor::Error)
+        .title("Load error")
+        .text(text)
+        .build()
+        .apply(world);
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct ErrorButtons;
+pub(super) struct ErrorButtons;
 
 impl modal::Buttons for ErrorButtons {
     fn iter() -> impl Iterator<Item = Self> { [Self].into_iter() }
@@ -5,9 +5,34 @@ use bevy::ecs::system::Resource;
 #[derive(clap::Parser, Resource, Default)]
 #[command(name = "traffloat", version = traffloat_version::VERSION, about)]
 pub struct Options {
-    pub save_file: Option<PathBuf>,
-    #[clap(long, default_value = "assets/")]
-    pub asset_dir: PathBuf,
+    pub save_file:       Option<PathBuf>,
+    #[clap(long, env = "TRAFFLOAT_ASSET_DIR", default_value = "assets/")]
+    pub asset_dir:       PathBuf,
+    /// Stores saves, configuration, mods, logs and caches under this directory
+    /// instead of the platform defaults.
+    #[clap(long, env = "TRAFFLOAT_DATA_DIR")]
+    pub data_dir:        Option<PathBuf>,
+    /// Panics at startup if the system ordering audit fails.
+    #[clap(long)]
+    pub audit_schedules: bool,
+    /// Volume of background music, from 0 to 1.
+    /// Overrides and replaces the remembered volume.
+    #[clap(long)]
+    pub music_volume:    Option<f32>,
+    /// Volume of alarms at hazardous buildings, from 0 to 1.
+    #[clap(long, default_value_t = 0.5)]
+    pub alarm_volume:    f32,
+    /// Whether to play background music tracks in random order.
+    /// Overrides and replaces the remembered setting.
+    #[clap(long)]
+    pub shuffle_music:   Option<bool>,
+    /// Language tag selecting number separators, e.g. `en-US` or `de-DE`.
+    #[clap(long, env = "LANG", default_value = "en")]
+    pub locale:          String,
+    /// Name of the UI theme, either built-in (`dark`, `light`, `high-contrast`)
+    /// or a `themes/<name>.json` file in the asset directory.
+    #[clap(long, env = "TRAFFLOAT_THEME")]
+    pub theme:           Option<String>,
 }
 
 impl Options {
@@ -0,0 +1,63 @@
+//! Standard locations of user files.
+//!
+//! By default, directories follow platform conventions,
+//! e.g. `$XDG_DATA_HOME/traffloat` on Linux,
+//! `~/Library/Application Support/traffloat` on macOS
+//! and `%APPDATA%\traffloat` on Windows.
+//! Passing `--data-dir` or setting `TRAFFLOAT_DATA_DIR` places all directories under one root instead,
+//! which is useful for portable installs and sandboxed packages.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::system::Resource;
+use directories::ProjectDirs;
+
+/// Directories for user files.
+#[allow(dead_code)] // not every directory has a consumer yet
+#[derive(Debug, Resource)]
+pub struct Paths {
+    /// Save files.
+    pub saves:  PathBuf,
+    /// User configuration.
+    pub config: PathBuf,
+    /// Installed mods.
+    pub mods:   PathBuf,
+    /// Log files.
+    pub logs:   PathBuf,
+    /// Files that can be regenerated.
+    pub cache:  PathBuf,
+}
+
+impl Paths {
+    /// Resolves the directories under `root`, or at the platform defaults if `root` is `None`.
+    pub fn resolve(root: Option<&Path>) -> Result<Self, String> {
+        if let Some(root) = root {
+            return Ok(Self {
+                saves:  root.join("saves"),
+                config: root.join("config"),
+                mods:   root.join("mods"),
+                logs:   root.join("logs"),
+                cache:  root.join("cache"),
+            });
+        }
+
+        let dirs = ProjectDirs::from("", "", "traffloat")
+            .ok_or_else(|| String::from("Cannot determine the home directory"))?;
+        Ok(Self {
+            saves:  dirs.data_dir().join("saves"),
+            config: dirs.config_dir().to_path_buf(),
+            mods:   dirs.data_dir().join("mods"),
+            logs:   dirs.data_local_dir().join("logs"),
+            cache:  dirs.cache_dir().to_path_buf(),
+        })
+    }
+
+    /// Creates the save directory if it does not exist.
+    ///
+    /// Other directories are created when they are first written to.
+    pub fn create_saves_dir(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.saves)
+            .map_err(|err| format!("Cannot create save directory {}: {err}", self.saves.display()))
+    }
+}
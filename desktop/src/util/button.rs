@@ -4,18 +4,19 @@ use std::marker::PhantomData;
 use std::mem;
 
 use bevy::app::{self, App};
-use bevy::color::Color;
 use bevy::ecs::bundle;
 use bevy::ecs::component::Component;
 use bevy::ecs::event::{Event, EventWriter};
-use bevy::ecs::query::Changed;
 use bevy::ecs::schedule::{IntoSystemConfigs, SystemSet};
-use bevy::ecs::system::Query;
+use bevy::ecs::system::{Query, Res};
+use bevy::ecs::world::Ref;
 use bevy::ui;
 use bevy::ui::node_bundles::ButtonBundle;
 use traffloat_base::partition::AppExt;
 use traffloat_base::EventWriterSystemSet;
 
+use crate::util::ui_style::{Theme, ThemeColor};
+
 pub struct Plugin<E>(PhantomData<fn() -> E>);
 
 impl<E> Default for Plugin<E> {
@@ -58,27 +59,34 @@ impl<E> Hash for HandleClickSystemSet<E> {
 pub struct LastInteraction(ui::Interaction);
 
 fn handle_buttons<E: Event + Clone>(
-    mut query: Query<
-        (&ui::Interaction, &mut ui::BackgroundColor, &mut LastInteraction, &OnClick<E>),
-        Changed<ui::Interaction>,
-    >,
+    theme: Res<Theme>,
+    mut query: Query<(
+        Ref<ui::Interaction>,
+        &mut ui::BackgroundColor,
+        &mut LastInteraction,
+        &OnClick<E>,
+    )>,
     mut event_writer: EventWriter<E>,
 ) {
     query.iter_mut().for_each(|(interaction, mut bg_color, mut last_interaction, on_click)| {
+        if !interaction.is_changed() && !theme.is_changed() {
+            return;
+        }
+
         let last_interaction = mem::replace(&mut last_interaction.0, *interaction);
 
         match *interaction {
             ui::Interaction::None => {
-                bg_color.0 = BUTTON_COLOR_IDLE;
+                bg_color.0 = theme.get(ThemeColor::ButtonIdle);
             }
             ui::Interaction::Hovered => {
-                bg_color.0 = BUTTON_COLOR_HOVER;
+                bg_color.0 = theme.get(ThemeColor::ButtonHover);
                 if let ui::Interaction::Pressed = last_interaction {
                     event_writer.send(on_click.0.clone());
                 }
             }
             ui::Interaction::Pressed => {
-                bg_color.0 = BUTTON_COLOR_PRESSED;
+                bg_color.0 = theme.get(ThemeColor::ButtonPressed);
             }
         }
     });
@@ -100,7 +108,3 @@ impl<E: Event> Bundle<E> {
         }
     }
 }
-
-const BUTTON_COLOR_IDLE: Color = Color::hsl(0., 0., 0.2);
-const BUTTON_COLOR_HOVER: Color = Color::hsl(0., 0., 0.4);
-const BUTTON_COLOR_PRESSED: Color = Color::hsl(0., 0., 0.6);
@@ -2,7 +2,6 @@ use core::fmt;
 use std::marker::PhantomData;
 
 use bevy::app::{self, App};
-use bevy::color::Color;
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::event::{Event, EventReader};
@@ -15,11 +14,12 @@ use bevy::state::app::AppExtStates;
 use bevy::state::state::{self, NextState};
 use bevy::text::{JustifyText, Text, TextStyle};
 use bevy::ui::node_bundles::{ButtonBundle, NodeBundle, TextBundle};
-use bevy::ui::{self, BackgroundColor, Style, UiRect};
+use bevy::ui::{self, Style, UiRect};
 use traffloat_base::{generic_state, EventReaderSystemSet};
 use typed_builder::TypedBuilder;
 
 use crate::util::button;
+use crate::util::ui_style::{ThemeColor, Themed};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Active {
@@ -33,8 +33,7 @@ generic_state!(pub ActiveState<But: Buttons>(Active));
 /// Displays a modal dialog.
 #[derive(TypedBuilder)]
 pub struct DisplayCommand<But: Buttons> {
-    #[builder(setter(into))]
-    background_color: Color,
+    background_color: ThemeColor,
     #[builder(setter(into))]
     title:            String,
     #[builder(setter(into))]
@@ -55,7 +54,7 @@ impl<But: Buttons> Command for DisplayCommand<But> {
 
 #[derive(Resource)]
 struct Param<But> {
-    background_color: Color,
+    background_color: ThemeColor,
     title:            String,
     text:             String,
     _ph:              PhantomData<fn(But) -> But>,
@@ -64,7 +63,7 @@ struct Param<But> {
 impl<But> Default for Param<But> {
     fn default() -> Self {
         Self {
-            background_color: <_>::default(),
+            background_color: ThemeColor::Panel,
             title:            <_>::default(),
             text:             <_>::default(),
             _ph:              PhantomData,
@@ -129,41 +128,49 @@ fn setup<But: Buttons>(mut commands: Commands, param: Res<Param<But>>) {
         ))
         .with_children(|builder| {
             builder
-                .spawn(NodeBundle {
-                    background_color: BackgroundColor(param.background_color),
-                    style: Style {
-                        justify_content: ui::JustifyContent::Center,
-                        justify_items: ui::JustifyItems::Center,
-                        align_content: ui::AlignContent::Center,
-                        align_items: ui::AlignItems::Center,
-                        flex_direction: ui::FlexDirection::Column,
-                        width: ui::Val::Percent(50.),
-                        height: ui::Val::Percent(30.),
-                        padding: UiRect::axes(ui::Val::Px(30.), ui::Val::Px(0.)),
+                .spawn((
+                    Themed::background(param.background_color),
+                    NodeBundle {
+                        style: Style {
+                            justify_content: ui::JustifyContent::Center,
+                            justify_items: ui::JustifyItems::Center,
+                            align_content: ui::AlignContent::Center,
+                            align_items: ui::AlignItems::Center,
+                            flex_direction: ui::FlexDirection::Column,
+                            width: ui::Val::Percent(50.),
+                            height: ui::Val::Percent(30.),
+                            padding: UiRect::axes(ui::Val::Px(30.), ui::Val::Px(0.)),
+                            ..Default::default()
+                        },
                         ..Default::default()
                     },
-                    ..Default::default()
-                })
+                ))
                 .with_children(|builder| {
-                    builder.spawn(TextBundle {
-                        text: Text::from_section(
-                            &param.title,
-                            TextStyle { font_size: 32., ..Default::default() },
-                        ),
-                        style: Style {
-                            margin: UiRect {
-                                bottom: ui::Val::Px(50.),
-                                ..UiRect::all(ui::Val::Px(0.))
+                    builder.spawn((
+                        Themed::text(),
+                        TextBundle {
+                            text: Text::from_section(
+                                &param.title,
+                                TextStyle { font_size: 32., ..Default::default() },
+                            ),
+                            style: Style {
+                                margin: UiRect {
+                                    bottom: ui::Val::Px(50.),
+                                    ..UiRect::all(ui::Val::Px(0.))
+                                },
+                                ..Default::default()
                             },
                             ..Default::default()
                         },
-                        ..Default::default()
-                    });
-                    builder.spawn(TextBundle {
-                        text: Text::from_section(&param.text, TextStyle::default()),
-                        style: Style { align_self: ui::AlignSelf::Start, ..Default::default() },
-                        ..Default::default()
-                    });
+                    ));
+                    builder.spawn((
+                        Themed::text(),
+                        TextBundle {
+                            text: Text::from_section(&param.text, TextStyle::default()),
+                            style: Style { align_self: ui::AlignSelf::Start, ..Default::default() },
+                            ..Default::default()
+                        },
+                    ));
 
                     for button in But::iter() {
                         builder
@@ -178,16 +185,22 @@ fn setup<But: Buttons>(mut commands: Commands, param: Res<Param<But>>) {
                                 ..button::Bundle::new(ClickEvent(button))
                             })
                             .with_children(|builder| {
-                                builder.spawn(TextBundle {
-                                    text: Text::from_section(button.label(), TextStyle::default())
+                                builder.spawn((
+                                    Themed::text(),
+                                    TextBundle {
+                                        text: Text::from_section(
+                                            button.label(),
+                                            TextStyle::default(),
+                                        )
                                         .with_justify(JustifyText::Center),
-                                    style: Style {
-                                        width: ui::Val::Percent(100.),
-                                        justify_content: ui::JustifyContent::Center,
+                                        style: Style {
+                                            width: ui::Val::Percent(100.),
+                                            justify_content: ui::JustifyContent::Center,
+                                            ..Default::default()
+                                        },
                                         ..Default::default()
                                     },
-                                    ..Default::default()
-                                });
+                                ));
                             });
                     }
                 });
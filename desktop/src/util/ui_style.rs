@@ -1,3 +1,297 @@
-use bevy::color::Color;
+//! Colors of client UI elements.
+//!
+//! UI plugins take colors from the active [`Theme`] instead of hardcoding them.
+//! Nodes with a [`Themed`] component are recolored whenever the theme changes,
+//! so F9 switches between the built-in themes live.
+//!
+//! The built-in themes are `dark`, `light` and `high-contrast`.
+//! Mods can supply further themes as `themes/<name>.json` in the asset directory,
+//! overriding individual [colors](ThemeColor) of a built-in base theme.
+//! The theme is selected with `--theme`,
+//! or restored from the config directory where the last switched theme is remembered.
 
-pub const ERROR_COLOR: Color = Color::srgb(0.4, 0.1, 0.1);
+use std::fs;
+use std::path::Path;
+
+use bevy::app::{self, App};
+use bevy::color::{Color, Srgba};
+use bevy::ecs::component::Component;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Query, Res, ResMut, Resource};
+use bevy::ecs::world::Ref;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::input::keyboard::KeyCode;
+use bevy::text::Text;
+use bevy::ui;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::options::Options;
+use crate::paths::Paths;
+
+/// Maintains the active theme.
+pub struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let world = app.world();
+        let options = world.resource::<Options>();
+        let saved = world.get_resource::<Paths>().and_then(|paths| read_saved_theme(paths));
+        let name = options.theme.clone().or(saved);
+
+        let theme = match name {
+            Some(name) => Theme::load(&options.asset_dir, &name).unwrap_or_else(|err| {
+                bevy::log::error!("cannot load theme {name}: {err}");
+                Theme::dark()
+            }),
+            None => Theme::dark(),
+        };
+        app.insert_resource(theme);
+
+        app.add_systems(
+            app::Update,
+            (cycle_theme_system.run_if(input_just_pressed(KeyCode::F9)), apply_theme_system)
+                .chain(),
+        );
+    }
+}
+
+/// A named color role in a theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThemeColor {
+    /// Full-screen backgrounds such as the main menu.
+    Background,
+    /// Floating panels such as the infobox and the search palette.
+    Panel,
+    /// Borders of floating panels.
+    PanelBorder,
+    /// Text on backgrounds, panels and buttons.
+    Text,
+    /// Buttons without interaction.
+    ButtonIdle,
+    /// Hovered buttons.
+    ButtonHover,
+    /// Pressed buttons.
+    ButtonPressed,
+    /// Error dialogs.
+    Error,
+}
+
+/// The colors of client UI elements.
+#[derive(Debug, Clone, Resource)]
+pub struct Theme {
+    /// The name of the theme.
+    pub name:           String,
+    /// See [`ThemeColor::Background`].
+    pub background:     Color,
+    /// See [`ThemeColor::Panel`].
+    pub panel:          Color,
+    /// See [`ThemeColor::PanelBorder`].
+    pub panel_border:   Color,
+    /// See [`ThemeColor::Text`].
+    pub text:           Color,
+    /// See [`ThemeColor::ButtonIdle`].
+    pub button_idle:    Color,
+    /// See [`ThemeColor::ButtonHover`].
+    pub button_hover:   Color,
+    /// See [`ThemeColor::ButtonPressed`].
+    pub button_pressed: Color,
+    /// See [`ThemeColor::Error`].
+    pub error:          Color,
+}
+
+const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "high-contrast"];
+
+impl Theme {
+    /// Returns the color for a role.
+    pub fn get(&self, color: ThemeColor) -> Color {
+        match color {
+            ThemeColor::Background => self.background,
+            ThemeColor::Panel => self.panel,
+            ThemeColor::PanelBorder => self.panel_border,
+            ThemeColor::Text => self.text,
+            ThemeColor::ButtonIdle => self.button_idle,
+            ThemeColor::ButtonHover => self.button_hover,
+            ThemeColor::ButtonPressed => self.button_pressed,
+            ThemeColor::Error => self.error,
+        }
+    }
+
+    fn get_mut(&mut self, color: ThemeColor) -> &mut Color {
+        match color {
+            ThemeColor::Background => &mut self.background,
+            ThemeColor::Panel => &mut self.panel,
+            ThemeColor::PanelBorder => &mut self.panel_border,
+            ThemeColor::Text => &mut self.text,
+            ThemeColor::ButtonIdle => &mut self.button_idle,
+            ThemeColor::ButtonHover => &mut self.button_hover,
+            ThemeColor::ButtonPressed => &mut self.button_pressed,
+            ThemeColor::Error => &mut self.error,
+        }
+    }
+
+    fn dark() -> Self {
+        Self {
+            name:           "dark".into(),
+            background:     Color::hsl(0., 0., 0.05),
+            panel:          Color::linear_rgb(0.05, 0.05, 0.15),
+            panel_border:   Color::linear_rgb(0.8, 0.6, 0.2),
+            text:           Color::WHITE,
+            button_idle:    Color::hsl(0., 0., 0.2),
+            button_hover:   Color::hsl(0., 0., 0.4),
+            button_pressed: Color::hsl(0., 0., 0.6),
+            error:          Color::srgb(0.4, 0.1, 0.1),
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            name:           "light".into(),
+            background:     Color::hsl(0., 0., 0.92),
+            panel:          Color::srgb(0.95, 0.95, 0.98),
+            panel_border:   Color::srgb(0.6, 0.45, 0.1),
+            text:           Color::hsl(0., 0., 0.1),
+            button_idle:    Color::hsl(0., 0., 0.8),
+            button_hover:   Color::hsl(0., 0., 0.7),
+            button_pressed: Color::hsl(0., 0., 0.6),
+            error:          Color::srgb(0.95, 0.75, 0.75),
+        }
+    }
+
+    fn high_contrast() -> Self {
+        Self {
+            name:           "high-contrast".into(),
+            background:     Color::BLACK,
+            panel:          Color::BLACK,
+            panel_border:   Color::srgb(1., 1., 0.),
+            text:           Color::WHITE,
+            button_idle:    Color::BLACK,
+            button_hover:   Color::srgb(0., 0., 0.6),
+            button_pressed: Color::srgb(0., 0., 1.),
+            error:          Color::srgb(0.6, 0., 0.),
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Loads a built-in theme or a theme file from the asset directory.
+    fn load(asset_dir: &Path, name: &str) -> Result<Self, String> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+
+        let path = asset_dir.join("themes").join(format!("{name}.json"));
+        let bytes = fs::read(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let file: ThemeFile =
+            serde_json::from_slice(&bytes).map_err(|err| format!("{}: {err}", path.display()))?;
+
+        let mut theme = Self::builtin(&file.base)
+            .ok_or_else(|| format!("unknown base theme {:?}", file.base))?;
+        theme.name = name.into();
+        for (color, [r, g, b, a]) in file.colors {
+            *theme.get_mut(color) = Srgba::new(r, g, b, a).into();
+        }
+        Ok(theme)
+    }
+}
+
+/// A mod-supplied theme.
+#[derive(Deserialize)]
+struct ThemeFile {
+    /// The built-in theme providing colors not listed in `colors`.
+    #[serde(default = "default_base")]
+    base:   String,
+    /// sRGBA components of overridden colors.
+    #[serde(default)]
+    colors: HashMap<ThemeColor, [f32; 4]>,
+}
+
+fn default_base() -> String { "dark".into() }
+
+/// Colors a UI node from the active theme.
+#[derive(Component, Default)]
+pub struct Themed {
+    /// Sets the [`ui::BackgroundColor`] of the node.
+    pub background: Option<ThemeColor>,
+    /// Sets the [`ui::BorderColor`] of the node.
+    pub border:     Option<ThemeColor>,
+    /// Sets the color of all sections of the [`Text`] of the node.
+    pub text:       Option<ThemeColor>,
+}
+
+impl Themed {
+    /// Colors a node with a background.
+    pub fn background(color: ThemeColor) -> Self {
+        Self { background: Some(color), ..Default::default() }
+    }
+
+    /// Colors a floating panel.
+    pub fn panel() -> Self {
+        Self {
+            background: Some(ThemeColor::Panel),
+            border: Some(ThemeColor::PanelBorder),
+            ..Default::default()
+        }
+    }
+
+    /// Colors a text node.
+    pub fn text() -> Self { Self { text: Some(ThemeColor::Text), ..Default::default() } }
+}
+
+const THEME_FILE: &str = "theme";
+
+fn read_saved_theme(paths: &Paths) -> Option<String> {
+    let name = fs::read_to_string(paths.config.join(THEME_FILE)).ok()?;
+    Some(name.trim().to_owned()).filter(|name| !name.is_empty())
+}
+
+fn cycle_theme_system(mut theme: ResMut<Theme>, paths: Option<Res<Paths>>) {
+    let index = BUILTIN_THEMES.iter().position(|&name| name == theme.name).map_or(0, |i| i + 1);
+    let name = BUILTIN_THEMES[index % BUILTIN_THEMES.len()];
+    *theme = Theme::builtin(name).expect("BUILTIN_THEMES only lists built-in themes");
+
+    if let Some(paths) = paths {
+        let result = fs::create_dir_all(&paths.config)
+            .and_then(|()| fs::write(paths.config.join(THEME_FILE), name));
+        if let Err(err) = result {
+            bevy::log::warn!("cannot remember theme: {err}");
+        }
+    }
+}
+
+fn apply_theme_system(
+    theme: Res<Theme>,
+    mut query: Query<(
+        Ref<Themed>,
+        Option<&mut ui::BackgroundColor>,
+        Option<&mut ui::BorderColor>,
+        Option<&mut Text>,
+    )>,
+) {
+    let theme_changed = theme.is_changed();
+    for (themed, background, border, text) in &mut query {
+        if !theme_changed && !themed.is_added() {
+            continue;
+        }
+
+        if let (Some(color), Some(mut background)) = (themed.background, background) {
+            background.0 = theme.get(color);
+        }
+        if let (Some(color), Some(mut border)) = (themed.border, border) {
+            border.0 = theme.get(color);
+        }
+        if let (Some(color), Some(mut text)) = (themed.text, text) {
+            for section in &mut text.sections {
+                section.style.color = theme.get(color);
+            }
+        }
+    }
+}
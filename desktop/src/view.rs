@@ -5,24 +5,45 @@ use bevy::ecs::query::With;
 use bevy::ecs::schedule::SystemSet;
 use bevy::ecs::system::{Commands, Query, Res, ResMut};
 use bevy::hierarchy::DespawnRecursiveExt;
-use bevy::state::state;
+use bevy::state::app::AppExtStates;
+use bevy::state::state::{self, SubStates};
 use bevy::transform::components::Transform;
 use bevy::winit::WinitSettings;
+use traffloat_view::format::{self, Locale};
 use traffloat_view::viewer;
 
+use crate::options::Options;
 use crate::AppState;
 
 // mod background;
+mod alarm;
+#[cfg(feature = "dev")]
+mod allocations;
 mod camera;
 mod delegate;
 mod diagnostics;
+mod music;
 mod object;
 
 pub(crate) struct Plugin;
 
 impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((diagnostics::Plugin, camera::Plugin, object::Plugin));
+        app.add_sub_state::<ViewMode>();
+        let locale = Locale::from_tag(&app.world().resource::<Options>().locale);
+        app.insert_resource(format::Formatter {
+            locale: locale.unwrap_or_default(),
+            ..Default::default()
+        });
+        app.add_plugins((
+            alarm::Plugin,
+            diagnostics::Plugin,
+            camera::Plugin,
+            music::Plugin,
+            object::Plugin,
+        ));
+        #[cfg(feature = "dev")]
+        app.add_plugins(allocations::Plugin);
 
         app.add_systems(state::OnEnter(AppState::GameView), setup_singleplayer_server);
         app.add_systems(state::OnEnter(AppState::GameView), setup_view);
@@ -36,6 +57,17 @@ struct Owned;
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
 struct InputSystemSet;
 
+/// How the camera is controlled in the game view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, SubStates)]
+#[source(AppState = AppState::GameView)]
+enum ViewMode {
+    /// The camera flies freely around the station.
+    #[default]
+    Orbit,
+    /// The camera walks inside a building, see [`object::walk`].
+    Walk,
+}
+
 fn setup_singleplayer_server(mut commands: Commands, viewer_ids: Res<viewer::SidIndex>) {
     commands.spawn((
         Owned,
@@ -0,0 +1,176 @@
+//! Alarm sounds at hazardous buildings.
+//!
+//! An alarm loops at each building with a facility whose [hazard score](Hazard)
+//! reaches [`ALARM_HAZARD`].
+//! Alarms are spatial, so they are panned towards the building relative to the camera.
+//! Hull structure between the camera and the alarm is approximated by
+//! the number of corridors on the shortest path from the building nearest to the camera,
+//! each of which attenuates the alarm by [`OCCLUSION_PER_HOP`].
+//! Sound does not carry to buildings disconnected from the nearest building.
+//!
+//! The alarm sound is read from [`ALARM_PATH`] in the asset directory if present.
+//!
+//! This module reads the simulation world directly
+//! and is only available in single-player sessions.
+
+use std::collections::VecDeque;
+
+use bevy::app::{self, App};
+use bevy::asset::{AssetServer, Handle};
+use bevy::audio::{
+    AudioBundle, AudioSinkPlayback, AudioSource, PlaybackSettings, SpatialAudioSink, Volume,
+};
+use bevy::core_pipeline::core_3d::Camera3d;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::{Entity, EntityHashMap};
+use bevy::ecs::query::{With, Without};
+use bevy::ecs::system::{Commands, Query, Res, Resource};
+use bevy::hierarchy::DespawnRecursiveExt;
+use bevy::math::Vec3;
+use bevy::state::condition::in_state;
+use bevy::state::state;
+use bevy::transform::components::{GlobalTransform, Transform};
+use bevy::transform::TransformBundle;
+use traffloat_base::debug;
+use traffloat_fluid::hazard::Hazard;
+use traffloat_graph::{building, corridor};
+
+use crate::options::Options;
+use crate::view::Owned;
+use crate::AppState;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(state::OnExit(AppState::GameView), teardown);
+        app.add_systems(app::Update, update_alarms_system.run_if(in_state(AppState::GameView)));
+    }
+}
+
+/// The hazard score at which a building sounds an alarm.
+const ALARM_HAZARD: f32 = 0.5;
+
+/// Volume multiplier for each corridor between the camera and an alarm.
+const OCCLUSION_PER_HOP: f32 = 0.5;
+
+/// Path of the alarm sound relative to the asset directory.
+const ALARM_PATH: &str = "sounds/alarm.ogg";
+
+#[derive(Resource)]
+struct AlarmSound(Handle<AudioSource>);
+
+/// A playing alarm.
+#[derive(Component)]
+struct Alarm {
+    building: Entity,
+}
+
+fn setup(mut commands: Commands, options: Res<Options>, asset_server: Res<AssetServer>) {
+    if !options.asset_dir.join(ALARM_PATH).exists() {
+        bevy::log::info!("no alarm sound at {ALARM_PATH}");
+        return;
+    }
+
+    commands.insert_resource(AlarmSound(asset_server.load(ALARM_PATH)));
+}
+
+fn teardown(mut commands: Commands) { commands.remove_resource::<AlarmSound>(); }
+
+fn update_alarms_system(
+    sound: Option<Res<AlarmSound>>,
+    options: Res<Options>,
+    building_query: Query<(Entity, &building::FacilityList, &Transform), With<building::Marker>>,
+    hazard_query: Query<&Hazard>,
+    corridor_query: Query<&corridor::Endpoints>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    mut alarm_query: Query<
+        (Entity, &Alarm, &mut Transform, Option<&SpatialAudioSink>),
+        Without<building::Marker>,
+    >,
+    mut commands: Commands,
+) {
+    let Some(sound) = sound else { return };
+
+    let mut alarming: EntityHashMap<Vec3> = building_query
+        .iter()
+        .filter(|(_, facilities, _)| {
+            facilities.iter().any(|facility| {
+                hazard_query.get(facility).is_ok_and(|hazard| hazard.score >= ALARM_HAZARD)
+            })
+        })
+        .map(|(building, _, transform)| (building, transform.translation))
+        .collect();
+
+    if alarming.is_empty() && alarm_query.is_empty() {
+        return;
+    }
+
+    let hops = camera_query
+        .get_single()
+        .ok()
+        .and_then(|camera| {
+            let camera = camera.translation();
+            let (nearest, _, _) = building_query.iter().min_by(|(_, _, a), (_, _, b)| {
+                a.translation
+                    .distance_squared(camera)
+                    .total_cmp(&b.translation.distance_squared(camera))
+            })?;
+            Some(hops_from(nearest, &corridor_query))
+        })
+        .unwrap_or_default();
+
+    for (entity, alarm, mut transform, sink) in &mut alarm_query {
+        let Some(position) = alarming.remove(&alarm.building) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        transform.translation = position;
+        if let Some(sink) = sink {
+            let volume = hops
+                .get(&alarm.building)
+                .map_or(0., |&hops| options.alarm_volume * OCCLUSION_PER_HOP.powi(hops));
+            sink.set_volume(volume);
+        }
+    }
+
+    for (building, position) in alarming {
+        commands.spawn((
+            Owned,
+            Alarm { building },
+            AudioBundle {
+                source:   sound.0.clone(),
+                // volume is set once the sink is created
+                settings: PlaybackSettings::LOOP.with_spatial(true).with_volume(Volume::new(0.)),
+            },
+            TransformBundle::from_transform(Transform::from_translation(position)),
+            debug::Bundle::new("Alarm"),
+        ));
+    }
+}
+
+/// Computes the number of corridors on the shortest path from `origin` to each reachable building.
+fn hops_from(origin: Entity, corridor_query: &Query<&corridor::Endpoints>) -> EntityHashMap<i32> {
+    let mut adjacency = EntityHashMap::<Vec<Entity>>::default();
+    for endpoints in corridor_query {
+        let (alpha, beta) = (endpoints.endpoints.alpha, endpoints.endpoints.beta);
+        adjacency.entry(alpha).or_default().push(beta);
+        adjacency.entry(beta).or_default().push(alpha);
+    }
+
+    let mut hops = EntityHashMap::default();
+    hops.insert(origin, 0);
+    let mut queue = VecDeque::from([origin]);
+    while let Some(building) = queue.pop_front() {
+        let next = hops[&building] + 1;
+        for &neighbor in adjacency.get(&building).into_iter().flatten() {
+            if !hops.contains_key(&neighbor) {
+                hops.insert(neighbor, next);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    hops
+}
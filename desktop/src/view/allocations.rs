@@ -0,0 +1,55 @@
+//! Counts heap allocations per frame in development builds.
+//!
+//! Allocation churn is especially costly in wasm builds,
+//! so the count is displayed with the other diagnostics to catch regressions early.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::app::{self, App};
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+
+/// Number of heap allocations in the last frame.
+pub(super) const ALLOCATIONS: DiagnosticPath = DiagnosticPath::const_new("allocations");
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(ALLOCATIONS));
+        app.add_systems(app::Last, measure_system);
+    }
+}
+
+static COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Forwards to the system allocator, counting allocations and reallocations.
+struct Counting;
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { System.dealloc(ptr, layout) }
+}
+
+#[allow(clippy::cast_precision_loss)] // counts beyond 2^52 per frame are not realistic
+fn measure_system(mut diagnostics: Diagnostics) {
+    let count = COUNT.swap(0, Ordering::Relaxed);
+    diagnostics.add_measurement(&ALLOCATIONS, || count as f64);
+}
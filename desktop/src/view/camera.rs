@@ -1,12 +1,14 @@
 use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use std::mem;
 
 use bevy::app::{self, App};
+use bevy::audio::SpatialListener;
 use bevy::color::Color;
 use bevy::core_pipeline::core_3d::{Camera3d, Camera3dBundle};
 use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
 use bevy::ecs::query::With;
 use bevy::ecs::schedule::IntoSystemConfigs;
-use bevy::ecs::system::{Commands, Query, Res};
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
 use bevy::hierarchy::BuildChildren;
 use bevy::input::keyboard::KeyCode;
 use bevy::input::ButtonInput;
@@ -19,10 +21,14 @@ use bevy::state::state;
 use bevy::time::Time;
 use bevy::transform::components::Transform;
 use traffloat_base::debug;
+use traffloat_view::camera::StartingCamera;
 
-use super::{diagnostics, InputSystemSet};
+use super::{diagnostics, InputSystemSet, ViewMode};
 use crate::AppState;
 
+mod intro;
+mod touch;
+
 pub(crate) struct Plugin;
 
 const MOVE_DISTANCE_PER_SECOND: f32 = 5.5;
@@ -32,10 +38,18 @@ const ZOOM_RATIO_PER_SECOND: f32 = 1.9_f32;
 impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.init_resource::<CameraInput>();
         app.add_systems(
             app::Update,
-            input_move_camera_system.run_if(in_state(AppState::GameView)).in_set(InputSystemSet),
+            (
+                (keyboard_camera_input_system, touch::camera_input_system)
+                    .in_set(InputSystemSet)
+                    .run_if(in_state(ViewMode::Orbit)),
+                apply_camera_input_system.after(InputSystemSet),
+            )
+                .run_if(in_state(AppState::GameView)),
         );
+        app.add_plugins((touch::Plugin, intro::Plugin));
 
         app.add_systems(app::Startup, register_camera_diagnostic_system);
         app.add_systems(app::Update, update_camera_diagnostic_system);
@@ -51,17 +65,27 @@ impl app::Plugin for Plugin {
     }
 }
 
-fn setup(mut commands: Commands) {
+fn setup(mut commands: Commands, starting: Option<Res<StartingCamera>>) {
+    let transform = match &starting {
+        Some(starting) => starting.transform.into(),
+        None => Transform::from_xyz(0., 0., -5.).looking_at(Vec3::ZERO, Vec3::Y),
+    };
+    if let Some(intro) = starting.as_deref().and_then(intro::Intro::new) {
+        commands.insert_resource(intro);
+    }
+
     commands.spawn((
         super::Owned,
         Camera3dBundle {
-            transform: Transform::from_xyz(0., 0., -5.).looking_at(Vec3::ZERO, Vec3::Y),
+            transform,
             camera_3d: Camera3d {
                 screen_space_specular_transmission_steps: 3,
                 ..Default::default()
             },
             ..Default::default()
         },
+        // pans spatial sounds such as alarms
+        SpatialListener::default(),
     ));
 
     commands.spawn((
@@ -83,81 +107,77 @@ fn setup(mut commands: Commands) {
     ));
 }
 
-fn input_move_camera_system(
+/// Camera movement requested by input devices in the current frame,
+/// consumed by [`apply_camera_input_system`].
+///
+/// Keyboard and touch input are mapped onto the same actions
+/// so that all devices share the camera behavior.
+#[derive(Resource)]
+struct CameraInput {
+    /// Translation in the local frame of the camera.
+    translate: Vec3,
+    /// Rotation angles about the local axes of the camera.
+    rotate:    Vec3,
+    /// Ratio to multiply the field of view by.
+    zoom:      f32,
+}
+
+impl Default for CameraInput {
+    fn default() -> Self { Self { translate: Vec3::ZERO, rotate: Vec3::ZERO, zoom: 1. } }
+}
+
+fn keyboard_camera_input_system(
     time: Res<Time>,
-    mut camera_query: Query<(&mut Transform, &mut camera::Projection), With<Camera3d>>,
     keys: Res<ButtonInput<KeyCode>>,
+    mut input: ResMut<CameraInput>,
 ) {
-    let Ok((mut tf, mut proj)) = camera_query.get_single_mut() else { return };
-
     let move_speed = time.delta_seconds() * MOVE_DISTANCE_PER_SECOND;
     let rotate_speed = time.delta_seconds() * ROTATE_ANGLE_PER_SECOND;
     let zoom_speed = ZOOM_RATIO_PER_SECOND.powf(time.delta_seconds());
 
     let is_rotate = keys.pressed(KeyCode::ShiftLeft);
 
-    if keys.pressed(KeyCode::KeyW) {
-        if is_rotate {
-            tf.rotate_local_x(rotate_speed);
-        } else {
-            let delta = tf.up() * move_speed;
-            tf.translation += delta;
-        }
-    }
-
-    if keys.pressed(KeyCode::KeyS) {
-        if is_rotate {
-            tf.rotate_local_x(-rotate_speed);
-        } else {
-            let delta = tf.down() * move_speed;
-            tf.translation += delta;
+    for (key, rotate, translate) in [
+        (KeyCode::KeyW, Vec3::X, Vec3::Y),
+        (KeyCode::KeyS, Vec3::NEG_X, Vec3::NEG_Y),
+        (KeyCode::KeyA, Vec3::Y, Vec3::NEG_X),
+        (KeyCode::KeyD, Vec3::NEG_Y, Vec3::X),
+        (KeyCode::KeyZ, Vec3::NEG_Z, Vec3::NEG_Z),
+        (KeyCode::KeyX, Vec3::Z, Vec3::Z),
+    ] {
+        if keys.pressed(key) {
+            if is_rotate {
+                input.rotate += rotate * rotate_speed;
+            } else {
+                input.translate += translate * move_speed;
+            }
         }
     }
 
-    if keys.pressed(KeyCode::KeyA) {
-        if is_rotate {
-            tf.rotate_local_y(rotate_speed);
-        } else {
-            let delta = tf.left() * move_speed;
-            tf.translation += delta;
-        }
+    if keys.pressed(KeyCode::Equal) {
+        input.zoom /= zoom_speed;
     }
 
-    if keys.pressed(KeyCode::KeyD) {
-        if is_rotate {
-            tf.rotate_local_y(-rotate_speed);
-        } else {
-            let delta = tf.right() * move_speed;
-            tf.translation += delta;
-        }
+    if keys.pressed(KeyCode::Minus) {
+        input.zoom *= zoom_speed;
     }
+}
 
-    if keys.pressed(KeyCode::KeyZ) {
-        if is_rotate {
-            tf.rotate_local_z(-rotate_speed);
-        } else {
-            let delta = tf.forward() * move_speed;
-            tf.translation += delta;
-        }
-    }
+fn apply_camera_input_system(
+    mut camera_query: Query<(&mut Transform, &mut camera::Projection), With<Camera3d>>,
+    mut input: ResMut<CameraInput>,
+) {
+    let input = mem::take(&mut *input);
+    let Ok((mut tf, mut proj)) = camera_query.get_single_mut() else { return };
 
-    if keys.pressed(KeyCode::KeyX) {
-        if is_rotate {
-            tf.rotate_local_z(rotate_speed);
-        } else {
-            let delta = tf.back() * move_speed;
-            tf.translation += delta;
-        }
-    }
+    let delta = tf.rotation * input.translate;
+    tf.translation += delta;
+    tf.rotate_local_x(input.rotate.x);
+    tf.rotate_local_y(input.rotate.y);
+    tf.rotate_local_z(input.rotate.z);
 
     if let camera::Projection::Perspective(ref mut proj) = *proj {
-        if keys.pressed(KeyCode::Equal) {
-            proj.fov /= zoom_speed;
-        }
-
-        if keys.pressed(KeyCode::Minus) {
-            proj.fov = (proj.fov * zoom_speed).min(PI);
-        }
+        proj.fov = (proj.fov * input.zoom).min(PI);
     }
 }
 
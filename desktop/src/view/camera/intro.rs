@@ -0,0 +1,184 @@
+//! Plays the scenario intro before handing camera control to the player.
+//!
+//! Systems in [`InputSystemSet`] are suspended while the intro is playing.
+//! Escape or a tap skips the intro.
+
+use bevy::app::{self, App};
+use bevy::color::Color;
+use bevy::core_pipeline::core_3d::Camera3d;
+use bevy::ecs::component::Component;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::common_conditions::{not, resource_exists};
+use bevy::ecs::schedule::{IntoSystemConfigs, IntoSystemSetConfigs};
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::hierarchy::BuildChildren;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::touch::Touches;
+use bevy::input::ButtonInput;
+use bevy::math::Vec3;
+use bevy::render::view::Visibility;
+use bevy::state::condition::in_state;
+use bevy::state::state;
+use bevy::text::{Text, TextSection, TextStyle};
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use bevy::ui::node_bundles::{NodeBundle, TextBundle};
+use bevy::ui::{self, Style, UiRect};
+use traffloat_base::debug;
+use traffloat_view::camera::{Keyframe, StartingCamera};
+
+use crate::view::{InputSystemSet, Owned};
+use crate::AppState;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(app::Update, InputSystemSet.run_if(not(resource_exists::<Intro>)));
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(state::OnExit(AppState::GameView), teardown);
+        app.add_systems(
+            app::Update,
+            (skip_intro_system, play_intro_system.after(skip_intro_system))
+                .run_if(in_state(AppState::GameView))
+                .run_if(resource_exists::<Intro>),
+        );
+    }
+}
+
+/// An intro in progress.
+#[derive(Resource)]
+pub(super) struct Intro {
+    /// The camera transform at the start of the current keyframe.
+    from:      Transform,
+    keyframes: Vec<Keyframe>,
+    /// Index of the current keyframe.
+    index:     usize,
+    /// Seconds elapsed since the start of the current keyframe.
+    elapsed:   f32,
+}
+
+impl Intro {
+    /// Starts an intro from the starting camera, or returns `None` if it has no keyframes.
+    pub(super) fn new(starting: &StartingCamera) -> Option<Self> {
+        (!starting.intro.is_empty()).then(|| Self {
+            from:      starting.transform.into(),
+            keyframes: starting.intro.clone(),
+            index:     0,
+            elapsed:   0.,
+        })
+    }
+}
+
+/// Marker component for the caption container node.
+#[derive(Component)]
+struct CaptionNode;
+
+/// Marker component for the caption text.
+#[derive(Component)]
+struct CaptionText;
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_self: ui::JustifySelf::Center,
+                    align_self: ui::AlignSelf::End,
+                    margin: UiRect::bottom(ui::Val::Percent(10.)),
+                    padding: UiRect::all(ui::Val::Px(8.)),
+                    ..Default::default()
+                },
+                background_color: ui::BackgroundColor(Color::linear_rgba(0., 0., 0., 0.6)),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            CaptionNode,
+            Owned,
+            debug::Bundle::new("IntroCaption"),
+        ))
+        .with_children(|b| {
+            b.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle { font_size: 20., ..Default::default() },
+                        )],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                CaptionText,
+            ));
+        });
+}
+
+fn teardown(mut commands: Commands) {
+    commands.remove_resource::<Intro>();
+    commands.remove_resource::<StartingCamera>();
+}
+
+fn skip_intro_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    mut intro: ResMut<Intro>,
+) {
+    if keys.just_pressed(KeyCode::Escape) || touches.any_just_pressed() {
+        let intro = &mut *intro;
+        if let Some(last) = intro.keyframes.last() {
+            intro.from = last.transform.into();
+        }
+        intro.index = intro.keyframes.len();
+    }
+}
+
+fn play_intro_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut intro: ResMut<Intro>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+    mut node_query: Query<&mut Visibility, With<CaptionNode>>,
+    mut text_query: Query<&mut Text, With<CaptionText>>,
+) {
+    let Ok(mut camera) = camera_query.get_single_mut() else { return };
+
+    intro.elapsed += time.delta_seconds();
+    let keyframe = loop {
+        let Some(keyframe) = intro.keyframes.get(intro.index) else { break None };
+        if intro.elapsed < keyframe.duration {
+            break Some(keyframe.clone());
+        }
+
+        intro.elapsed -= keyframe.duration;
+        intro.from = keyframe.transform.into();
+        intro.index += 1;
+    };
+
+    let caption = keyframe.as_ref().and_then(|keyframe| keyframe.caption.as_ref());
+    for mut visibility in &mut node_query {
+        *visibility = if caption.is_some() { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if let Some(caption) = caption {
+        for mut text in &mut text_query {
+            text.sections[0].value = caption.render_to_string();
+        }
+    }
+
+    let Some(keyframe) = keyframe else {
+        camera.translation = intro.from.translation;
+        camera.rotation = intro.from.rotation;
+        commands.remove_resource::<Intro>();
+        return;
+    };
+
+    let to = Transform::from(keyframe.transform);
+    let progress = smoothstep(intro.elapsed / keyframe.duration);
+    camera.translation = Vec3::lerp(intro.from.translation, to.translation, progress);
+    camera.rotation = intro.from.rotation.slerp(to.rotation, progress);
+}
+
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
@@ -0,0 +1,92 @@
+//! Touch gestures for camera control and UI scaling on small screens.
+//!
+//! - Dragging with one finger pans the camera.
+//! - Dragging with two fingers orbits the camera.
+//! - Pinching zooms the camera.
+//!
+//! Selection by tapping is handled by the picking backend, which treats touches as pointers.
+
+use bevy::app::{self, App};
+use bevy::ecs::query::With;
+use bevy::ecs::system::{Query, Res, ResMut, Resource};
+use bevy::input::touch::Touches;
+use bevy::math::{Vec2, Vec3};
+use bevy::ui::UiScale;
+use bevy::window::{PrimaryWindow, Window};
+
+use super::CameraInput;
+
+/// Camera translation per logical pixel dragged.
+const PAN_DISTANCE_PER_PIXEL: f32 = 0.01;
+/// Camera rotation in radians per logical pixel dragged.
+const ORBIT_ANGLE_PER_PIXEL: f32 = 0.005;
+
+/// The shorter window side in logical pixels at which the UI is displayed at full scale.
+const REFERENCE_SHORT_SIDE: f32 = 720.;
+/// The smallest UI scale on small screens.
+const MIN_UI_SCALE: f32 = 0.6;
+/// Additional UI scale after touch input is detected, to enlarge tap targets.
+const TOUCH_UI_SCALE: f32 = 1.25;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchMode>();
+        app.add_systems(app::Update, scale_ui_system);
+    }
+}
+
+/// Whether touch input has been used in this session.
+#[derive(Default, Resource)]
+struct TouchMode(bool);
+
+pub(super) fn camera_input_system(
+    touches: Res<Touches>,
+    mut input: ResMut<CameraInput>,
+    mut mode: ResMut<TouchMode>,
+) {
+    if touches.any_just_pressed() && !mode.0 {
+        mode.0 = true;
+    }
+
+    let mut active = touches.iter();
+    match (active.next(), active.next(), active.next()) {
+        (Some(touch), None, None) => {
+            let delta = touch.delta();
+            // screen y points downwards; the scene follows the finger
+            input.translate += Vec3::new(-delta.x, delta.y, 0.) * PAN_DISTANCE_PER_PIXEL;
+        }
+        (Some(first), Some(second), None) => {
+            let midpoint_delta = (first.delta() + second.delta()) / 2.;
+            input.rotate +=
+                Vec3::new(-midpoint_delta.y, -midpoint_delta.x, 0.) * ORBIT_ANGLE_PER_PIXEL;
+
+            let previous = first.previous_position().distance(second.previous_position());
+            let current = first.position().distance(second.position());
+            if previous > 0. && current > 0. {
+                // spreading fingers narrows the field of view
+                input.zoom *= previous / current;
+            }
+        }
+        _ => {} // no touch, or too many fingers to interpret
+    }
+}
+
+fn scale_ui_system(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mode: Res<TouchMode>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = window_query.get_single() else { return };
+
+    let short_side = Vec2::new(window.width(), window.height()).min_element();
+    let mut scale = (short_side / REFERENCE_SHORT_SIDE).clamp(MIN_UI_SCALE, 1.);
+    if mode.0 {
+        scale *= TOUCH_UI_SCALE;
+    }
+
+    if (ui_scale.0 - scale).abs() > f32::EPSILON {
+        ui_scale.0 = scale;
+    }
+}
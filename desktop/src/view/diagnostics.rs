@@ -14,6 +14,7 @@ use bevy::text::{Text, TextSection, TextStyle};
 use bevy::ui::node_bundles::{NodeBundle, TextBundle};
 use bevy::ui::{self, Style, UiRect};
 use traffloat_base::debug;
+use traffloat_base::save::seed::WorldSeed;
 use typed_builder::TypedBuilder;
 
 use crate::AppState;
@@ -46,6 +47,15 @@ impl app::Plugin for Plugin {
                             .build(),
                         debug::Bundle::new("DiagnosticDisplay"),
                     ));
+                    #[cfg(feature = "dev")]
+                    b.spawn((
+                        Display::builder()
+                            .horizontal_priority(1)
+                            .label("Allocs")
+                            .target(super::allocations::ALLOCATIONS)
+                            .build(),
+                        debug::Bundle::new("DiagnosticDisplay"),
+                    ));
                 });
         });
     }
@@ -126,6 +136,7 @@ pub struct DisplayGroup {
 fn display_diagnostic_system(
     mut label_query: Query<&mut Text, With<LabelDisplay>>,
     sources: Res<DiagnosticsStore>,
+    world_seed: Res<WorldSeed>,
     display_group_query: Query<(&DisplayGroup, &hierarchy::Children)>,
     display_query: Query<&Display>,
 ) {
@@ -184,4 +195,12 @@ fn display_diagnostic_system(
             style: TextStyle { font_size: 12., ..Default::default() },
         });
     }
+
+    // displayed so that players can include it in bug reports
+    if let Some(seed) = world_seed.seed {
+        display_text.sections.push(TextSection {
+            value: format!("Seed {seed}\n"),
+            style: TextStyle { color: Color::WHITE, font_size: 12., ..Default::default() },
+        });
+    }
 }
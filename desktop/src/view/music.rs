@@ -0,0 +1,237 @@
+//! Background music during the game view.
+//!
+//! The playlist is read from `music/playlist.json` in the asset directory,
+//! listing asset paths of tracks together with their [tension tier](Tier).
+//! Scenarios and mods provide music by shipping tracks and a playlist in their asset directory.
+//!
+//! The tense tier is selected while any fluid container is contaminated,
+//! and the calm tier otherwise.
+//! Switching tracks crossfades between the old and the new track over [`CROSSFADE_SECS`].
+//!
+//! The volume and shuffle [settings](Settings) are remembered in the config directory.
+//! `--music-volume` and `--shuffle-music` override the remembered settings and replace them.
+//!
+//! This module reads the simulation world directly
+//! and is only available in single-player sessions.
+
+use std::fs;
+
+use bevy::app::{self, App};
+use bevy::asset::AssetServer;
+use bevy::audio::{AudioBundle, AudioSink, AudioSinkPlayback, PlaybackSettings, Volume};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::common_conditions::resource_changed;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::hierarchy::DespawnRecursiveExt;
+use bevy::state::condition::in_state;
+use bevy::state::state;
+use bevy::time::Time;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use traffloat_fluid::recycling::Contaminated;
+
+use crate::options::Options;
+use crate::paths::Paths;
+use crate::view::Owned;
+use crate::AppState;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let world = app.world();
+        let options = world.resource::<Options>();
+        let mut settings = world
+            .get_resource::<Paths>()
+            .and_then(|paths| read_settings(paths))
+            .unwrap_or_default();
+        if let Some(volume) = options.music_volume {
+            settings.volume = volume;
+        }
+        if let Some(shuffle) = options.shuffle_music {
+            settings.shuffle = shuffle;
+        }
+        settings.volume = settings.volume.clamp(0., 1.);
+        app.insert_resource(settings);
+        app.add_systems(app::Update, write_settings_system.run_if(resource_changed::<Settings>));
+
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(state::OnExit(AppState::GameView), teardown);
+        app.add_systems(
+            app::Update,
+            (select_track_system, fade_system.after(select_track_system))
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+/// Duration of a crossfade between two tracks.
+const CROSSFADE_SECS: f32 = 3.;
+
+/// File name of the remembered settings in the config directory.
+const SETTINGS_FILE: &str = "music.json";
+
+/// User settings for background music.
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Volume of background music, from 0 to 1.
+    pub volume:  f32,
+    /// Plays tracks in random order.
+    pub shuffle: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self { Self { volume: 0.5, shuffle: false } }
+}
+
+fn read_settings(paths: &Paths) -> Option<Settings> {
+    let bytes = fs::read(paths.config.join(SETTINGS_FILE)).ok()?;
+    serde_json::from_slice(&bytes)
+        .inspect_err(|err| bevy::log::warn!("invalid music settings: {err}"))
+        .ok()
+}
+
+fn write_settings_system(settings: Res<Settings>, paths: Option<Res<Paths>>) {
+    let Some(paths) = paths else { return };
+    let result =
+        serde_json::to_vec_pretty(&*settings).map_err(std::io::Error::from).and_then(|bytes| {
+            fs::create_dir_all(&paths.config)
+                .and_then(|()| fs::write(paths.config.join(SETTINGS_FILE), bytes))
+        });
+    if let Err(err) = result {
+        bevy::log::warn!("cannot remember music settings: {err}");
+    }
+}
+
+/// Path of the playlist definition relative to the asset directory.
+const PLAYLIST_PATH: &str = "music/playlist.json";
+
+#[derive(Deserialize)]
+struct PlaylistDef {
+    tracks: Vec<TrackDef>,
+}
+
+#[derive(Deserialize)]
+struct TrackDef {
+    /// Asset path of the audio file.
+    path: String,
+    #[serde(default)]
+    tier: Tier,
+}
+
+/// The intensity of the music, driven by the world state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Tier {
+    #[default]
+    Calm,
+    Tense,
+}
+
+#[derive(Resource)]
+struct Playlist {
+    tracks: Vec<TrackDef>,
+    /// Index of the last track played in each tier.
+    cursor: [usize; 2],
+}
+
+impl Playlist {
+    fn next_track(&mut self, tier: Tier, shuffle: bool) -> Option<&TrackDef> {
+        let candidates: Vec<usize> =
+            (0..self.tracks.len()).filter(|&index| self.tracks[index].tier == tier).collect();
+        let cursor = &mut self.cursor[tier as usize];
+
+        let index = if shuffle {
+            *candidates.choose(&mut rand::thread_rng())?
+        } else {
+            let position = candidates.iter().position(|&index| index > *cursor);
+            *position.map_or_else(|| candidates.first(), |position| candidates.get(position))?
+        };
+        *cursor = index;
+        self.tracks.get(index)
+    }
+}
+
+/// A playing track.
+#[derive(Component)]
+struct Track {
+    tier:     Tier,
+    /// Whether the track is fading out to be replaced.
+    outgoing: bool,
+}
+
+fn setup(mut commands: Commands, options: Res<Options>) {
+    let path = options.asset_dir.join(PLAYLIST_PATH);
+    let def = match fs::read(&path) {
+        Ok(bytes) => match serde_json::from_slice::<PlaylistDef>(&bytes) {
+            Ok(def) => def,
+            Err(err) => {
+                bevy::log::error!("invalid playlist {}: {err}", path.display());
+                return;
+            }
+        },
+        Err(err) => {
+            bevy::log::info!("no music playlist at {}: {err}", path.display());
+            return;
+        }
+    };
+
+    commands.insert_resource(Playlist { tracks: def.tracks, cursor: [usize::MAX; 2] });
+}
+
+fn teardown(mut commands: Commands) { commands.remove_resource::<Playlist>(); }
+
+fn select_track_system(
+    playlist: Option<ResMut<Playlist>>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    contaminated_query: Query<(), With<Contaminated>>,
+    mut track_query: Query<(Entity, &mut Track)>,
+    mut commands: Commands,
+) {
+    let Some(mut playlist) = playlist else { return };
+
+    let tier = if contaminated_query.is_empty() { Tier::Calm } else { Tier::Tense };
+
+    let mut current = track_query.iter_mut().filter(|(_, track)| !track.outgoing);
+    match current.next() {
+        Some((_, track)) if track.tier == tier => return,
+        Some((_, mut track)) => track.outgoing = true,
+        None => {}
+    }
+
+    let Some(def) = playlist.next_track(tier, settings.shuffle) else { return };
+    commands.spawn((
+        Owned,
+        Track { tier, outgoing: false },
+        AudioBundle {
+            source:   asset_server.load(def.path.clone()),
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(0.)),
+        },
+    ));
+}
+
+fn fade_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    track_query: Query<(Entity, &Track, &AudioSink)>,
+    mut commands: Commands,
+) {
+    let step = settings.volume * time.delta_seconds() / CROSSFADE_SECS;
+
+    for (entity, track, sink) in &track_query {
+        if track.outgoing {
+            let volume = (sink.volume() - step).max(0.);
+            sink.set_volume(volume);
+            if volume <= 0. {
+                commands.entity(entity).despawn_recursive();
+            }
+        } else {
+            sink.set_volume((sink.volume() + step).min(settings.volume));
+        }
+    }
+}
@@ -1,20 +1,29 @@
 use bevy::app::{self, App};
 use bevy::asset::AssetServer;
 use bevy::ecs::event::EventReader;
+use bevy::ecs::query::With;
 use bevy::ecs::schedule::IntoSystemConfigs;
-use bevy::ecs::system::{Commands, Res, ResMut};
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
 use bevy::hierarchy::BuildChildren;
 use bevy::prelude::SpatialBundle;
 use bevy::render;
 use bevy::transform::components::Transform;
 use traffloat_base::{debug, EventReaderSystemSet};
-use traffloat_view::viewable;
+use traffloat_view::{viewable, viewer};
 
-use super::delegate;
+use super::{delegate, Owned};
 
+mod control_group;
+mod export;
+mod fluid_debug;
+mod haze;
 mod infobox;
 mod layers;
 mod metrics;
+mod panels;
+mod search;
+mod tank;
+mod walk;
 
 pub(crate) struct Plugin;
 
@@ -22,11 +31,31 @@ impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<delegate::SidIndex<viewable::Sid>>();
 
-        app.add_plugins((infobox::Plugin, layers::Plugin, metrics::Plugin));
+        app.add_plugins((
+            control_group::Plugin,
+            export::Plugin,
+            fluid_debug::Plugin,
+            haze::Plugin,
+            infobox::Plugin,
+            layers::Plugin,
+            metrics::Plugin,
+            panels::Plugin,
+            search::Plugin,
+            tank::Plugin,
+            walk::Plugin,
+        ));
 
         app.add_systems(
             app::Update,
-            handle_show_system.in_set(EventReaderSystemSet::<viewable::ShowEvent>::default()),
+            (
+                handle_show_system.in_set(EventReaderSystemSet::<viewable::ShowEvent>::default()),
+                handle_move_system
+                    .in_set(EventReaderSystemSet::<viewable::MoveEvent>::default())
+                    .after(handle_show_system),
+                handle_hide_system
+                    .in_set(EventReaderSystemSet::<viewable::HideEvent>::default())
+                    .after(handle_show_system),
+            ),
         );
     }
 }
@@ -69,3 +98,39 @@ fn handle_show_system(
         }
     }
 }
+
+fn handle_hide_system(
+    mut commands: Commands,
+    mut reader: EventReader<viewable::HideEvent>,
+    sid_index: Res<delegate::SidIndex<viewable::Sid>>,
+) {
+    for event in reader.read() {
+        // the viewable may be hidden repeatedly, e.g. when a clustered viewable leaves the range
+        if let Some(viewable_id) = sid_index.get(event.viewable) {
+            commands.entity(viewable_id).insert(render::view::Visibility::Hidden);
+        }
+    }
+}
+
+fn handle_move_system(
+    mut reader: EventReader<viewable::MoveEvent>,
+    sid_index: Res<delegate::SidIndex<viewable::Sid>>,
+    viewer_query: Query<&viewer::Sid, With<Owned>>,
+    mut query: Query<&mut Transform>,
+) {
+    let Ok(&viewer_sid) = viewer_query.get_single() else { return };
+
+    for event in reader.read() {
+        if event.viewer != viewer_sid {
+            continue;
+        }
+
+        let Some(viewable_id) = sid_index.get(event.viewable) else {
+            bevy::log::warn!("received move event with unknown viewable id {:?}", event.viewable);
+            continue;
+        };
+        if let Ok(mut transform) = query.get_mut(viewable_id) {
+            *transform = Transform::from(event.transform);
+        }
+    }
+}
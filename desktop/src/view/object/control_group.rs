@@ -0,0 +1,111 @@
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{EventReader, EventWriter};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
+use bevy::input::keyboard::KeyCode;
+use bevy::input::ButtonInput;
+use bevy::state::condition::in_state;
+use traffloat_base::EventReaderSystemSet;
+use traffloat_view::viewer::control_group;
+use traffloat_view::{viewable, viewer};
+
+use super::infobox::{Focus, FocusChangeEvent, FocusType};
+use crate::view::{delegate, InputSystemSet, Owned};
+use crate::AppState;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            app::Update,
+            (
+                input_control_group_system.in_set(InputSystemSet),
+                handle_recall_system
+                    .in_set(EventReaderSystemSet::<control_group::RecallEvent>::default()),
+            )
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+const GROUP_KEYS: [(u8, KeyCode); 10] = [
+    (1, KeyCode::Digit1),
+    (2, KeyCode::Digit2),
+    (3, KeyCode::Digit3),
+    (4, KeyCode::Digit4),
+    (5, KeyCode::Digit5),
+    (6, KeyCode::Digit6),
+    (7, KeyCode::Digit7),
+    (8, KeyCode::Digit8),
+    (9, KeyCode::Digit9),
+    (0, KeyCode::Digit0),
+];
+
+/// Ctrl + digit assigns the focused object to a group,
+/// Ctrl + Shift + digit adds the focused object to a group,
+/// and digit alone recalls the group.
+fn input_control_group_system(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    focus: Res<Focus>,
+    delegate_query: Query<&delegate::Marker<viewable::Sid>>,
+    viewer_query: Query<Entity, (With<viewer::Sid>, With<Owned>)>,
+) {
+    let Ok(viewer) = viewer_query.get_single() else { return };
+    let is_assign = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let is_extend = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for (group, key) in GROUP_KEYS {
+        if !keys.just_pressed(key) {
+            continue;
+        }
+
+        if is_assign {
+            let viewables = focus
+                .entity
+                .and_then(|entity| delegate_query.get(entity).ok())
+                .map(|&delegate::Marker(sid)| sid)
+                .into_iter()
+                .collect();
+            if is_extend {
+                commands.add(control_group::Extend { viewer, group, viewables });
+            } else {
+                commands.add(control_group::Assign { viewer, group, viewables });
+            }
+        } else {
+            commands.add(control_group::Recall { viewer, group });
+        }
+    }
+}
+
+fn handle_recall_system(
+    mut reader: EventReader<control_group::RecallEvent>,
+    sid_index: Res<delegate::SidIndex<viewable::Sid>>,
+    viewer_query: Query<&viewer::Sid, With<Owned>>,
+    mut focus: ResMut<Focus>,
+    mut focus_change_writer: EventWriter<FocusChangeEvent>,
+) {
+    let Ok(&viewer_sid) = viewer_query.get_single() else { return };
+
+    for event in reader.read() {
+        if event.viewer != viewer_sid {
+            continue;
+        }
+
+        // Focus only supports a single object,
+        // so recalling the group repeatedly cycles through the visible members.
+        let members: Vec<Entity> =
+            event.viewables.iter().filter_map(|&sid| sid_index.get(sid)).collect();
+        let next = focus
+            .entity
+            .and_then(|focused| members.iter().position(|&member| member == focused))
+            .map_or(0, |index| (index + 1) % members.len());
+        let Some(&entity) = members.get(next) else { continue };
+        focus.entity = Some(entity);
+        focus.focus_type = FocusType::Locked;
+        focus_change_writer.send_default();
+    }
+}
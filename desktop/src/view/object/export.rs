@@ -0,0 +1,201 @@
+//! Exports the data shown in the infobox for bug reports.
+//!
+//! Ctrl+E exports the focused object and its descendants as JSON,
+//! and Ctrl+Shift+E exports the same data as CSV with one row per metric.
+//! The export is built from the same delegate entities the infobox renders,
+//! so it contains exactly what the client knows.
+//! Native builds copy the export to the clipboard, while web builds download it as a file.
+
+use bevy::app::{self, App};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Query, Res};
+use bevy::hierarchy::Children;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::ButtonInput;
+use bevy::state::condition::in_state;
+use serde::Serialize;
+use traffloat_view::appearance::Appearance;
+use traffloat_view::{format, metrics as view_metrics, viewable};
+
+use super::infobox::Focus;
+use super::metrics::Known;
+use crate::view::delegate;
+use crate::AppState;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(app::Update, export_system.run_if(in_state(AppState::GameView)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    #[cfg_attr(not(target_family = "wasm"), allow(dead_code))]
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), allow(dead_code))]
+    fn mime(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ObjectSnapshot {
+    label:   String,
+    /// Depth of the object below the focused object.
+    depth:   u32,
+    metrics: Vec<MetricSnapshot>,
+}
+
+#[derive(Serialize)]
+struct MetricSnapshot {
+    name:      String,
+    value:     f32,
+    unit:      format::Unit,
+    /// The value as displayed in the infobox.
+    formatted: String,
+}
+
+fn export_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    focus: Res<Focus>,
+    object_query: Query<
+        (&Appearance, &Known, Option<&Children>),
+        With<delegate::Marker<viewable::Sid>>,
+    >,
+    metric_query: Query<&view_metrics::ClientTypeData, With<delegate::Marker<view_metrics::Sid>>>,
+    metric_sid_index: Res<delegate::SidIndex<view_metrics::Sid>>,
+    formatter: Res<format::Formatter>,
+) {
+    let is_ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !is_ctrl || !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let is_shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let format = if is_shift { Format::Csv } else { Format::Json };
+
+    let Some(focus) = focus.entity else {
+        bevy::log::info!("no focused object to export");
+        return;
+    };
+
+    let mut objects = Vec::new();
+    let mut stack = vec![(focus, 0)];
+    while let Some((entity, depth)) = stack.pop() {
+        let Ok((appearance, known, children)) = object_query.get(entity) else { continue };
+
+        let metrics = known
+            .0
+            .iter()
+            .map(|(&ty, &value)| {
+                let def = metric_sid_index.get(ty).and_then(|entity| metric_query.get(entity).ok());
+                let (name, unit) = match def {
+                    Some(def) => (
+                        def.display_label.render_to_string(),
+                        format::Unit::from_metadata(&def.metadata),
+                    ),
+                    None => (format!("{ty:?}"), format::Unit::Scalar),
+                };
+                MetricSnapshot { name, value, unit, formatted: formatter.format(value, unit) }
+            })
+            .collect();
+        objects.push(ObjectSnapshot { label: appearance.label.render_to_string(), depth, metrics });
+
+        // reversed so that children are exported in display order
+        stack.extend(children.into_iter().flatten().rev().map(|&child| (child, depth + 1)));
+    }
+
+    let content = match format {
+        Format::Json => match serde_json::to_string_pretty(&objects) {
+            Ok(content) => content,
+            Err(err) => {
+                bevy::log::error!("cannot encode export: {err}");
+                return;
+            }
+        },
+        Format::Csv => to_csv(&objects),
+    };
+
+    match deliver(format, content) {
+        Ok(()) => bevy::log::info!("exported {} objects as {format:?}", objects.len()),
+        Err(err) => bevy::log::error!("cannot export: {err}"),
+    }
+}
+
+fn to_csv(objects: &[ObjectSnapshot]) -> String {
+    let mut output = String::from("object,depth,metric,value,unit\n");
+    for object in objects {
+        for metric in &object.metrics {
+            let unit = serde_json::to_value(metric.unit).expect("unit is always serializable");
+            let fields = [
+                csv_field(&object.label),
+                object.depth.to_string(),
+                csv_field(&metric.name),
+                metric.value.to_string(),
+                unit.as_str().unwrap_or_default().to_string(),
+            ];
+            output.push_str(&fields.join(","));
+            output.push('\n');
+        }
+    }
+    output
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn deliver(_format: Format, content: String) -> Result<(), String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|err| err.to_string())?;
+    clipboard.set_text(content).map_err(|err| err.to_string())
+}
+
+#[cfg(target_family = "wasm")]
+fn deliver(format: Format, content: String) -> Result<(), String> {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let js_err = |err: JsValue| format!("{err:?}");
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document to download from")?;
+
+    let parts = js_sys::Array::of1(&JsValue::from_str(&content));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(format.mime());
+    let blob =
+        web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_err)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_err)?;
+
+    let anchor: web_sys::HtmlAnchorElement = document
+        .create_element("a")
+        .map_err(js_err)?
+        .dyn_into()
+        .map_err(|_| "created element is not an anchor")?;
+    anchor.set_href(&url);
+    anchor.set_download(&format!("traffloat-export.{}", format.extension()));
+    anchor.click();
+
+    web_sys::Url::revoke_object_url(&url).map_err(js_err)
+}
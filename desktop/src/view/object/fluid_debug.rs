@@ -0,0 +1,201 @@
+//! F3 toggles an overlay showing fluid solver internals of the focused building.
+//!
+//! This overlay reads the simulation world directly
+//! and is only available in single-player sessions.
+
+use std::fmt::Write;
+
+use bevy::app::{self, App};
+use bevy::color::Color;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::hierarchy::BuildChildren;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::ButtonInput;
+use bevy::render::view::Visibility;
+use bevy::state::condition::in_state;
+use bevy::state::state;
+use bevy::text::{Text, TextSection, TextStyle};
+use bevy::ui::node_bundles::{NodeBundle, TextBundle};
+use bevy::ui::{self, Style, UiRect};
+use traffloat_base::debug;
+use traffloat_fluid::container;
+use traffloat_fluid::diagnostics::{ContainerReport, PipeReport, Watch};
+use traffloat_graph::building::FacilityList;
+use traffloat_view::viewable;
+
+use super::infobox::Focus;
+use crate::view::{delegate, InputSystemSet, Owned};
+use crate::AppState;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Overlay>();
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(state::OnExit(AppState::GameView), reset);
+        app.add_systems(
+            app::Update,
+            (
+                toggle_overlay_system.in_set(InputSystemSet),
+                update_watch_system.after(toggle_overlay_system),
+                display_overlay_system.after(update_watch_system),
+            )
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+/// State of the fluid debug overlay.
+#[derive(Default, Resource)]
+struct Overlay {
+    enabled: bool,
+    /// Simulation entities currently marked with [`Watch`].
+    watched: Vec<Entity>,
+}
+
+/// Marker component for the overlay container node.
+#[derive(Component)]
+struct ContainerNode;
+
+/// Marker component for the overlay text.
+#[derive(Component)]
+struct OverlayText;
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_self: ui::JustifySelf::Start,
+                    align_self: ui::AlignSelf::End,
+                    margin: UiRect::all(ui::Val::Px(5.)),
+                    padding: UiRect::all(ui::Val::Px(5.)),
+                    ..Default::default()
+                },
+                background_color: ui::BackgroundColor(Color::linear_rgba(0., 0., 0., 0.6)),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            ContainerNode,
+            Owned,
+            debug::Bundle::new("FluidDebugOverlay"),
+        ))
+        .with_children(|b| {
+            b.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle { font_size: 12., ..Default::default() },
+                        )],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                OverlayText,
+            ));
+        });
+}
+
+fn reset(mut overlay: ResMut<Overlay>) { *overlay = Overlay::default(); }
+
+fn toggle_overlay_system(keys: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<Overlay>) {
+    if keys.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+/// Moves [`Watch`] markers to the containers and pipes of the focused building.
+fn update_watch_system(
+    mut commands: Commands,
+    mut overlay: ResMut<Overlay>,
+    focus: Res<Focus>,
+    delegate_query: Query<&delegate::Marker<viewable::Sid>>,
+    viewable_index: Res<viewable::SidIndex>,
+    building_query: Query<&FacilityList>,
+    container_query: Query<&container::Pipes, With<container::Marker>>,
+) {
+    if !overlay.is_changed() && !focus.is_changed() {
+        return;
+    }
+
+    for entity in overlay.watched.drain(..) {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<(Watch, ContainerReport, PipeReport)>();
+        }
+    }
+
+    if !overlay.enabled {
+        return;
+    }
+
+    let facilities = focus
+        .entity
+        .and_then(|entity| delegate_query.get(entity).ok())
+        .and_then(|&delegate::Marker(sid)| viewable_index.get(sid))
+        .and_then(|building| building_query.get(building).ok());
+    let Some(facilities) = facilities else { return };
+
+    let mut watched = Vec::new();
+    for facility in facilities.iter() {
+        let Ok(pipes) = container_query.get(facility) else { continue };
+        watched.push(facility);
+        watched.extend(pipes.pipes.iter().copied());
+    }
+    watched.sort_unstable();
+    watched.dedup();
+
+    for &entity in &watched {
+        commands.entity(entity).insert(Watch);
+    }
+    overlay.watched = watched;
+}
+
+fn display_overlay_system(
+    overlay: Res<Overlay>,
+    mut node_query: Query<&mut Visibility, With<ContainerNode>>,
+    mut text_query: Query<&mut Text, With<OverlayText>>,
+    container_query: Query<(Entity, &ContainerReport)>,
+    pipe_query: Query<(Entity, &PipeReport)>,
+) {
+    for mut visibility in &mut node_query {
+        *visibility = if overlay.enabled { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if !overlay.enabled {
+        return;
+    }
+
+    let mut output = String::from("Fluid solver");
+    for (container, report) in container_query.iter_many(&overlay.watched) {
+        _ = write!(
+            output,
+            "\n{container:?}: equilibrium {:.3}{}",
+            report.equilibrium_pressure.quantity,
+            if report.idle { " (idle)" } else { "" },
+        );
+        for (pipe, flow) in &report.pipe_flows {
+            _ = write!(output, "\n  {pipe:?} inflow {:.4}", flow.quantity);
+        }
+    }
+    for (pipe, report) in pipe_query.iter_many(&overlay.watched) {
+        _ = write!(
+            output,
+            "\n{pipe:?}: conductance {:.4}, force {:.4}/{:.4} (pump {:.4}/{:.4}), net a->b {:.4}",
+            report.conductance,
+            report.force.alpha.quantity,
+            report.force.beta.quantity,
+            report.pump_force.alpha.quantity,
+            report.pump_force.beta.quantity,
+            report.ab_mass.quantity,
+        );
+    }
+
+    for mut text in &mut text_query {
+        text.sections[0].value.clone_from(&output);
+    }
+}
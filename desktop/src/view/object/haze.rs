@@ -0,0 +1,151 @@
+//! Renders haze, e.g. smoke, in viewables holding [hazy fluids](traffloat_fluid::config::Haze).
+//!
+//! The haze is a translucent shell around the proximal mesh of the viewable,
+//! with the opacity of the haze opacity metric
+//! and the color of the most abundant hazy fluid synced to the delegate.
+//! Like [tanks](super::tank), haze only appears for viewers subscribed to the fluid metrics.
+
+use bevy::app::{self, App};
+use bevy::asset::{AssetServer, Assets, Handle};
+use bevy::color::{Alpha, Color};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::{Added, With};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
+use bevy::hierarchy::BuildChildren;
+use bevy::pbr::{PbrBundle, StandardMaterial};
+use bevy::render::alpha::AlphaMode;
+use bevy::render::view::Visibility;
+use bevy::state::condition::in_state;
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use traffloat_base::debug;
+use traffloat_fluid::container;
+use traffloat_view::appearance::{Appearance, Layer};
+use traffloat_view::{metrics as view_metrics, viewable};
+
+use super::{layers, metrics, tank};
+use crate::view::delegate;
+use crate::AppState;
+
+/// Fraction of the remaining difference to the target opacity covered per second.
+const OPACITY_SMOOTHING: f32 = 1.;
+
+/// Alpha of the haze shell at full opacity,
+/// below 1 so that the outline of the viewable remains visible.
+const MAX_ALPHA: f32 = 0.75;
+
+/// Haze below this alpha is hidden.
+const MIN_ALPHA: f32 = 0.01;
+
+/// Scale of the haze shell relative to the proximal mesh, avoiding z-fighting.
+const SHELL_SCALE: f32 = 1.02;
+
+/// Color of hazy fluids without a specified color.
+const FALLBACK_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            app::Update,
+            (spawn_haze_system, update_haze_system.after(spawn_haze_system))
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+/// The haze shell of a viewable.
+#[derive(Component)]
+struct HazeShell {
+    /// The delegate viewable owning the shell.
+    delegate: Entity,
+    /// The currently displayed opacity, approaching the synced opacity over time.
+    opacity:  f32,
+}
+
+fn spawn_haze_system(
+    mut commands: Commands,
+    query: Query<(Entity, &Appearance), (Added<Appearance>, With<delegate::Marker<viewable::Sid>>)>,
+    assets: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (delegate, appearance) in &query {
+        let Layer::Pbr { mesh, .. } = appearance.proximal else { continue };
+
+        commands.entity(delegate).with_children(|b| {
+            b.spawn((
+                PbrBundle {
+                    mesh: layers::create_mesh_handle(&assets, mesh),
+                    material: materials.add(StandardMaterial {
+                        base_color: FALLBACK_COLOR.with_alpha(0.),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..Default::default()
+                    }),
+                    transform: Transform::from_scale([SHELL_SCALE; 3].into()),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                HazeShell { delegate, opacity: 0. },
+                debug::Bundle::new("HazeShell"),
+            ));
+        });
+    }
+}
+
+fn update_haze_system(
+    time: Res<Time>,
+    mut shell_query: Query<(&mut HazeShell, &mut Visibility, &Handle<StandardMaterial>)>,
+    known_query: Query<&metrics::Known, With<delegate::Marker<viewable::Sid>>>,
+    metric_query: Query<&view_metrics::ClientTypeData, With<delegate::Marker<view_metrics::Sid>>>,
+    metric_sid_index: Res<delegate::SidIndex<view_metrics::Sid>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let smoothing = 1. - (-OPACITY_SMOOTHING * time.delta_seconds()).exp();
+
+    for (mut shell, mut visibility, material) in &mut shell_query {
+        let Ok(known) = known_query.get(shell.delegate) else { continue };
+
+        let mut target = 0.;
+        let mut dominant: Option<(f32, Color)> = None;
+        for (&ty, &magnitude) in &known.0 {
+            let Some(data) =
+                metric_sid_index.get(ty).and_then(|entity| metric_query.get(entity).ok())
+            else {
+                continue;
+            };
+
+            if data.metadata.contains_key(&container::HAZE_OPACITY_METADATA) {
+                target = magnitude.clamp(0., 1.);
+            } else if data.metadata.contains_key(&container::HAZE_METADATA)
+                && dominant.map_or(true, |(mass, _)| magnitude > mass)
+            {
+                let color = data.metadata.get(&container::COLOR_METADATA);
+                dominant =
+                    Some((magnitude, color.and_then(tank::parse_color).unwrap_or(FALLBACK_COLOR)));
+            }
+        }
+
+        shell.opacity += (target - shell.opacity) * smoothing;
+        let alpha = shell.opacity * MAX_ALPHA;
+
+        let new_visibility =
+            if alpha < MIN_ALPHA { Visibility::Hidden } else { Visibility::Inherited };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+        if new_visibility == Visibility::Hidden {
+            continue;
+        }
+
+        let color = dominant.map_or(FALLBACK_COLOR, |(_, color)| color).with_alpha(alpha);
+        if materials.get(material).is_some_and(|material| material.base_color != color) {
+            if let Some(material) = materials.get_mut(material) {
+                material.base_color = color;
+            }
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use bevy::app::{self, App};
-use bevy::color::Color;
 use bevy::ecs::bundle::Bundle;
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
@@ -7,6 +6,7 @@ use bevy::ecs::event::{Event, EventReader, EventWriter};
 use bevy::ecs::query::With;
 use bevy::ecs::schedule::IntoSystemConfigs;
 use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::ecs::world::World;
 use bevy::hierarchy::{self, BuildChildren, DespawnRecursiveExt, HierarchyQueryExt};
 use bevy::render::view::Visibility;
 use bevy::state::state::{self};
@@ -19,10 +19,12 @@ use bevy_mod_picking::prelude::{self as pick, Pointer};
 use bevy_mod_picking::PickableBundle;
 use traffloat_base::debug;
 use traffloat_base::partition::AppExt;
+use traffloat_graph::ownership::{self, access, faction};
 use traffloat_view::appearance::Appearance;
 use traffloat_view::viewable;
 
 use super::metrics;
+use crate::util::ui_style::Themed;
 use crate::view::delegate;
 use crate::{view, AppState};
 
@@ -40,6 +42,7 @@ impl app::Plugin for Plugin {
         app.add_systems(app::Update, update_hierarchy_system);
         app.add_systems(app::Update, update_box_visibility_system);
         app.add_systems(app::Update, update_viewable_label_system.after(update_hierarchy_system));
+        app.add_systems(app::Update, update_access_label_system.after(update_hierarchy_system));
     }
 }
 
@@ -55,12 +58,11 @@ fn setup(mut commands: Commands) {
                 padding: UiRect::all(ui::Val::Px(5.)),
                 ..Default::default()
             },
-            background_color: ui::BackgroundColor(Color::linear_rgb(0.05, 0.05, 0.15)),
-            border_color: ui::BorderColor(Color::linear_rgb(0.8, 0.6, 0.2)),
             visibility: Visibility::Hidden,
             focus_policy: ui::FocusPolicy::Block,
             ..Default::default()
         },
+        Themed::panel(),
         ContainerNode,
         view::Owned,
         debug::Bundle::new("Infobox"),
@@ -83,6 +85,10 @@ struct RootInfo;
 #[derive(Component)]
 struct LabelDisplay;
 
+/// Marker component for the ownership and access display node.
+#[derive(Component)]
+struct AccessDisplay;
+
 fn update_hierarchy_system(
     mut commands: Commands,
     mut focus_change_events: EventReader<FocusChangeEvent>,
@@ -153,6 +159,7 @@ fn spawn_hierarchy(
             b.spawn((
                 ViewableInfo(viewable_entity),
                 LabelDisplay,
+                Themed::text(),
                 TextBundle {
                     text: Text {
                         sections: vec![TextSection::new(
@@ -168,6 +175,22 @@ fn spawn_hierarchy(
                 },
                 debug::Bundle::new("Infobox/Viewable/Label"),
             ));
+            b.spawn((
+                ViewableInfo(viewable_entity),
+                AccessDisplay,
+                Themed::text(),
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle { font_size: 12., ..Default::default() },
+                        )],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                debug::Bundle::new("Infobox/Viewable/Access"),
+            ));
             metrics::spawn_ui(b, viewable_entity);
         })
         .id();
@@ -226,6 +249,58 @@ fn update_viewable_label_system(
     }
 }
 
+/// Displays the owner and access list of the structure behind each viewable.
+///
+/// This reads the simulation world directly
+/// and is only available in single-player sessions.
+fn update_access_label_system(world: &mut World) {
+    let mut display_query = world.query_filtered::<(Entity, &ViewableInfo), With<AccessDisplay>>();
+    let displays: Vec<_> = display_query
+        .iter(world)
+        .map(|(display, &ViewableInfo(viewable_entity))| (display, viewable_entity))
+        .collect();
+
+    for (display, viewable_entity) in displays {
+        let structure = world
+            .get::<delegate::Marker<viewable::Sid>>(viewable_entity)
+            .and_then(|&delegate::Marker(sid)| world.resource::<viewable::SidIndex>().get(sid));
+        let value = structure.map(|structure| access_summary(world, structure)).unwrap_or_default();
+
+        if let Some(mut text) = world.get_mut::<Text>(display) {
+            let section = text.sections.get_mut(0).expect("set during init");
+            if section.value != value {
+                section.value = value;
+            }
+        }
+    }
+}
+
+fn access_summary(world: &World, structure: Entity) -> String {
+    let Some(owner) = ownership::owner_of(world, structure) else { return String::new() };
+
+    let faction_name = |faction: Entity| {
+        let mut name = String::new();
+        if let Some(label) = world.get::<faction::Label>(faction) {
+            label.label.render(&mut name);
+        }
+        name
+    };
+
+    let mut output = format!("Owner: {}", faction_name(owner));
+    match access::access_of(world, structure) {
+        Some(access) if access.public => output.push_str("\nShared with everyone"),
+        Some(access) if !access.shared.is_empty() => {
+            let mut names: Vec<_> =
+                access.shared.iter().map(|&faction| faction_name(faction)).collect();
+            names.sort_unstable();
+            output.push_str("\nShared with ");
+            output.push_str(&names.join(", "));
+        }
+        _ => {}
+    }
+    output
+}
+
 #[derive(Debug, Resource)]
 pub struct Focus {
     pub entity:     Option<Entity>,
@@ -251,7 +326,7 @@ pub(super) fn object_bundle() -> impl Bundle {
 }
 
 #[derive(Default, Event)]
-struct FocusChangeEvent;
+pub(super) struct FocusChangeEvent;
 
 fn on_object_over(
     event: Listener<Pointer<pick::Over>>,
@@ -1,19 +1,30 @@
+//! Selects the appearance layer of each delegate viewable by its distance to the camera.
+//!
+//! Viewables outside the viewer range are never shown, since the server only sends viewables
+//! found in the spatial index around the viewer.
+//! Among the shown viewables, those outside the camera frustum keep their previous layer
+//! instead of being reevaluated every frame; bevy culls their meshes from rendering.
+//! The numbers of drawn and culled layers are displayed with the other diagnostics.
+
 use std::mem::size_of;
 use std::ops::RangeBounds;
 
 use bevy::app::{self, App};
 use bevy::asset::{AssetServer, Handle};
 use bevy::core_pipeline::core_3d::Camera3d;
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
 use bevy::ecs::bundle::Bundle;
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::query::{With, Without};
 use bevy::ecs::schedule::IntoSystemConfigs;
-use bevy::ecs::system::Query;
+use bevy::ecs::system::{Commands, Query};
 use bevy::gltf::GltfAssetLabel;
+use bevy::hierarchy::BuildChildren;
 use bevy::pbr::{PbrBundle, StandardMaterial};
 use bevy::prelude::SpatialBundle;
 use bevy::render::mesh::Mesh;
+use bevy::render::primitives::{Frustum, Sphere};
 use bevy::state::condition::in_state;
 use bevy::transform::components::{GlobalTransform, Transform};
 use bevy::{hierarchy, render};
@@ -21,17 +32,27 @@ use traffloat_base::debug;
 use traffloat_view::appearance::{self, Layer};
 use traffloat_view::viewable;
 
-use crate::view::delegate;
+use crate::view::{delegate, diagnostics};
 use crate::AppState;
 
 pub(super) struct Plugin;
 
 impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(app::Update, select_layer_system.run_if(in_state(AppState::GameView)));
+        app.register_diagnostic(Diagnostic::new(DIAG_PATH_DRAWN));
+        app.register_diagnostic(Diagnostic::new(DIAG_PATH_CULLED));
+        app.add_systems(app::Startup, register_culling_diagnostic_system);
+        app.add_systems(
+            app::Update,
+            (select_layer_system, update_culling_diagnostic_system)
+                .run_if(in_state(AppState::GameView)),
+        );
     }
 }
 
+const DIAG_PATH_DRAWN: DiagnosticPath = DiagnosticPath::const_new("traffloat/culling/drawn");
+const DIAG_PATH_CULLED: DiagnosticPath = DiagnosticPath::const_new("traffloat/culling/culled");
+
 #[derive(Component)]
 pub(super) struct Layered;
 
@@ -42,7 +63,10 @@ pub(super) struct LayerRefs {
     interior: Entity,
 }
 
-fn create_mesh_handle(assets: &AssetServer, glb_ref: appearance::GlbMeshRef) -> Handle<Mesh> {
+pub(super) fn create_mesh_handle(
+    assets: &AssetServer,
+    glb_ref: appearance::GlbMeshRef,
+) -> Handle<Mesh> {
     let mut path_buf = vec![0u8; size_of::<appearance::GlbSha>() * 2 + 4];
     hex::encode_to_slice(glb_ref.sha.0, &mut path_buf[..glb_ref.sha.0.len() * 2]).unwrap();
     path_buf[glb_ref.sha.0.len() * 2..].copy_from_slice(b".glb");
@@ -121,16 +145,25 @@ pub(super) fn select_layer_system(
         (With<delegate::Marker<viewable::Sid>>, Without<Layered>),
     >,
     mut layer_query: Query<&mut render::view::Visibility, With<Layered>>,
-    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    camera_query: Query<(&GlobalTransform, &Frustum), With<Camera3d>>,
 ) {
-    let Ok(camera_pos) = camera_query.get_single() else { return };
+    let Ok((camera_pos, frustum)) = camera_query.get_single() else { return };
 
     parent_query.iter().filter(|(_, vis, _, _)| vis.get()).for_each(
         |(_, _, layers, parent_pos)| {
+            let determinant = parent_pos.affine().matrix3.determinant();
+
+            // The layer selection of objects outside the frustum does not affect the frame.
+            let bounds =
+                Sphere { center: parent_pos.translation_vec3a(), radius: determinant.abs().cbrt() };
+            if !frustum.intersects_sphere(&bounds, true) {
+                return;
+            }
+
             let distance_sq = parent_pos.translation().distance_squared(camera_pos.translation());
 
             // rough estimate display area of the object assuming it is a 1x1x1 cube.
-            let transform_det_23 = parent_pos.affine().matrix3.determinant().powf(2. / 3.);
+            let transform_det_23 = determinant.powf(2. / 3.);
             let far_dist_sq = transform_det_23 * 16.; // TODO make this magic number configurable
 
             update_layer(&mut layer_query, layers.distal, far_dist_sq.., distance_sq);
@@ -190,3 +223,55 @@ pub(super) fn spawn_all(
     );
     LayerRefs { distal, proximal, interior }
 }
+
+fn register_culling_diagnostic_system(mut commands: Commands) {
+    commands
+        .spawn((
+            diagnostics::DisplayGroup::builder()
+                .vertical_priority(15)
+                .id("culling")
+                .label("Culling")
+                .build(),
+            debug::Bundle::new("CullingDiagnostic"),
+        ))
+        .with_children(|b| {
+            b.spawn(
+                diagnostics::Display::builder()
+                    .horizontal_priority(0)
+                    .label("Drawn")
+                    .target(DIAG_PATH_DRAWN)
+                    .build(),
+            );
+            b.spawn(
+                diagnostics::Display::builder()
+                    .horizontal_priority(1)
+                    .label("Culled")
+                    .target(DIAG_PATH_CULLED)
+                    .build(),
+            );
+        });
+}
+
+/// Counts the layers selected for display that were drawn or culled in the last frame.
+#[allow(clippy::cast_precision_loss)] // layer counts are far below 2^52
+fn update_culling_diagnostic_system(
+    mut diagnostics: Diagnostics,
+    layer_query: Query<
+        (&render::view::InheritedVisibility, &render::view::ViewVisibility),
+        With<Layered>,
+    >,
+) {
+    let (mut drawn, mut culled) = (0usize, 0usize);
+    for (inherited, view) in &layer_query {
+        if inherited.get() {
+            if view.get() {
+                drawn += 1;
+            } else {
+                culled += 1;
+            }
+        }
+    }
+
+    diagnostics.add_measurement(&DIAG_PATH_DRAWN, || drawn as f64);
+    diagnostics.add_measurement(&DIAG_PATH_CULLED, || culled as f64);
+}
@@ -12,8 +12,9 @@ use bevy::hierarchy::ChildBuilder;
 use bevy::text::{Text, TextSection, TextStyle};
 use bevy::ui::node_bundles::TextBundle;
 use traffloat_base::{debug, EventReaderSystemSet};
-use traffloat_view::{metrics as view_metrics, viewable};
+use traffloat_view::{format, metrics as view_metrics, viewable};
 
+use crate::util::ui_style::{Theme, ThemeColor};
 use crate::view::delegate;
 
 pub(super) struct Plugin;
@@ -31,8 +32,9 @@ impl app::Plugin for Plugin {
     }
 }
 
+/// The last received magnitude of each metric type for a delegate viewable.
 #[derive(Component)]
-struct Known(BTreeMap<view_metrics::Sid, f32>);
+pub(super) struct Known(pub(super) BTreeMap<view_metrics::Sid, f32>);
 
 pub(super) fn object_bundle() -> impl Bundle { (Known(BTreeMap::new()),) }
 
@@ -83,27 +85,36 @@ fn update_text_system(
     object_query: Query<&Known, With<delegate::Marker<viewable::Sid>>>,
     metric_query: Query<&view_metrics::ClientTypeData, With<delegate::Marker<view_metrics::Sid>>>,
     metric_sid_index: Res<delegate::SidIndex<view_metrics::Sid>>,
+    formatter: Res<format::Formatter>,
+    theme: Res<Theme>,
 ) {
     for (mut display, &ValueDisplay(viewable_entity)) in &mut display_query {
         let Ok(object_known) = object_query.get(viewable_entity) else { return };
 
         display.sections.clear();
         display.sections.extend(object_known.0.iter().map(|(&ty, &value)| {
-            let ty_label = if let Some(entity) = metric_sid_index.get(ty) {
+            let (ty_label, unit) = if let Some(entity) = metric_sid_index.get(ty) {
                 match metric_query.get(entity) {
-                    Ok(def) => def.display_label.render_to_string(),
+                    Ok(def) => (
+                        def.display_label.render_to_string(),
+                        format::Unit::from_metadata(&def.metadata),
+                    ),
                     Err(err) => {
                         bevy::log::warn!("metric SID has invalid metric delegate entity: {err:?}");
-                        format!("{ty:?}")
+                        (format!("{ty:?}"), format::Unit::Scalar)
                     }
                 }
             } else {
                 bevy::log::warn!("object has invalid metric SID: {ty:?}");
-                format!("{ty:?}")
+                (format!("{ty:?}"), format::Unit::Scalar)
             };
             TextSection::new(
-                format!("{ty_label}: {value}\n"),
-                TextStyle { font_size: 16., ..Default::default() },
+                format!("{ty_label}: {}\n", formatter.format(value, unit)),
+                TextStyle {
+                    font_size: 16.,
+                    color: theme.get(ThemeColor::Text),
+                    ..Default::default()
+                },
             )
         }));
     }
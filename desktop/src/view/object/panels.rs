@@ -0,0 +1,260 @@
+//! Dashboard panels declared by mods and scenarios.
+//!
+//! Each `panels/*.json` file in the asset directory declares a panel
+//! with a title, a screen corner and a list of rows:
+//!
+//! ```json
+//! {
+//!     "title": "Life support",
+//!     "anchor": "topRight",
+//!     "rows": [
+//!         {"type": "metric", "label": "Hazard", "metric": "Hazard", "aggregation": "max"},
+//!         {"type": "text", "text": "Keep the hazard below 50%."}
+//!     ]
+//! }
+//! ```
+//!
+//! A metric row is bound to the metric type with the display label `metric`,
+//! aggregated over all objects visible to the client.
+//! Panels are spawned in the order of their file names when entering the game view.
+
+use std::fs;
+use std::path::Path;
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::query::With;
+use bevy::ecs::system::{Commands, Query, Res};
+use bevy::hierarchy::{BuildChildren, ChildBuilder};
+use bevy::state::state;
+use bevy::text::{Text, TextSection, TextStyle};
+use bevy::ui::node_bundles::{NodeBundle, TextBundle};
+use bevy::ui::{self, Style, UiRect};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use traffloat_base::debug;
+use traffloat_view::{format, metrics as view_metrics, viewable};
+
+use super::metrics::Known;
+use crate::options::Options;
+use crate::util::ui_style::{Theme, ThemeColor, Themed};
+use crate::view::delegate;
+use crate::{view, AppState};
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(app::Update, update_metric_rows_system);
+    }
+}
+
+/// Directory of panel declarations relative to the asset directory.
+const PANELS_DIR: &str = "panels";
+
+#[derive(Deserialize)]
+struct PanelDef {
+    title:  String,
+    #[serde(default)]
+    anchor: Anchor,
+    rows:   Vec<RowDef>,
+}
+
+/// The screen corner at which a panel is placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Anchor {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RowDef {
+    /// Static text.
+    Text { text: String },
+    /// The aggregated value of a metric type.
+    Metric {
+        label:       String,
+        /// Display label of the metric type.
+        metric:      String,
+        #[serde(default)]
+        aggregation: Aggregation,
+    },
+}
+
+/// How metric values of all visible objects are combined.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Aggregation {
+    #[default]
+    Sum,
+    Mean,
+    Max,
+}
+
+impl Aggregation {
+    fn apply(self, values: &[f32]) -> Option<f32> {
+        if values.is_empty() {
+            return None;
+        }
+        let sum: f32 = values.iter().sum();
+        Some(match self {
+            Self::Sum => sum,
+            #[allow(clippy::cast_precision_loss)] // the number of visible objects is small
+            Self::Mean => sum / values.len() as f32,
+            Self::Max => values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        })
+    }
+}
+
+/// A text node displaying the aggregated value of a metric type.
+#[derive(Component)]
+struct MetricRow {
+    label:       String,
+    metric:      String,
+    aggregation: Aggregation,
+}
+
+fn setup(mut commands: Commands, options: Res<Options>) {
+    let panels = read_panels(&options.asset_dir.join(PANELS_DIR));
+
+    let mut columns = HashMap::new();
+    for (name, def) in panels {
+        let column = *columns.entry(def.anchor).or_insert_with(|| {
+            let (justify_self, align_self) = match def.anchor {
+                Anchor::TopLeft => (ui::JustifySelf::Start, ui::AlignSelf::Start),
+                Anchor::TopRight => (ui::JustifySelf::End, ui::AlignSelf::Start),
+                Anchor::BottomLeft => (ui::JustifySelf::Start, ui::AlignSelf::End),
+                Anchor::BottomRight => (ui::JustifySelf::End, ui::AlignSelf::End),
+            };
+            commands
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            justify_self,
+                            align_self,
+                            flex_direction: ui::FlexDirection::Column,
+                            row_gap: ui::Val::Px(5.),
+                            margin: UiRect::all(ui::Val::Px(5.)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    view::Owned,
+                    debug::Bundle::new("PanelColumn"),
+                ))
+                .id()
+        });
+
+        commands.entity(column).with_children(|b| spawn_panel(b, &name, def));
+    }
+}
+
+fn read_panels(dir: &Path) -> Vec<(String, PanelDef)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            bevy::log::info!("no panels at {}: {err}", dir.display());
+            return Vec::new();
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let result = fs::read(&path).map_err(|err| err.to_string()).and_then(|bytes| {
+                serde_json::from_slice::<PanelDef>(&bytes).map_err(|err| err.to_string())
+            });
+            match result {
+                Ok(def) => Some((path.display().to_string(), def)),
+                Err(err) => {
+                    bevy::log::error!("invalid panel {}: {err}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn spawn_panel(b: &mut ChildBuilder, name: &str, def: PanelDef) {
+    let name = name.to_owned();
+    b.spawn((
+        NodeBundle {
+            style: Style {
+                flex_direction: ui::FlexDirection::Column,
+                border: UiRect::all(ui::Val::Px(2.)),
+                padding: UiRect::all(ui::Val::Px(5.)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Themed::panel(),
+        debug::Bundle::new_with(move || format!("Panel({name})")),
+    ))
+    .with_children(|b| {
+        b.spawn((
+            TextBundle::from_section(def.title, TextStyle { font_size: 16., ..Default::default() }),
+            Themed::text(),
+            debug::Bundle::new("PanelTitle"),
+        ));
+
+        for row in def.rows {
+            match row {
+                RowDef::Text { text } => {
+                    b.spawn((
+                        TextBundle::from_section(
+                            text,
+                            TextStyle { font_size: 12., ..Default::default() },
+                        ),
+                        Themed::text(),
+                        debug::Bundle::new("PanelText"),
+                    ));
+                }
+                RowDef::Metric { label, metric, aggregation } => {
+                    b.spawn((
+                        TextBundle { text: Text::from_sections([]), ..Default::default() },
+                        MetricRow { label, metric, aggregation },
+                        debug::Bundle::new("PanelMetric"),
+                    ));
+                }
+            }
+        }
+    });
+}
+
+fn update_metric_rows_system(
+    mut row_query: Query<(&mut Text, &MetricRow)>,
+    object_query: Query<&Known, With<delegate::Marker<viewable::Sid>>>,
+    metric_query: Query<(&view_metrics::ClientTypeData, &delegate::Marker<view_metrics::Sid>)>,
+    formatter: Res<format::Formatter>,
+    theme: Res<Theme>,
+) {
+    for (mut display, row) in &mut row_query {
+        let ty =
+            metric_query.iter().find(|(def, _)| def.display_label.render_to_string() == row.metric);
+
+        let value = ty.and_then(|(def, &delegate::Marker(sid))| {
+            let values: Vec<f32> =
+                object_query.iter().filter_map(|known| known.0.get(&sid).copied()).collect();
+            let value = row.aggregation.apply(&values)?;
+            Some(formatter.format(value, format::Unit::from_metadata(&def.metadata)))
+        });
+
+        display.sections.clear();
+        display.sections.push(TextSection::new(
+            format!("{}: {}", row.label, value.as_deref().unwrap_or("-")),
+            TextStyle { font_size: 12., color: theme.get(ThemeColor::Text), ..Default::default() },
+        ));
+    }
+}
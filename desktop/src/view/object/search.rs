@@ -0,0 +1,204 @@
+//! Ctrl+K opens a palette to search visible objects by label.
+//!
+//! While the palette is open, keyboard input is captured by the palette
+//! and systems in [`InputSystemSet`] are suspended.
+//! Enter focuses the best match; Escape closes the palette.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{EventReader, EventWriter};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::{IntoSystemConfigs, IntoSystemSetConfigs};
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::hierarchy::BuildChildren;
+use bevy::input::keyboard::{Key, KeyCode, KeyboardInput};
+use bevy::input::{ButtonInput, ButtonState};
+use bevy::render::view::Visibility;
+use bevy::state::condition::in_state;
+use bevy::state::state;
+use bevy::text::{Text, TextSection, TextStyle};
+use bevy::ui::node_bundles::{NodeBundle, TextBundle};
+use bevy::ui::{self, Style, UiRect};
+use traffloat_base::debug;
+use traffloat_view::appearance::Appearance;
+use traffloat_view::{search, viewable};
+
+use super::infobox::{Focus, FocusChangeEvent, FocusType};
+use crate::util::ui_style::Themed;
+use crate::view::{delegate, InputSystemSet, Owned};
+use crate::AppState;
+
+/// Maximum number of results displayed in the palette.
+const MAX_RESULTS: usize = 8;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Palette>();
+        app.configure_sets(app::Update, InputSystemSet.run_if(is_palette_closed));
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(state::OnExit(AppState::GameView), reset);
+        app.add_systems(
+            app::Update,
+            (input_palette_system.before(InputSystemSet), update_palette_text_system)
+                .chain()
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+/// State of the search palette.
+#[derive(Default, Resource)]
+struct Palette {
+    open:  bool,
+    query: String,
+}
+
+fn is_palette_closed(palette: Res<Palette>) -> bool { !palette.open }
+
+/// Marker component for the palette container node.
+#[derive(Component)]
+struct ContainerNode;
+
+/// Marker component for the palette text.
+#[derive(Component)]
+struct PaletteText;
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: ui::Val::Px(400.),
+                    justify_self: ui::JustifySelf::Center,
+                    align_self: ui::AlignSelf::Start,
+                    margin: UiRect::top(ui::Val::Px(50.)),
+                    border: UiRect::all(ui::Val::Px(2.)),
+                    padding: UiRect::all(ui::Val::Px(5.)),
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                focus_policy: ui::FocusPolicy::Block,
+                ..Default::default()
+            },
+            Themed::panel(),
+            ContainerNode,
+            Owned,
+            debug::Bundle::new("SearchPalette"),
+        ))
+        .with_children(|b| {
+            b.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle { font_size: 16., ..Default::default() },
+                        )],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                PaletteText,
+                Themed::text(),
+            ));
+        });
+}
+
+fn reset(mut palette: ResMut<Palette>) { *palette = Palette::default(); }
+
+/// Collects the labels of all visible objects into a search index.
+fn build_index(
+    delegate_query: &Query<(Entity, &Appearance), With<delegate::Marker<viewable::Sid>>>,
+) -> search::Index<Entity> {
+    let mut index = search::Index::default();
+    for (entity, appearance) in delegate_query {
+        let label = appearance.label.render_to_string();
+        if !label.is_empty() {
+            index.insert(label, entity);
+        }
+    }
+    index
+}
+
+fn input_palette_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut key_reader: EventReader<KeyboardInput>,
+    mut palette: ResMut<Palette>,
+    delegate_query: Query<(Entity, &Appearance), With<delegate::Marker<viewable::Sid>>>,
+    mut focus: ResMut<Focus>,
+    mut focus_change_writer: EventWriter<FocusChangeEvent>,
+) {
+    let is_ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if is_ctrl && keys.just_pressed(KeyCode::KeyK) {
+        palette.open = !palette.open;
+        palette.query.clear();
+        key_reader.clear();
+        return;
+    }
+
+    if !palette.open {
+        key_reader.clear();
+        return;
+    }
+
+    for event in key_reader.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Escape => {
+                palette.open = false;
+                palette.query.clear();
+            }
+            Key::Enter => {
+                let index = build_index(&delegate_query);
+                if let Some(result) = index.search(&palette.query, 1).first() {
+                    focus.entity = Some(*result.target);
+                    focus.focus_type = FocusType::Locked;
+                    focus_change_writer.send_default();
+                }
+                palette.open = false;
+                palette.query.clear();
+            }
+            Key::Backspace => {
+                palette.query.pop();
+            }
+            Key::Space => palette.query.push(' '),
+            Key::Character(chars) if !is_ctrl => palette.query.push_str(chars),
+            _ => {}
+        }
+    }
+}
+
+fn update_palette_text_system(
+    palette: Res<Palette>,
+    mut container_query: Query<&mut Visibility, With<ContainerNode>>,
+    mut text_query: Query<&mut Text, With<PaletteText>>,
+    delegate_query: Query<(Entity, &Appearance), With<delegate::Marker<viewable::Sid>>>,
+) {
+    if !palette.is_changed() {
+        return;
+    }
+
+    for mut visibility in &mut container_query {
+        *visibility = if palette.open { Visibility::Visible } else { Visibility::Hidden };
+    }
+
+    if !palette.open {
+        return;
+    }
+
+    let index = build_index(&delegate_query);
+    let mut output = format!("> {}", palette.query);
+    for result in index.search(&palette.query, MAX_RESULTS) {
+        output.push('\n');
+        output.push_str(result.label);
+    }
+
+    for mut text in &mut text_query {
+        text.sections[0].value.clone_from(&output);
+    }
+}
@@ -0,0 +1,144 @@
+//! Renders the fluid level inside viewables with a [tank](appearance::Tank).
+//!
+//! The fill level and the dominant fluid are derived from the metrics synced to the delegate,
+//! so tanks only animate for viewers subscribed to the fluid metrics.
+
+use bevy::app::{self, App};
+use bevy::asset::{AssetServer, Assets, Handle};
+use bevy::color::{Alpha, Color};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::{Added, With};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
+use bevy::hierarchy::BuildChildren;
+use bevy::pbr::{PbrBundle, StandardMaterial};
+use bevy::render::alpha::AlphaMode;
+use bevy::state::condition::in_state;
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use traffloat_base::debug;
+use traffloat_fluid::container;
+use traffloat_view::appearance::{self, Appearance};
+use traffloat_view::{metrics as view_metrics, viewable};
+
+use super::{layers, metrics};
+use crate::view::delegate;
+use crate::AppState;
+
+/// Fraction of the remaining distance to the target level covered per second.
+const LEVEL_SMOOTHING: f32 = 2.;
+
+/// Color of fluids without a specified color.
+const FALLBACK_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            app::Update,
+            (spawn_tank_system, update_tank_system.after(spawn_tank_system))
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+/// The fluid mesh of a tank.
+#[derive(Component)]
+struct TankFluid {
+    /// The delegate viewable owning the tank.
+    delegate: Entity,
+    tank:     appearance::Tank,
+    /// The currently displayed fill level, approaching the synced level over time.
+    level:    f32,
+}
+
+fn spawn_tank_system(
+    mut commands: Commands,
+    query: Query<(Entity, &Appearance), (Added<Appearance>, With<delegate::Marker<viewable::Sid>>)>,
+    assets: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (delegate, appearance) in &query {
+        let Some(tank) = appearance.tank else { continue };
+
+        commands.entity(delegate).with_children(|b| {
+            b.spawn((
+                PbrBundle {
+                    mesh: layers::create_mesh_handle(&assets, tank.mesh),
+                    material: materials.add(StandardMaterial {
+                        base_color: FALLBACK_COLOR.with_alpha(0.8),
+                        alpha_mode: AlphaMode::Blend,
+                        ..Default::default()
+                    }),
+                    transform: level_transform(tank, 0.),
+                    ..Default::default()
+                },
+                TankFluid { delegate, tank, level: 0. },
+                debug::Bundle::new("TankFluid"),
+            ));
+        });
+    }
+}
+
+fn update_tank_system(
+    time: Res<Time>,
+    mut tank_query: Query<(&mut TankFluid, &mut Transform, &Handle<StandardMaterial>)>,
+    known_query: Query<&metrics::Known, With<delegate::Marker<viewable::Sid>>>,
+    metric_query: Query<&view_metrics::ClientTypeData, With<delegate::Marker<view_metrics::Sid>>>,
+    metric_sid_index: Res<delegate::SidIndex<view_metrics::Sid>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let smoothing = 1. - (-LEVEL_SMOOTHING * time.delta_seconds()).exp();
+
+    for (mut fluid, mut transform, material) in &mut tank_query {
+        let Ok(known) = known_query.get(fluid.delegate) else { continue };
+
+        let mut target = None;
+        let mut dominant: Option<(f32, Color)> = None;
+        for (&ty, &magnitude) in &known.0 {
+            let Some(data) =
+                metric_sid_index.get(ty).and_then(|entity| metric_query.get(entity).ok())
+            else {
+                continue;
+            };
+
+            if data.metadata.contains_key(&container::FILL_LEVEL_METADATA) {
+                target = Some(magnitude.clamp(0., 1.));
+            } else if let Some(color) = data.metadata.get(&container::COLOR_METADATA) {
+                if dominant.map_or(true, |(mass, _)| magnitude > mass) {
+                    dominant = Some((magnitude, parse_color(color).unwrap_or(FALLBACK_COLOR)));
+                }
+            }
+        }
+
+        if let Some(target) = target {
+            fluid.level += (target - fluid.level) * smoothing;
+            *transform = level_transform(fluid.tank, fluid.level);
+        }
+
+        let color = dominant.map_or(FALLBACK_COLOR, |(_, color)| color).with_alpha(0.8);
+        if materials.get(material).is_some_and(|material| material.base_color != color) {
+            if let Some(material) = materials.get_mut(material) {
+                material.base_color = color;
+            }
+        }
+    }
+}
+
+/// Scales the full tank mesh along the Y axis, keeping the tank floor in place.
+fn level_transform(tank: appearance::Tank, level: f32) -> Transform {
+    // Zero scale produces a degenerate transform.
+    let level = level.max(1e-3);
+    let mut transform = Transform::from_xyz(0., tank.bottom * (1. - level), 0.);
+    transform.scale.y = level;
+    transform
+}
+
+pub(super) fn parse_color(value: &serde_json::Value) -> Option<Color> {
+    let [r, g, b] = value.as_array()?.as_slice() else { return None };
+    #[allow(clippy::cast_possible_truncation)] // colors do not need f64 precision
+    let channel = |value: &serde_json::Value| value.as_f64().map(|v| v as f32);
+    Some(Color::srgb(channel(r)?, channel(g)?, channel(b)?))
+}
@@ -0,0 +1,320 @@
+//! First-person walk mode inside buildings.
+//!
+//! F enters the focused building, placing the camera at its center.
+//! WASD walks on the horizontal plane of the camera and the arrow keys look around.
+//! Movement is confined to the building, which is modelled as a unit sphere scaled by its transform.
+//! Each corridor of the building is a door on the sphere towards the other endpoint;
+//! E walks through the nearest door into the adjacent building.
+//! F or Escape returns to the orbit camera.
+//!
+//! The interior itself is the [interior layer](traffloat_view::appearance::Appearance::interior)
+//! of the building, displayed automatically when the camera is within its bounds.
+//!
+//! This mode reads the simulation world directly
+//! and is only available in single-player sessions.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::app::{self, App};
+use bevy::color::Color;
+use bevy::core_pipeline::core_3d::Camera3d;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::{With, Without};
+use bevy::ecs::schedule::{Condition, IntoSystemConfigs};
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::hierarchy::BuildChildren;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::ButtonInput;
+use bevy::math::{EulerRot, Quat, Vec3};
+use bevy::render::view::Visibility;
+use bevy::state::condition::in_state;
+use bevy::state::state::{self, NextState};
+use bevy::text::{Text, TextSection, TextStyle};
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use bevy::ui::node_bundles::{NodeBundle, TextBundle};
+use bevy::ui::{self, Style, UiRect};
+use traffloat_base::debug;
+use traffloat_graph::{building, corridor};
+use traffloat_view::appearance::Appearance;
+use traffloat_view::viewable;
+
+use super::infobox::Focus;
+use crate::view::{delegate, InputSystemSet, Owned, ViewMode};
+use crate::AppState;
+
+/// Walking speed in world units per second.
+const WALK_DISTANCE_PER_SECOND: f32 = 2.;
+/// Looking speed in radians per second.
+const LOOK_ANGLE_PER_SECOND: f32 = 1.5;
+/// Maximum distance from the building center in the unit sphere space of the building.
+const WALK_RADIUS: f32 = 0.8;
+/// Distance from a door within which E walks through it, in the unit sphere space.
+const DOOR_RANGE: f32 = 0.3;
+/// Maximum pitch, slightly less than vertical to avoid gimbal flips.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.1;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(state::OnEnter(AppState::GameView), setup);
+        app.add_systems(state::OnExit(ViewMode::Walk), exit_walk);
+        app.add_systems(
+            app::Update,
+            (
+                (
+                    enter_walk_system
+                        .run_if(in_state(ViewMode::Orbit))
+                        .run_if(input_just_pressed(KeyCode::KeyF)),
+                    leave_walk_system.run_if(in_state(ViewMode::Walk)).run_if(
+                        input_just_pressed(KeyCode::KeyF)
+                            .or_else(input_just_pressed(KeyCode::Escape)),
+                    ),
+                )
+                    .chain()
+                    .in_set(InputSystemSet),
+                (
+                    walk_input_system.in_set(InputSystemSet).after(leave_walk_system),
+                    door_system.in_set(InputSystemSet).after(walk_input_system),
+                    display_hint_system.after(door_system),
+                )
+                    .run_if(in_state(ViewMode::Walk)),
+            )
+                .run_if(in_state(AppState::GameView)),
+        );
+    }
+}
+
+/// State of the walk mode, present while in [`ViewMode::Walk`].
+#[derive(Resource)]
+struct Walk {
+    /// The simulation entity of the building the camera is in.
+    building:     Entity,
+    /// The camera transform to restore when returning to orbit mode.
+    orbit_camera: Transform,
+    /// Rotation of the camera about the world Y axis.
+    yaw:          f32,
+    /// Rotation of the camera about its local X axis.
+    pitch:        f32,
+    /// The door the camera is close to, if any.
+    near_door:    Option<Door>,
+}
+
+/// A corridor leading out of the current building.
+#[derive(Clone, Copy)]
+struct Door {
+    /// The building at the other end of the corridor.
+    other:     Entity,
+    /// Direction from the current building center towards the other building, in world space.
+    direction: Vec3,
+}
+
+/// Marker component for the hint container node.
+#[derive(Component)]
+struct HintNode;
+
+/// Marker component for the hint text.
+#[derive(Component)]
+struct HintText;
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_self: ui::JustifySelf::Center,
+                    align_self: ui::AlignSelf::Center,
+                    margin: UiRect::top(ui::Val::Percent(20.)),
+                    padding: UiRect::all(ui::Val::Px(5.)),
+                    ..Default::default()
+                },
+                background_color: ui::BackgroundColor(Color::linear_rgba(0., 0., 0., 0.6)),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            HintNode,
+            Owned,
+            debug::Bundle::new("WalkHint"),
+        ))
+        .with_children(|b| {
+            b.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle { font_size: 16., ..Default::default() },
+                        )],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                HintText,
+            ));
+        });
+}
+
+fn enter_walk_system(
+    mut commands: Commands,
+    mut next_mode: ResMut<NextState<ViewMode>>,
+    focus: Res<Focus>,
+    delegate_query: Query<&delegate::Marker<viewable::Sid>>,
+    viewable_index: Res<viewable::SidIndex>,
+    building_query: Query<(), With<building::Marker>>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+) {
+    let building = focus
+        .entity
+        .and_then(|entity| delegate_query.get(entity).ok())
+        .and_then(|&delegate::Marker(sid)| viewable_index.get(sid))
+        .filter(|&building| building_query.contains(building));
+    let Some(building) = building else { return };
+    let Ok(&orbit_camera) = camera_query.get_single() else { return };
+
+    commands.insert_resource(Walk {
+        building,
+        orbit_camera,
+        yaw: yaw_towards(*orbit_camera.forward()),
+        pitch: 0.,
+        near_door: None,
+    });
+    next_mode.set(ViewMode::Walk);
+}
+
+fn leave_walk_system(mut next_mode: ResMut<NextState<ViewMode>>) { next_mode.set(ViewMode::Orbit); }
+
+fn exit_walk(
+    mut commands: Commands,
+    walk: Option<Res<Walk>>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+    mut hint_query: Query<&mut Visibility, With<HintNode>>,
+) {
+    if let (Some(walk), Ok(mut camera)) = (walk, camera_query.get_single_mut()) {
+        *camera = walk.orbit_camera;
+    }
+    for mut visibility in &mut hint_query {
+        *visibility = Visibility::Hidden;
+    }
+    commands.remove_resource::<Walk>();
+}
+
+fn walk_input_system(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    walk: Option<ResMut<Walk>>,
+    building_query: Query<&Transform, (With<building::Marker>, Without<Camera3d>)>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let Some(mut walk) = walk else { return };
+    let Ok(mut camera) = camera_query.get_single_mut() else { return };
+    let Ok(building_tf) = building_query.get(walk.building) else { return };
+
+    let look_speed = time.delta_seconds() * LOOK_ANGLE_PER_SECOND;
+    for (key, yaw, pitch) in [
+        (KeyCode::ArrowLeft, 1., 0.),
+        (KeyCode::ArrowRight, -1., 0.),
+        (KeyCode::ArrowUp, 0., 1.),
+        (KeyCode::ArrowDown, 0., -1.),
+    ] {
+        if keys.pressed(key) {
+            walk.yaw += yaw * look_speed;
+            walk.pitch = (walk.pitch + pitch * look_speed).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+    }
+    let heading = Quat::from_rotation_y(walk.yaw);
+    camera.rotation = Quat::from_euler(EulerRot::YXZ, walk.yaw, walk.pitch, 0.);
+
+    let mut step = Vec3::ZERO;
+    for (key, direction) in [
+        (KeyCode::KeyW, Vec3::NEG_Z),
+        (KeyCode::KeyS, Vec3::Z),
+        (KeyCode::KeyA, Vec3::NEG_X),
+        (KeyCode::KeyD, Vec3::X),
+    ] {
+        if keys.pressed(key) {
+            step += direction;
+        }
+    }
+    let step = heading * step.normalize_or_zero() * time.delta_seconds() * WALK_DISTANCE_PER_SECOND;
+
+    let building_affine = building_tf.compute_affine();
+    let local = building_affine.inverse().transform_point3(camera.translation + step);
+    camera.translation = building_affine.transform_point3(local.clamp_length_max(WALK_RADIUS));
+}
+
+fn door_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    walk: Option<ResMut<Walk>>,
+    building_query: Query<&Transform, (With<building::Marker>, Without<Camera3d>)>,
+    corridor_query: Query<&corridor::Endpoints, With<corridor::Marker>>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let Some(mut walk) = walk else { return };
+    let Ok(mut camera) = camera_query.get_single_mut() else { return };
+    let Ok(building_tf) = building_query.get(walk.building) else { return };
+
+    let inverse = building_tf.compute_affine().inverse();
+    let camera_local = inverse.transform_point3(camera.translation);
+
+    walk.near_door = corridor_query
+        .iter()
+        .filter_map(|endpoints| {
+            let other = match endpoints.endpoints {
+                corridor::Binary { alpha, beta } if alpha == walk.building => beta,
+                corridor::Binary { alpha, beta } if beta == walk.building => alpha,
+                _ => return None,
+            };
+            let other_tf = building_query.get(other).ok()?;
+            let direction = (other_tf.translation - building_tf.translation).normalize_or_zero();
+            let door_local = inverse.transform_vector3(direction).normalize_or_zero() * WALK_RADIUS;
+            let distance = door_local.distance(camera_local);
+            (distance < DOOR_RANGE).then_some((distance, Door { other, direction }))
+        })
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, door)| door);
+
+    let Some(door) = walk.near_door else { return };
+    if !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let Ok(other_tf) = building_query.get(door.other) else { return };
+
+    // Arrive just inside the door of the other building leading back, facing inwards.
+    let other_affine = other_tf.compute_affine();
+    let entry_local = other_affine.inverse().transform_vector3(-door.direction).normalize_or_zero()
+        * (WALK_RADIUS - DOOR_RANGE * 1.5);
+    camera.translation = other_affine.transform_point3(entry_local);
+    walk.building = door.other;
+    walk.yaw = yaw_towards(door.direction);
+    walk.pitch = 0.;
+    walk.near_door = None;
+}
+
+fn display_hint_system(
+    walk: Option<Res<Walk>>,
+    appearance_query: Query<&Appearance, With<building::Marker>>,
+    mut node_query: Query<&mut Visibility, With<HintNode>>,
+    mut text_query: Query<&mut Text, With<HintText>>,
+) {
+    let hint = walk.and_then(|walk| walk.near_door).map(|door| {
+        let label = appearance_query.get(door.other).map_or_else(
+            |_| String::from("next building"),
+            |appearance| appearance.label.render_to_string(),
+        );
+        format!("E: walk to {label}")
+    });
+
+    for mut visibility in &mut node_query {
+        *visibility = if hint.is_some() { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if let Some(hint) = hint {
+        for mut text in &mut text_query {
+            text.sections[0].value.clone_from(&hint);
+        }
+    }
+}
+
+/// Computes the yaw about the world Y axis at which the camera faces `direction`.
+fn yaw_towards(direction: Vec3) -> f32 { f32::atan2(-direction.x, -direction.z) }
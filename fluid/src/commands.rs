@@ -1,7 +1,8 @@
 use bevy::ecs::entity::Entity;
 use bevy::ecs::system::{Commands, Query, SystemState};
 use bevy::ecs::world::{Command, World};
-use bevy::hierarchy::{self, BuildChildren};
+use bevy::hierarchy::{self, BuildChildren, BuildWorldChildren};
+use traffloat_base::Ref;
 use traffloat_graph::corridor::Binary;
 use typed_builder::TypedBuilder;
 
@@ -11,7 +12,7 @@ use crate::{config, container, pipe, units};
 #[derive(TypedBuilder)]
 pub struct CreateContainerElement {
     /// The container to add element to.
-    pub container: Entity,
+    pub container: Ref<container::Marker>,
     /// The element fluid type.
     pub ty:        config::Type,
     /// The initial mass of fluid.
@@ -68,9 +69,10 @@ impl Command for CreateContainerElement {
             });
         }
 
-        let container_element =
-            world.spawn(container::element::Bundle::builder().ty(self.ty).mass(self.mass).build());
-        let container_element = container_element.id();
+        let container_element = world
+            .spawn(container::element::Bundle::builder().ty(self.ty).mass(self.mass).build())
+            .set_parent(self.container.entity())
+            .id();
 
         let mut state = SystemState::<(
             Commands,
@@ -82,7 +84,7 @@ impl Command for CreateContainerElement {
             state.get_mut(world);
 
         let pipes = container_query
-            .get(self.container)
+            .get(self.container.entity())
             .expect("CreateContainerElement.container must be a container entity");
 
         for &pipe in &pipes.pipes {
@@ -90,11 +92,13 @@ impl Command for CreateContainerElement {
                 &mut commands,
                 &pipe_query,
                 &mut pipe_element_query,
-                self.container,
+                self.container.entity(),
                 self.ty,
                 pipe,
                 container_element,
             );
         }
+
+        state.apply(world);
     }
 }
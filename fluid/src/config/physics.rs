@@ -0,0 +1,129 @@
+use bevy::ecs::system::{Res, Resource};
+use bevy::ecs::world::World;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::save;
+
+/// Physical constants of the fluid model.
+///
+/// Scenarios override these to simulate exotic settings,
+/// e.g. a simplified "arcade" model where all fluids flow alike.
+/// The defaults match the behavior of scenarios that do not declare them.
+#[derive(Debug, Clone, Resource)]
+pub struct Physics {
+    /// Gross volume flowing through a pipe per unit of pressure difference
+    /// between its endpoints, before resistance.
+    pub pressure_flow_coefficient: f32,
+    /// Exponent of fluid viscosity when distributing pipe flow among fluid types.
+    ///
+    /// At 1, flow is inversely proportional to viscosity.
+    /// At 0, all fluid types flow in proportion to their concentration only.
+    pub viscosity_sensitivity:     f32,
+    /// Multiplier on the vacuum specific volume of every fluid type.
+    ///
+    /// Larger values make fluids expand further into partially filled containers,
+    /// so that containers leave vacuum phase and saturate at lower masses.
+    pub vacuum_expansion:          f32,
+    /// Acceleration settling fluids towards the bottom of containers,
+    /// relative to standard gravity.
+    ///
+    /// At 0, fluids stay mixed in every container.
+    /// At 1 or above, immiscible fluids are fully [stratified](crate::container::strata)
+    /// as seen from pipe ports; values in between partially separate them.
+    pub gravity:                   f32,
+    /// Volume of each fluid exchanged through a pipe per unit of transfer weight,
+    /// in addition to the flow driven by pressure differences.
+    ///
+    /// Both sides of a pipe exchange fluids in proportion to their concentration,
+    /// so fluids spread between containers at equal pressure.
+    /// The exchange is scaled by viscosity and pipe resistance like pressure-driven flow.
+    /// At 0, fluids only move with pressure differences.
+    pub diffusion_coefficient:     f32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            pressure_flow_coefficient: 1.,
+            viscosity_sensitivity:     1.,
+            vacuum_expansion:          1.,
+            gravity:                   0.,
+            diffusion_coefficient:     0.,
+        }
+    }
+}
+
+/// Save schema for physical constants.
+///
+/// Omitted fields keep their default values.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// See [`Physics::pressure_flow_coefficient`].
+    #[serde(default)]
+    pub pressure_flow_coefficient: Option<f32>,
+    /// See [`Physics::viscosity_sensitivity`].
+    #[serde(default)]
+    pub viscosity_sensitivity:     Option<f32>,
+    /// See [`Physics::vacuum_expansion`].
+    #[serde(default)]
+    pub vacuum_expansion:          Option<f32>,
+    /// See [`Physics::gravity`].
+    #[serde(default)]
+    pub gravity:                   Option<f32>,
+    /// See [`Physics::diffusion_coefficient`].
+    #[serde(default)]
+    pub diffusion_coefficient:     Option<f32>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.fluid.PhysicsConfig";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(mut writer: save::Writer<Save>, (): (), physics: Res<Physics>) {
+            writer.write(
+                (),
+                Save {
+                    pressure_flow_coefficient: Some(physics.pressure_flow_coefficient),
+                    viscosity_sensitivity:     Some(physics.viscosity_sensitivity),
+                    vacuum_expansion:          Some(physics.vacuum_expansion),
+                    gravity:                   Some(physics.gravity),
+                    diffusion_coefficient:     Some(physics.diffusion_coefficient),
+                },
+            );
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            let mut physics = world.resource_mut::<Physics>();
+            if let Some(value) = def.pressure_flow_coefficient {
+                anyhow::ensure!(value >= 0., "pressure_flow_coefficient must be non-negative");
+                physics.pressure_flow_coefficient = value;
+            }
+            if let Some(value) = def.viscosity_sensitivity {
+                physics.viscosity_sensitivity = value;
+            }
+            if let Some(value) = def.vacuum_expansion {
+                anyhow::ensure!(value > 0., "vacuum_expansion must be positive");
+                physics.vacuum_expansion = value;
+            }
+            if let Some(value) = def.gravity {
+                anyhow::ensure!(value >= 0., "gravity must be non-negative");
+                physics.gravity = value;
+            }
+            if let Some(value) = def.diffusion_coefficient {
+                anyhow::ensure!(value >= 0., "diffusion_coefficient must be non-negative");
+                physics.diffusion_coefficient = value;
+            }
+
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -13,14 +13,14 @@ use crate::units;
 /// Identifies a type of fluid.
 ///
 /// Each fluid type is an entity, and `Type` is just a typed wrapper for such entities.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
 pub struct Type(pub Entity);
 
 /// A [`SystemParam`] to access the registered fluid types.
 #[derive(SystemParam)]
 pub struct Types<'w, 's>(Query<'w, 's, (Entity, &'static TypeDef)>);
 
-impl<'w, 's> Types<'w, 's> {
+impl Types<'_, '_> {
     /// Get a fluid type definition by type ID.
     #[must_use]
     pub fn get(&self, ty: Type) -> &TypeDef {
@@ -83,13 +83,50 @@ pub struct TypeDef {
 
     /// The amplitification coefficient for saturated fluids.
     pub saturation_gamma: f32,
+
+    /// Whether the fluid separates from other fluids into its own layer
+    /// when [stratification](crate::container::strata) is active.
+    #[serde(default)]
+    pub immiscible: bool,
+
+    /// The sRGB color of the fluid when rendered in tanks.
+    ///
+    /// Clients choose a fallback color if unspecified.
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+
+    /// Renders the fluid as haze filling its compartment, e.g. smoke.
+    #[serde(default)]
+    pub haze: Option<Haze>,
+}
+
+/// Presentation of a fluid suspended in a compartment.
+///
+/// Densities are measured as the mass of the fluid per unit of container capacity,
+/// so that the same amount of smoke obscures a small room more than a large one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub struct Haze {
+    /// The density at which the haze starts to be visible.
+    pub threshold: units::Density,
+    /// The density at which the haze fully obscures the compartment.
+    pub opaque:    units::Density,
+}
+
+impl Haze {
+    /// The opacity of the haze at `density`, in the range `[0, 1]`.
+    #[must_use]
+    pub fn opacity(&self, density: units::Density) -> f32 {
+        let range = (self.opaque.quantity - self.threshold.quantity).max(f32::EPSILON);
+        ((density.quantity - self.threshold.quantity) / range).clamp(0., 1.)
+    }
 }
 
 /// Save schema for scalar values.
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Save {
+    /// The type definition.
     #[serde(flatten)]
-    def: TypeDef,
+    pub def: TypeDef,
 }
 
 impl save::Def for Save {
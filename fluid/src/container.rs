@@ -7,6 +7,10 @@
 //!
 //! A storage for a [facility] should share the same entity as the facility entity.
 //! A container for a [duct] should share the same entity as the duct entity.
+//!
+//! Instead of constructing the container components manually,
+//! the spawner of a facility or duct may insert a [`Declared`] component,
+//! which gets expanded into the container and its initial elements automatically.
 
 use std::iter;
 
@@ -16,25 +20,28 @@ use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::query::With;
 use bevy::ecs::schedule::{IntoSystemConfigs, SystemSet};
-use bevy::ecs::system::{Commands, Query};
+use bevy::ecs::system::{Commands, Local, Query, Res};
 use bevy::ecs::world::World;
-use bevy::hierarchy;
+use bevy::hierarchy::{self, BuildChildren};
 use bevy::state::condition::in_state;
 use bevy::state::state::States;
 use derive_more::From;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use traffloat_base::save;
+use traffloat_base::{debug, save};
 use traffloat_graph::building::facility;
 use traffloat_graph::corridor::duct;
 use typed_builder::TypedBuilder;
 
-use crate::{config, units};
+use crate::{config, hazard, units};
 
 pub mod element;
+pub mod strata;
 
 mod metrics;
+pub(crate) use metrics::RegisterMetricType;
+pub use metrics::{COLOR_METADATA, FILL_LEVEL_METADATA, HAZE_METADATA, HAZE_OPACITY_METADATA};
 
 #[cfg(test)]
 mod tests;
@@ -48,10 +55,21 @@ impl<St: States + Copy> app::Plugin for Plugin<St> {
 
         app.add_systems(
             app::Update,
-            rebalance_system.in_set(SystemSets::Rebalance).run_if(in_state(self.0)),
+            (
+                spawn_declared_system.before(SystemSets::Rebalance).run_if(in_state(self.0)),
+                rebalance_system.in_set(SystemSets::Rebalance).run_if(in_state(self.0)),
+                strata::update_strata_system
+                    .in_set(SystemSets::Rebalance)
+                    .after(rebalance_system)
+                    .run_if(in_state(self.0)),
+            ),
         );
         save::add_def::<Save>(app);
         save::add_def::<element::Save>(app);
+        debug::invariant::require::<
+            Marker,
+            (CurrentPressure, CurrentVolume, MaxVolume, MaxPressure, Pipes),
+        >(app);
     }
 }
 
@@ -61,8 +79,8 @@ pub enum SystemSets {
     /// Rebalance volume and pressure within each container based on the mass.
     ///
     /// [`element::Mass`]-mutating systems should execute before this set.
-    /// Systems that read [`CurrentVolume`], [`CurrentPressure`], [`element::Volume`] or
-    /// [`ExplosionMarker`] should execute after this set.
+    /// Systems that read [`CurrentVolume`], [`CurrentPressure`], [`element::Volume`],
+    /// [`strata::Strata`] or [`ExplosionMarker`] should execute after this set.
     Rebalance,
 }
 
@@ -79,6 +97,10 @@ pub struct Bundle {
     max_pressure:     MaxPressure,
     #[builder(default = Pipes { pipes: <_>::default() })]
     pipes:            Pipes,
+    #[builder(default)]
+    strata:           strata::Strata,
+    #[builder(default)]
+    hazard:           hazard::Hazard,
     #[builder(default, setter(skip))]
     _marker:          Marker,
 }
@@ -135,9 +157,56 @@ pub struct Pipes {
 #[component(storage = "SparseSet")]
 pub struct ExplosionMarker;
 
+/// Declares the fluid storage of a facility or duct.
+///
+/// When this component is inserted to an entity,
+/// the container components and the initial container elements
+/// are spawned on the same entity in the next update,
+/// after which this component is removed.
+#[derive(Component, TypedBuilder)]
+pub struct Declared {
+    /// Container capacity.
+    #[builder(setter(into))]
+    pub max_volume:   units::Volume,
+    /// Container pressure limit.
+    #[builder(setter(into))]
+    pub max_pressure: units::Pressure,
+    /// Initial mass of each fluid type in the container.
+    #[builder(default)]
+    pub elements:     Vec<(config::Type, units::Mass)>,
+}
+
+/// Expands [`Declared`] components into containers.
+fn spawn_declared_system(mut commands: Commands, query: Query<(Entity, &Declared)>) {
+    for (entity, declared) in &query {
+        commands
+            .entity(entity)
+            .insert(
+                Bundle::builder()
+                    .max_volume(declared.max_volume)
+                    .max_pressure(declared.max_pressure)
+                    .build(),
+            )
+            .with_children(|builder| {
+                for &(ty, mass) in &declared.elements {
+                    builder.spawn(element::Bundle::builder().ty(ty).mass(mass).build());
+                }
+            })
+            .remove::<Declared>();
+    }
+}
+
+#[derive(Default)]
+struct ElementState {
+    critical_pressure: units::Pressure,
+    saturation_gamma:  f32,
+}
+
 /// Rebalance the volume of fluids in a system.
 fn rebalance_system(
+    mut buf: Local<Vec<Option<ElementState>>>,
     types: config::Types,
+    physics: Res<config::Physics>,
     mut containers_query: Query<(
         Entity,
         &hierarchy::Children,
@@ -149,16 +218,9 @@ fn rebalance_system(
     mut elements_query: Query<(&config::Type, &element::Mass, &mut element::Volume)>,
     mut commands: Commands,
 ) {
-    #[derive(Default)]
-    struct ElementState {
-        critical_pressure: units::Pressure,
-        saturation_gamma:  f32,
-    }
-
-    let mut buf = Vec::<Option<ElementState>>::default();
-
     containers_query.iter_mut().for_each(
         |(container_entity, elements, mut pressure, mut occupied, max_volume, max_pressure)| {
+            buf.clear();
             buf.resize_with(elements.len(), <_>::default);
 
             let previous_pressure = pressure.pressure;
@@ -167,7 +229,7 @@ fn rebalance_system(
             // First compute the vacuum volume and temporarily save them in the current volume component.
             // Even if they won't end up as the eventual value if it is not vacuum phase,
             // this would serve as a buffer memory.
-            for (state, &element) in iter::zip(&mut buf, elements) {
+            for (state, &element) in iter::zip(buf.iter_mut(), elements) {
                 let Ok((&ty, mass, mut volume)) = elements_query.get_mut(element) else { continue };
                 let def = types.get(ty);
 
@@ -176,7 +238,7 @@ fn rebalance_system(
                     saturation_gamma:  def.saturation_gamma,
                 });
 
-                volume.volume = mass.mass * def.vacuum_specific_volume;
+                volume.volume = mass.mass * def.vacuum_specific_volume * physics.vacuum_expansion;
                 total_vacuum_volume += volume.volume;
             }
 
@@ -194,7 +256,7 @@ fn rebalance_system(
             occupied.volume = max_volume.volume;
 
             let mut saturated_pressure = base_pressure;
-            for (state, &element) in iter::zip(&buf, elements) {
+            for (state, &element) in iter::zip(buf.iter(), elements) {
                 let Some(state) = state else { continue };
 
                 // scale volume proportionally to add up to approximately max_volume
@@ -9,11 +9,12 @@ use bevy::ecs::world::World;
 use bevy::hierarchy;
 use bevy::state::state::States;
 use bevy::utils::HashMap;
+use serde_json::Value as JsonValue;
 use traffloat_base::partition;
-use traffloat_view::{metrics, viewer};
+use traffloat_view::{format, metrics, viewer, DisplayText};
 
 use super::element;
-use crate::config;
+use crate::{config, units};
 
 /// Maintains the state within each container.
 pub(crate) struct Plugin<St>(pub(super) St);
@@ -26,9 +27,90 @@ impl<St: States + Copy> app::Plugin for Plugin<St> {
             on_new_viewer_system
                 .in_set(partition::EventWriterSystemSet::<metrics::NewTypeEvent>::default()),
         );
+        metrics::add_fed_type::<
+            (&super::CurrentVolume, &super::MaxVolume),
+            With<super::Marker>,
+            (),
+            _,
+        >(
+            app,
+            metrics::TypeDef {
+                update_frequency: Duration::from_secs(1),
+                display_label:    DisplayText::Custom { value: "Fill level".into() },
+                aggregation:      metrics::Aggregation::Mean,
+            },
+            [(FILL_LEVEL_METADATA, JsonValue::Bool(true)), format::Unit::Ratio.metadata()],
+            |entity, ()| {
+                let current = entity.get::<super::CurrentVolume>().expect("requested in query");
+                let max = entity.get::<super::MaxVolume>().expect("requested in query");
+                if max.volume.quantity > 0. {
+                    current.volume.quantity / max.volume.quantity
+                } else {
+                    0.
+                }
+            },
+        );
+        metrics::add_fed_type::<
+            (&hierarchy::Children, &super::MaxVolume),
+            With<super::Marker>,
+            (Query<(&config::Type, &element::Mass)>, Query<&config::TypeDef>),
+            _,
+        >(
+            app,
+            metrics::TypeDef {
+                update_frequency: Duration::from_secs(1),
+                display_label:    DisplayText::Custom { value: "Haze".into() },
+                aggregation:      metrics::Aggregation::Mean,
+            },
+            [(HAZE_OPACITY_METADATA, JsonValue::Bool(true)), format::Unit::Ratio.metadata()],
+            |entity, (element_query, type_query)| {
+                let elements = entity.get::<hierarchy::Children>().expect("requested in query");
+                let max = entity.get::<super::MaxVolume>().expect("requested in query");
+                if max.volume.quantity <= 0. {
+                    return 0.;
+                }
+
+                elements
+                    .iter()
+                    .filter_map(|&element| {
+                        let (ty, mass) = element_query.get(element).ok()?;
+                        let haze = type_query.get(ty.0).ok()?.haze?;
+                        let density = mass.mass.quantity / max.volume.quantity;
+                        Some(haze.opacity(units::Density { quantity: density }))
+                    })
+                    .fold(0., f32::max)
+            },
+        );
     }
 }
 
+/// Metadata key of per-fluid mass metrics,
+/// holding the [fluid color](config::TypeDef::color) as an sRGB `[r, g, b]` array.
+pub const COLOR_METADATA: metrics::MetadataKey = metrics::MetadataKey::new("traffloat.fluid.color");
+
+/// Metadata key identifying the fill level metric,
+/// i.e. the ratio of current volume to max volume of a container.
+pub const FILL_LEVEL_METADATA: metrics::MetadataKey =
+    metrics::MetadataKey::new("traffloat.fluid.fillLevel");
+
+/// Metadata key of per-fluid mass metrics,
+/// present with the value `true` if the fluid is rendered as [haze](config::TypeDef::haze).
+pub const HAZE_METADATA: metrics::MetadataKey = metrics::MetadataKey::new("traffloat.fluid.haze");
+
+/// Metadata key identifying the haze opacity metric,
+/// i.e. the [opacity](config::Haze::opacity) of the thickest haze in a container.
+pub const HAZE_OPACITY_METADATA: metrics::MetadataKey =
+    metrics::MetadataKey::new("traffloat.fluid.hazeOpacity");
+
+fn fluid_metadata(def: &config::TypeDef) -> HashMap<metrics::MetadataKey, JsonValue> {
+    def.color
+        .map(|color| (COLOR_METADATA, JsonValue::from(color.to_vec())))
+        .into_iter()
+        .chain(def.haze.map(|_| (HAZE_METADATA, JsonValue::Bool(true))))
+        .chain([format::Unit::Mass.metadata()])
+        .collect()
+}
+
 /// System set in which the metric type is registered for a new fluid type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
 pub struct RegisterMetricType;
@@ -36,11 +118,11 @@ pub struct RegisterMetricType;
 fn on_create_type_system(world: &mut World) {
     let fluid_type = world.resource::<config::CreatedType>().get();
 
-    let display_label = {
+    let (display_label, metadata) = {
         let def = world
             .get::<config::TypeDef>(fluid_type.0)
             .expect("CreatedType should have a valid TypeDef");
-        def.display_label.clone()
+        (def.display_label.clone(), fluid_metadata(def))
     };
 
     let metric_type = metrics::create_type(
@@ -48,6 +130,7 @@ fn on_create_type_system(world: &mut World) {
         metrics::TypeDef {
             update_frequency: Duration::from_secs(2),
             display_label:    display_label.clone(),
+            aggregation:      metrics::Aggregation::Sum,
         },
     );
     world.flush();
@@ -83,21 +166,21 @@ fn on_create_type_system(world: &mut World) {
             viewer,
             data: metrics::ClientTypeData {
                 display_label: display_label.clone(),
-                metadata:      HashMap::new(),
+                metadata:      metadata.clone(),
             },
         });
     }
 }
 
 fn on_new_viewer_system(
-    fluid_type_query: Query<&metrics::Type, With<config::TypeDef>>,
+    fluid_type_query: Query<(&metrics::Type, &config::TypeDef)>,
     viewer_query: Query<&viewer::Sid, query::Added<viewer::Sid>>,
     metric_type_query: Query<(&metrics::TypeDef, &metrics::Sid), With<metrics::TypeDef>>,
     mut writer: EventWriter<metrics::NewTypeEvent>,
 ) {
     writer.send_batch(viewer_query.iter().flat_map(|&viewer| {
         let metric_type_query = &metric_type_query;
-        fluid_type_query.iter().map(move |&ty| {
+        fluid_type_query.iter().map(move |(&ty, def)| {
             let (ty_def, &ty_sid) =
                 metric_type_query.get(ty.0).expect("invalid metric type reference");
             metrics::NewTypeEvent {
@@ -105,7 +188,7 @@ fn on_new_viewer_system(
                 ty: ty_sid,
                 data: metrics::ClientTypeData {
                     display_label: ty_def.display_label.clone(),
-                    metadata:      HashMap::new(),
+                    metadata:      fluid_metadata(def),
                 },
             }
         })
@@ -0,0 +1,115 @@
+//! Stratification of immiscible fluids by density.
+//!
+//! Under [gravity](config::Physics::gravity),
+//! each fluid type marked [immiscible](config::TypeDef::immiscible) settles into its own layer,
+//! while all other fluid types share one mixed layer.
+//! Layers are stacked by density with the densest at the bottom,
+//! each occupying a height proportional to its volume.
+//! Pipes with a declared [port height](crate::pipe::Ports) draw from the layer at that height
+//! instead of the whole mixture.
+
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::system::{Query, Res};
+use bevy::hierarchy;
+use smallvec::{smallvec, SmallVec};
+
+use super::{element, CurrentVolume};
+use crate::{config, units};
+
+/// The fluid layers of a container.
+///
+/// Only updated while gravity is positive.
+#[derive(Component, Default)]
+pub struct Strata {
+    /// Layers from bottom to top.
+    layers: Vec<Layer>,
+}
+
+impl Strata {
+    /// Returns the layer at `height`,
+    /// measured from 0 at the bottom to 1 at the fluid surface.
+    ///
+    /// Heights above the surface resolve to the top layer.
+    #[must_use]
+    pub fn layer_at(&self, height: f32) -> Option<&Layer> {
+        self.layers.iter().find(|layer| height <= layer.top).or(self.layers.last())
+    }
+
+    /// Iterates over the layers from bottom to top.
+    pub fn iter(&self) -> impl Iterator<Item = &Layer> { self.layers.iter() }
+}
+
+/// A layer of fluids in a container.
+pub struct Layer {
+    /// Height of the top of the layer,
+    /// measured from 0 at the bottom to 1 at the fluid surface.
+    pub top:      f32,
+    /// Mean density of the fluids in the layer.
+    pub density:  f32,
+    /// Total volume of the fluids in the layer.
+    pub volume:   units::Volume,
+    /// Container elements in the layer.
+    pub elements: SmallVec<[Entity; 2]>,
+}
+
+pub(super) fn update_strata_system(
+    physics: Res<config::Physics>,
+    types: config::Types,
+    mut containers_query: Query<(&hierarchy::Children, &CurrentVolume, &mut Strata)>,
+    elements_query: Query<(&config::Type, &element::Mass, &element::Volume)>,
+) {
+    if physics.gravity <= 0. {
+        return;
+    }
+
+    containers_query.iter_mut().for_each(|(elements, occupied, mut strata)| {
+        let strata = &mut *strata;
+        strata.layers.clear();
+
+        let mut mixed_mass = units::Mass::default();
+        let mut mixed = Layer {
+            top:      0.,
+            density:  0.,
+            volume:   units::Volume::default(),
+            elements: SmallVec::new(),
+        };
+        for &element in elements {
+            let Ok((&ty, mass, volume)) = elements_query.get(element) else { continue };
+            if types.get(ty).immiscible {
+                strata.layers.push(Layer {
+                    top:      0.,
+                    density:  density(mass.mass, volume.volume),
+                    volume:   volume.volume,
+                    elements: smallvec![element],
+                });
+            } else {
+                mixed_mass += mass.mass;
+                mixed.volume += volume.volume;
+                mixed.elements.push(element);
+            }
+        }
+        if !mixed.elements.is_empty() {
+            mixed.density = density(mixed_mass, mixed.volume);
+            strata.layers.push(mixed);
+        }
+
+        strata.layers.sort_by(|a, b| b.density.total_cmp(&a.density));
+
+        let mut top = 0.;
+        for layer in &mut strata.layers {
+            if occupied.volume.quantity > 0. {
+                top += layer.volume.quantity / occupied.volume.quantity;
+            }
+            layer.top = top;
+        }
+    });
+}
+
+fn density(mass: units::Mass, volume: units::Volume) -> f32 {
+    if volume.quantity > 0. {
+        mass.quantity / volume.quantity
+    } else {
+        0.
+    }
+}
@@ -2,7 +2,7 @@ use std::iter;
 
 use approx::assert_relative_eq;
 use bevy::app::App;
-use bevy::hierarchy::BuildWorldChildren;
+use bevy::hierarchy::{BuildWorldChildren, Children};
 use bevy::state::app::{AppExtStates, StatesPlugin};
 use bevy::time::TimePlugin;
 use traffloat_base::{save, EmptyState};
@@ -27,7 +27,9 @@ struct ElementSetup {
     expect_volume:          f32,
 }
 
-fn do_test(setup: ContainerSetup) {
+fn do_test(setup: ContainerSetup) { do_test_with_physics(setup, config::Physics::default()); }
+
+fn do_test_with_physics(setup: ContainerSetup, physics: config::Physics) {
     let mut app = App::new();
     app.add_plugins((
         TimePlugin,
@@ -50,6 +52,9 @@ fn do_test(setup: ContainerSetup) {
                     vacuum_specific_volume: fluid.vacuum_specific_volume.into(),
                     critical_pressure:      fluid.critical_pressure.into(),
                     saturation_gamma:       fluid.saturation_gamma,
+                    immiscible:             false,
+                    color:                  None,
+                    haze:                   None,
                 },
             )
         })
@@ -57,6 +62,7 @@ fn do_test(setup: ContainerSetup) {
 
     let config = Scalar::default();
     app.insert_resource(config);
+    app.insert_resource(physics);
     app.add_plugins(super::Plugin(EmptyState));
 
     let mut container = app.world_mut().spawn(
@@ -138,6 +144,25 @@ fn mixture_vacuum() {
     });
 }
 
+#[test]
+fn vacuum_expansion() {
+    do_test_with_physics(
+        ContainerSetup {
+            max_pressure:    100.,
+            max_volume:      100.,
+            expect_pressure: 20. / 100.,
+            elements:        vec![ElementSetup {
+                mass:                   5.,
+                vacuum_specific_volume: 2.,
+                critical_pressure:      50.,
+                saturation_gamma:       100.,
+                expect_volume:          20.,
+            }],
+        },
+        config::Physics { vacuum_expansion: 2., ..config::Physics::default() },
+    );
+}
+
 #[test]
 fn mixture_compression() {
     do_test(ContainerSetup {
@@ -187,3 +212,58 @@ fn mixture_saturation() {
         ],
     });
 }
+
+#[test]
+fn declared_container() {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        StatesPlugin,
+        save::Plugin,
+        traffloat_view::Plugin,
+        config::Plugin,
+    ));
+    app.init_state::<EmptyState>();
+
+    let ty = config::create_type(
+        &mut app.world_mut().commands(),
+        config::TypeDef {
+            display_label:          DisplayText::default(),
+            viscosity:              units::Viscosity::default(), // unused
+            vacuum_specific_volume: 2.0.into(),
+            critical_pressure:      50.0.into(),
+            saturation_gamma:       100.,
+            immiscible:             false,
+            color:                  None,
+            haze:                   None,
+        },
+    );
+
+    app.insert_resource(Scalar::default());
+    app.add_plugins(super::Plugin(EmptyState));
+
+    let container_entity = app
+        .world_mut()
+        .spawn(
+            super::Declared::builder()
+                .max_volume(100.)
+                .max_pressure(100.)
+                .elements(vec![(ty, 5.0.into())])
+                .build(),
+        )
+        .id();
+
+    app.update();
+
+    let container = app.world().entity(container_entity);
+    assert!(container.get::<super::Declared>().is_none());
+    assert_relative_eq!(container.get::<super::MaxVolume>().unwrap().volume.quantity, 100.);
+    assert_relative_eq!(container.get::<super::MaxPressure>().unwrap().pressure.quantity, 100.);
+    assert_relative_eq!(container.get::<super::CurrentVolume>().unwrap().volume.quantity, 10.);
+
+    let children = container.get::<Children>().unwrap();
+    assert_eq!(children.len(), 1);
+    let element_entity = app.world().entity(children[0]);
+    assert_eq!(element_entity.get::<config::Type>(), Some(&ty));
+    assert_relative_eq!(element_entity.get::<element::Mass>().unwrap().mass.quantity, 5.);
+}
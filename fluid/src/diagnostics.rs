@@ -0,0 +1,166 @@
+//! Records solver internals of selected containers and pipes for debugging.
+//!
+//! Inserting [`Watch`] on a container or pipe entity causes a [`ContainerReport`]
+//! or [`PipeReport`] to be updated on the same entity after each simulation cycle.
+//! Unwatched entities incur no cost.
+//!
+//! The values are only meant for developer overlays and may change without notice.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res};
+use bevy::hierarchy;
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use traffloat_graph::building;
+use traffloat_graph::corridor::{Binary, Endpoint};
+
+use crate::config::Scalar;
+use crate::pipe::{self, element, force, pump, resistance};
+use crate::{container, units};
+
+/// Records reports of watched entities.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            app::Update,
+            (report_container_system, report_pipe_system)
+                .after(container::SystemSets::Rebalance)
+                .run_if(in_state(self.0)),
+        );
+    }
+}
+
+/// Marks a container or pipe whose solver internals should be reported.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Watch;
+
+/// Solver internals of a watched container in the last simulation cycle.
+#[derive(Component)]
+pub struct ContainerReport {
+    /// Net mass flowing into the container through each adjacent pipe.
+    /// Negative values indicate outflow.
+    pub pipe_flows:           Vec<(Entity, units::Mass)>,
+    /// The pressure that the container and its neighbours would converge to
+    /// without further production or consumption,
+    /// estimated as the volume-weighted mean pressure of the neighbourhood.
+    pub equilibrium_pressure: units::Pressure,
+    /// Whether all pipe flows are below the element creation threshold,
+    /// i.e. the container is effectively at rest.
+    pub idle:                 bool,
+}
+
+/// Solver internals of a watched pipe in the last simulation cycle.
+#[derive(Component)]
+pub struct PipeReport {
+    /// Total resistance of the pipe.
+    pub resistance:  units::Resistance,
+    /// Reciprocal of the resistance.
+    pub conductance: f32,
+    /// The directed gross volumetric flow on each side.
+    pub force:       Binary<units::Volume>,
+    /// The force contributed by a [pump](pump::Pump) on each side before resistance,
+    /// or zero if the pipe is not pumped.
+    pub pump_force:  Binary<units::Volume>,
+    /// Net mass transferred from alpha to beta, summed over all fluid types.
+    pub ab_mass:     units::Mass,
+}
+
+fn pipe_ab_mass(
+    elements: Option<&hierarchy::Children>,
+    element_query: &Query<&element::AbTransferMass>,
+) -> units::Mass {
+    elements
+        .into_iter()
+        .flatten()
+        .filter_map(|&element| element_query.get(element).ok())
+        .map(|transfer| transfer.mass)
+        .sum()
+}
+
+fn report_container_system(
+    config: Res<Scalar>,
+    watched_query: Query<(Entity, &container::Pipes), (With<Watch>, With<container::Marker>)>,
+    pipe_query: Query<(&pipe::Containers, Option<&hierarchy::Children>)>,
+    element_query: Query<&element::AbTransferMass>,
+    pressure_query: Query<(&container::CurrentPressure, &container::MaxVolume)>,
+    mut commands: Commands,
+) {
+    for (container, pipes) in &watched_query {
+        let mut pipe_flows = Vec::new();
+        let mut neighbours = vec![container];
+
+        for &pipe in &pipes.pipes {
+            let Ok((endpoints, elements)) = pipe_query.get(pipe) else { continue };
+            let Some(side) = endpoints.endpoints.find(&container) else { continue };
+
+            let ab_mass = pipe_ab_mass(elements, &element_query);
+            let inflow = match side {
+                Endpoint::Alpha => -ab_mass,
+                Endpoint::Beta => ab_mass,
+            };
+            pipe_flows.push((pipe, inflow));
+            neighbours.push(*endpoints.endpoints.as_endpoint(!side));
+        }
+
+        let (weighted_pressure, total_volume) = neighbours
+            .iter()
+            .filter_map(|&entity| pressure_query.get(entity).ok())
+            .fold((0., 0.), |(pressure, volume), (current, max)| {
+                (
+                    pressure + current.pressure.quantity * max.volume.quantity,
+                    volume + max.volume.quantity,
+                )
+            });
+        let equilibrium_pressure = units::Pressure {
+            quantity: if total_volume > 0. { weighted_pressure / total_volume } else { 0. },
+        };
+
+        let idle = pipe_flows.iter().all(|&(_, flow)| {
+            let magnitude = units::Mass { quantity: flow.quantity.abs() };
+            magnitude < config.creation_threshold
+        });
+
+        commands.entity(container).insert(ContainerReport {
+            pipe_flows,
+            equilibrium_pressure,
+            idle,
+        });
+    }
+}
+
+fn report_pipe_system(
+    watched_query: Query<
+        (
+            Entity,
+            &resistance::Dynamic,
+            &force::Directed,
+            Option<&pump::Pump>,
+            Option<&hierarchy::Parent>,
+            Option<&hierarchy::Children>,
+        ),
+        With<Watch>,
+    >,
+    element_query: Query<&element::AbTransferMass>,
+    rate_query: Query<&building::mode::Rate>,
+    mut commands: Commands,
+) {
+    for (pipe, resistance, force, pump, parent, elements) in &watched_query {
+        let pump_force = pump.map_or_else(Binary::default, |pump| {
+            pump.effective_force(pump::rate_of(parent, &rate_query))
+        });
+        commands.entity(pipe).insert(PipeReport {
+            resistance: resistance.resistance,
+            conductance: resistance.resistance.quantity.recip(),
+            force: Binary { alpha: force.force.alpha, beta: force.force.beta },
+            pump_force,
+            ab_mass: pipe_ab_mass(elements, &element_query),
+        });
+    }
+}
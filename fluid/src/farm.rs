@@ -0,0 +1,269 @@
+//! Farms grow crops by consuming and producing fluids in their container.
+//!
+//! A farm is a container with a [`Farm`] component.
+//! In each simulation cycle, if the container holds enough of every [input](Farm::inputs),
+//! the inputs are consumed, the [byproducts](Farm::byproducts) are produced
+//! and the growth progress advances by one cycle.
+//! Consumption, production and growth are scaled by
+//! the [operating rate](building::mode::Rate) of the farm building.
+//! Growth stalls without consuming anything if any input is insufficient.
+//!
+//! When the progress reaches [`Farm::growth_period`],
+//! the [harvest](Farm::harvest) is produced, a [`HarvestEvent`] is emitted
+//! and the progress is reset.
+//!
+//! Since the farm container is typically the ambient container of a building,
+//! byproducts such as oxygen are released into the building atmosphere
+//! and distributed to connected containers through pipes.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, EventWriter};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
+use bevy::ecs::world::World;
+use bevy::hierarchy::{self, DespawnRecursiveExt};
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::partition::{self, AppExt};
+use traffloat_base::{save, Ref};
+use traffloat_graph::building;
+
+use crate::config::{self, Scalar};
+use crate::{commands, container, ledger, units};
+
+#[cfg(test)]
+mod tests;
+
+/// Simulates farm growth.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<HarvestEvent>();
+        app.add_systems(
+            app::Update,
+            grow_system
+                .before(container::SystemSets::Rebalance)
+                .in_set(partition::EventWriterSystemSet::<HarvestEvent>::default())
+                .run_if(in_state(self.0)),
+        );
+        save::add_def::<Save>(app);
+    }
+}
+
+/// Growth parameters and state of a farm.
+#[derive(Component)]
+pub struct Farm {
+    /// Fluid mass consumed per growing cycle.
+    pub inputs:        Vec<(config::Type, units::Mass)>,
+    /// Fluid mass produced per growing cycle.
+    pub byproducts:    Vec<(config::Type, units::Mass)>,
+    /// Fluid mass produced at the end of each growth period.
+    pub harvest:       Vec<(config::Type, units::Mass)>,
+    /// Number of growing cycles in a growth period.
+    pub growth_period: u32,
+    /// Number of growing cycles elapsed in the current growth period,
+    /// weighted by the operating rate.
+    pub progress:      f32,
+}
+
+/// A farm has completed a growth period.
+#[derive(Debug, Event)]
+pub struct HarvestEvent {
+    /// The farm container entity.
+    pub farm: Entity,
+}
+
+fn grow_system(
+    config: Res<Scalar>,
+    mut farm_query: Query<
+        (Entity, &mut Farm, &hierarchy::Parent, Option<&hierarchy::Children>),
+        With<container::Marker>,
+    >,
+    rate_query: Query<&building::mode::Rate>,
+    mut element_query: Query<(&config::Type, &mut container::element::Mass)>,
+    mut harvest_writer: EventWriter<HarvestEvent>,
+    mut ledger: ResMut<ledger::Ledger>,
+    mut commands: Commands,
+) {
+    for (container, mut farm, parent, elements) in &mut farm_query {
+        let rate = building::mode::Rate::of(rate_query.get(parent.get()).ok());
+        if rate <= 0. {
+            continue;
+        }
+
+        let elements: Vec<Entity> = elements.into_iter().flatten().copied().collect();
+        let find_element =
+            |element_query: &Query<(&config::Type, &mut container::element::Mass)>,
+             ty: config::Type| {
+                elements.iter().copied().find(|&element| {
+                    element_query.get(element).is_ok_and(|(&element_ty, _)| element_ty == ty)
+                })
+            };
+
+        let sufficient = farm.inputs.iter().all(|&(ty, required)| {
+            find_element(&element_query, ty).is_some_and(|element| {
+                let (_, mass) = element_query.get(element).expect("checked in find_element");
+                mass.mass >= required * rate
+            })
+        });
+        if !sufficient {
+            continue;
+        }
+
+        for &(ty, required) in &farm.inputs {
+            let element = find_element(&element_query, ty).expect("checked above");
+            let (_, mut mass) = element_query.get_mut(element).expect("checked above");
+            mass.mass -= required * rate;
+            ledger.record(
+                ledger::Key { ty, cause: ledger::Cause::Farming, container },
+                -(required * rate),
+            );
+            if mass.mass < config.deletion_threshold {
+                commands.entity(element).despawn_recursive();
+            }
+        }
+
+        let mut produce = |ty: config::Type, produced: units::Mass| {
+            let key = ledger::Key { ty, cause: ledger::Cause::Farming, container };
+            match find_element(&element_query, ty) {
+                Some(element) => {
+                    let (_, mut mass) = element_query.get_mut(element).expect("checked above");
+                    mass.mass += produced;
+                    ledger.record(key, produced);
+                }
+                None if produced < config.creation_threshold => {} // negligible mass
+                None => {
+                    ledger.record(key, produced);
+                    commands.add(
+                        commands::CreateContainerElement::builder()
+                            .container(Ref::new_unchecked(container))
+                            .ty(ty)
+                            .mass(produced)
+                            .build(),
+                    );
+                }
+            }
+        };
+
+        for &(ty, produced) in &farm.byproducts {
+            produce(ty, produced * rate);
+        }
+
+        farm.progress += rate;
+        #[allow(clippy::cast_precision_loss)] // growth periods are small
+        let growth_period = farm.growth_period as f32;
+        if farm.progress >= growth_period {
+            farm.progress = 0.;
+            for &(ty, produced) in &farm.harvest {
+                produce(ty, produced);
+            }
+            harvest_writer.send(HarvestEvent { farm: container });
+        }
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The farm container.
+    pub container:     save::Id<container::Save>,
+    /// Fluid mass consumed per growing cycle.
+    pub inputs:        Vec<SaveAmount>,
+    /// Fluid mass produced per growing cycle.
+    pub byproducts:    Vec<SaveAmount>,
+    /// Fluid mass produced at the end of each growth period.
+    pub harvest:       Vec<SaveAmount>,
+    /// Number of growing cycles in a growth period.
+    pub growth_period: u32,
+    /// Number of growing cycles elapsed in the current growth period,
+    /// weighted by the operating rate.
+    pub progress:      f32,
+}
+
+/// An amount of a fluid type, used in saves.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveAmount {
+    /// The fluid type.
+    pub ty:   save::Id<config::SaveType>,
+    /// The fluid mass.
+    pub mass: units::Mass,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.fluid.Farm";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (container_dep, type_dep): (
+                save::StoreDepend<container::Save>,
+                save::StoreDepend<config::SaveType>,
+            ),
+            query: Query<(Entity, &Farm)>,
+        ) {
+            let amounts = |amounts: &[(config::Type, units::Mass)]| {
+                amounts
+                    .iter()
+                    .map(|&(ty, mass)| SaveAmount { ty: type_dep.must_get(ty), mass })
+                    .collect()
+            };
+
+            writer.write_all(query.iter().map(|(entity, farm)| {
+                (
+                    entity,
+                    Save {
+                        container:     container_dep.must_get(entity),
+                        inputs:        amounts(&farm.inputs),
+                        byproducts:    amounts(&farm.byproducts),
+                        harvest:       amounts(&farm.harvest),
+                        growth_period: farm.growth_period,
+                        progress:      farm.progress,
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (container_dep, type_dep): &(
+                save::LoadDepend<container::Save>,
+                save::LoadDepend<config::SaveType>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let amounts = |amounts: Vec<SaveAmount>| {
+                amounts
+                    .into_iter()
+                    .map(|amount| Ok((type_dep.get(amount.ty)?, amount.mass)))
+                    .collect::<anyhow::Result<Vec<_>>>()
+            };
+
+            let container = container_dep.get(def.container)?;
+            anyhow::ensure!(def.growth_period > 0, "farm growth period must be positive");
+            let farm = Farm {
+                inputs:        amounts(def.inputs)?,
+                byproducts:    amounts(def.byproducts)?,
+                harvest:       amounts(def.harvest)?,
+                growth_period: def.growth_period,
+                progress:      def.progress,
+            };
+            world.entity_mut(container).insert(farm);
+            Ok(container)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
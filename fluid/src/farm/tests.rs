@@ -0,0 +1,51 @@
+use approx::assert_relative_eq;
+use bevy::math::Vec3;
+
+use super::Farm;
+use crate::{test_util, units};
+
+#[test]
+fn grow_and_harvest() {
+    let mut app = test_util::new_app();
+    let [water, oxygen, food] = [1., 1., 1.].map(|svol| test_util::create_type(&mut app, svol));
+    let (_, container) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    test_util::add_fluid(&mut app, container, water, 10.);
+    app.world_mut().entity_mut(container).insert(Farm {
+        inputs:        vec![(water, units::Mass { quantity: 1. })],
+        byproducts:    vec![(oxygen, units::Mass { quantity: 0.5 })],
+        harvest:       vec![(food, units::Mass { quantity: 3. })],
+        growth_period: 2,
+        progress:      0.,
+    });
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, water), 9.);
+    assert_relative_eq!(test_util::fluid_mass(&app, container, food), 0.);
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, water), 8.);
+    assert_relative_eq!(test_util::fluid_mass(&app, container, oxygen), 1.);
+    assert_relative_eq!(test_util::fluid_mass(&app, container, food), 3.);
+}
+
+#[test]
+fn remove_depleted_input() {
+    let mut app = test_util::new_app();
+    let water = test_util::create_type(&mut app, 1.);
+    let (_, container) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    test_util::add_fluid(&mut app, container, water, 1.);
+    app.world_mut().entity_mut(container).insert(Farm {
+        inputs:        vec![(water, units::Mass { quantity: 1. })],
+        byproducts:    Vec::new(),
+        harvest:       Vec::new(),
+        growth_period: 10,
+        progress:      0.,
+    });
+
+    app.update();
+    assert!(test_util::elements(&app, container).is_empty());
+
+    // growth stalls without the input
+    app.update();
+    assert_relative_eq!(app.world().get::<Farm>(container).unwrap().progress, 1.);
+}
@@ -0,0 +1,433 @@
+//! Fire and combustion.
+//!
+//! Fire is enabled by declaring a [`Combustion`] configuration in the scenario.
+//! A container with a [`Fire`] component burns every simulation cycle,
+//! consuming the oxidizer and the fuel and producing the combustion products
+//! (e.g. carbon dioxide and smoke) in proportion to the fire [intensity](Fire::intensity).
+//!
+//! The intensity grows while the oxidizer occupies at least [`Combustion::sustain_fraction`]
+//! of the occupied volume of the container, and decays otherwise.
+//! Thus a compartment is suppressed by flooding it with inert gas,
+//! which dilutes the oxidizer below the sustaining concentration.
+//! The fire is extinguished when its intensity drops to zero or the fuel runs out.
+//!
+//! Fires are started by [`Ignite`] and spread through [pipes](crate::pipe),
+//! including openings between [rooms](crate#rooms),
+//! to adjacent containers that are [ignitable](Combustion::ignition_fraction).
+//! Every ignition and extinction is reported as a [`FireEvent`].
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::event::{Event, EventWriter};
+use bevy::ecs::query::{With, Without};
+use bevy::ecs::schedule::common_conditions::resource_exists;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy::{self, DespawnRecursiveExt};
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::partition::{self, AppExt};
+use traffloat_base::{save, Ref};
+
+use crate::config::{self, Scalar};
+use crate::reaction::{SaveTerm, Term};
+use crate::{commands, container, ledger, pipe, units};
+
+#[cfg(test)]
+mod tests;
+
+/// Simulates fires in containers.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<FireEvent>();
+        app.add_systems(
+            app::Update,
+            (
+                burn_system.before(container::SystemSets::Rebalance),
+                spread_system.after(container::SystemSets::Rebalance),
+            )
+                .in_set(partition::EventWriterSystemSet::<FireEvent>::default())
+                .run_if(resource_exists::<Combustion>)
+                .run_if(in_state(self.0)),
+        );
+        save::add_def::<SaveCombustion>(app);
+        save::add_def::<SaveFire>(app);
+    }
+}
+
+/// Parameters of combustion.
+///
+/// Fires cannot start if the scenario does not declare this resource.
+#[derive(Resource)]
+pub struct Combustion {
+    /// The fluid consumed to sustain fires, with the mass consumed per cycle at full intensity.
+    pub oxidizer:          Term,
+    /// The fluid burnt by fires, with the mass consumed per cycle at full intensity.
+    pub fuel:              Term,
+    /// Fluids produced by fires, with the masses produced per cycle at full intensity.
+    pub products:          Vec<Term>,
+    /// The minimum proportion of the occupied volume of a container held by the oxidizer
+    /// for a fire to ignite or spread into it.
+    pub ignition_fraction: f32,
+    /// The minimum proportion of the occupied volume of a container held by the oxidizer
+    /// for a fire to keep growing.
+    pub sustain_fraction:  f32,
+    /// Intensity gained per cycle while sustained, also the intensity of a new fire.
+    pub growth:            f32,
+    /// Intensity lost per cycle while not sustained.
+    pub decay:             f32,
+}
+
+/// A fire burning in a container.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Fire {
+    /// The proportion of the full burn rate, in the range `(0, 1]`.
+    pub intensity: f32,
+}
+
+/// A fire has started or has been extinguished in a container.
+#[derive(Debug, Event)]
+pub struct FireEvent {
+    /// The container entity.
+    pub container: Entity,
+    /// Whether the container is now burning.
+    pub burning:   bool,
+}
+
+/// Starts a fire in a container.
+///
+/// The fire dies out in the next cycles
+/// if the container does not hold enough oxidizer and fuel.
+/// This command has no effect if the container is already burning
+/// or the scenario does not declare [`Combustion`].
+pub struct Ignite {
+    /// The container to ignite.
+    pub container: Ref<container::Marker>,
+}
+
+impl Command for Ignite {
+    fn apply(self, world: &mut World) {
+        let Some(container) = self.container.get(world).map(|entity| entity.id()) else { return };
+        let Some(combustion) = world.get_resource::<Combustion>() else { return };
+        let intensity = combustion.growth;
+        if world.get::<Fire>(container).is_some() {
+            return;
+        }
+
+        world.entity_mut(container).insert(Fire { intensity });
+        world.send_event(FireEvent { container, burning: true });
+    }
+}
+
+/// The proportion of the occupied volume of a container held by fluid type `ty`.
+fn volume_fraction<'a>(
+    elements: impl IntoIterator<Item = (&'a config::Type, &'a container::element::Volume)>,
+    occupied: &container::CurrentVolume,
+    ty: config::Type,
+) -> f32 {
+    if occupied.volume.quantity <= 0. {
+        return 0.;
+    }
+    let volume: f32 = elements
+        .into_iter()
+        .filter(|&(&element_ty, _)| element_ty == ty)
+        .map(|(_, volume)| volume.volume.quantity)
+        .sum();
+    volume / occupied.volume.quantity
+}
+
+type BurnElementQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static config::Type,
+        &'static mut container::element::Mass,
+        &'static container::element::Volume,
+    ),
+>;
+
+fn burn_system(
+    config: Res<Scalar>,
+    combustion: Res<Combustion>,
+    mut fire_query: Query<
+        (Entity, &mut Fire, &hierarchy::Children, &container::CurrentVolume),
+        With<container::Marker>,
+    >,
+    mut element_query: BurnElementQuery,
+    mut ledger: ResMut<ledger::Ledger>,
+    mut writer: EventWriter<FireEvent>,
+    mut commands: Commands,
+) {
+    for (container, mut fire, elements, occupied) in &mut fire_query {
+        let find_element = |element_query: &BurnElementQuery, ty: config::Type| {
+            elements.iter().copied().find(|&element| {
+                element_query.get(element).is_ok_and(|(&element_ty, _, _)| element_ty == ty)
+            })
+        };
+
+        let oxidizer_fraction = volume_fraction(
+            elements.iter().filter_map(|&element| {
+                element_query.get(element).ok().map(|(ty, _, volume)| (ty, volume))
+            }),
+            occupied,
+            combustion.oxidizer.ty,
+        );
+        if oxidizer_fraction >= combustion.sustain_fraction {
+            fire.intensity = (fire.intensity + combustion.growth).min(1.);
+        } else {
+            fire.intensity -= combustion.decay;
+        }
+
+        let mut ratio = 1f32;
+        for input in [&combustion.oxidizer, &combustion.fuel] {
+            let requested = input.mass * fire.intensity;
+            if requested.quantity <= 0. {
+                continue;
+            }
+            let available = match find_element(&element_query, input.ty) {
+                Some(element) => element_query.get(element).expect("checked above").1.mass,
+                None => units::Mass::default(),
+            };
+            ratio = ratio.min(available.quantity / requested.quantity);
+        }
+
+        if fire.intensity <= 0. || ratio <= 0. {
+            commands.entity(container).remove::<Fire>();
+            writer.send(FireEvent { container, burning: false });
+            continue;
+        }
+
+        let scale = fire.intensity * ratio.min(1.);
+
+        for input in [&combustion.oxidizer, &combustion.fuel] {
+            let Some(element) = find_element(&element_query, input.ty) else { continue };
+            let (_, mut mass, _) = element_query.get_mut(element).expect("checked above");
+            let consumed = input.mass * scale;
+            let consumed = if mass.mass < consumed { mass.mass } else { consumed };
+            mass.mass -= consumed;
+            ledger.record(
+                ledger::Key { ty: input.ty, cause: ledger::Cause::Combustion, container },
+                -consumed,
+            );
+            if mass.mass < config.deletion_threshold {
+                commands.entity(element).despawn_recursive();
+            }
+        }
+
+        for output in &combustion.products {
+            let produced = output.mass * scale;
+            let key = ledger::Key { ty: output.ty, cause: ledger::Cause::Combustion, container };
+            match find_element(&element_query, output.ty) {
+                Some(element) => {
+                    let (_, mut mass, _) = element_query.get_mut(element).expect("checked above");
+                    mass.mass += produced;
+                    ledger.record(key, produced);
+                }
+                None if produced < config.creation_threshold => {} // negligible mass
+                None => {
+                    ledger.record(key, produced);
+                    commands.add(
+                        commands::CreateContainerElement::builder()
+                            .container(Ref::new_unchecked(container))
+                            .ty(output.ty)
+                            .mass(produced)
+                            .build(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn spread_system(
+    combustion: Res<Combustion>,
+    fire_query: Query<&container::Pipes, With<Fire>>,
+    pipe_query: Query<&pipe::Containers>,
+    container_query: Query<
+        (&hierarchy::Children, &container::CurrentVolume),
+        (With<container::Marker>, Without<Fire>),
+    >,
+    element_query: Query<(&config::Type, &container::element::Volume)>,
+    mut writer: EventWriter<FireEvent>,
+    mut commands: Commands,
+) {
+    let mut ignited = EntityHashSet::default();
+
+    for pipes in &fire_query {
+        for &pipe in &pipes.pipes {
+            let Ok(containers) = pipe_query.get(pipe) else { continue };
+            for &neighbor in containers.endpoints.iter() {
+                let Ok((elements, occupied)) = container_query.get(neighbor) else { continue };
+                if ignited.contains(&neighbor) {
+                    continue;
+                }
+
+                let elements =
+                    || elements.iter().filter_map(|&element| element_query.get(element).ok());
+                let has_fuel = elements().any(|(&ty, _)| ty == combustion.fuel.ty);
+                let oxidizer_fraction =
+                    volume_fraction(elements(), occupied, combustion.oxidizer.ty);
+                if has_fuel && oxidizer_fraction >= combustion.ignition_fraction {
+                    ignited.insert(neighbor);
+                    commands.entity(neighbor).insert(Fire { intensity: combustion.growth });
+                    writer.send(FireEvent { container: neighbor, burning: true });
+                }
+            }
+        }
+    }
+}
+
+/// Save schema for combustion parameters.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveCombustion {
+    /// See [`Combustion::oxidizer`].
+    pub oxidizer:          SaveTerm,
+    /// See [`Combustion::fuel`].
+    pub fuel:              SaveTerm,
+    /// See [`Combustion::products`].
+    pub products:          Vec<SaveTerm>,
+    /// See [`Combustion::ignition_fraction`].
+    pub ignition_fraction: f32,
+    /// See [`Combustion::sustain_fraction`].
+    pub sustain_fraction:  f32,
+    /// See [`Combustion::growth`].
+    pub growth:            f32,
+    /// See [`Combustion::decay`].
+    pub decay:             f32,
+}
+
+impl save::Def for SaveCombustion {
+    const TYPE: &'static str = "traffloat.save.fluid.Combustion";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveCombustion>,
+            (type_dep,): (save::StoreDepend<config::SaveType>,),
+            combustion: Option<Res<Combustion>>,
+        ) {
+            let Some(combustion) = combustion else { return };
+            let store_term =
+                |term: &Term| SaveTerm { ty: type_dep.must_get(term.ty), mass: term.mass };
+            writer.write(
+                (),
+                SaveCombustion {
+                    oxidizer:          store_term(&combustion.oxidizer),
+                    fuel:              store_term(&combustion.fuel),
+                    products:          combustion.products.iter().map(store_term).collect(),
+                    ignition_fraction: combustion.ignition_fraction,
+                    sustain_fraction:  combustion.sustain_fraction,
+                    growth:            combustion.growth,
+                    decay:             combustion.decay,
+                },
+            );
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: SaveCombustion,
+            (type_dep,): &(save::LoadDepend<config::SaveType>,),
+        ) -> anyhow::Result<()> {
+            let load_term = |term: SaveTerm| {
+                anyhow::ensure!(
+                    term.mass.quantity >= 0.,
+                    "combustion term mass must be non-negative"
+                );
+                Ok(Term { ty: type_dep.get(term.ty)?, mass: term.mass })
+            };
+            anyhow::ensure!(
+                (0. ..=1.).contains(&def.ignition_fraction)
+                    && (0. ..=1.).contains(&def.sustain_fraction),
+                "oxidizer fractions must be in the range [0, 1]"
+            );
+            anyhow::ensure!(
+                def.growth > 0. && def.growth <= 1. && def.decay > 0.,
+                "growth must be in the range (0, 1] and decay must be positive"
+            );
+
+            world.insert_resource(Combustion {
+                oxidizer:          load_term(def.oxidizer)?,
+                fuel:              load_term(def.fuel)?,
+                products:          def
+                    .products
+                    .into_iter()
+                    .map(load_term)
+                    .collect::<anyhow::Result<_>>()?,
+                ignition_fraction: def.ignition_fraction,
+                sustain_fraction:  def.sustain_fraction,
+                growth:            def.growth,
+                decay:             def.decay,
+            });
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for burning containers.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveFire {
+    /// The burning container.
+    pub container: save::Id<container::Save>,
+    /// See [`Fire::intensity`].
+    pub intensity: f32,
+}
+
+impl save::Def for SaveFire {
+    const TYPE: &'static str = "traffloat.save.fluid.Fire";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveFire>,
+            (container_dep,): (save::StoreDepend<container::Save>,),
+            query: Query<(Entity, &Fire)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, fire)| {
+                (
+                    entity,
+                    SaveFire {
+                        container: container_dep.must_get(entity),
+                        intensity: fire.intensity,
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: SaveFire,
+            (container_dep,): &(save::LoadDepend<container::Save>,),
+        ) -> anyhow::Result<Entity> {
+            anyhow::ensure!(
+                def.intensity > 0. && def.intensity <= 1.,
+                "fire intensity must be in the range (0, 1]"
+            );
+            let container = container_dep.get(def.container)?;
+            world.entity_mut(container).insert(Fire { intensity: def.intensity });
+            Ok(container)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::Command;
+use traffloat_base::Ref;
+use traffloat_graph::corridor::Binary;
+
+use super::{Combustion, Fire, Ignite};
+use crate::reaction::Term;
+use crate::{config, test_util, units};
+
+struct Types {
+    oxidizer: config::Type,
+    fuel:     config::Type,
+    smoke:    config::Type,
+}
+
+fn setup() -> (App, Types) {
+    let mut app = test_util::new_app();
+    let types = Types {
+        oxidizer: test_util::create_type(&mut app, 1.),
+        fuel:     test_util::create_type(&mut app, 1.),
+        smoke:    test_util::create_type(&mut app, 1.),
+    };
+    app.insert_resource(Combustion {
+        oxidizer:          Term { ty: types.oxidizer, mass: units::Mass { quantity: 1. } },
+        fuel:              Term { ty: types.fuel, mass: units::Mass { quantity: 1. } },
+        products:          vec![Term { ty: types.smoke, mass: units::Mass { quantity: 2. } }],
+        ignition_fraction: 0.2,
+        sustain_fraction:  0.2,
+        growth:            1.,
+        decay:             0.5,
+    });
+    (app, types)
+}
+
+fn ignite(app: &mut App, container: Entity) {
+    Ignite { container: Ref::new_unchecked(container) }.apply(app.world_mut());
+}
+
+fn is_burning(app: &App, container: Entity) -> bool { app.world().get::<Fire>(container).is_some() }
+
+#[test]
+fn burns_out_without_fuel() {
+    let (mut app, types) = setup();
+    let container = test_util::spawn_container(&mut app, 100.);
+    test_util::add_fluid(&mut app, container, types.oxidizer, 10.);
+    test_util::add_fluid(&mut app, container, types.fuel, 2.);
+
+    ignite(&mut app, container);
+    assert!(is_burning(&app, container));
+
+    for _ in 0..5 {
+        app.update();
+    }
+
+    assert!(!is_burning(&app, container));
+    assert!(test_util::fluid_mass(&app, container, types.fuel) < 1e-3);
+    assert!((test_util::fluid_mass(&app, container, types.oxidizer) - 8.).abs() < 1e-3);
+    assert!((test_util::fluid_mass(&app, container, types.smoke) - 4.).abs() < 1e-3);
+}
+
+#[test]
+fn decays_without_oxidizer() {
+    let (mut app, types) = setup();
+    let container = test_util::spawn_container(&mut app, 100.);
+    test_util::add_fluid(&mut app, container, types.oxidizer, 1.);
+    test_util::add_fluid(&mut app, container, types.fuel, 100.);
+
+    ignite(&mut app, container);
+    for _ in 0..5 {
+        app.update();
+    }
+
+    assert!(!is_burning(&app, container));
+    assert!(test_util::fluid_mass(&app, container, types.fuel) > 90.);
+}
+
+#[test]
+fn spreads_to_ignitable_neighbor() {
+    let (mut app, types) = setup();
+    let containers = Binary::from_fn(|_| test_util::spawn_container(&mut app, 100.));
+    test_util::connect(&mut app, containers);
+    for container in containers.iter() {
+        test_util::add_fluid(&mut app, *container, types.oxidizer, 10.);
+        test_util::add_fluid(&mut app, *container, types.fuel, 10.);
+    }
+
+    ignite(&mut app, containers.alpha);
+    app.update();
+
+    assert!(is_burning(&app, containers.alpha));
+    assert!(is_burning(&app, containers.beta));
+}
+
+#[test]
+fn does_not_spread_without_fuel() {
+    let (mut app, types) = setup();
+    let containers = Binary::from_fn(|_| test_util::spawn_container(&mut app, 100.));
+    test_util::connect(&mut app, containers);
+    test_util::add_fluid(&mut app, containers.alpha, types.oxidizer, 10.);
+    test_util::add_fluid(&mut app, containers.alpha, types.fuel, 10.);
+    test_util::add_fluid(&mut app, containers.beta, types.oxidizer, 10.);
+
+    ignite(&mut app, containers.alpha);
+    app.update();
+
+    assert!(is_burning(&app, containers.alpha));
+    assert!(!is_burning(&app, containers.beta));
+}
+
+#[test]
+fn ignite_despawned_container() {
+    let (mut app, _) = setup();
+    let container = test_util::spawn_container(&mut app, 100.);
+    app.world_mut().despawn(container);
+
+    ignite(&mut app, container);
+    assert!(app.world().get_entity(container).is_none());
+}
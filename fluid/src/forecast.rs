@@ -0,0 +1,43 @@
+//! Sampling of fluid state in [sandbox forecasts](traffloat_base::save::sandbox).
+//!
+//! Fluid type entities in a sandbox world differ from those in the live world,
+//! so samples identify fluid types by their display label.
+
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::world::World;
+use traffloat_view::DisplayText;
+
+use crate::{config, container, units};
+
+/// The total mass of each fluid type in a world.
+#[derive(Debug, Clone)]
+pub struct Totals {
+    /// Display label and total mass of each fluid type.
+    pub totals: Vec<(DisplayText, units::Mass)>,
+}
+
+/// Computes the total mass of each fluid type over all containers.
+///
+/// This function can be used as the sampler of a
+/// [`RunCommand`](traffloat_base::save::sandbox::RunCommand).
+pub fn sample_totals(world: &mut World) -> Totals {
+    let mut totals: Vec<(config::Type, DisplayText, units::Mass)> = world
+        .query::<(Entity, &config::TypeDef)>()
+        .iter(world)
+        .map(|(entity, def)| {
+            (config::Type(entity), def.display_label.clone(), units::Mass::default())
+        })
+        .collect();
+
+    for (&ty, mass) in world
+        .query_filtered::<(&config::Type, &container::element::Mass), With<container::element::Marker>>()
+        .iter(world)
+    {
+        if let Some((_, _, total)) = totals.iter_mut().find(|(other, _, _)| *other == ty) {
+            *total += mass.mass;
+        }
+    }
+
+    Totals { totals: totals.into_iter().map(|(_, label, total)| (label, total)).collect() }
+}
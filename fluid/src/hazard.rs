@@ -0,0 +1,119 @@
+//! Hazard rating of containers.
+//!
+//! Each container has a [`Hazard`] score in the range `[0, 1]`,
+//! derived from the pressure relative to its limit, waste contamination and fires.
+//! Consumers such as alerts and overlays should read the score
+//! instead of deriving their own thresholds from the underlying state.
+//! The score is also reported as a metric for each container.
+//!
+//! Temperature and structural integrity are not simulated yet
+//! and will be added as further factors.
+
+use std::time::Duration;
+
+use bevy::app::{self, App};
+use bevy::ecs::change_detection::DetectChangesMut;
+use bevy::ecs::component::Component;
+use bevy::ecs::query::{Has, With};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Query, Res};
+use bevy::hierarchy;
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use traffloat_base::save::tunables::{self, Tunable, Tunables};
+use traffloat_view::{format, metrics, DisplayText};
+
+use crate::{config, container, fire, recycling};
+
+/// The pressure ratio to [`container::MaxPressure`] below which pressure is not hazardous.
+///
+/// The pressure factor increases linearly from this ratio to 1 at the limit.
+pub const SAFE_PRESSURE_RATIO: Tunable =
+    Tunable { name: "fluid.hazard.safe_pressure_ratio", default: 0.8 };
+
+/// Maintains the hazard rating of containers.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        tunables::register(app, SAFE_PRESSURE_RATIO);
+        metrics::add_fed_type::<&Hazard, With<container::Marker>, (), _>(
+            app,
+            metrics::TypeDef {
+                update_frequency: Duration::from_secs(1),
+                display_label:    DisplayText::Custom { value: "Hazard".into() },
+                aggregation:      metrics::Aggregation::Mean,
+            },
+            [format::Unit::Ratio.metadata()],
+            |entity, ()| entity.get::<Hazard>().expect("requested in query").score,
+        );
+        app.add_systems(
+            app::Update,
+            update_hazard_system.after(container::SystemSets::Rebalance).run_if(in_state(self.0)),
+        );
+    }
+}
+
+/// The hazard rating of a container.
+///
+/// Included in the [container bundle](container::Bundle).
+///
+/// Each factor is in the range `[0, 1]`, where 0 is safe and 1 is critical.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Hazard {
+    /// The overall score, i.e. the most severe factor.
+    pub score:         f32,
+    /// Pressure approaching the [limit](container::MaxPressure), or 1 if exploded.
+    pub pressure:      f32,
+    /// Waste volume relative to its [tolerance](recycling::Waste::tolerance).
+    pub contamination: f32,
+    /// [Fire](fire::Fire) intensity.
+    pub fire:          f32,
+}
+
+fn update_hazard_system(
+    tunables: Res<Tunables>,
+    mut container_query: Query<(
+        &mut Hazard,
+        &hierarchy::Children,
+        &container::CurrentPressure,
+        &container::MaxPressure,
+        &container::MaxVolume,
+        Has<container::ExplosionMarker>,
+        Option<&fire::Fire>,
+    )>,
+    element_query: Query<(&config::Type, &container::element::Volume)>,
+    waste_query: Query<&recycling::Waste>,
+) {
+    let safe_ratio = tunables.get(SAFE_PRESSURE_RATIO);
+
+    for (mut hazard, elements, pressure, max_pressure, max_volume, exploded, fire) in
+        &mut container_query
+    {
+        let pressure = if exploded {
+            1.
+        } else if max_pressure.pressure.quantity > 0. {
+            let ratio = pressure.pressure.quantity / max_pressure.pressure.quantity;
+            ((ratio - safe_ratio) / (1. - safe_ratio).max(f32::EPSILON)).clamp(0., 1.)
+        } else {
+            0.
+        };
+
+        let contamination = elements
+            .iter()
+            .filter_map(|&element| {
+                let (ty, volume) = element_query.get(element).ok()?;
+                let waste = waste_query.get(ty.0).ok()?;
+                let tolerated = max_volume.volume.quantity * waste.tolerance;
+                Some(if tolerated > 0. { volume.volume.quantity / tolerated } else { 1. })
+            })
+            .fold(0., f32::max)
+            .min(1.);
+
+        let fire = fire.map_or(0., |fire| fire.intensity.clamp(0., 1.));
+
+        let score = pressure.max(contamination).max(fire);
+        // only mark as changed if the rating changed, for consumers filtering with `Changed<Hazard>`
+        hazard.set_if_neq(Hazard { score, pressure, contamination, fire });
+    }
+}
@@ -0,0 +1,118 @@
+//! The ledger attributes fluid production and consumption to their causes.
+//!
+//! Systems that create or destroy fluid mass record each change in the [`Ledger`],
+//! keyed by the fluid type, the [cause](Cause) and the container in which the change happened.
+//! Changes are accumulated over a period of [`Ledger::period`] simulation cycles,
+//! after which the totals are moved to [`Ledger::last`] for clients to query.
+//!
+//! Transfer through pipes only moves mass between containers
+//! and is therefore not recorded in the ledger.
+
+use std::collections::BTreeMap;
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{ResMut, Resource};
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+
+use crate::{config, container, units};
+
+/// Maintains the ledger.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ledger>();
+        app.add_systems(
+            app::Update,
+            rotate_system.after(container::SystemSets::Rebalance).run_if(in_state(self.0)),
+        );
+    }
+}
+
+/// The process causing a change in fluid mass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cause {
+    /// Extraction from a [deposit](crate::mining::Deposit).
+    Mining,
+    /// Conversion by a [recycler](crate::recycling::Recycler).
+    Recycling,
+    /// Growth of a [farm](crate::farm::Farm).
+    Farming,
+    /// Conversion by a [reaction](crate::reaction::Reaction).
+    Reaction,
+    /// Burning of a [fire](crate::fire::Fire).
+    Combustion,
+}
+
+/// Identifies a ledger entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    /// The fluid type changed.
+    pub ty:        config::Type,
+    /// The cause of the change.
+    pub cause:     Cause,
+    /// The container in which the change happened.
+    pub container: Entity,
+}
+
+/// Accumulated mass changes over a period.
+#[derive(Debug, Default, Clone)]
+pub struct Period {
+    /// Net mass change of each entry.
+    /// Production is positive and consumption is negative.
+    pub deltas: BTreeMap<Key, units::Mass>,
+}
+
+impl Period {
+    /// Iterates over the entries of a fluid type.
+    pub fn of_type(&self, ty: config::Type) -> impl Iterator<Item = (&Key, units::Mass)> {
+        self.deltas.iter().filter(move |(key, _)| key.ty == ty).map(|(key, &delta)| (key, delta))
+    }
+
+    /// Returns the net change of a fluid type over the period.
+    #[must_use]
+    pub fn net(&self, ty: config::Type) -> units::Mass {
+        self.of_type(ty).map(|(_, delta)| delta).sum()
+    }
+}
+
+/// Records fluid production and consumption.
+#[derive(Resource)]
+pub struct Ledger {
+    /// Number of simulation cycles in each period.
+    pub period: u32,
+    /// Number of simulation cycles elapsed in the current period.
+    elapsed:    u32,
+    /// Changes in the current period.
+    current:    Period,
+    /// Changes in the last completed period.
+    pub last:   Period,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self { period: 60, elapsed: 0, current: Period::default(), last: Period::default() }
+    }
+}
+
+impl Ledger {
+    /// Records a mass change in the current period.
+    pub fn record(&mut self, key: Key, delta: units::Mass) {
+        *self.current.deltas.entry(key).or_default() += delta;
+    }
+
+    /// Changes recorded in the current incomplete period.
+    #[must_use]
+    pub fn current(&self) -> &Period { &self.current }
+}
+
+fn rotate_system(mut ledger: ResMut<Ledger>) {
+    ledger.elapsed += 1;
+    if ledger.elapsed >= ledger.period {
+        ledger.elapsed = 0;
+        ledger.last = std::mem::take(&mut ledger.current);
+    }
+}
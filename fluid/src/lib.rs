@@ -8,10 +8,24 @@ use bevy::state::state::States;
 
 pub mod config;
 pub mod container;
+pub mod diagnostics;
+pub mod farm;
+pub mod fire;
+pub mod forecast;
+pub mod hazard;
+pub mod ledger;
+pub mod mining;
 pub mod pipe;
+pub mod reaction;
+pub mod recycling;
+pub mod room;
+pub mod trigger;
 pub mod units;
 
 mod commands;
+mod reshape;
+#[cfg(test)]
+mod test_util;
 pub use commands::*;
 
 /// Initializes fluid simulation systems.
@@ -19,6 +33,20 @@ pub struct Plugin<St>(pub St);
 
 impl<St: States + Copy> app::Plugin for Plugin<St> {
     fn build(&self, app: &mut App) {
-        app.add_plugins((config::Plugin, container::Plugin(self.0), pipe::Plugin(self.0)));
+        app.add_plugins((
+            config::Plugin,
+            container::Plugin(self.0),
+            diagnostics::Plugin(self.0),
+            pipe::Plugin(self.0),
+            farm::Plugin(self.0),
+            fire::Plugin(self.0),
+            hazard::Plugin(self.0),
+            ledger::Plugin(self.0),
+            mining::Plugin(self.0),
+            reaction::Plugin(self.0),
+            recycling::Plugin(self.0),
+            trigger::Plugin(self.0),
+            reshape::Plugin,
+        ));
     }
 }
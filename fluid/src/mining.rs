@@ -0,0 +1,386 @@
+//! Mining extracts fluids from finite deposits in asteroids.
+//!
+//! A deposit is a [building] with a [`Deposit`] component,
+//! holding a finite mass of a single fluid type.
+//! The contents of a deposit are unknown until it is [surveyed](Survey),
+//! after which the remaining mass is exposed as a metric on the building
+//! and the deposit becomes mineable.
+//!
+//! A miner is a container with a [`Miner`] component.
+//! In each simulation cycle, each miner extracts fluid from the nearest surveyed deposit
+//! that is within [`Miner::range`] from the miner building
+//! or connected to the miner building by a corridor with an [`Arm`] component.
+//! The extraction rate is scaled by the [operating rate](building::mode::Rate) of the miner building,
+//! and extraction stops when the miner container is full.
+//! A [`DepletedEvent`] is emitted when a deposit runs out.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, EventWriter};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::{IntoSystemConfigs, Schedules};
+use bevy::ecs::system::{Commands, Query, ResMut};
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy;
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use bevy::transform::components::Transform;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::partition::{self, AppExt};
+use traffloat_base::{error, save, Ref};
+use traffloat_graph::{building, corridor};
+use traffloat_view::metrics;
+
+use crate::{commands, config, container, ledger, units};
+
+#[cfg(test)]
+mod tests;
+
+/// Extracts fluids from deposits.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<DepletedEvent>();
+        app.add_systems(
+            config::OnCreateType,
+            on_create_type_system.after(container::RegisterMetricType),
+        );
+        app.add_systems(
+            app::Update,
+            extract_system
+                .before(container::SystemSets::Rebalance)
+                .in_set(partition::EventWriterSystemSet::<DepletedEvent>::default())
+                .run_if(in_state(self.0)),
+        );
+        save::add_def::<SaveDeposit>(app);
+        save::add_def::<SaveMiner>(app);
+        save::add_def::<SaveArm>(app);
+    }
+}
+
+/// A finite amount of fluid in an asteroid building.
+#[derive(Component)]
+pub struct Deposit {
+    /// The type of fluid in the deposit.
+    pub ty:        config::Type,
+    /// The remaining mass of fluid.
+    pub remaining: units::Mass,
+}
+
+/// Marks a deposit as surveyed.
+#[derive(Component)]
+pub struct Surveyed;
+
+/// Extracts fluid from nearby deposits into the container on the same entity.
+#[derive(Component)]
+pub struct Miner {
+    /// Maximum mass extracted per simulation cycle.
+    pub rate:  units::Mass,
+    /// Maximum distance between the miner building and a deposit building
+    /// for deposits not connected by an [`Arm`].
+    pub range: f32,
+}
+
+/// Marks a corridor as a mining arm,
+/// allowing miners in either endpoint to extract from a deposit in the other endpoint
+/// regardless of [`Miner::range`].
+#[derive(Component)]
+pub struct Arm;
+
+/// A deposit has been depleted.
+///
+/// The [`Deposit`] component is removed from the building.
+#[derive(Debug, Event)]
+pub struct DepletedEvent {
+    /// The building that contained the deposit.
+    pub deposit: Entity,
+    /// The type of fluid in the deposit.
+    pub ty:      config::Type,
+}
+
+/// Surveys a deposit, revealing its contents and allowing it to be mined.
+pub struct Survey {
+    /// The deposit building.
+    pub deposit: Ref<Deposit>,
+}
+
+impl Command for Survey {
+    fn apply(self, world: &mut World) {
+        match self.deposit.resolve_mut(world) {
+            Ok(mut deposit) => {
+                deposit.insert(Surveyed);
+            }
+            Err(err) => error::reject(world, err),
+        }
+    }
+}
+
+fn on_create_type_system(world: &mut World) {
+    let fluid_type = world.resource::<config::CreatedType>().get();
+    let &metric_type = world
+        .get::<metrics::Type>(fluid_type.0)
+        .expect("metric type is registered before deposit feeders");
+
+    let feeder = metrics::make_external_value_feeder_system::<
+        (Entity, &Deposit),
+        With<Surveyed>,
+        With<building::Marker>,
+        (),
+        _,
+    >(
+        world,
+        move |(entity, deposit), ()| {
+            (deposit.ty == fluid_type).then_some((entity, deposit.remaining.quantity))
+        },
+        metric_type,
+    );
+    let mut schedules = world.resource_mut::<Schedules>();
+    schedules.add_systems(metrics::BroadcastSchedule, feeder);
+}
+
+#[allow(clippy::too_many_arguments)] // bevy system parameters
+fn extract_system(
+    miner_query: Query<
+        (
+            Entity,
+            &Miner,
+            &hierarchy::Parent,
+            &container::CurrentVolume,
+            &container::MaxVolume,
+            Option<&hierarchy::Children>,
+        ),
+        With<container::Marker>,
+    >,
+    building_query: Query<(&Transform, Option<&building::mode::Rate>), With<building::Marker>>,
+    mut deposit_query: Query<(Entity, &Transform, &mut Deposit), With<Surveyed>>,
+    arm_query: Query<&corridor::Endpoints, With<Arm>>,
+    mut element_query: Query<(&config::Type, &mut container::element::Mass)>,
+    mut depleted_writer: EventWriter<DepletedEvent>,
+    mut ledger: ResMut<ledger::Ledger>,
+    mut commands: Commands,
+) {
+    for (container, miner, parent, current_volume, max_volume, elements) in &miner_query {
+        if current_volume.volume >= max_volume.volume {
+            continue;
+        }
+
+        let miner_building = parent.get();
+        let Ok((miner_tf, rate)) = building_query.get(miner_building) else { continue };
+        let max_mass = miner.rate * building::mode::Rate::of(rate);
+        if max_mass.quantity <= 0. {
+            continue;
+        }
+
+        let target = deposit_query
+            .iter()
+            .filter(|(_, _, deposit)| deposit.remaining.quantity > 0.)
+            .map(|(entity, tf, _)| (entity, tf.translation.distance(miner_tf.translation)))
+            .filter(|&(entity, distance)| {
+                distance <= miner.range
+                    || arm_query.iter().any(|endpoints| {
+                        endpoints.endpoints.find(&miner_building).is_some()
+                            && endpoints.endpoints.find(&entity).is_some()
+                    })
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((target, _)) = target else { continue };
+
+        let (_, _, mut deposit) = deposit_query.get_mut(target).expect("found in iteration");
+        let mass = if deposit.remaining < max_mass { deposit.remaining } else { max_mass };
+        deposit.remaining -= mass;
+        let ty = deposit.ty;
+        ledger.record(ledger::Key { ty, cause: ledger::Cause::Mining, container }, mass);
+
+        let existing = elements.into_iter().flatten().find(|&&element| {
+            element_query.get(element).is_ok_and(|(&element_ty, _)| element_ty == ty)
+        });
+        match existing {
+            Some(&element) => {
+                let (_, mut element_mass) = element_query.get_mut(element).expect("checked above");
+                element_mass.mass += mass;
+            }
+            None => {
+                commands.add(
+                    commands::CreateContainerElement::builder()
+                        .container(Ref::new_unchecked(container))
+                        .ty(ty)
+                        .mass(mass)
+                        .build(),
+                );
+            }
+        }
+
+        if deposit.remaining.quantity <= 0. {
+            commands.entity(target).remove::<Deposit>();
+            depleted_writer.send(DepletedEvent { deposit: target, ty });
+        }
+    }
+}
+
+/// Save schema for deposits.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveDeposit {
+    /// The building containing the deposit.
+    pub building:  save::Id<building::Save>,
+    /// The type of fluid in the deposit.
+    pub ty:        save::Id<config::SaveType>,
+    /// The remaining mass of fluid.
+    pub remaining: units::Mass,
+    /// Whether the deposit has been surveyed.
+    pub surveyed:  bool,
+}
+
+impl save::Def for SaveDeposit {
+    const TYPE: &'static str = "traffloat.save.fluid.Deposit";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveDeposit>,
+            (building_dep, type_dep): (
+                save::StoreDepend<building::Save>,
+                save::StoreDepend<config::SaveType>,
+            ),
+            query: Query<(Entity, &Deposit, Option<&Surveyed>)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, deposit, surveyed)| {
+                (
+                    entity,
+                    SaveDeposit {
+                        building:  building_dep.must_get(entity),
+                        ty:        type_dep.must_get(deposit.ty),
+                        remaining: deposit.remaining,
+                        surveyed:  surveyed.is_some(),
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: SaveDeposit,
+            (building_dep, type_dep): &(
+                save::LoadDepend<building::Save>,
+                save::LoadDepend<config::SaveType>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let building = building_dep.get(def.building)?;
+            let ty = type_dep.get(def.ty)?;
+
+            let mut entity = world.entity_mut(building);
+            entity.insert(Deposit { ty, remaining: def.remaining });
+            if def.surveyed {
+                entity.insert(Surveyed);
+            }
+            Ok(building)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for miners.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveMiner {
+    /// The container receiving extracted fluid.
+    pub container: save::Id<container::Save>,
+    /// Maximum mass extracted per simulation cycle.
+    pub rate:      units::Mass,
+    /// Maximum distance to a deposit not connected by an arm.
+    pub range:     f32,
+}
+
+impl save::Def for SaveMiner {
+    const TYPE: &'static str = "traffloat.save.fluid.Miner";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveMiner>,
+            (container_dep,): (save::StoreDepend<container::Save>,),
+            query: Query<(Entity, &Miner)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, miner)| {
+                (
+                    entity,
+                    SaveMiner {
+                        container: container_dep.must_get(entity),
+                        rate:      miner.rate,
+                        range:     miner.range,
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: SaveMiner,
+            (container_dep,): &(save::LoadDepend<container::Save>,),
+        ) -> anyhow::Result<Entity> {
+            let container = container_dep.get(def.container)?;
+            world.entity_mut(container).insert(Miner { rate: def.rate, range: def.range });
+            Ok(container)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for mining arms.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveArm {
+    /// The corridor used as a mining arm.
+    pub corridor: save::Id<corridor::Save>,
+}
+
+impl save::Def for SaveArm {
+    const TYPE: &'static str = "traffloat.save.fluid.MiningArm";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveArm>,
+            (corridor_dep,): (save::StoreDepend<corridor::Save>,),
+            query: Query<Entity, With<Arm>>,
+        ) {
+            writer.write_all(
+                query
+                    .iter()
+                    .map(|entity| (entity, SaveArm { corridor: corridor_dep.must_get(entity) })),
+            );
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: SaveArm,
+            (corridor_dep,): &(save::LoadDepend<corridor::Save>,),
+        ) -> anyhow::Result<Entity> {
+            let corridor = corridor_dep.get(def.corridor)?;
+            world.entity_mut(corridor).insert(Arm);
+            Ok(corridor)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,59 @@
+use approx::assert_relative_eq;
+use bevy::ecs::world::Command;
+use bevy::math::Vec3;
+use traffloat_base::Ref;
+
+use super::{Deposit, Miner, Survey};
+use crate::{test_util, units};
+
+fn setup(
+    deposit_position: Vec3,
+    survey: bool,
+) -> (bevy::app::App, crate::config::Type, [bevy::ecs::entity::Entity; 2]) {
+    let mut app = test_util::new_app();
+    let ore = test_util::create_type(&mut app, 1.);
+
+    let (_, container) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    app.world_mut()
+        .entity_mut(container)
+        .insert(Miner { rate: units::Mass { quantity: 2. }, range: 10. });
+
+    let (deposit, _) = test_util::spawn_building(&mut app, deposit_position, 100.);
+    app.world_mut()
+        .entity_mut(deposit)
+        .insert(Deposit { ty: ore, remaining: units::Mass { quantity: 3. } });
+    if survey {
+        Survey { deposit: Ref::new_unchecked(deposit) }.apply(app.world_mut());
+    }
+
+    (app, ore, [container, deposit])
+}
+
+#[test]
+fn extract_until_depleted() {
+    let (mut app, ore, [container, deposit]) = setup(Vec3::new(5., 0., 0.), true);
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, ore), 2.);
+    assert_relative_eq!(app.world().get::<Deposit>(deposit).unwrap().remaining.quantity, 1.);
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, ore), 3.);
+    assert!(app.world().get::<Deposit>(deposit).is_none());
+}
+
+#[test]
+fn ignore_unsurveyed_deposit() {
+    let (mut app, ore, [container, _]) = setup(Vec3::new(5., 0., 0.), false);
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, ore), 0.);
+}
+
+#[test]
+fn ignore_deposit_out_of_range() {
+    let (mut app, ore, [container, _]) = setup(Vec3::new(50., 0., 0.), true);
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, ore), 0.);
+}
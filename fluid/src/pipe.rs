@@ -9,17 +9,24 @@
 //!
 //! In each simulation cycle, the following sequence of events takes place:
 //! 1. Compute the [resistance] of each pipe.
-//! 2. Add the [force] in each direction to the resistance
+//! 2. Add the [force] in each direction, including [pumps](pump), to the resistance
 //!    as the [directed gross flow](force::Directed).
 //! 3. Compute the [base transfer weight](element::TransferWeight) of each pipe element.
-//! 4. Distribute the available flow rate for each directed pipe element.
+//! 4. Distribute the available flow rate for each directed pipe element,
+//!    plus the [diffusion](config::Physics::diffusion_coefficient) of each side by its weight.
 //! 5. Perform container element mass updates, lazily creating/deleting pipe elements during the process.
 //!
+//! Under gravity, a pipe with declared [`Ports`] draws from the
+//! [stratified](container::strata) layer at the port height of each container
+//! instead of the whole mixture in step 3.
+//!
 //! A storage for a intra-building inter-facility connections
 //! should reference the building entity as its parent.
 //! A storage for a the connection from a facility to a duct
 //! should reference the duct entity as its parent.
 
+use std::collections::BTreeMap;
+
 use bevy::app::App;
 use bevy::ecs::bundle;
 use bevy::ecs::component::{Component, ComponentId};
@@ -35,9 +42,10 @@ use bevy::{app, hierarchy};
 use derive_more::From;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use traffloat_base::{debug, save};
+use traffloat_base::save::tunables::{self, Tunable, Tunables};
+use traffloat_base::{debug, save, Ref};
 use traffloat_graph::building::facility;
-use traffloat_graph::corridor::{duct, Binary};
+use traffloat_graph::corridor::{duct, Binary, Endpoint};
 use typed_builder::TypedBuilder;
 
 use crate::config::{self, Scalar};
@@ -45,17 +53,29 @@ use crate::{commands, container, units};
 
 pub mod element;
 pub mod force;
+pub mod liquid_pump;
+pub mod pump;
 pub mod resistance;
 
 #[cfg(test)]
 mod tests;
 
+/// Multiplier applied to the flow rate of every pipe.
+pub const TRANSFER_RATE: Tunable = Tunable { name: "fluid.pipe.transfer_rate", default: 1. };
+
 /// Executes fluid mass transfer between containers.
 pub(super) struct Plugin<St>(pub(super) St);
 
 impl<St: States + Copy> app::Plugin for Plugin<St> {
     fn build(&self, app: &mut App) {
-        app.add_plugins((resistance::Plugin(self.0), force::Plugin(self.0)));
+        app.add_plugins((
+            resistance::Plugin(self.0),
+            force::Plugin(self.0),
+            pump::Plugin(self.0),
+            liquid_pump::Plugin(self.0),
+        ));
+        tunables::register(app, TRANSFER_RATE);
+        save::add_def::<Save>(app);
         app.add_systems(
             app::Update,
             (
@@ -87,6 +107,8 @@ pub struct Bundle {
     dynamic_resistance: resistance::Dynamic,
     #[builder(default = force::Directed { force: <_>::default() })]
     force:              force::Directed,
+    #[builder(default)]
+    ports:              Ports,
     #[builder(default, setter(skip))]
     _marker:            Marker,
     #[builder(default = debug::Bundle::new("FluidPipe"))]
@@ -104,38 +126,69 @@ pub struct Containers {
     pub endpoints: Binary<Entity>,
 }
 
+/// Heights of the pipe openings in its endpoint containers.
+#[derive(Component, Default, Clone, Copy)]
+pub struct Ports {
+    /// The height of the opening in each container,
+    /// measured from 0 at the bottom to 1 at the fluid surface.
+    ///
+    /// `None` draws from the whole mixture even if the container is stratified.
+    pub heights: Binary<Option<f32>>,
+}
+
 fn update_transfer_weight_system(
     types: config::Types,
+    physics: Res<config::Physics>,
     mut pipe_elements_query: Query<(
         &mut element::TransferWeight,
         &config::Type,
         &element::ContainerElements,
+        &hierarchy::Parent,
     )>,
+    pipes_query: Query<&Ports>,
     container_elements_query: Query<(&container::element::Volume, &hierarchy::Parent)>,
-    containers_query: Query<&container::CurrentVolume>,
+    containers_query: Query<(&container::CurrentVolume, Option<&container::strata::Strata>)>,
 ) {
-    pipe_elements_query.iter_mut().for_each(|(mut weights_write, &ty, endpoints)| {
+    let settling = physics.gravity.clamp(0., 1.);
+
+    pipe_elements_query.iter_mut().for_each(|(mut weights_write, &ty, endpoints, pipe)| {
         let def = types.get(ty);
+        let ports =
+            pipes_query.get(pipe.get()).map_or_else(|_| Binary::default(), |ports| ports.heights);
 
-        weights_write.output = endpoints.containers.as_ref().map(|&entity| {
+        weights_write.output = endpoints.containers.zip(ports).map(|(entity, port)| {
             let concentration = entity.map_or(0., |entity| {
                 let (volume, parent) = container_elements_query
                     .get(entity)
                     .expect("ContainerElements must contain a valid container element entity");
-                let total_volume = containers_query
+                let (total_volume, strata) = containers_query
                     .get(parent.get())
-                    .expect("Parent of container element must be a container entity")
-                    .volume;
-                volume.volume.quantity / total_volume.quantity
+                    .expect("Parent of container element must be a container entity");
+                let mixed = volume.volume.quantity / total_volume.volume.quantity;
+
+                let layer = port.zip(strata).and_then(|(height, strata)| strata.layer_at(height));
+                match layer {
+                    Some(layer) if settling > 0. && layer.volume.quantity > 0. => {
+                        let stratified = if layer.elements.contains(&entity) {
+                            volume.volume.quantity / layer.volume.quantity
+                        } else {
+                            0.
+                        };
+                        mixed + (stratified - mixed) * settling
+                    }
+                    _ => mixed,
+                }
             });
-            concentration / def.viscosity.quantity
+            concentration / def.viscosity.quantity.powf(physics.viscosity_sensitivity)
         });
     });
 }
 
 fn distribute_transfer_weight_system(
     config: Res<Scalar>,
-    pipes_query: Query<(&hierarchy::Children, &force::Directed, &Containers)>,
+    physics: Res<config::Physics>,
+    tunables: Res<Tunables>,
+    pipes_query: Query<(&hierarchy::Children, &force::Directed, &resistance::Dynamic, &Containers)>,
     mut pipe_elements_query: Query<(
         &config::Type,
         &element::TransferWeight,
@@ -148,7 +201,9 @@ fn distribute_transfer_weight_system(
     )>,
     mut commands: Commands,
 ) {
-    for (elements, force, containers) in pipes_query.iter() {
+    let transfer_rate = tunables.get(TRANSFER_RATE);
+
+    for (elements, force, resistance, containers) in pipes_query.iter() {
         let weight_sum = elements
             .iter()
             .filter_map(|&element| pipe_elements_query.get(element).ok())
@@ -157,7 +212,20 @@ fn distribute_transfer_weight_system(
                 sum.zip(element).map(|(a, b)| a + b)
             });
 
-        let volume_per_weight = force.force.zip(weight_sum).map(|(a, b)| a / b);
+        // a side without weight, e.g. a port above stale layers, transfers nothing
+        let volume_per_weight = force.force.zip(weight_sum).map(|(a, b)| {
+            if b > 0. {
+                a * transfer_rate / b
+            } else {
+                <_>::default()
+            }
+        });
+
+        let diffusion = if resistance.resistance.quantity > 0. {
+            physics.diffusion_coefficient * transfer_rate / resistance.resistance.quantity
+        } else {
+            0.
+        };
 
         for &element in elements {
             let Ok((ty, weight, container_elements, mut mass_ab)) =
@@ -166,7 +234,9 @@ fn distribute_transfer_weight_system(
                 continue;
             };
 
-            let volume_output = volume_per_weight.zip(weight.output).map(|(a, b)| a * b);
+            let volume_output = volume_per_weight
+                .zip(weight.output)
+                .map(|(a, b)| a * b + units::Volume { quantity: diffusion * b });
 
             let mut mass_volume_comps =
                 container_elements.containers.query_mut_with_entity(&mut container_elements_query);
@@ -196,7 +266,7 @@ fn distribute_transfer_weight_system(
                         None => {
                             commands.add(
                                 commands::CreateContainerElement::builder()
-                                    .container(*container)
+                                    .container(Ref::new_unchecked(*container))
                                     .ty(*ty)
                                     .mass(*delta_mass)
                                     .build(),
@@ -215,6 +285,53 @@ fn distribute_transfer_weight_system(
     }
 }
 
+/// Registers a new pipe in the [`container::Pipes`] of its endpoints
+/// and creates pipe elements for the fluids already present in either container.
+///
+/// Fluids created in the containers afterwards are linked by
+/// [`CreateContainerElement`](commands::CreateContainerElement).
+pub(crate) fn connect(world: &mut World, pipe: Entity) {
+    let endpoints = world.get::<Containers>(pipe).expect("pipe must have Containers").endpoints;
+
+    let mut elements: BTreeMap<config::Type, Binary<Option<Entity>>> = BTreeMap::new();
+    for endpoint in [Endpoint::Alpha, Endpoint::Beta] {
+        let container = *endpoints.as_endpoint(endpoint);
+        world
+            .get_mut::<container::Pipes>(container)
+            .expect("pipe endpoints must be containers")
+            .pipes
+            .push(pipe);
+
+        for &child in world.get::<hierarchy::Children>(container).into_iter().flatten() {
+            let Some(&ty) = world.get::<config::Type>(child) else { continue };
+            let containers = elements.entry(ty).or_insert_with(|| Binary::from_fn(|_| None));
+            *containers.as_endpoint_mut(endpoint) = Some(child);
+        }
+    }
+
+    world.entity_mut(pipe).with_children(|builder| {
+        for (ty, containers) in elements {
+            builder.spawn(
+                element::Bundle::builder()
+                    .ty(ty)
+                    .container_elements(element::ContainerElements { containers })
+                    .build(),
+            );
+        }
+    });
+}
+
+/// Disconnects a pipe from both containers and despawns it.
+pub(crate) fn disconnect(world: &mut World, pipe: Entity) {
+    let endpoints = world.get::<Containers>(pipe).expect("pipe must have Containers").endpoints;
+    for container in endpoints {
+        if let Some(mut pipes) = world.get_mut::<container::Pipes>(container) {
+            pipes.pipes.retain(|&mut other| other != pipe);
+        }
+    }
+    world.entity_mut(pipe).despawn_recursive();
+}
+
 fn remove_element_hook(mut world: DeferredWorld, container_element: Entity, _: ComponentId) {
     let ty = world
         .get::<config::Type>(container_element)
@@ -254,6 +371,9 @@ fn remove_element_hook(mut world: DeferredWorld, container_element: Entity, _: C
 }
 
 /// Save schema.
+///
+/// Pipes between facilities of the same building also model internal openings
+/// between [rooms](crate#rooms).
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Save {
     /// Containers connected by this pipe.
@@ -263,20 +383,26 @@ pub struct Save {
     pub containers:       Binary<save::Id<container::Save>>,
     /// Resistance contributed by the pipe shape.
     pub shape_resistance: units::Resistance,
+    /// Heights of the openings in the containers, see [`Ports::heights`].
+    #[serde(default)]
+    pub ports:            Binary<Option<f32>>,
 }
 
 impl save::Def for Save {
-    const TYPE: &'static str = "traffloat.save.fluid.Container";
+    const TYPE: &'static str = "traffloat.save.fluid.Pipe";
 
     type Runtime = Entity;
 
     fn store_system() -> impl save::StoreSystem<Def = Self> {
         fn store_system(
             mut writer: save::Writer<Save>,
-            (container_dep,): (save::StoreDepend<container::Save>,),
-            query: Query<(Entity, &Containers, &resistance::FromShape), With<Marker>>,
+            (container_dep, _element_dep): (
+                save::StoreDepend<container::Save>,
+                save::StoreDepend<container::element::Save>,
+            ),
+            query: Query<(Entity, &Containers, &resistance::FromShape, &Ports), With<Marker>>,
         ) {
-            writer.write_all(query.iter().map(|(entity, containers, shape_resistance)| {
+            writer.write_all(query.iter().map(|(entity, containers, shape_resistance, ports)| {
                 (
                     entity,
                     Save {
@@ -284,6 +410,7 @@ impl save::Def for Save {
                             .endpoints
                             .map(|endpoint| container_dep.must_get(endpoint)),
                         shape_resistance: shape_resistance.resistance,
+                        ports:            ports.heights,
                     },
                 )
             }));
@@ -297,18 +424,28 @@ impl save::Def for Save {
         fn loader(
             world: &mut World,
             def: Save,
-            (container_dep,): &(save::LoadDepend<container::Save>,),
+            // elements are loaded first so that the pipe is linked to the existing fluids
+            (container_dep, _element_dep): &(
+                save::LoadDepend<container::Save>,
+                save::LoadDepend<container::element::Save>,
+            ),
         ) -> anyhow::Result<Entity> {
             enum Parent {
                 Duct(Entity),
                 Building(Entity),
             }
 
+            anyhow::ensure!(
+                def.ports.iter().flatten().all(|height| (0. ..=1.).contains(height)),
+                "pipe port heights must be between 0 and 1"
+            );
+
             let container_entities =
                 def.containers.try_map(|container| container_dep.get(container))?;
             let bundle = Bundle::builder()
                 .containers(Containers { endpoints: container_entities })
                 .shape_resistance(def.shape_resistance)
+                .ports(Ports { heights: def.ports })
                 .build();
 
             let parent_candidates = container_entities.try_map(|container| {
@@ -346,9 +483,9 @@ impl save::Def for Save {
                 }
             };
 
-            let mut container = world.spawn(bundle);
-            container.set_parent(parent);
-            Ok(container.id())
+            let pipe = world.spawn(bundle).set_parent(parent).id();
+            connect(world, pipe);
+            Ok(pipe)
         }
 
         save::LoadFn::new(loader)
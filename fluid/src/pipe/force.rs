@@ -16,13 +16,13 @@
 use bevy::app::{self, App};
 use bevy::ecs::component::Component;
 use bevy::ecs::schedule::{IntoSystemConfigs, IntoSystemSetConfigs, SystemSet};
-use bevy::ecs::system::Query;
+use bevy::ecs::system::{Query, Res};
 use bevy::state::condition::in_state;
 use bevy::state::state::States;
 use traffloat_graph::corridor::Binary;
 
 use super::{resistance, Containers};
-use crate::{container, units};
+use crate::{config, container, units};
 
 pub(super) struct Plugin<St>(pub(super) St);
 
@@ -47,15 +47,14 @@ impl<St: States + Copy> app::Plugin for Plugin<St> {
     }
 }
 
-const VOLUME_PER_PRESSURE_DELTA: f32 = 1.;
-
 fn init_force(
+    physics: Res<config::Physics>,
     mut pipe_query: Query<(&mut Directed, &Containers)>,
     container_query: Query<&container::CurrentPressure>,
 ) {
     pipe_query.iter_mut().for_each(|(mut directed, containers)| {
         let pressure = containers.endpoints.query(&container_query).map(|comp| comp.pressure);
-        let ab = (pressure.alpha - pressure.beta).quantity * VOLUME_PER_PRESSURE_DELTA;
+        let ab = (pressure.alpha - pressure.beta).quantity * physics.pressure_flow_coefficient;
         directed.force.alpha = units::Volume { quantity: ab };
         directed.force.beta = units::Volume { quantity: -ab };
     });
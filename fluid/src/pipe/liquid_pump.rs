@@ -0,0 +1,330 @@
+//! Liquid pumps move a selected fluid from one end of a pipe to the other.
+//!
+//! Unlike a [pump](super::pump), which only biases diffusion,
+//! a [`LiquidPump`] transfers up to [`LiquidPump::rate`] of a single fluid type
+//! from the source container to the destination container every simulation cycle,
+//! scaled by the [operating rate](building::mode::Rate) of the parent building of the pipe.
+//!
+//! The pump [stalls](Stalled) when the source container has none of the fluid
+//! or when the pressure of the destination container reaches [`LiquidPump::max_head`].
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, EventWriter};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res};
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy::{self, DespawnRecursiveExt};
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::partition::{self, AppExt};
+use traffloat_base::{error, save, Ref};
+use traffloat_graph::building;
+use traffloat_graph::corridor::Endpoint;
+
+use super::{pump, Containers};
+use crate::config::{self, Scalar};
+use crate::{commands, container, units};
+
+#[cfg(test)]
+mod tests;
+
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<StallEvent>();
+        app.add_systems(
+            app::Update,
+            transfer_system
+                .before(container::SystemSets::Rebalance)
+                .in_set(partition::EventWriterSystemSet::<StallEvent>::default())
+                .run_if(in_state(self.0)),
+        );
+        save::add_def::<Save>(app);
+    }
+}
+
+/// Transfers a fluid through a pipe.
+#[derive(Component)]
+pub struct LiquidPump {
+    /// The fluid type transferred.
+    pub ty:       config::Type,
+    /// The endpoint of the pipe from which the fluid is taken.
+    pub source:   Endpoint,
+    /// Maximum mass transferred per simulation cycle at full operating rate.
+    pub rate:     units::Mass,
+    /// The destination pressure at which the pump stalls.
+    pub max_head: units::Pressure,
+}
+
+/// Marks a liquid pump that did not transfer any fluid in the last simulation cycle.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Stalled {
+    /// The reason for stalling.
+    pub reason: StallReason,
+}
+
+/// The reason why a liquid pump stalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallReason {
+    /// The source container has none of the pumped fluid.
+    SourceEmpty,
+    /// The destination pressure has reached the maximum head.
+    HeadExceeded,
+}
+
+/// The stall state of a liquid pump has changed.
+#[derive(Debug, Event)]
+pub struct StallEvent {
+    /// The pipe entity.
+    pub pipe:   Entity,
+    /// The reason for stalling, or `None` if the pump has resumed.
+    pub reason: Option<StallReason>,
+}
+
+fn transfer_system(
+    config: Res<Scalar>,
+    pump_query: Query<(
+        Entity,
+        &LiquidPump,
+        &Containers,
+        Option<&hierarchy::Parent>,
+        Option<&Stalled>,
+    )>,
+    rate_query: Query<&building::mode::Rate>,
+    container_query: Query<(&container::CurrentPressure, Option<&hierarchy::Children>)>,
+    mut element_query: Query<(&config::Type, &mut container::element::Mass)>,
+    mut writer: EventWriter<StallEvent>,
+    mut commands: Commands,
+) {
+    for (pipe, pump, containers, parent, was_stalled) in &pump_query {
+        let source = *containers.endpoints.as_endpoint(pump.source);
+        let dest = *containers.endpoints.as_endpoint(!pump.source);
+        let max_mass = pump.rate * pump::rate_of(parent, &rate_query);
+
+        let find_element =
+            |element_query: &Query<(&config::Type, &mut container::element::Mass)>,
+             container: Entity| {
+                let (_, elements) = container_query
+                    .get(container)
+                    .expect("pipe endpoints must be valid containers");
+                // empty containers have no `Children` component
+                elements
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .find(|&element| element_query.get(element).is_ok_and(|(&ty, _)| ty == pump.ty))
+            };
+
+        let (dest_pressure, _) =
+            container_query.get(dest).expect("pipe endpoints must be valid containers");
+        let source_element = find_element(&element_query, source);
+
+        let stall = if dest_pressure.pressure >= pump.max_head {
+            Some(StallReason::HeadExceeded)
+        } else if source_element.is_none() {
+            Some(StallReason::SourceEmpty)
+        } else {
+            None
+        };
+
+        if stall != was_stalled.map(|stalled| stalled.reason) {
+            if let Some(reason) = stall {
+                commands.entity(pipe).insert(Stalled { reason });
+            } else {
+                commands.entity(pipe).remove::<Stalled>();
+            }
+            writer.send(StallEvent { pipe, reason: stall });
+        }
+
+        let (None, Some(source_element)) = (stall, source_element) else { continue };
+        if max_mass.quantity <= 0. {
+            continue;
+        }
+
+        let (_, mut source_mass) = element_query.get_mut(source_element).expect("checked above");
+        let moved = if source_mass.mass < max_mass { source_mass.mass } else { max_mass };
+        source_mass.mass -= moved;
+        if source_mass.mass < config.deletion_threshold {
+            commands.entity(source_element).despawn_recursive();
+        }
+
+        match find_element(&element_query, dest) {
+            Some(dest_element) => {
+                let (_, mut dest_mass) =
+                    element_query.get_mut(dest_element).expect("checked above");
+                dest_mass.mass += moved;
+            }
+            None if moved < config.creation_threshold => {} // negligible mass
+            None => commands.add(
+                commands::CreateContainerElement::builder()
+                    .container(Ref::new_unchecked(dest))
+                    .ty(pump.ty)
+                    .mass(moved)
+                    .build(),
+            ),
+        }
+    }
+}
+
+/// Installs or reconfigures a liquid pump on a pipe.
+///
+/// The change is [rejected](error::reject) if the entity is not a pipe,
+/// the fluid type is invalid, or the rate or head is negative.
+pub struct SetLiquidPump {
+    /// The pipe entity.
+    pub pipe:     Ref<super::Marker>,
+    /// The fluid type to transfer.
+    pub ty:       config::Type,
+    /// The endpoint of the pipe from which the fluid is taken.
+    pub source:   Endpoint,
+    /// Maximum mass transferred per simulation cycle at full operating rate.
+    pub rate:     units::Mass,
+    /// The destination pressure at which the pump stalls.
+    pub max_head: units::Pressure,
+}
+
+impl SetLiquidPump {
+    /// Checks whether the pump configuration is valid.
+    ///
+    /// # Errors
+    /// Returns an error if the entity is not a pipe,
+    /// the fluid type is invalid, or the rate or head is negative.
+    pub fn validate(&self, world: &World) -> Result<(), error::Error> {
+        let pipe = self.pipe.resolve(world)?;
+
+        if world.get::<config::TypeDef>(self.ty.0).is_none() {
+            let message = format!("{:?} is not a fluid type", self.ty.0);
+            return Err(
+                error::Error::not_found("fluid.pump.invalid_type", message).with_entity(pipe.id())
+            );
+        }
+        if self.rate.quantity < 0. || self.max_head.quantity < 0. {
+            let message = "liquid pump rate and head must be non-negative";
+            return Err(
+                error::Error::validation("fluid.pump.negative", message).with_entity(pipe.id())
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Command for SetLiquidPump {
+    fn apply(self, world: &mut World) {
+        if let Err(err) = self.validate(world) {
+            return error::reject(world, err);
+        }
+
+        world.entity_mut(self.pipe.entity()).insert(LiquidPump {
+            ty:       self.ty,
+            source:   self.source,
+            rate:     self.rate,
+            max_head: self.max_head,
+        });
+    }
+}
+
+/// Removes the liquid pump from a pipe.
+pub struct RemoveLiquidPump {
+    /// The pipe entity.
+    pub pipe: Ref<super::Marker>,
+}
+
+impl Command for RemoveLiquidPump {
+    fn apply(self, world: &mut World) {
+        if let Err(err) = self.pipe.resolve(world).map(drop) {
+            return error::reject(world, err);
+        }
+
+        world.entity_mut(self.pipe.entity()).remove::<(LiquidPump, Stalled)>();
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The pipe on which the pump is installed.
+    pub pipe:     save::Id<super::Save>,
+    /// The fluid type transferred.
+    pub ty:       save::Id<config::SaveType>,
+    /// The container from which the fluid is taken.
+    /// Must be an endpoint of the pipe.
+    pub source:   save::Id<container::Save>,
+    /// Maximum mass transferred per simulation cycle at full operating rate.
+    pub rate:     units::Mass,
+    /// The destination pressure at which the pump stalls.
+    pub max_head: units::Pressure,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.fluid.LiquidPump";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (pipe_dep, type_dep, container_dep): (
+                save::StoreDepend<super::Save>,
+                save::StoreDepend<config::SaveType>,
+                save::StoreDepend<container::Save>,
+            ),
+            query: Query<(Entity, &LiquidPump, &Containers)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, pump, containers)| {
+                (
+                    entity,
+                    Save {
+                        pipe:     pipe_dep.must_get(entity),
+                        ty:       type_dep.must_get(pump.ty),
+                        source:   container_dep
+                            .must_get(*containers.endpoints.as_endpoint(pump.source)),
+                        rate:     pump.rate,
+                        max_head: pump.max_head,
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (pipe_dep, type_dep, container_dep): &(
+                save::LoadDepend<super::Save>,
+                save::LoadDepend<config::SaveType>,
+                save::LoadDepend<container::Save>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let pipe = pipe_dep.get(def.pipe)?;
+            let source_container = container_dep.get(def.source)?;
+            let containers = world.get::<Containers>(pipe).expect("pipe must have Containers");
+            let source = containers.endpoints.find(&source_container).ok_or_else(|| {
+                anyhow::anyhow!("liquid pump source is not an endpoint of the pipe")
+            })?;
+            anyhow::ensure!(
+                def.rate.quantity >= 0. && def.max_head.quantity >= 0.,
+                "liquid pump rate and head must be non-negative"
+            );
+
+            world.entity_mut(pipe).insert(LiquidPump {
+                ty: type_dep.get(def.ty)?,
+                source,
+                rate: def.rate,
+                max_head: def.max_head,
+            });
+            Ok(pipe)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,126 @@
+use approx::assert_relative_eq;
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Events;
+use bevy::ecs::world::Command;
+use traffloat_base::Ref;
+use traffloat_graph::corridor::{Binary, Endpoint};
+
+use super::{LiquidPump, RemoveLiquidPump, SetLiquidPump, StallEvent, StallReason, Stalled};
+use crate::{config, test_util, units};
+
+struct Setup {
+    app:        App,
+    ty:         config::Type,
+    containers: Binary<Entity>,
+    pipe:       Entity,
+}
+
+fn setup(max_head: f32) -> Setup {
+    let mut app = test_util::new_app();
+    let ty = test_util::create_type(&mut app, 1.);
+    let containers = Binary::from_fn(|_| test_util::spawn_container(&mut app, 100.));
+    let pipe = test_util::connect(&mut app, containers);
+    set_pump(ty, pipe, 1., max_head).apply(app.world_mut());
+    Setup { app, ty, containers, pipe }
+}
+
+fn set_pump(ty: config::Type, pipe: Entity, rate: f32, max_head: f32) -> SetLiquidPump {
+    SetLiquidPump {
+        pipe: Ref::new_unchecked(pipe),
+        ty,
+        source: Endpoint::Alpha,
+        rate: units::Mass { quantity: rate },
+        max_head: units::Pressure { quantity: max_head },
+    }
+}
+
+fn stall_reason(app: &App, pipe: Entity) -> Option<StallReason> {
+    app.world().get::<Stalled>(pipe).map(|stalled| stalled.reason)
+}
+
+fn drain_stall_events(app: &mut App) -> Vec<Option<StallReason>> {
+    app.world_mut().resource_mut::<Events<StallEvent>>().drain().map(|event| event.reason).collect()
+}
+
+#[test]
+fn pump_into_empty_container() {
+    let Setup { mut app, ty, containers, pipe } = setup(100.);
+    test_util::add_fluid(&mut app, containers.alpha, ty, 10.);
+
+    app.update();
+
+    assert_eq!(stall_reason(&app, pipe), None);
+    assert_eq!(drain_stall_events(&mut app), Vec::new());
+    assert!(test_util::fluid_mass(&app, containers.beta, ty) >= 1. - 1e-4);
+    assert_relative_eq!(
+        test_util::fluid_mass(&app, containers.alpha, ty)
+            + test_util::fluid_mass(&app, containers.beta, ty),
+        10.,
+        epsilon = 1e-4
+    );
+}
+
+#[test]
+fn stall_on_empty_source() {
+    let Setup { mut app, ty, containers, pipe } = setup(100.);
+
+    app.update();
+    assert_eq!(stall_reason(&app, pipe), Some(StallReason::SourceEmpty));
+    assert_eq!(drain_stall_events(&mut app), vec![Some(StallReason::SourceEmpty)]);
+
+    app.update();
+    assert_eq!(drain_stall_events(&mut app), Vec::new(), "unchanged stall state is not reported");
+
+    test_util::add_fluid(&mut app, containers.alpha, ty, 10.);
+    app.update();
+    assert_eq!(stall_reason(&app, pipe), None);
+    assert_eq!(drain_stall_events(&mut app), vec![None]);
+}
+
+#[test]
+fn stall_on_head_exceeded() {
+    let Setup { mut app, ty, containers, pipe } = setup(0.);
+    test_util::add_fluid(&mut app, containers.alpha, ty, 10.);
+
+    app.update();
+
+    assert_eq!(stall_reason(&app, pipe), Some(StallReason::HeadExceeded));
+    assert_eq!(drain_stall_events(&mut app), vec![Some(StallReason::HeadExceeded)]);
+}
+
+#[test]
+fn reject_invalid_pump() {
+    let Setup { app, ty, containers, pipe } = setup(100.);
+    let key = |command: SetLiquidPump| {
+        command.validate(app.world()).map_err(|err| err.detail().key.to_string()).err()
+    };
+
+    assert_eq!(key(set_pump(ty, pipe, 1., 1.)), None);
+    assert_eq!(key(set_pump(ty, pipe, -1., 1.)).as_deref(), Some("fluid.pump.negative"));
+    assert_eq!(key(set_pump(ty, pipe, 1., -1.)).as_deref(), Some("fluid.pump.negative"));
+    assert_eq!(
+        key(set_pump(config::Type(containers.alpha), pipe, 1., 1.)).as_deref(),
+        Some("fluid.pump.invalid_type")
+    );
+    assert!(key(set_pump(ty, containers.alpha, 1., 1.)).is_some(), "containers are not pipes");
+}
+
+#[test]
+fn reconfigure_and_remove() {
+    let Setup { mut app, ty, pipe, .. } = setup(100.);
+    app.update();
+    assert_eq!(stall_reason(&app, pipe), Some(StallReason::SourceEmpty));
+
+    set_pump(ty, pipe, 2., 50.).apply(app.world_mut());
+    let pump = app.world().get::<LiquidPump>(pipe).expect("pump should be installed");
+    assert_relative_eq!(pump.rate.quantity, 2.);
+    assert_relative_eq!(pump.max_head.quantity, 50.);
+
+    RemoveLiquidPump { pipe: Ref::new_unchecked(pipe) }.apply(app.world_mut());
+    assert!(app.world().get::<LiquidPump>(pipe).is_none());
+    assert_eq!(stall_reason(&app, pipe), None);
+
+    app.update();
+    assert_eq!(stall_reason(&app, pipe), None, "removed pumps do not stall");
+}
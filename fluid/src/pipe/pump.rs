@@ -0,0 +1,119 @@
+//! Pumps and fans push fluids through a pipe in a fixed direction.
+//!
+//! A pipe with a [`Pump`] component receives an [additive](force::SystemSets::Additive) force
+//! on each side in addition to the pressure difference,
+//! scaled by the [operating rate](building::mode::Rate) of the parent building of the pipe.
+//! Pipes parented by a duct are always pumped at full rate.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::Query;
+use bevy::ecs::world::World;
+use bevy::hierarchy;
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::save;
+use traffloat_graph::building;
+use traffloat_graph::corridor::Binary;
+
+use super::force;
+use crate::units;
+
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            app::Update,
+            apply_pump_system.in_set(force::SystemSets::Additive).run_if(in_state(self.0)),
+        );
+        save::add_def::<Save>(app);
+    }
+}
+
+/// Adds a directed force to a pipe.
+#[derive(Component)]
+pub struct Pump {
+    /// The force pushing fluid from each side to the other side at full operating rate.
+    ///
+    /// A fan that only pushes from alpha to beta has a zero `beta` force.
+    pub force: Binary<units::Volume>,
+}
+
+impl Pump {
+    /// Returns the force contributed by the pump at the given operating rate.
+    #[must_use]
+    pub fn effective_force(&self, rate: f32) -> Binary<units::Volume> {
+        self.force.map(|force| force * rate)
+    }
+}
+
+/// Returns the operating rate of the pump on a pipe with the given parent.
+pub(crate) fn rate_of(
+    parent: Option<&hierarchy::Parent>,
+    rate_query: &Query<&building::mode::Rate>,
+) -> f32 {
+    building::mode::Rate::of(parent.and_then(|parent| rate_query.get(parent.get()).ok()))
+}
+
+fn apply_pump_system(
+    mut pipe_query: Query<(&mut force::Directed, &Pump, Option<&hierarchy::Parent>)>,
+    rate_query: Query<&building::mode::Rate>,
+) {
+    pipe_query.iter_mut().for_each(|(mut directed, pump, parent)| {
+        let contribution = pump.effective_force(rate_of(parent, &rate_query));
+        directed.force.as_mut().zip(contribution).each_mut(|(force, delta)| **force += *delta);
+    });
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The pumped pipe.
+    pub pipe:  save::Id<super::Save>,
+    /// The force pushing fluid from each side at full operating rate.
+    pub force: Binary<units::Volume>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.fluid.Pump";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (pipe_dep,): (save::StoreDepend<super::Save>,),
+            query: Query<(Entity, &Pump)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, pump)| {
+                (entity, Save { pipe: pipe_dep.must_get(entity), force: pump.force })
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (pipe_dep,): &(save::LoadDepend<super::Save>,),
+        ) -> anyhow::Result<Entity> {
+            let pipe = pipe_dep.get(def.pipe)?;
+            anyhow::ensure!(
+                def.force.iter().all(|force| force.quantity >= 0.),
+                "pump force must be non-negative"
+            );
+            world.entity_mut(pipe).insert(Pump { force: def.force });
+            Ok(pipe)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
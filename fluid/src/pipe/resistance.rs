@@ -41,6 +41,9 @@
 //! When a static resistance value for a pipe needs to be recomputed,
 //! emit a [`RecomputeStaticEvent`] for the pipe entity
 //! in a system scheduled **before** the [`SystemSets::Compute`] system set.
+//! This plugin already does so for pipes in the ducts of a corridor
+//! when an endpoint building is [moved](event::BuildingMoved)
+//! or the [endpoints change](event::EndpointsChanged).
 //!
 //! ```
 //! use bevy::prelude::*;
@@ -97,15 +100,18 @@
 
 use bevy::app::{self, App};
 use bevy::ecs::component::Component;
-use bevy::ecs::entity::Entity;
-use bevy::ecs::event::{Event, EventReader};
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::event::{Event, EventReader, EventWriter};
+use bevy::ecs::query::With;
 use bevy::ecs::schedule::{IntoSystemConfigs, IntoSystemSetConfigs, SystemSet};
 use bevy::ecs::system::Query;
+use bevy::hierarchy;
 use bevy::state::condition::in_state;
 use bevy::state::state::States;
 use derive_more::From;
-use traffloat_base::partition::AppExt;
+use traffloat_base::partition::{AppExt, EventWriterSystemSet};
 use traffloat_base::EventReaderSystemSet;
+use traffloat_graph::{corridor, event};
 
 use crate::units;
 
@@ -117,11 +123,19 @@ impl<St: States + Copy> app::Plugin for Plugin<St> {
         app.add_systems(
             app::Update,
             (
-                static_to_dynamic_system.after(SystemSets::Static).before(SystemSets::Dynamic),
+                static_to_dynamic_system
+                    .after(SystemSets::Static)
+                    .before(SystemSets::Dynamic)
+                    .in_set(SystemSets::Compute),
                 init_static
                     .before(SystemSets::Static)
                     .in_set(SystemSets::Compute)
                     .in_set(EventReaderSystemSet::<RecomputeStaticEvent>::default()),
+                recompute_corridor_pipes_system
+                    .before(SystemSets::Compute)
+                    .in_set(EventReaderSystemSet::<event::BuildingMoved>::default())
+                    .in_set(EventReaderSystemSet::<event::EndpointsChanged>::default())
+                    .in_set(EventWriterSystemSet::<RecomputeStaticEvent>::default()),
             )
                 .run_if(in_state(self.0)),
         );
@@ -158,6 +172,41 @@ fn init_static(
     }
 }
 
+/// Requests recomputation of static resistance for pipes in corridors whose geometry changed.
+fn recompute_corridor_pipes_system(
+    mut moved_reader: EventReader<event::BuildingMoved>,
+    mut endpoints_reader: EventReader<event::EndpointsChanged>,
+    corridor_query: Query<(Entity, &corridor::Endpoints, &corridor::DuctList)>,
+    children_query: Query<&hierarchy::Children>,
+    pipe_query: Query<(), With<super::Marker>>,
+    mut writer: EventWriter<RecomputeStaticEvent>,
+) {
+    let mut corridors: EntityHashSet =
+        endpoints_reader.read().map(|event| event.corridor).collect();
+    for event in moved_reader.read() {
+        corridors.extend(
+            corridor_query
+                .iter()
+                .filter(|(_, endpoints, _)| endpoints.endpoints.find(&event.building).is_some())
+                .map(|(corridor, _, _)| corridor),
+        );
+    }
+
+    for corridor in corridors {
+        // the corridor may have been removed after the event was sent
+        let Ok((_, _, ducts)) = corridor_query.get(corridor) else { continue };
+        let pipes = ducts
+            .duct_list
+            .iter()
+            .chain([&ducts.ambient])
+            .filter_map(|&duct| children_query.get(duct).ok())
+            .flatten()
+            .copied()
+            .filter(|&pipe| pipe_query.contains(pipe));
+        writer.send_batch(pipes.map(|entity| RecomputeStaticEvent { entity }));
+    }
+}
+
 /// Contributes static resistance as a dynamic resistance,
 /// and acts as a partition between static and dynamic resistance.
 ///
@@ -165,7 +214,8 @@ fn init_static(
 /// - All dynamic resistance contributors must execute after this.
 fn static_to_dynamic_system(mut query: Query<(&Static, &mut Dynamic)>) {
     query.iter_mut().for_each(|(static_, mut dynamic)| {
-        dynamic.resistance += static_.resistance;
+        // overwrite the value from the previous cycle since this is the first dynamic contributor
+        dynamic.resistance = static_.resistance;
     });
 }
 
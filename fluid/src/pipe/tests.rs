@@ -2,16 +2,21 @@ use std::iter;
 
 use approx::assert_relative_eq;
 use bevy::app::App;
+use bevy::ecs::entity::Entity;
 use bevy::ecs::world::Command;
+use bevy::hierarchy::{BuildWorldChildren, Children};
+use bevy::math::Vec3;
 use bevy::state::app::{AppExtStates, StatesPlugin};
 use bevy::time::TimePlugin;
-use traffloat_base::{save, EmptyState};
-use traffloat_graph::corridor::Binary;
+use traffloat_base::{save, EmptyState, Ref};
+use traffloat_graph::corridor::{self, duct, Binary, Endpoint};
+use traffloat_graph::MoveBuilding;
 use traffloat_view::DisplayText;
 use typed_builder::TypedBuilder;
 
 use crate::config::{self, Scalar};
-use crate::{commands, container, pipe, units};
+use crate::pipe::resistance;
+use crate::{commands, container, pipe, test_util, units};
 
 struct Setup {
     elements:   Vec<ElementSetup>,
@@ -46,6 +51,7 @@ fn do_test(setup: Setup) {
         StatesPlugin,
         save::Plugin,
         traffloat_view::Plugin,
+        traffloat_graph::Plugin,
         config::Plugin,
         container::Plugin(EmptyState),
         pipe::Plugin(EmptyState),
@@ -65,6 +71,9 @@ fn do_test(setup: Setup) {
                     vacuum_specific_volume: element.vacuum_specific_volume,
                     critical_pressure:      element.critical_pressure,
                     saturation_gamma:       element.saturation_gamma,
+                    immiscible:             false,
+                    color:                  None,
+                    haze:                   None,
                 },
             )
         })
@@ -73,35 +82,42 @@ fn do_test(setup: Setup) {
 
     let containers = Binary::from_fn(|endpoint| {
         let container_setup = setup.containers.as_endpoint(endpoint);
-        let entity = app.world_mut().spawn(
-            container::Bundle::builder()
-                .max_volume(container_setup.max_volume)
-                .max_pressure(container_setup.max_pressure)
+        app.world_mut()
+            .spawn(
+                container::Bundle::builder()
+                    .max_volume(container_setup.max_volume)
+                    .max_pressure(container_setup.max_pressure)
+                    .build(),
+            )
+            .id()
+    });
+
+    let pipe = app
+        .world_mut()
+        .spawn(
+            pipe::Bundle::builder()
+                .shape_resistance(units::Resistance { quantity: 1. })
+                .static_resistance(resistance::Static {
+                    resistance: units::Resistance { quantity: 1. },
+                })
+                .containers(containers)
                 .build(),
-        );
-        let entity = entity.id();
+        )
+        .id();
+    for container in containers.iter() {
+        app.world_mut().get_mut::<container::Pipes>(*container).unwrap().pipes.push(pipe);
+    }
 
+    for endpoint in [Endpoint::Alpha, Endpoint::Beta] {
         for (element, &ty) in iter::zip(&setup.elements, &types) {
             commands::CreateContainerElement::builder()
-                .container(entity)
+                .container(Ref::new_unchecked(*containers.as_endpoint(endpoint)))
                 .ty(ty)
                 .mass(element.mass.into_endpoint(endpoint))
                 .build()
                 .apply(app.world_mut());
         }
-
-        entity
-    });
-
-    let _pipe = {
-        let entity = app.world_mut().spawn(
-            pipe::Bundle::builder()
-                .shape_resistance(units::Resistance { quantity: 1. })
-                .containers(containers)
-                .build(),
-        );
-        entity.id()
-    };
+    }
 
     for _ in 0..100 {
         app.update();
@@ -143,3 +159,186 @@ fn filled_to_empty() {
         .into(),
     });
 }
+
+/// Creates two containers connected by a pipe with `ports`,
+/// where alpha holds a dense and a light immiscible fluid under gravity.
+fn stratified_pair(ports: pipe::Ports) -> (App, [config::Type; 2], Binary<Entity>) {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        StatesPlugin,
+        save::Plugin,
+        traffloat_view::Plugin,
+        traffloat_graph::Plugin,
+        config::Plugin,
+        container::Plugin(EmptyState),
+        pipe::Plugin(EmptyState),
+    ));
+    app.init_state::<EmptyState>();
+    app.insert_resource(config::Physics { gravity: 1., ..config::Physics::default() });
+
+    let types = [1., 2.].map(|vacuum_specific_volume: f32| {
+        config::create_type(
+            &mut app.world_mut().commands(),
+            config::TypeDef {
+                display_label:          DisplayText::default(),
+                viscosity:              units::Viscosity { quantity: 1. },
+                vacuum_specific_volume: vacuum_specific_volume.into(),
+                critical_pressure:      units::Pressure { quantity: 10. },
+                saturation_gamma:       10.,
+                immiscible:             true,
+                color:                  None,
+                haze:                   None,
+            },
+        )
+    });
+
+    let containers = Binary::from_fn(|_| {
+        app.world_mut()
+            .spawn(
+                container::Bundle::builder()
+                    .max_volume(units::Volume { quantity: 10. })
+                    .max_pressure(units::Pressure { quantity: 10. })
+                    .build(),
+            )
+            .id()
+    });
+
+    let pipe = app
+        .world_mut()
+        .spawn(
+            pipe::Bundle::builder()
+                .shape_resistance(units::Resistance { quantity: 1. })
+                .static_resistance(resistance::Static {
+                    resistance: units::Resistance { quantity: 1. },
+                })
+                .containers(containers)
+                .ports(ports)
+                .build(),
+        )
+        .id();
+    for container in containers.iter() {
+        app.world_mut().get_mut::<container::Pipes>(*container).unwrap().pipes.push(pipe);
+    }
+
+    // the dense fluid settles below the light fluid in alpha
+    for ty in types {
+        commands::CreateContainerElement::builder()
+            .container(Ref::new_unchecked(containers.alpha))
+            .ty(ty)
+            .mass(units::Mass { quantity: 2. })
+            .build()
+            .apply(app.world_mut());
+    }
+
+    (app, types, containers)
+}
+
+/// Runs two containers at equal pressure connected by a pipe,
+/// where alpha and beta each hold a different fluid,
+/// and returns the mass of the alpha fluid that reached beta.
+fn diffused_mass(diffusion_coefficient: f32) -> f32 {
+    let (mut app, types, containers) = stratified_pair(pipe::Ports::default());
+    app.insert_resource(config::Physics { diffusion_coefficient, ..config::Physics::default() });
+    commands::CreateContainerElement::builder()
+        .container(Ref::new_unchecked(containers.beta))
+        .ty(types[1])
+        .mass(units::Mass { quantity: 3. })
+        .build()
+        .apply(app.world_mut());
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    element_mass(&app, containers.beta, types[0])
+}
+
+#[test]
+fn diffuse_at_equal_pressure() {
+    assert_relative_eq!(diffused_mass(0.), 0.);
+    assert!(diffused_mass(1.) > 0.);
+}
+
+fn element_mass(app: &App, container: Entity, ty: config::Type) -> f32 {
+    app.world()
+        .get::<Children>(container)
+        .into_iter()
+        .flatten()
+        .filter(|&&element| app.world().get::<config::Type>(element) == Some(&ty))
+        .filter_map(|&element| app.world().get::<container::element::Mass>(element))
+        .map(|mass| mass.mass.quantity)
+        .sum()
+}
+
+/// Asserts that `drawn` reaches beta while alpha still holds `kept` layered on the other side,
+/// and that `kept` does not reach beta until `drawn` is drained from alpha.
+fn assert_draws_layer(ports: pipe::Ports, drawn_index: usize) {
+    let (mut app, types, containers) = stratified_pair(ports);
+    let (drawn, kept) = (types[drawn_index], types[1 - drawn_index]);
+
+    for _ in 0..10 {
+        app.update();
+        if element_mass(&app, containers.alpha, drawn) > 0. {
+            assert_relative_eq!(element_mass(&app, containers.beta, kept), 0.);
+        }
+    }
+
+    assert!(element_mass(&app, containers.beta, drawn) > 0.);
+}
+
+#[test]
+fn bottom_port_draws_dense_layer() {
+    assert_draws_layer(pipe::Ports { heights: Binary { alpha: Some(0.), beta: None } }, 0);
+}
+
+#[test]
+fn top_port_draws_light_layer() {
+    assert_draws_layer(pipe::Ports { heights: Binary { alpha: Some(1.), beta: None } }, 1);
+}
+
+#[test]
+fn zero_weight_side_transfers_nothing() {
+    // beta holds no fluid, so its side of the pipe has zero transfer weight
+    let (mut app, types, containers) = stratified_pair(pipe::Ports::default());
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    for ty in types {
+        let masses = containers.map(|container| element_mass(&app, container, ty));
+        assert!(masses.alpha.is_finite() && masses.beta.is_finite());
+        assert_relative_eq!(masses.alpha + masses.beta, 2., epsilon = 1e-4);
+    }
+}
+
+#[test]
+fn recompute_static_resistance_when_building_moves() {
+    let mut app = test_util::new_app();
+    let (alpha, alpha_ambient) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    let (beta, _) = test_util::spawn_building(&mut app, Vec3::new(10., 0., 0.), 100.);
+    let duct = test_util::spawn_container(&mut app, 100.);
+    app.world_mut().entity_mut(duct).insert(duct::Bundle::builder().build());
+    app.world_mut()
+        .spawn(
+            corridor::Bundle::builder()
+                .endpoints(corridor::Endpoints { endpoints: Binary { alpha, beta } })
+                .duct_list(corridor::DuctList { duct_list: Vec::new(), ambient: duct })
+                .build(),
+        )
+        .add_child(duct);
+    let pipe = test_util::connect(&mut app, Binary { alpha: alpha_ambient, beta: duct });
+    app.world_mut().entity_mut(pipe).set_parent(duct);
+    let static_resistance =
+        |app: &App| app.world().get::<resistance::Static>(pipe).unwrap().resistance.quantity;
+
+    app.world_mut().get_mut::<resistance::Static>(pipe).unwrap().resistance.quantity = 5.;
+    app.update();
+    assert_relative_eq!(static_resistance(&app), 5.);
+
+    MoveBuilding { building: Ref::new_unchecked(beta), translation: Vec3::new(20., 0., 0.) }
+        .apply(app.world_mut());
+    app.update();
+    assert_relative_eq!(static_resistance(&app), 1.);
+}
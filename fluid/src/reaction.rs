@@ -0,0 +1,280 @@
+//! Reactions convert fluids in a container into other fluids.
+//!
+//! A container with a [`Reaction`] component consumes each of its inputs
+//! and produces each of its outputs every simulation cycle,
+//! scaled by the [operating rate](building::mode::Rate) of the building.
+//! If an input is insufficient for the requested rate,
+//! the [`Underflow`] policy of the reaction decides whether
+//! the reaction pauses or proceeds at the rate allowed by the scarcest input.
+//!
+//! The proportion of the requested rate actually achieved is stored in [`Progress`]
+//! and reported as a metric for each container.
+
+use std::time::Duration;
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
+use bevy::ecs::world::World;
+use bevy::hierarchy::{self, DespawnRecursiveExt};
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{save, Ref};
+use traffloat_graph::building;
+use traffloat_view::{format, metrics, DisplayText};
+
+use crate::config::{self, Scalar};
+use crate::{commands, container, ledger, units};
+
+/// Executes reactions in containers.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        metrics::add_fed_type::<&Progress, With<Reaction>, (), _>(
+            app,
+            metrics::TypeDef {
+                update_frequency: Duration::from_secs(1),
+                display_label:    DisplayText::Custom { value: "Reaction progress".into() },
+                aggregation:      metrics::Aggregation::Mean,
+            },
+            [format::Unit::Ratio.metadata()],
+            |entity, ()| entity.get::<Progress>().expect("requested in query").ratio,
+        );
+        app.add_systems(
+            app::Update,
+            react_system.before(container::SystemSets::Rebalance).run_if(in_state(self.0)),
+        );
+        save::add_def::<SaveReaction>(app);
+    }
+}
+
+/// Converts a set of fluids into another set of fluids in the same container.
+///
+/// Insert together with [`Progress`].
+#[derive(Component)]
+pub struct Reaction {
+    /// Fluids consumed by the reaction.
+    pub inputs:    Vec<Term>,
+    /// Fluids produced by the reaction.
+    pub outputs:   Vec<Term>,
+    /// Behavior when an input is insufficient.
+    pub underflow: Underflow,
+}
+
+/// A fluid consumed or produced by a reaction.
+#[derive(Debug, Clone, Copy)]
+pub struct Term {
+    /// The fluid type.
+    pub ty:   config::Type,
+    /// Mass consumed or produced per simulation cycle at full operating rate.
+    pub mass: units::Mass,
+}
+
+/// Behavior of a reaction when an input is insufficient for the requested rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Underflow {
+    /// The reaction does not run at all in this cycle.
+    Pause,
+    /// The reaction runs at the rate allowed by the scarcest input.
+    Partial,
+}
+
+/// The proportion of the requested rate achieved by a reaction in the last cycle.
+///
+/// This is 1 if all inputs were sufficient,
+/// and 0 if the reaction was paused or the building is not operating.
+#[derive(Component, Default)]
+pub struct Progress {
+    /// The achieved proportion in the range `[0, 1]`.
+    pub ratio: f32,
+}
+
+fn react_system(
+    config: Res<Scalar>,
+    mut reaction_query: Query<
+        (Entity, &Reaction, &mut Progress, &hierarchy::Parent, &hierarchy::Children),
+        With<container::Marker>,
+    >,
+    rate_query: Query<&building::mode::Rate>,
+    mut element_query: Query<(&config::Type, &mut container::element::Mass)>,
+    mut ledger: ResMut<ledger::Ledger>,
+    mut commands: Commands,
+) {
+    for (container, reaction, mut progress, parent, elements) in &mut reaction_query {
+        progress.ratio = 0.;
+
+        let rate = building::mode::Rate::of(rate_query.get(parent.get()).ok());
+        if rate <= 0. {
+            continue;
+        }
+
+        let find_element =
+            |element_query: &Query<(&config::Type, &mut container::element::Mass)>,
+             ty: config::Type| {
+                elements.iter().copied().find(|&element| {
+                    element_query.get(element).is_ok_and(|(&element_ty, _)| element_ty == ty)
+                })
+            };
+
+        let mut ratio = 1f32;
+        for input in &reaction.inputs {
+            let requested = input.mass * rate;
+            if requested.quantity <= 0. {
+                continue;
+            }
+            let available = match find_element(&element_query, input.ty) {
+                Some(element) => element_query.get(element).expect("checked above").1.mass,
+                None => units::Mass::default(),
+            };
+            ratio = ratio.min(available.quantity / requested.quantity);
+        }
+        if ratio < 1. && reaction.underflow == Underflow::Pause {
+            continue;
+        }
+        if ratio <= 0. {
+            continue;
+        }
+        progress.ratio = ratio;
+
+        let scale = rate * ratio;
+
+        for input in &reaction.inputs {
+            let Some(element) = find_element(&element_query, input.ty) else { continue };
+            let (_, mut mass) = element_query.get_mut(element).expect("checked above");
+            let consumed = input.mass * scale;
+            let consumed = if mass.mass < consumed { mass.mass } else { consumed };
+            mass.mass -= consumed;
+            ledger.record(
+                ledger::Key { ty: input.ty, cause: ledger::Cause::Reaction, container },
+                -consumed,
+            );
+            if mass.mass < config.deletion_threshold {
+                commands.entity(element).despawn_recursive();
+            }
+        }
+
+        for output in &reaction.outputs {
+            let produced = output.mass * scale;
+            let key = ledger::Key { ty: output.ty, cause: ledger::Cause::Reaction, container };
+            match find_element(&element_query, output.ty) {
+                Some(element) => {
+                    let (_, mut mass) = element_query.get_mut(element).expect("checked above");
+                    mass.mass += produced;
+                    ledger.record(key, produced);
+                }
+                None if produced < config.creation_threshold => {} // negligible mass
+                None => {
+                    ledger.record(key, produced);
+                    commands.add(
+                        commands::CreateContainerElement::builder()
+                            .container(Ref::new_unchecked(container))
+                            .ty(output.ty)
+                            .mass(produced)
+                            .build(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Save schema for a fluid consumed or produced by a reaction.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveTerm {
+    /// The fluid type.
+    pub ty:   save::Id<config::SaveType>,
+    /// Mass per simulation cycle at full operating rate.
+    pub mass: units::Mass,
+}
+
+/// Save schema for reactions.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveReaction {
+    /// The container in which the reaction happens.
+    pub container: save::Id<container::Save>,
+    /// Fluids consumed by the reaction.
+    pub inputs:    Vec<SaveTerm>,
+    /// Fluids produced by the reaction.
+    pub outputs:   Vec<SaveTerm>,
+    /// Behavior when an input is insufficient.
+    pub underflow: Underflow,
+}
+
+impl save::Def for SaveReaction {
+    const TYPE: &'static str = "traffloat.save.fluid.Reaction";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveReaction>,
+            (container_dep, type_dep): (
+                save::StoreDepend<container::Save>,
+                save::StoreDepend<config::SaveType>,
+            ),
+            query: Query<(Entity, &Reaction)>,
+        ) {
+            let store_terms = |terms: &[Term]| {
+                terms
+                    .iter()
+                    .map(|term| SaveTerm { ty: type_dep.must_get(term.ty), mass: term.mass })
+                    .collect()
+            };
+            writer.write_all(query.iter().map(|(entity, reaction)| {
+                (
+                    entity,
+                    SaveReaction {
+                        container: container_dep.must_get(entity),
+                        inputs:    store_terms(&reaction.inputs),
+                        outputs:   store_terms(&reaction.outputs),
+                        underflow: reaction.underflow,
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: SaveReaction,
+            (container_dep, type_dep): &(
+                save::LoadDepend<container::Save>,
+                save::LoadDepend<config::SaveType>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let load_terms = |terms: Vec<SaveTerm>| {
+                terms
+                    .into_iter()
+                    .map(|term| {
+                        anyhow::ensure!(
+                            term.mass.quantity >= 0.,
+                            "reaction term mass must be non-negative"
+                        );
+                        Ok(Term { ty: type_dep.get(term.ty)?, mass: term.mass })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()
+            };
+
+            let container = container_dep.get(def.container)?;
+            let reaction = Reaction {
+                inputs:    load_terms(def.inputs)?,
+                outputs:   load_terms(def.outputs)?,
+                underflow: def.underflow,
+            };
+            world.entity_mut(container).insert((reaction, Progress::default()));
+            Ok(container)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
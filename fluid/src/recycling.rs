@@ -0,0 +1,298 @@
+//! Waste fluids and recycling.
+//!
+//! A fluid type is a waste if its type entity has a [`Waste`] component.
+//! A container is [contaminated](Contaminated) while the volume of any waste fluid in it
+//! exceeds [`Waste::tolerance`] of the container capacity.
+//! Other modules may apply penalties to structures adjacent to contaminated containers.
+//!
+//! A container with a [`Recycler`] component converts a fluid into another fluid
+//! in the same container every simulation cycle, losing a proportion of the mass.
+//! The conversion rate is scaled by the [operating rate](building::mode::Rate) of the building.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, EventWriter};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
+use bevy::ecs::world::World;
+use bevy::hierarchy::{self, DespawnRecursiveExt};
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::partition::{self, AppExt};
+use traffloat_base::{save, Ref};
+use traffloat_graph::building;
+
+use crate::config::{self, Scalar};
+use crate::{commands, container, ledger, units};
+
+#[cfg(test)]
+mod tests;
+
+/// Maintains waste contamination and recycling.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<ContaminationEvent>();
+        app.add_systems(
+            app::Update,
+            (
+                recycle_system.before(container::SystemSets::Rebalance),
+                update_contamination_system
+                    .after(container::SystemSets::Rebalance)
+                    .in_set(partition::EventWriterSystemSet::<ContaminationEvent>::default()),
+            )
+                .run_if(in_state(self.0)),
+        );
+        save::add_def::<SaveWaste>(app);
+        save::add_def::<SaveRecycler>(app);
+    }
+}
+
+/// Marks a fluid type as waste.
+#[derive(Component)]
+pub struct Waste {
+    /// The maximum proportion of container capacity
+    /// that the waste fluid may occupy before the container is contaminated.
+    pub tolerance: f32,
+}
+
+/// A marker component on containers holding waste beyond tolerance.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Contaminated;
+
+/// The contamination state of a container has changed.
+#[derive(Debug, Event)]
+pub struct ContaminationEvent {
+    /// The container entity.
+    pub container:    Entity,
+    /// Whether the container is now contaminated.
+    pub contaminated: bool,
+}
+
+/// Converts a fluid into another fluid in the same container.
+#[derive(Component)]
+pub struct Recycler {
+    /// The fluid type consumed.
+    pub input:      config::Type,
+    /// The fluid type produced.
+    pub output:     config::Type,
+    /// Maximum input mass consumed per simulation cycle at full operating rate.
+    pub rate:       units::Mass,
+    /// The ratio of output mass to consumed input mass.
+    pub efficiency: f32,
+}
+
+fn recycle_system(
+    config: Res<Scalar>,
+    recycler_query: Query<
+        (Entity, &Recycler, &hierarchy::Parent, &hierarchy::Children),
+        With<container::Marker>,
+    >,
+    rate_query: Query<&building::mode::Rate>,
+    mut element_query: Query<(&config::Type, &mut container::element::Mass)>,
+    mut ledger: ResMut<ledger::Ledger>,
+    mut commands: Commands,
+) {
+    for (container, recycler, parent, elements) in &recycler_query {
+        let max_mass = recycler.rate * building::mode::Rate::of(rate_query.get(parent.get()).ok());
+        if max_mass.quantity <= 0. {
+            continue;
+        }
+
+        let find_element =
+            |element_query: &Query<(&config::Type, &mut container::element::Mass)>,
+             ty: config::Type| {
+                elements.iter().copied().find(|&element| {
+                    element_query.get(element).is_ok_and(|(&element_ty, _)| element_ty == ty)
+                })
+            };
+
+        let Some(input) = find_element(&element_query, recycler.input) else { continue };
+        let output = find_element(&element_query, recycler.output);
+
+        let (_, mut input_mass) = element_query.get_mut(input).expect("checked above");
+        let consumed = if input_mass.mass < max_mass { input_mass.mass } else { max_mass };
+        input_mass.mass -= consumed;
+        ledger.record(
+            ledger::Key { ty: recycler.input, cause: ledger::Cause::Recycling, container },
+            -consumed,
+        );
+        if input_mass.mass < config.deletion_threshold {
+            commands.entity(input).despawn_recursive();
+        }
+
+        let produced = consumed * recycler.efficiency;
+        let output_key =
+            ledger::Key { ty: recycler.output, cause: ledger::Cause::Recycling, container };
+        match output {
+            Some(output) => {
+                let (_, mut output_mass) = element_query.get_mut(output).expect("checked above");
+                output_mass.mass += produced;
+                ledger.record(output_key, produced);
+            }
+            None if produced < config.creation_threshold => {} // negligible mass
+            None => {
+                ledger.record(output_key, produced);
+                commands.add(
+                    commands::CreateContainerElement::builder()
+                        .container(Ref::new_unchecked(container))
+                        .ty(recycler.output)
+                        .mass(produced)
+                        .build(),
+                );
+            }
+        }
+    }
+}
+
+fn update_contamination_system(
+    container_query: Query<(
+        Entity,
+        &hierarchy::Children,
+        &container::MaxVolume,
+        Option<&Contaminated>,
+    )>,
+    element_query: Query<(&config::Type, &container::element::Volume)>,
+    waste_query: Query<&Waste>,
+    mut writer: EventWriter<ContaminationEvent>,
+    mut commands: Commands,
+) {
+    for (container, elements, max_volume, was_contaminated) in &container_query {
+        let contaminated = elements.iter().any(|&element| {
+            let Ok((ty, volume)) = element_query.get(element) else { return false };
+            let Ok(waste) = waste_query.get(ty.0) else { return false };
+            volume.volume > max_volume.volume * waste.tolerance
+        });
+
+        if contaminated != was_contaminated.is_some() {
+            if contaminated {
+                commands.entity(container).insert(Contaminated);
+            } else {
+                commands.entity(container).remove::<Contaminated>();
+            }
+            writer.send(ContaminationEvent { container, contaminated });
+        }
+    }
+}
+
+/// Save schema for waste fluid types.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveWaste {
+    /// The waste fluid type.
+    pub ty:        save::Id<config::SaveType>,
+    /// The proportion of container capacity tolerated.
+    pub tolerance: f32,
+}
+
+impl save::Def for SaveWaste {
+    const TYPE: &'static str = "traffloat.save.fluid.Waste";
+
+    type Runtime = config::Type;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveWaste>,
+            (type_dep,): (save::StoreDepend<config::SaveType>,),
+            query: Query<(Entity, &Waste)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, waste)| {
+                let ty = config::Type(entity);
+                (ty, SaveWaste { ty: type_dep.must_get(ty), tolerance: waste.tolerance })
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: SaveWaste,
+            (type_dep,): &(save::LoadDepend<config::SaveType>,),
+        ) -> anyhow::Result<config::Type> {
+            let ty = type_dep.get(def.ty)?;
+            world.entity_mut(ty.0).insert(Waste { tolerance: def.tolerance });
+            Ok(ty)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for recyclers.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveRecycler {
+    /// The container performing the conversion.
+    pub container:  save::Id<container::Save>,
+    /// The fluid type consumed.
+    pub input:      save::Id<config::SaveType>,
+    /// The fluid type produced.
+    pub output:     save::Id<config::SaveType>,
+    /// Maximum input mass consumed per simulation cycle.
+    pub rate:       units::Mass,
+    /// The ratio of output mass to consumed input mass.
+    pub efficiency: f32,
+}
+
+impl save::Def for SaveRecycler {
+    const TYPE: &'static str = "traffloat.save.fluid.Recycler";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveRecycler>,
+            (container_dep, type_dep): (
+                save::StoreDepend<container::Save>,
+                save::StoreDepend<config::SaveType>,
+            ),
+            query: Query<(Entity, &Recycler)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, recycler)| {
+                (
+                    entity,
+                    SaveRecycler {
+                        container:  container_dep.must_get(entity),
+                        input:      type_dep.must_get(recycler.input),
+                        output:     type_dep.must_get(recycler.output),
+                        rate:       recycler.rate,
+                        efficiency: recycler.efficiency,
+                    },
+                )
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: SaveRecycler,
+            (container_dep, type_dep): &(
+                save::LoadDepend<container::Save>,
+                save::LoadDepend<config::SaveType>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let container = container_dep.get(def.container)?;
+            let recycler = Recycler {
+                input:      type_dep.get(def.input)?,
+                output:     type_dep.get(def.output)?,
+                rate:       def.rate,
+                efficiency: def.efficiency,
+            };
+            world.entity_mut(container).insert(recycler);
+            Ok(container)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,28 @@
+use approx::assert_relative_eq;
+use bevy::math::Vec3;
+
+use super::Recycler;
+use crate::{test_util, units};
+
+#[test]
+fn convert_with_loss() {
+    let mut app = test_util::new_app();
+    let [waste, water] = [1., 1.].map(|svol| test_util::create_type(&mut app, svol));
+    let (_, container) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    test_util::add_fluid(&mut app, container, waste, 3.);
+    app.world_mut().entity_mut(container).insert(Recycler {
+        input:      waste,
+        output:     water,
+        rate:       units::Mass { quantity: 2. },
+        efficiency: 0.5,
+    });
+
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, waste), 1.);
+    assert_relative_eq!(test_util::fluid_mass(&app, container, water), 1.);
+
+    // only the remaining input is consumed, and the depleted element is removed
+    app.update();
+    assert_relative_eq!(test_util::fluid_mass(&app, container, water), 1.5);
+    assert_eq!(test_util::elements(&app, container).len(), 1);
+}
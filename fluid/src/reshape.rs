@@ -0,0 +1,221 @@
+//! Maintains duct containers when corridors are split or merged.
+//!
+//! When a corridor is split, each duct container is divided between the two new ducts
+//! proportional to the split ratio, including both capacity and fluid mass.
+//! Pipes towards the far endpoint are moved to the new duct.
+//! The junction building has no storages initially,
+//! so no pipes are created at the junction.
+//!
+//! When two corridors are merged, pipes connected to the junction are removed,
+//! and the capacity and fluids of each removed duct are added to the kept duct.
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::{Entity, EntityHashMap};
+use bevy::ecs::world::World;
+use bevy::hierarchy::{self, BuildWorldChildren, DespawnRecursiveExt};
+use traffloat_graph::corridor::reshape;
+
+use crate::{config, container, pipe, units};
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(reshape::OnSplit, split_system);
+        app.add_systems(reshape::OnMerge, merge_system);
+    }
+}
+
+fn split_system(world: &mut World) {
+    let splitting = world.resource::<reshape::CurrentSplit>().get().clone();
+
+    for &(original, split_off) in &splitting.ducts {
+        let Some(max_volume) = world.get::<container::MaxVolume>(original).map(|c| c.volume) else {
+            continue;
+        };
+        let max_pressure = world
+            .get::<container::MaxPressure>(original)
+            .expect("container must have MaxPressure")
+            .pressure;
+
+        world.get_mut::<container::MaxVolume>(original).expect("checked above").volume =
+            max_volume * splitting.ratio;
+        world.entity_mut(split_off).insert(
+            container::Bundle::builder()
+                .max_volume(max_volume * (1. - splitting.ratio))
+                .max_pressure(max_pressure)
+                .build(),
+        );
+
+        let mut element_map = EntityHashMap::default();
+        for (element, ty, mass) in container_elements(world, original) {
+            world.get_mut::<container::element::Mass>(element).expect("checked in query").mass =
+                mass * splitting.ratio;
+            let new_element = world
+                .spawn(
+                    container::element::Bundle::builder()
+                        .ty(ty)
+                        .mass(mass * (1. - splitting.ratio))
+                        .build(),
+                )
+                .set_parent(split_off)
+                .id();
+            element_map.insert(element, new_element);
+        }
+
+        let far_pipes: Vec<Entity> = container_pipes(world, original)
+            .into_iter()
+            .filter(|&pipe| {
+                pipe_peer_building(world, pipe, original) == Some(splitting.far_endpoint)
+            })
+            .collect();
+        for pipe in far_pipes {
+            move_pipe(world, pipe, original, split_off, &element_map);
+        }
+    }
+}
+
+fn merge_system(world: &mut World) {
+    let merging = world.resource::<reshape::CurrentMerge>().get().clone();
+
+    for &(kept, removed) in &merging.ducts {
+        for duct in [kept, removed] {
+            let junction_pipes: Vec<Entity> = container_pipes(world, duct)
+                .into_iter()
+                .filter(|&pipe| pipe_peer_building(world, pipe, duct) == Some(merging.junction))
+                .collect();
+            for pipe in junction_pipes {
+                pipe::disconnect(world, pipe);
+            }
+        }
+
+        let Some(removed_max_volume) = world.get::<container::MaxVolume>(removed).map(|c| c.volume)
+        else {
+            continue;
+        };
+        let removed_max_pressure = world
+            .get::<container::MaxPressure>(removed)
+            .expect("container must have MaxPressure")
+            .pressure;
+
+        if world.get::<container::Marker>(kept).is_some() {
+            world
+                .get_mut::<container::MaxVolume>(kept)
+                .expect("container must have MaxVolume")
+                .volume += removed_max_volume;
+            let mut max_pressure = world
+                .get_mut::<container::MaxPressure>(kept)
+                .expect("container must have MaxPressure");
+            if removed_max_pressure < max_pressure.pressure {
+                max_pressure.pressure = removed_max_pressure;
+            }
+        } else {
+            world.entity_mut(kept).insert(
+                container::Bundle::builder()
+                    .max_volume(removed_max_volume)
+                    .max_pressure(removed_max_pressure)
+                    .build(),
+            );
+        }
+
+        let kept_elements = container_elements(world, kept);
+        let mut element_map = EntityHashMap::default();
+        let mut merged_elements = Vec::new();
+        for (element, ty, mass) in container_elements(world, removed) {
+            if let Some(&(kept_element, _, _)) =
+                kept_elements.iter().find(|&&(_, kept_ty, _)| kept_ty == ty)
+            {
+                world
+                    .get_mut::<container::element::Mass>(kept_element)
+                    .expect("checked in query")
+                    .mass += mass;
+                element_map.insert(element, kept_element);
+                merged_elements.push(element);
+            } else {
+                world.entity_mut(element).set_parent(kept);
+                element_map.insert(element, element);
+            }
+        }
+
+        for pipe in container_pipes(world, removed) {
+            move_pipe(world, pipe, removed, kept, &element_map);
+        }
+
+        for element in merged_elements {
+            world.entity_mut(element).despawn_recursive();
+        }
+    }
+}
+
+/// Lists the `(entity, type, mass)` of each element in a container.
+fn container_elements(
+    world: &World,
+    container: Entity,
+) -> Vec<(Entity, config::Type, units::Mass)> {
+    world
+        .get::<hierarchy::Children>(container)
+        .into_iter()
+        .flatten()
+        .filter_map(|&child| {
+            let ty = world.get::<config::Type>(child)?;
+            let mass = world.get::<container::element::Mass>(child)?;
+            Some((child, *ty, mass.mass))
+        })
+        .collect()
+}
+
+fn container_pipes(world: &World, container: Entity) -> Vec<Entity> {
+    world.get::<container::Pipes>(container).map(|pipes| pipes.pipes.to_vec()).unwrap_or_default()
+}
+
+/// Returns the building of the facility at the other end of a duct pipe.
+fn pipe_peer_building(world: &World, pipe: Entity, duct: Entity) -> Option<Entity> {
+    let endpoints = world.get::<pipe::Containers>(pipe)?.endpoints;
+    let duct_side = endpoints.find(&duct)?;
+    let peer = *endpoints.as_endpoint(!duct_side);
+    world.get::<hierarchy::Parent>(peer).map(hierarchy::Parent::get)
+}
+
+/// Reconnects a pipe from container `from` to container `to`.
+///
+/// `element_map` maps the elements of `from` to the corresponding elements of `to`.
+fn move_pipe(
+    world: &mut World,
+    pipe: Entity,
+    from: Entity,
+    to: Entity,
+    element_map: &EntityHashMap<Entity>,
+) {
+    let side = {
+        let mut containers =
+            world.get_mut::<pipe::Containers>(pipe).expect("Pipes must contain pipe entities");
+        let side = containers.endpoints.find(&from).expect(
+            "each pipe in container adjacency list must have one endpoint as the container",
+        );
+        *containers.endpoints.as_endpoint_mut(side) = to;
+        side
+    };
+
+    let pipe_elements: Vec<Entity> =
+        world.get::<hierarchy::Children>(pipe).into_iter().flatten().copied().collect();
+    for pipe_element in pipe_elements {
+        let Some(mut container_elements) =
+            world.get_mut::<pipe::element::ContainerElements>(pipe_element)
+        else {
+            continue;
+        };
+        let element = container_elements.containers.as_endpoint_mut(side);
+        *element = element.and_then(|element| element_map.get(&element).copied());
+    }
+
+    world
+        .get_mut::<container::Pipes>(from)
+        .expect("container must have Pipes")
+        .pipes
+        .retain(|&mut other| other != pipe);
+    world.get_mut::<container::Pipes>(to).expect("container must have Pipes").pipes.push(pipe);
+    world.entity_mut(pipe).set_parent(to);
+}
@@ -0,0 +1,149 @@
+use approx::assert_relative_eq;
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::Command;
+use bevy::hierarchy::BuildWorldChildren;
+use bevy::math::Vec3;
+use traffloat_graph::corridor::{self, duct, reshape, Binary};
+use traffloat_view::appearance::Appearance;
+
+use crate::{config, container, pipe, test_util};
+
+struct Setup {
+    app:       App,
+    types:     [config::Type; 2],
+    corridor:  Entity,
+    duct:      Entity,
+    ambients:  [Entity; 2],
+    far_pipe:  Entity,
+    near_pipe: Entity,
+}
+
+/// Connects two buildings with a corridor whose ambient duct
+/// holds two fluids and is piped to the ambient facility of each building.
+fn setup() -> Setup {
+    let mut app = test_util::new_app();
+    let types = [1., 2.].map(|svol| test_util::create_type(&mut app, svol));
+    let (alpha, alpha_ambient) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    let (beta, beta_ambient) = test_util::spawn_building(&mut app, Vec3::new(40., 0., 0.), 100.);
+
+    let duct = test_util::spawn_container(&mut app, 100.);
+    app.world_mut().entity_mut(duct).insert(duct::Bundle::builder().build());
+    let corridor = app
+        .world_mut()
+        .spawn(
+            corridor::Bundle::builder()
+                .endpoints(corridor::Endpoints { endpoints: Binary { alpha, beta } })
+                .duct_list(corridor::DuctList { duct_list: Vec::new(), ambient: duct })
+                .build(),
+        )
+        .add_child(duct)
+        .id();
+
+    test_util::add_fluid(&mut app, duct, types[0], 8.);
+    test_util::add_fluid(&mut app, duct, types[1], 4.);
+    let near_pipe = test_util::connect(&mut app, Binary { alpha: alpha_ambient, beta: duct });
+    let far_pipe = test_util::connect(&mut app, Binary { alpha: duct, beta: beta_ambient });
+
+    Setup {
+        app,
+        types,
+        corridor,
+        duct,
+        ambients: [alpha_ambient, beta_ambient],
+        far_pipe,
+        near_pipe,
+    }
+}
+
+fn max_volume(app: &App, container: Entity) -> f32 {
+    app.world().get::<container::MaxVolume>(container).unwrap().volume.quantity
+}
+
+fn pipes(app: &App, container: Entity) -> Vec<Entity> {
+    app.world().get::<container::Pipes>(container).unwrap().pipes.to_vec()
+}
+
+fn split(setup: &mut Setup, ratio: f32) -> (Entity, Entity) {
+    reshape::Split::builder()
+        .corridor(setup.corridor)
+        .ratio(ratio)
+        .appearance(Appearance::null())
+        .build()
+        .apply(setup.app.world_mut());
+
+    let junction =
+        setup.app.world().get::<corridor::Endpoints>(setup.corridor).unwrap().endpoints.beta;
+    let split_off_duct = setup
+        .app
+        .world_mut()
+        .query::<(&corridor::Endpoints, &corridor::DuctList)>()
+        .iter(setup.app.world())
+        .find(|(endpoints, _)| endpoints.endpoints.alpha == junction)
+        .map(|(_, ducts)| ducts.ambient)
+        .unwrap();
+    (junction, split_off_duct)
+}
+
+#[test]
+fn split_divides_capacity_and_mass() {
+    let mut setup = setup();
+    let (_, split_off) = split(&mut setup, 0.25);
+    let app = &setup.app;
+
+    assert_relative_eq!(max_volume(app, setup.duct), 25.);
+    assert_relative_eq!(max_volume(app, split_off), 75.);
+    for (ty, total) in setup.types.into_iter().zip([8., 4.]) {
+        assert_relative_eq!(test_util::fluid_mass(app, setup.duct, ty), total * 0.25);
+        assert_relative_eq!(test_util::fluid_mass(app, split_off, ty), total * 0.75);
+    }
+
+    assert_eq!(pipes(app, setup.duct), [setup.near_pipe]);
+    assert_eq!(pipes(app, split_off), [setup.far_pipe]);
+    let far_containers = app.world().get::<pipe::Containers>(setup.far_pipe).unwrap();
+    assert_eq!(far_containers.endpoints.alpha, split_off);
+}
+
+#[test]
+fn merge_restores_capacity_and_mass() {
+    let mut setup = setup();
+    let (junction, split_off) = split(&mut setup, 0.25);
+    reshape::Merge::builder().junction(junction).build().apply(setup.app.world_mut());
+    let app = &setup.app;
+
+    assert!(app.world().get_entity(split_off).is_none());
+    assert_relative_eq!(max_volume(app, setup.duct), 100.);
+    for (ty, total) in setup.types.into_iter().zip([8., 4.]) {
+        assert_relative_eq!(test_util::fluid_mass(app, setup.duct, ty), total);
+    }
+    assert_eq!(test_util::elements(app, setup.duct).len(), 2);
+
+    let mut merged_pipes = pipes(app, setup.duct);
+    merged_pipes.sort();
+    let mut expected_pipes = vec![setup.near_pipe, setup.far_pipe];
+    expected_pipes.sort();
+    assert_eq!(merged_pipes, expected_pipes);
+}
+
+#[test]
+fn simulation_conserves_mass_after_reshape() {
+    let mut setup = setup();
+    let (junction, split_off) = split(&mut setup, 0.5);
+
+    setup.app.update();
+    assert_relative_eq!(total_mass(&setup, &[setup.duct, split_off]), 12., epsilon = 1e-4);
+
+    reshape::Merge::builder().junction(junction).build().apply(setup.app.world_mut());
+    setup.app.update();
+    assert_relative_eq!(total_mass(&setup, &[setup.duct]), 12., epsilon = 1e-4);
+}
+
+/// Sums the mass of all fluids in the ducts and the building facilities.
+fn total_mass(setup: &Setup, ducts: &[Entity]) -> f32 {
+    let containers = ducts.iter().chain(&setup.ambients);
+    containers
+        .flat_map(|&container| {
+            setup.types.map(|ty| test_util::fluid_mass(&setup.app, container, ty))
+        })
+        .sum()
+}
@@ -0,0 +1,149 @@
+//! Rooms divide a building into facilities connected by internal openings.
+//!
+//! Each room is a facility of the building whose container holds the atmosphere of the room.
+//! An opening between two rooms is a [pipe](crate::pipe) between their containers,
+//! parented to the building,
+//! so gas concentration gradients within the building are solved by the pipe solver.
+//!
+//! Openings are created by [`CreateOpening`] and removed by [`RemoveOpening`],
+//! e.g. when the building def declares its room layout or a door is opened or sealed.
+//! Since openings are ordinary pipes, they are saved and loaded with other pipes.
+
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy::{self, BuildWorldChildren};
+use traffloat_base::{error, Ref};
+use traffloat_graph::building::{self, facility};
+use traffloat_graph::corridor::Binary;
+
+use crate::pipe::resistance;
+use crate::{container, pipe, units};
+
+#[cfg(test)]
+mod tests;
+
+/// Returns the building of a room container,
+/// or `None` if the container is not held by a facility.
+#[must_use]
+pub fn building_of(world: &World, container: Entity) -> Option<Entity> {
+    let facility = world.get::<hierarchy::Parent>(container)?.get();
+    world.get::<facility::Marker>(facility)?;
+    let building = world.get::<hierarchy::Parent>(facility)?.get();
+    world.get::<building::Marker>(building).map(|_| building)
+}
+
+/// Checks whether a pipe is an opening between two rooms of the same building.
+#[must_use]
+pub fn is_opening(world: &World, pipe: Entity) -> bool {
+    world.get::<pipe::Containers>(pipe).is_some_and(|containers| {
+        let buildings = containers.endpoints.map(|container| building_of(world, container));
+        buildings.alpha.is_some() && buildings.alpha == buildings.beta
+    })
+}
+
+/// Opens a passage between the containers of two rooms in the same building.
+///
+/// The change is [rejected](error::reject) if either container is not held by a facility,
+/// the rooms are in different buildings or already connected,
+/// or the resistance is not positive.
+pub struct CreateOpening {
+    /// The containers of the two rooms.
+    pub containers:       Binary<Ref<container::Marker>>,
+    /// Resistance of the opening, which decreases with its size.
+    pub shape_resistance: units::Resistance,
+}
+
+impl CreateOpening {
+    /// Checks whether the opening is valid, returning the building containing both rooms.
+    ///
+    /// # Errors
+    /// Returns an error if either container is not held by a facility,
+    /// the rooms are in different buildings or already connected,
+    /// or the resistance is not positive.
+    pub fn validate(&self, world: &World) -> Result<Entity, error::Error> {
+        let containers = self.containers.try_map(|container| container.resolve(world))?;
+        let containers = containers.map(|container| container.id());
+
+        if containers.alpha == containers.beta {
+            let message = "an opening must connect two different rooms";
+            return Err(error::Error::validation("fluid.room.same_room", message)
+                .with_entity(containers.alpha));
+        }
+        let buildings = containers.try_map(|container| {
+            building_of(world, container).ok_or_else(|| {
+                let message = format!("{container:?} is not the container of a room");
+                error::Error::validation("fluid.room.not_room", message).with_entity(container)
+            })
+        })?;
+        if buildings.alpha != buildings.beta {
+            let message = "rooms of an opening must be in the same building";
+            return Err(error::Error::validation("fluid.room.different_buildings", message)
+                .with_entity(containers.alpha)
+                .with_entity(containers.beta));
+        }
+
+        let pipes = world.get::<container::Pipes>(containers.alpha).expect("checked above");
+        let connected = pipes.pipes.iter().any(|&pipe| {
+            world
+                .get::<pipe::Containers>(pipe)
+                .is_some_and(|other| other.endpoints.find(&containers.beta).is_some())
+        });
+        if connected {
+            let message = "the rooms are already connected";
+            return Err(error::Error::validation("fluid.room.already_open", message)
+                .with_entity(containers.alpha)
+                .with_entity(containers.beta));
+        }
+
+        if self.shape_resistance.quantity <= 0. {
+            let message = "opening resistance must be positive";
+            return Err(error::Error::validation("fluid.room.resistance", message)
+                .with_entity(containers.alpha)
+                .with_entity(containers.beta));
+        }
+
+        Ok(buildings.alpha)
+    }
+}
+
+impl Command for CreateOpening {
+    fn apply(self, world: &mut World) {
+        let building = match self.validate(world) {
+            Ok(building) => building,
+            Err(err) => return error::reject(world, err),
+        };
+
+        let bundle = pipe::Bundle::builder()
+            .containers(self.containers.map(Ref::entity))
+            .shape_resistance(self.shape_resistance)
+            // openings have no static contributors other than their shape
+            .static_resistance(resistance::Static { resistance: self.shape_resistance })
+            .build();
+        let opening = world.spawn(bundle).set_parent(building).id();
+        pipe::connect(world, opening);
+    }
+}
+
+/// Seals an opening between two rooms.
+///
+/// The change is [rejected](error::reject) if the pipe is not an opening.
+pub struct RemoveOpening {
+    /// The pipe of the opening.
+    pub pipe: Ref<pipe::Marker>,
+}
+
+impl Command for RemoveOpening {
+    fn apply(self, world: &mut World) {
+        let pipe = match self.pipe.resolve(world) {
+            Ok(pipe) => pipe.id(),
+            Err(err) => return error::reject(world, err),
+        };
+        if !is_opening(world, pipe) {
+            let message = format!("{pipe:?} is not an opening between rooms");
+            let err = error::Error::validation("fluid.room.not_opening", message).with_entity(pipe);
+            return error::reject(world, err);
+        }
+
+        pipe::disconnect(world, pipe);
+    }
+}
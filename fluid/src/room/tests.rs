@@ -0,0 +1,139 @@
+use approx::assert_relative_eq;
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Events;
+use bevy::ecs::world::Command;
+use bevy::hierarchy::BuildWorldChildren;
+use bevy::math::Vec3;
+use traffloat_base::{error, Ref};
+use traffloat_graph::building::facility;
+use traffloat_graph::corridor::Binary;
+
+use super::{CreateOpening, RemoveOpening};
+use crate::{config, container, test_util, units};
+
+fn new_app() -> App {
+    let mut app = test_util::new_app();
+    app.add_plugins(error::Plugin);
+    app
+}
+
+/// Spawns a room facility in `building`, returning its container.
+fn spawn_room(app: &mut App, building: Entity) -> Entity {
+    let container = test_util::spawn_container(app, 100.);
+    app.world_mut().spawn(facility::Marker).set_parent(building).add_child(container);
+    container
+}
+
+fn open(app: &mut App, rooms: [Entity; 2]) {
+    CreateOpening {
+        containers:       Binary::from(rooms).map(Ref::new_unchecked),
+        shape_resistance: units::Resistance { quantity: 1. },
+    }
+    .apply(app.world_mut());
+}
+
+fn openings(app: &App, container: Entity) -> Vec<Entity> {
+    app.world().get::<container::Pipes>(container).unwrap().pipes.to_vec()
+}
+
+fn rejections(app: &App) -> Vec<String> {
+    let events = app.world().resource::<Events<error::RejectedEvent>>();
+    events.get_reader().read(events).map(|event| event.error.detail().key.to_string()).collect()
+}
+
+fn gas_masses(app: &App, rooms: [Entity; 2], ty: config::Type) -> [f32; 2] {
+    rooms.map(|room| test_util::fluid_mass(app, room, ty))
+}
+
+/// Asserts that the gas added to the first room has not reached the second room.
+fn assert_closed(app: &App, rooms: [Entity; 2], ty: config::Type) {
+    let [alpha, beta] = gas_masses(app, rooms, ty);
+    assert_relative_eq!(alpha, 10.);
+    assert_relative_eq!(beta, 0.);
+}
+
+#[test]
+fn gas_diffuses_through_opening() {
+    let mut app = new_app();
+    let ty = test_util::create_type(&mut app, 1.);
+    let (building, _) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    let rooms = [spawn_room(&mut app, building), spawn_room(&mut app, building)];
+    test_util::add_fluid(&mut app, rooms[0], ty, 10.);
+
+    app.update();
+    assert_closed(&app, rooms, ty);
+
+    open(&mut app, rooms);
+    assert_eq!(rejections(&app), Vec::<String>::new());
+    app.update();
+    let [alpha, beta] = gas_masses(&app, rooms, ty);
+    assert!(beta > 0., "gas should flow into the empty room");
+    assert!(alpha > beta, "a gradient should remain shortly after opening");
+
+    for _ in 0..100 {
+        app.update();
+    }
+    let [later_alpha, later_beta] = gas_masses(&app, rooms, ty);
+    assert!(later_alpha - later_beta < alpha - beta, "the gradient should narrow over time");
+    assert!((later_alpha + later_beta - 10.).abs() < 1e-3, "gas mass should be conserved");
+}
+
+#[test]
+fn seal_opening() {
+    let mut app = new_app();
+    let ty = test_util::create_type(&mut app, 1.);
+    let (building, _) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    let rooms = [spawn_room(&mut app, building), spawn_room(&mut app, building)];
+    open(&mut app, rooms);
+    let [opening] = openings(&app, rooms[0])[..] else { panic!("one opening expected") };
+    assert_eq!(openings(&app, rooms[1]), vec![opening]);
+
+    RemoveOpening { pipe: Ref::new_unchecked(opening) }.apply(app.world_mut());
+    assert!(app.world().get_entity(opening).is_none());
+    assert_eq!(openings(&app, rooms[0]), Vec::new());
+    assert_eq!(openings(&app, rooms[1]), Vec::new());
+
+    test_util::add_fluid(&mut app, rooms[0], ty, 10.);
+    app.update();
+    assert_closed(&app, rooms, ty);
+}
+
+#[test]
+fn reject_invalid_openings() {
+    let mut app = new_app();
+    let (building, ambient) = test_util::spawn_building(&mut app, Vec3::ZERO, 100.);
+    let (other_building, _) = test_util::spawn_building(&mut app, Vec3::X * 10., 100.);
+    let room = spawn_room(&mut app, building);
+    let other_room = spawn_room(&mut app, building);
+    let spare_room = spawn_room(&mut app, building);
+    let foreign_room = spawn_room(&mut app, other_building);
+
+    open(&mut app, [room, room]);
+    open(&mut app, [room, ambient]);
+    open(&mut app, [room, foreign_room]);
+    open(&mut app, [room, other_room]);
+    open(&mut app, [other_room, room]);
+    CreateOpening {
+        containers:       Binary::from([room, spare_room]).map(Ref::new_unchecked),
+        shape_resistance: units::Resistance { quantity: 0. },
+    }
+    .apply(app.world_mut());
+    RemoveOpening {
+        pipe: Ref::new_unchecked(test_util::connect(&mut app, [ambient, room].into())),
+    }
+    .apply(app.world_mut());
+
+    assert_eq!(
+        rejections(&app),
+        [
+            "fluid.room.same_room",
+            "fluid.room.not_room",
+            "fluid.room.different_buildings",
+            "fluid.room.already_open",
+            "fluid.room.resistance",
+            "fluid.room.not_opening",
+        ]
+    );
+    assert_eq!(openings(&app, room).len(), 2, "only the valid opening and the test pipe remain");
+}
@@ -0,0 +1,150 @@
+//! Shared setup for tests of fluid behavior.
+
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::Command;
+use bevy::hierarchy::{BuildWorldChildren, Children};
+use bevy::math::Vec3;
+use bevy::state::app::{AppExtStates, StatesPlugin};
+use bevy::time::TimePlugin;
+use bevy::transform::components::Transform;
+use traffloat_base::{save, EmptyState, Ref};
+use traffloat_graph::building;
+use traffloat_graph::corridor::Binary;
+use traffloat_view::appearance::Appearance;
+use traffloat_view::{viewable, DisplayText};
+
+use crate::pipe::resistance;
+use crate::{commands, config, container, pipe, units};
+
+/// Creates an app with the complete fluid simulation.
+pub(crate) fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        StatesPlugin,
+        save::Plugin,
+        traffloat_view::Plugin,
+        traffloat_graph::Plugin,
+        crate::Plugin(EmptyState),
+    ));
+    app.init_state::<EmptyState>();
+    app
+}
+
+/// Creates a miscible fluid type with the given vacuum specific volume.
+pub(crate) fn create_type(app: &mut App, vacuum_specific_volume: f32) -> config::Type {
+    let ty = config::create_type(
+        &mut app.world_mut().commands(),
+        config::TypeDef {
+            display_label:          DisplayText::default(),
+            viscosity:              units::Viscosity { quantity: 1. },
+            vacuum_specific_volume: vacuum_specific_volume.into(),
+            critical_pressure:      units::Pressure { quantity: 100. },
+            saturation_gamma:       10.,
+            immiscible:             false,
+            color:                  None,
+            haze:                   None,
+        },
+    );
+    app.world_mut().flush();
+    ty
+}
+
+/// Spawns an empty container.
+pub(crate) fn spawn_container(app: &mut App, max_volume: f32) -> Entity {
+    app.world_mut()
+        .spawn(
+            container::Bundle::builder()
+                .max_volume(units::Volume { quantity: max_volume })
+                .max_pressure(units::Pressure { quantity: 100. })
+                .build(),
+        )
+        .id()
+}
+
+/// Spawns a building at `translation` whose ambient facility is an empty container,
+/// returning the building and the container.
+pub(crate) fn spawn_building(
+    app: &mut App,
+    translation: Vec3,
+    max_volume: f32,
+) -> (Entity, Entity) {
+    let ambient = spawn_container(app, max_volume);
+
+    let world = app.world_mut();
+    let sid = viewable::next_sid(world);
+    let building = world
+        .spawn(
+            building::Bundle::builder()
+                .viewable(
+                    viewable::StationaryBundle::builder()
+                        .base(
+                            viewable::BaseBundle::builder()
+                                .sid(sid)
+                                .appearance(Appearance::null())
+                                .build(),
+                        )
+                        .transform(Transform::from_translation(translation))
+                        .build(),
+                )
+                .facility_list(building::FacilityList { ambient, non_ambient: Vec::new() })
+                .build(),
+        )
+        .add_child(ambient)
+        .id();
+    (building, ambient)
+}
+
+/// Connects two containers with a pipe of unit resistance.
+pub(crate) fn connect(app: &mut App, containers: Binary<Entity>) -> Entity {
+    let pipe = app
+        .world_mut()
+        .spawn(
+            pipe::Bundle::builder()
+                .shape_resistance(units::Resistance { quantity: 1. })
+                .static_resistance(resistance::Static {
+                    resistance: units::Resistance { quantity: 1. },
+                })
+                .containers(containers)
+                .build(),
+        )
+        .id();
+    for container in containers.iter() {
+        app.world_mut().get_mut::<container::Pipes>(*container).unwrap().pipes.push(pipe);
+    }
+    pipe
+}
+
+/// Adds fluid to a container.
+pub(crate) fn add_fluid(app: &mut App, container: Entity, ty: config::Type, mass: f32) {
+    commands::CreateContainerElement::builder()
+        .container(Ref::new_unchecked(container))
+        .ty(ty)
+        .mass(units::Mass { quantity: mass })
+        .build()
+        .apply(app.world_mut());
+}
+
+/// Returns the mass of a fluid type in a container.
+pub(crate) fn fluid_mass(app: &App, container: Entity, ty: config::Type) -> f32 {
+    app.world()
+        .get::<Children>(container)
+        .into_iter()
+        .flatten()
+        .filter(|&&element| app.world().get::<config::Type>(element) == Some(&ty))
+        .filter_map(|&element| app.world().get::<container::element::Mass>(element))
+        .map(|mass| mass.mass.quantity)
+        .sum()
+}
+
+/// Returns the fluid elements in a container.
+pub(crate) fn elements(app: &App, container: Entity) -> Vec<Entity> {
+    app.world()
+        .get::<Children>(container)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&element| app.world().get::<container::element::Mass>(element).is_some())
+        .collect()
+}
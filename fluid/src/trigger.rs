@@ -0,0 +1,456 @@
+//! Scenario triggers firing actions when conditions are met.
+//!
+//! A [`Trigger`] entity is declared in the scenario with a [`Condition`] and a list of [`Action`]s.
+//! Triggers are evaluated once per simulation cycle after containers are rebalanced.
+//! An armed trigger fires all its actions when its condition holds and is then disarmed.
+//! A [repeating](Repeat::Repeat) trigger is rearmed once its condition clears,
+//! with a hysteresis margin for thresholds so that a value oscillating around the threshold
+//! does not fire the trigger every cycle.
+//! Triggers that can never fire again are despawned.
+//!
+//! Triggers live in this crate because their conditions and actions
+//! span both fluids and the structural graph.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, EventWriter};
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Resource};
+use bevy::ecs::world::World;
+use bevy::hierarchy::DespawnRecursiveExt;
+use bevy::state::condition::in_state;
+use bevy::state::state::States;
+use bevy::utils::HashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::partition::{self, AppExt};
+use traffloat_base::{debug, save, Ref};
+use traffloat_graph::building;
+use traffloat_view::DisplayText;
+
+use crate::{config, container, fire, units};
+
+#[cfg(test)]
+mod tests;
+
+/// Evaluates scenario triggers.
+pub(super) struct Plugin<St>(pub(super) St);
+
+impl<St: States + Copy> app::Plugin for Plugin<St> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Clock>();
+        app.add_partitioned_event::<Announcement>();
+        app.add_partitioned_event::<Signal>();
+        app.add_systems(
+            app::Update,
+            evaluate_system
+                .after(container::SystemSets::Rebalance)
+                .in_set(partition::EventWriterSystemSet::<Announcement>::default())
+                .in_set(partition::EventWriterSystemSet::<Signal>::default())
+                .run_if(in_state(self.0)),
+        );
+        save::add_def::<SaveClock>(app);
+        save::add_def::<SaveTrigger>(app);
+    }
+}
+
+/// The number of simulation cycles elapsed since the scenario started.
+#[derive(Debug, Default, Resource)]
+pub struct Clock {
+    /// The current cycle.
+    pub cycle: u64,
+}
+
+/// A scenario trigger.
+#[derive(Component)]
+pub struct Trigger {
+    /// The condition firing the trigger.
+    pub condition: Condition,
+    /// Actions executed when the trigger fires.
+    pub actions:   Vec<Action>,
+    /// Whether the trigger may fire more than once.
+    pub repeat:    Repeat,
+    /// Whether the trigger fires when its condition holds.
+    pub armed:     bool,
+}
+
+/// A condition of a [`Trigger`].
+#[derive(Debug, Clone, Copy)]
+pub enum Condition {
+    /// Holds from the given [cycle](Clock) onwards.
+    Cycle {
+        /// The first cycle in which the condition holds.
+        at: u64,
+    },
+    /// Holds when the total mass of a fluid type over all containers crosses a threshold.
+    FluidMass {
+        /// The fluid type.
+        ty:         config::Type,
+        /// The direction in which the threshold is crossed.
+        comparison: Comparison,
+        /// The threshold mass.
+        threshold:  units::Mass,
+        /// The distance beyond the threshold, in the opposite direction,
+        /// that the total mass must reach before a repeating trigger is rearmed.
+        hysteresis: units::Mass,
+    },
+    /// Holds when a building is removed, e.g. destroyed.
+    BuildingRemoved {
+        /// The building entity.
+        building: Entity,
+    },
+}
+
+/// The direction in which a threshold is crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Comparison {
+    /// The value is below the threshold.
+    Below,
+    /// The value is above the threshold.
+    Above,
+}
+
+/// Whether a [`Trigger`] may fire more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Repeat {
+    /// The trigger is despawned after firing.
+    Once,
+    /// The trigger is rearmed after its condition clears.
+    Repeat,
+}
+
+/// An action executed when a [`Trigger`] fires.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Sends an [`Announcement`] to be displayed to players.
+    Announce {
+        /// The message.
+        message: DisplayText,
+    },
+    /// Sends a [`Signal`] for other modules to react to, e.g. unlocking content.
+    Signal {
+        /// The signal name.
+        name: String,
+    },
+    /// Starts a [fire](fire::Ignite) in a container.
+    Ignite {
+        /// The container entity.
+        container: Entity,
+    },
+}
+
+/// A message announced by a trigger.
+#[derive(Debug, Event)]
+pub struct Announcement {
+    /// The trigger entity.
+    pub trigger: Entity,
+    /// The message.
+    pub message: DisplayText,
+}
+
+/// A named signal sent by a trigger.
+#[derive(Debug, Event)]
+pub struct Signal {
+    /// The trigger entity.
+    pub trigger: Entity,
+    /// The signal name.
+    pub name:    String,
+}
+
+/// The state of a condition in the current cycle.
+struct Evaluation {
+    /// Whether an armed trigger fires.
+    holds:      bool,
+    /// Whether a disarmed repeating trigger is rearmed.
+    clear:      bool,
+    /// Whether the condition may ever hold again after clearing.
+    can_repeat: bool,
+}
+
+fn evaluate_system(
+    mut clock: ResMut<Clock>,
+    mut trigger_query: Query<(Entity, &mut Trigger)>,
+    element_query: Query<(&config::Type, &container::element::Mass)>,
+    building_query: Query<(), With<building::Marker>>,
+    mut announcements: EventWriter<Announcement>,
+    mut signals: EventWriter<Signal>,
+    mut commands: Commands,
+) {
+    clock.cycle += 1;
+
+    let mut totals: Option<HashMap<config::Type, units::Mass>> = None;
+
+    for (entity, mut trigger) in &mut trigger_query {
+        let evaluation = match trigger.condition {
+            Condition::Cycle { at } => {
+                Evaluation { holds: clock.cycle >= at, clear: false, can_repeat: false }
+            }
+            Condition::FluidMass { ty, comparison, threshold, hysteresis } => {
+                let totals = totals.get_or_insert_with(|| {
+                    let mut totals = HashMap::new();
+                    for (&ty, mass) in &element_query {
+                        *totals.entry(ty).or_default() += mass.mass;
+                    }
+                    totals
+                });
+                let total = totals.get(&ty).copied().unwrap_or_default();
+                match comparison {
+                    Comparison::Below => Evaluation {
+                        holds:      total < threshold,
+                        clear:      total >= threshold + hysteresis,
+                        can_repeat: true,
+                    },
+                    Comparison::Above => Evaluation {
+                        holds:      total > threshold,
+                        clear:      total <= threshold - hysteresis,
+                        can_repeat: true,
+                    },
+                }
+            }
+            Condition::BuildingRemoved { building } => Evaluation {
+                holds:      building_query.get(building).is_err(),
+                clear:      false,
+                can_repeat: false,
+            },
+        };
+
+        if !trigger.armed {
+            if evaluation.clear {
+                trigger.armed = true;
+            }
+            continue;
+        }
+        if !evaluation.holds {
+            continue;
+        }
+
+        for action in &trigger.actions {
+            match action {
+                Action::Announce { message } => {
+                    announcements.send(Announcement { trigger: entity, message: message.clone() });
+                }
+                Action::Signal { name } => {
+                    signals.send(Signal { trigger: entity, name: name.clone() });
+                }
+                &Action::Ignite { container } => {
+                    commands.add(fire::Ignite { container: Ref::new_unchecked(container) });
+                }
+            }
+        }
+
+        if trigger.repeat == Repeat::Once || !evaluation.can_repeat {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            trigger.armed = false;
+        }
+    }
+}
+
+/// Save schema for the trigger clock.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveClock {
+    /// See [`Clock::cycle`].
+    pub cycle: u64,
+}
+
+impl save::Def for SaveClock {
+    const TYPE: &'static str = "traffloat.save.TriggerClock";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(mut writer: save::Writer<SaveClock>, (): (), clock: Res<Clock>) {
+            writer.write((), SaveClock { cycle: clock.cycle });
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(world: &mut World, def: SaveClock, (): &()) -> anyhow::Result<()> {
+            world.resource_mut::<Clock>().cycle = def.cycle;
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for triggers.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveTrigger {
+    /// See [`Trigger::condition`].
+    pub condition: SaveCondition,
+    /// See [`Trigger::actions`].
+    pub actions:   Vec<SaveAction>,
+    /// See [`Trigger::repeat`].
+    pub repeat:    Repeat,
+    /// See [`Trigger::armed`].
+    #[serde(default = "default_armed")]
+    pub armed:     bool,
+}
+
+fn default_armed() -> bool { true }
+
+/// Save schema for [`Condition`].
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum SaveCondition {
+    /// See [`Condition::Cycle`].
+    Cycle {
+        /// The first cycle in which the condition holds.
+        at: u64,
+    },
+    /// See [`Condition::FluidMass`].
+    FluidMass {
+        /// The fluid type.
+        ty:         save::Id<config::SaveType>,
+        /// The direction in which the threshold is crossed.
+        comparison: Comparison,
+        /// The threshold mass.
+        threshold:  units::Mass,
+        /// The rearming margin beyond the threshold.
+        #[serde(default)]
+        hysteresis: units::Mass,
+    },
+    /// See [`Condition::BuildingRemoved`].
+    BuildingRemoved {
+        /// The building.
+        building: save::Id<building::Save>,
+    },
+}
+
+/// Save schema for [`Action`].
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum SaveAction {
+    /// See [`Action::Announce`].
+    Announce {
+        /// The message.
+        message: DisplayText,
+    },
+    /// See [`Action::Signal`].
+    Signal {
+        /// The signal name.
+        name: String,
+    },
+    /// See [`Action::Ignite`].
+    Ignite {
+        /// The container.
+        container: save::Id<container::Save>,
+    },
+}
+
+impl save::Def for SaveTrigger {
+    const TYPE: &'static str = "traffloat.save.Trigger";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveTrigger>,
+            (type_dep, building_dep, container_dep): (
+                save::StoreDepend<config::SaveType>,
+                save::StoreDepend<building::Save>,
+                save::StoreDepend<container::Save>,
+            ),
+            query: Query<(Entity, &Trigger)>,
+        ) {
+            writer.write_all(query.iter().filter_map(|(entity, trigger)| {
+                let condition = match trigger.condition {
+                    Condition::Cycle { at } => SaveCondition::Cycle { at },
+                    Condition::FluidMass { ty, comparison, threshold, hysteresis } => {
+                        SaveCondition::FluidMass {
+                            ty: type_dep.must_get(ty),
+                            comparison,
+                            threshold,
+                            hysteresis,
+                        }
+                    }
+                    // the building may be removed in the cycle before the trigger is evaluated,
+                    // in which case the trigger is not saved since it would fire immediately anyway
+                    Condition::BuildingRemoved { building } => {
+                        SaveCondition::BuildingRemoved { building: building_dep.get(building)? }
+                    }
+                };
+                let actions = trigger
+                    .actions
+                    .iter()
+                    .map(|action| match *action {
+                        Action::Announce { ref message } => {
+                            SaveAction::Announce { message: message.clone() }
+                        }
+                        Action::Signal { ref name } => SaveAction::Signal { name: name.clone() },
+                        Action::Ignite { container } => {
+                            SaveAction::Ignite { container: container_dep.must_get(container) }
+                        }
+                    })
+                    .collect();
+                Some((
+                    entity,
+                    SaveTrigger {
+                        condition,
+                        actions,
+                        repeat: trigger.repeat,
+                        armed: trigger.armed,
+                    },
+                ))
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: SaveTrigger,
+            (type_dep, building_dep, container_dep): &(
+                save::LoadDepend<config::SaveType>,
+                save::LoadDepend<building::Save>,
+                save::LoadDepend<container::Save>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let condition = match def.condition {
+                SaveCondition::Cycle { at } => Condition::Cycle { at },
+                SaveCondition::FluidMass { ty, comparison, threshold, hysteresis } => {
+                    anyhow::ensure!(
+                        hysteresis.quantity >= 0.,
+                        "trigger hysteresis must be non-negative"
+                    );
+                    Condition::FluidMass {
+                        ty: type_dep.get(ty)?,
+                        comparison,
+                        threshold,
+                        hysteresis,
+                    }
+                }
+                SaveCondition::BuildingRemoved { building } => {
+                    Condition::BuildingRemoved { building: building_dep.get(building)? }
+                }
+            };
+            let actions = def
+                .actions
+                .into_iter()
+                .map(|action| {
+                    Ok(match action {
+                        SaveAction::Announce { message } => Action::Announce { message },
+                        SaveAction::Signal { name } => Action::Signal { name },
+                        SaveAction::Ignite { container } => {
+                            Action::Ignite { container: container_dep.get(container)? }
+                        }
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            let trigger = Trigger { condition, actions, repeat: def.repeat, armed: def.armed };
+            Ok(world.spawn((trigger, debug::Bundle::new("Trigger"))).id())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::EventReader;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{ResMut, Resource};
+use bevy::ecs::world::Command;
+use bevy::hierarchy::Children;
+use traffloat_base::partition::EventReaderSystemSet;
+use traffloat_base::save;
+
+use super::{Action, Clock, Comparison, Condition, Repeat, Signal, Trigger};
+use crate::{config, container, test_util, units};
+
+#[derive(Default, Resource)]
+struct Received(Vec<String>);
+
+fn new_app() -> App {
+    fn receive_system(mut reader: EventReader<Signal>, mut received: ResMut<Received>) {
+        received.0.extend(reader.read().map(|signal| signal.name.clone()));
+    }
+
+    let mut app = test_util::new_app();
+    app.init_resource::<Received>();
+    app.add_systems(app::Update, receive_system.in_set(EventReaderSystemSet::<Signal>::default()));
+    app
+}
+
+fn received(app: &App) -> usize { app.world().resource::<Received>().0.len() }
+
+fn signal() -> Vec<Action> { vec![Action::Signal { name: "go".into() }] }
+
+/// Spawns a container with a single fluid element, returning the element.
+fn spawn_mass(app: &mut App, ty: config::Type, mass: f32) -> Entity {
+    let container = test_util::spawn_container(app, 100.);
+    test_util::add_fluid(app, container, ty, mass);
+    app.world().get::<Children>(container).unwrap()[0]
+}
+
+fn set_mass(app: &mut App, element: Entity, mass: f32) {
+    app.world_mut().get_mut::<container::element::Mass>(element).unwrap().mass.quantity = mass;
+}
+
+fn mass_trigger(ty: config::Type, repeat: Repeat) -> Trigger {
+    Trigger {
+        condition: Condition::FluidMass {
+            ty,
+            comparison: Comparison::Above,
+            threshold: units::Mass { quantity: 10. },
+            hysteresis: units::Mass { quantity: 5. },
+        },
+        actions: signal(),
+        repeat,
+        armed: true,
+    }
+}
+
+#[test]
+fn fire_once() {
+    let mut app = new_app();
+    let trigger = app
+        .world_mut()
+        .spawn(Trigger {
+            condition: Condition::Cycle { at: 3 },
+            actions:   signal(),
+            repeat:    Repeat::Once,
+            armed:     true,
+        })
+        .id();
+
+    for _ in 0..2 {
+        app.update();
+    }
+    assert_eq!(received(&app), 0);
+
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(received(&app), 1);
+    assert!(app.world().get_entity(trigger).is_none());
+}
+
+#[test]
+fn repeat_after_hysteresis() {
+    let mut app = new_app();
+    let ty = test_util::create_type(&mut app, 1.);
+    let element = spawn_mass(&mut app, ty, 12.);
+    app.world_mut().spawn(mass_trigger(ty, Repeat::Repeat));
+
+    app.update();
+    app.update();
+    assert_eq!(received(&app), 1, "fires once while the condition holds");
+
+    // within the hysteresis band, the trigger stays disarmed
+    set_mass(&mut app, element, 8.);
+    app.update();
+    set_mass(&mut app, element, 12.);
+    app.update();
+    assert_eq!(received(&app), 1, "rearmed without clearing the hysteresis band");
+
+    // clearing the band rearms the trigger
+    set_mass(&mut app, element, 4.);
+    app.update();
+    set_mass(&mut app, element, 12.);
+    app.update();
+    assert_eq!(received(&app), 2);
+}
+
+#[test]
+fn save_and_load() {
+    let mut app = new_app();
+    let ty = test_util::create_type(&mut app, 1.);
+    // no fluid of the type exists, so the trigger fires immediately and is disarmed
+    app.world_mut().spawn(Trigger {
+        condition: Condition::FluidMass {
+            ty,
+            comparison: Comparison::Below,
+            threshold: units::Mass { quantity: 10. },
+            hysteresis: units::Mass { quantity: 5. },
+        },
+        ..mass_trigger(ty, Repeat::Repeat)
+    });
+
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Clock>().cycle, 3);
+
+    let data = Arc::new(Mutex::new(None));
+    save::StoreCommand {
+        format:      save::Format::Json,
+        on_complete: Box::new({
+            let data = Arc::clone(&data);
+            move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+        }),
+    }
+    .apply(app.world_mut());
+    let data = data.lock().unwrap().take().expect("StoreCommand completes synchronously");
+
+    let mut app = new_app();
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(app.world_mut());
+
+    assert_eq!(app.world().resource::<Clock>().cycle, 3);
+    let trigger = app.world_mut().query::<&Trigger>().single(app.world());
+    assert!(!trigger.armed, "the trigger fired before saving");
+    assert_eq!(trigger.repeat, Repeat::Repeat);
+}
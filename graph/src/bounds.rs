@@ -0,0 +1,281 @@
+//! World boundary and build limits.
+//!
+//! The [`WorldBounds`] resource restricts where buildings may be placed,
+//! and [`BuildLimits`] caps the number of buildings each faction may own.
+//! Both are loaded from the scenario and are unrestricted by default.
+//!
+//! Commands that place or transfer buildings validate against these limits
+//! with [`validate_placement`] and [`validate_ownership`],
+//! rejecting the command with a [`PlacementError`] on violation.
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::system::{Res, Resource};
+use bevy::ecs::world::World;
+use bevy::math::Vec3;
+use bevy::transform::components::Transform;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{error, proto, save};
+use traffloat_view::viewable;
+
+use crate::{building, corridor, ownership};
+
+/// Maintains world bounds and build limits.
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldBounds>();
+        app.init_resource::<BuildLimits>();
+        app.init_resource::<MaxBoundingRadius>();
+        save::add_def::<Save>(app);
+    }
+}
+
+/// The region in which buildings may be placed.
+#[derive(Debug, Clone, Default, Resource, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum WorldBounds {
+    /// Buildings may be placed anywhere.
+    #[default]
+    Unbounded,
+    /// Buildings must be within a sphere.
+    Sphere {
+        /// Center of the sphere.
+        center: proto::Position,
+        /// Radius of the sphere.
+        radius: f32,
+    },
+    /// Buildings must be within an axis-aligned box.
+    Aabb {
+        /// The corner with the smallest coordinates.
+        min: proto::Position,
+        /// The corner with the largest coordinates.
+        max: proto::Position,
+    },
+}
+
+impl WorldBounds {
+    /// Checks whether a sphere lies entirely within the bounds.
+    #[must_use]
+    pub fn contains_sphere(&self, center: Vec3, radius: f32) -> bool {
+        match *self {
+            Self::Unbounded => true,
+            Self::Sphere { center: bounds_center, radius: bounds_radius } => {
+                center.distance(bounds_center.into()) + radius <= bounds_radius
+            }
+            Self::Aabb { min, max } => {
+                let (min, max): (Vec3, Vec3) = (min.into(), max.into());
+                (center - radius).cmpge(min).all() && (center + radius).cmple(max).all()
+            }
+        }
+    }
+}
+
+/// Soft limits on the size of the world.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct BuildLimits {
+    /// The maximum number of buildings owned by each faction, or `None` for no limit.
+    pub max_buildings_per_faction: Option<u32>,
+}
+
+/// Reasons for rejecting a building placement or transfer.
+#[derive(Debug, thiserror::Error)]
+pub enum PlacementError {
+    /// The building would extend beyond [`WorldBounds`].
+    #[error("building would extend beyond the world boundary")]
+    OutOfBounds,
+    /// The building would overlap with another building.
+    #[error("building would overlap with {other:?}")]
+    Overlap {
+        /// The overlapped building.
+        other: Entity,
+    },
+    /// An adjacent corridor would exceed [`corridor::Limits::max_length`].
+    #[error("corridor to {other:?} would be too long")]
+    CorridorTooLong {
+        /// The building at the other end of the corridor.
+        other: Entity,
+    },
+    /// The faction already owns [`BuildLimits::max_buildings_per_faction`] buildings.
+    #[error("{faction:?} already owns the maximum of {limit} buildings")]
+    FactionLimit {
+        /// The faction at its limit.
+        faction: Entity,
+        /// The limit reached.
+        limit:   u32,
+    },
+}
+
+impl PlacementError {
+    /// Converts into a command rejection about `building`.
+    #[must_use]
+    pub fn into_error(self, building: Entity) -> error::Error {
+        let message = self.to_string();
+        match self {
+            Self::OutOfBounds => error::Error::validation("graph.placement.out_of_bounds", message)
+                .with_entity(building),
+            Self::Overlap { other } => error::Error::validation("graph.placement.overlap", message)
+                .with_entity(building)
+                .with_entity(other),
+            Self::CorridorTooLong { other } => {
+                error::Error::validation("graph.placement.corridor_too_long", message)
+                    .with_entity(building)
+                    .with_entity(other)
+            }
+            Self::FactionLimit { faction, .. } => {
+                error::Error::capacity("graph.placement.faction_limit", message)
+                    .with_entity(building)
+                    .with_entity(faction)
+            }
+        }
+    }
+}
+
+/// Buildings are modelled as unit spheres scaled by their transform.
+pub(crate) fn bounding_radius(transform: &Transform) -> f32 { transform.scale.max_element() }
+
+/// An upper bound of the bounding radius of all buildings,
+/// used to find overlap candidates in the [spatial index](viewable::SpatialIndex).
+///
+/// The bound is raised when a building is created and never lowered.
+#[derive(Default, Resource)]
+pub(crate) struct MaxBoundingRadius(f32);
+
+impl MaxBoundingRadius {
+    /// Raises the bound to include a building with `transform`.
+    pub(crate) fn include(&mut self, transform: &Transform) {
+        self.0 = self.0.max(bounding_radius(transform));
+    }
+}
+
+/// Checks whether a building can be placed with `transform`.
+///
+/// `building` is the building being moved, which is excluded from overlap checks,
+/// or `None` for a new building.
+///
+/// # Errors
+/// Returns the first violated constraint.
+pub fn validate_placement(
+    world: &mut World,
+    building: Option<Entity>,
+    transform: &Transform,
+) -> Result<(), PlacementError> {
+    let radius = bounding_radius(transform);
+    if !world.resource::<WorldBounds>().contains_sphere(transform.translation, radius) {
+        return Err(PlacementError::OutOfBounds);
+    }
+
+    let max_radius = world.resource::<MaxBoundingRadius>().0;
+    let indexed = world
+        .resource::<viewable::SpatialIndex>()
+        .within_radius(transform.translation, radius + max_radius);
+    let candidates = match indexed {
+        Some(candidates) => candidates,
+        // the index is only rebuilt in the next update after a building is spawned or moved
+        None => world.query_filtered::<Entity, With<building::Marker>>().iter(world).collect(),
+    };
+    let overlap = candidates.into_iter().find(|&other| {
+        Some(other) != building
+            && world.get::<building::Marker>(other).is_some()
+            && world.get::<Transform>(other).is_some_and(|other_tf| {
+                other_tf.translation.distance(transform.translation)
+                    < radius + bounding_radius(other_tf)
+            })
+    });
+    if let Some(other) = overlap {
+        return Err(PlacementError::Overlap { other });
+    }
+
+    let Some(building) = building else { return Ok(()) };
+    let max_length = world.resource::<corridor::Limits>().max_length;
+    let adjacent_endpoints: Vec<Entity> = world
+        .query_filtered::<&corridor::Endpoints, With<corridor::Marker>>()
+        .iter(world)
+        .filter_map(|endpoints| {
+            let side = endpoints.endpoints.find(&building)?;
+            Some(*endpoints.endpoints.as_endpoint(!side))
+        })
+        .collect();
+    for other in adjacent_endpoints {
+        let other_pos = world
+            .get::<Transform>(other)
+            .expect("corridor endpoints must be buildings")
+            .translation;
+        if other_pos.distance(transform.translation) > max_length {
+            return Err(PlacementError::CorridorTooLong { other });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether `faction` may take ownership of another building.
+///
+/// # Errors
+/// Returns [`PlacementError::FactionLimit`] if the faction is at its limit.
+pub fn validate_ownership(world: &mut World, faction: Entity) -> Result<(), PlacementError> {
+    let Some(limit) = world.resource::<BuildLimits>().max_buildings_per_faction else {
+        return Ok(());
+    };
+
+    let owned = world
+        .query_filtered::<&ownership::Owner, With<building::Marker>>()
+        .iter(world)
+        .filter(|owner| owner.faction == faction)
+        .count();
+    if owned >= limit as usize {
+        return Err(PlacementError::FactionLimit { faction, limit });
+    }
+
+    Ok(())
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The world boundary.
+    pub bounds:                    WorldBounds,
+    /// The maximum number of buildings owned by each faction.
+    #[serde(default)]
+    pub max_buildings_per_faction: Option<u32>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.WorldBounds";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (): (),
+            (bounds, limits): (Res<WorldBounds>, Res<BuildLimits>),
+        ) {
+            writer.write(
+                (),
+                Save {
+                    bounds:                    bounds.clone(),
+                    max_buildings_per_faction: limits.max_buildings_per_faction,
+                },
+            );
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            world.insert_resource(def.bounds);
+            world.insert_resource(BuildLimits {
+                max_buildings_per_faction: def.max_buildings_per_faction,
+            });
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
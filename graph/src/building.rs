@@ -18,6 +18,7 @@ use traffloat_view::{appearance, viewable};
 use typed_builder::TypedBuilder;
 
 pub mod facility;
+pub mod mode;
 
 /// Maintain buildings.
 pub struct Plugin;
@@ -26,6 +27,8 @@ impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         save::add_def::<Save>(app);
         save::add_def::<facility::Save>(app);
+        app.add_plugins(mode::Plugin);
+        debug::invariant::require::<Marker, (FacilityList, Transform, viewable::Sid)>(app);
     }
 }
 
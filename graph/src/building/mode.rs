@@ -0,0 +1,209 @@
+//! Operating modes scale the activity of a building.
+//!
+//! Features of a building, such as the fluid processes in its facilities,
+//! multiply their rates by the [`Rate`] of the building.
+//! Buildings without an [`OperatingMode`] operate at full rate.
+//!
+//! The current rate is exposed to viewers as a metric on the building.
+
+use std::time::Duration;
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::system::Query;
+use bevy::ecs::world::{Command, World};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{error, save, Ref};
+use traffloat_view::{format, metrics, DisplayText};
+
+/// Maintains building operating modes.
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        metrics::add_fed_type::<Option<&Rate>, With<super::Marker>, (), _>(
+            app,
+            metrics::TypeDef {
+                update_frequency: Duration::from_secs(1),
+                display_label:    DisplayText::Custom { value: "Operating rate".into() },
+                aggregation:      metrics::Aggregation::Mean,
+            },
+            [format::Unit::Ratio.metadata()],
+            |entity, ()| Rate::of(entity.get::<Rate>()),
+        );
+        save::add_def::<Save>(app);
+    }
+}
+
+/// An operating mode of a building.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum Mode {
+    /// The building operates at full rate.
+    Active,
+    /// The building does not operate but remains ready to resume.
+    Standby,
+    /// The building is shut down.
+    Off,
+    /// A building-specific mode defined in [`CustomModes`].
+    Custom {
+        /// Index of the mode in [`CustomModes::modes`].
+        index: usize,
+    },
+}
+
+/// The current operating mode of a building.
+#[derive(Component)]
+pub struct OperatingMode {
+    /// The current mode.
+    pub mode: Mode,
+}
+
+/// Building-specific operating modes available in addition to the standard modes.
+#[derive(Component, Default)]
+pub struct CustomModes {
+    /// The available custom modes.
+    pub modes: Vec<CustomMode>,
+}
+
+/// A building-specific operating mode, e.g. "overdrive".
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CustomMode {
+    /// Display name of the mode.
+    pub label: DisplayText,
+    /// The rate multiplier applied to building features in this mode.
+    pub rate:  f32,
+}
+
+/// The rate multiplier of a building derived from its operating mode.
+///
+/// This component is maintained by [`SetMode`] and should not be modified directly.
+#[derive(Component)]
+pub struct Rate {
+    /// The multiplier applied to the rates of building features.
+    pub rate: f32,
+}
+
+impl Rate {
+    /// Returns the rate of a building that may not have an operating mode.
+    #[must_use]
+    pub fn of(rate: Option<&Self>) -> f32 { rate.map_or(1., |rate| rate.rate) }
+}
+
+/// Changes the operating mode of a building.
+///
+/// The change is [rejected](error::reject) if the building does not define the custom mode.
+/// Use [`SetMode::rate`] to validate the change in advance.
+pub struct SetMode {
+    /// The building entity.
+    pub building: Ref<super::Marker>,
+    /// The new operating mode.
+    pub mode:     Mode,
+}
+
+impl SetMode {
+    /// Checks whether the mode can be set on the building and returns the resulting rate.
+    ///
+    /// # Errors
+    /// Returns an error if the entity is not a building
+    /// or the building does not define the custom mode.
+    pub fn rate(&self, world: &World) -> Result<f32, error::Error> {
+        let building = self.building.resolve(world)?;
+
+        Ok(match self.mode {
+            Mode::Active => 1.,
+            Mode::Standby | Mode::Off => 0.,
+            Mode::Custom { index } => {
+                let custom = building.get::<CustomModes>().and_then(|modes| modes.modes.get(index));
+                let Some(custom) = custom else {
+                    let message = format!("{:?} has no custom mode {index}", building.id());
+                    return Err(error::Error::validation("graph.mode.undefined_custom", message)
+                        .with_entity(building.id()));
+                };
+                custom.rate
+            }
+        })
+    }
+}
+
+impl Command for SetMode {
+    fn apply(self, world: &mut World) {
+        let rate = match self.rate(world) {
+            Ok(rate) => rate,
+            Err(err) => return error::reject(world, err),
+        };
+
+        world
+            .entity_mut(self.building.entity())
+            .insert((OperatingMode { mode: self.mode }, Rate { rate }));
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The building.
+    pub building: save::Id<super::Save>,
+    /// The current operating mode.
+    pub mode:     Mode,
+    /// Building-specific custom modes.
+    #[serde(default)]
+    pub custom:   Vec<CustomMode>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.OperatingMode";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (building_dep,): (save::StoreDepend<super::Save>,),
+            query: Query<
+                (Entity, Option<&OperatingMode>, Option<&CustomModes>),
+                With<super::Marker>,
+            >,
+        ) {
+            writer.write_all(query.iter().filter_map(|(entity, mode, custom)| {
+                if mode.is_none() && custom.is_none() {
+                    return None;
+                }
+
+                Some((
+                    entity,
+                    Save {
+                        building: building_dep.must_get(entity),
+                        mode:     mode.map_or(Mode::Active, |mode| mode.mode),
+                        custom:   custom.map(|custom| custom.modes.clone()).unwrap_or_default(),
+                    },
+                ))
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (building_dep,): &(save::LoadDepend<super::Save>,),
+        ) -> anyhow::Result<Entity> {
+            let building = building_dep.get(def.building)?;
+            if let Mode::Custom { index } = def.mode {
+                anyhow::ensure!(index < def.custom.len(), "undefined custom mode {index}");
+            }
+
+            world.entity_mut(building).insert(CustomModes { modes: def.custom });
+            SetMode { building: Ref::new_unchecked(building), mode: def.mode }.apply(world);
+            Ok(building)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,141 @@
+//! Applies a command to many structures at once.
+//!
+//! A [`BulkCommand`] resolves its [targets](Targets) from an explicit selection
+//! or a [filter](tag::Filter), validates the [action](Action) on every target,
+//! and applies it to all targets only if every validation succeeds.
+//! The outcome for each target is reported through [`BulkCommand::on_complete`].
+
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::{Command, World};
+use traffloat_base::{error, Ref};
+
+use crate::building::mode;
+use crate::{building, corridor, ownership, tag};
+
+/// An operation that can be applied to each target of a [`BulkCommand`].
+pub trait Action: Send + 'static {
+    /// Checks whether the action can be applied to `entity`.
+    ///
+    /// # Errors
+    /// Returns the reason if the action cannot be applied.
+    fn validate(&self, world: &World, entity: Entity) -> Result<(), error::Error>;
+
+    /// Applies the action to `entity`.
+    ///
+    /// Only called after [`validate`](Action::validate) succeeded for the same entity.
+    fn apply(&self, world: &mut World, entity: Entity);
+}
+
+/// Sets the operating mode of each target building.
+impl Action for mode::Mode {
+    fn validate(&self, world: &World, entity: Entity) -> Result<(), error::Error> {
+        mode::SetMode { building: Ref::new_unchecked(entity), mode: *self }.rate(world).map(|_| ())
+    }
+
+    fn apply(&self, world: &mut World, entity: Entity) {
+        mode::SetMode { building: Ref::new_unchecked(entity), mode: *self }.apply(world);
+    }
+}
+
+/// Assigns a tag to each target.
+impl Action for tag::Tag {
+    fn validate(&self, world: &World, entity: Entity) -> Result<(), error::Error> {
+        let is_structure = world.get_entity(entity).is_some_and(|entity| {
+            entity.contains::<building::Marker>() || entity.contains::<corridor::Marker>()
+        });
+        if !is_structure {
+            let message = format!("{entity:?} is not a building or corridor");
+            return Err(
+                error::Error::not_found("graph.structure.not_found", message).with_entity(entity)
+            );
+        }
+        Ok(())
+    }
+
+    fn apply(&self, world: &mut World, entity: Entity) {
+        tag::AddTag { entity, tag: self.clone() }.apply(world);
+    }
+}
+
+/// The structures targeted by a [`BulkCommand`].
+pub enum Targets {
+    /// An explicit selection of entities.
+    Selection(Vec<Entity>),
+    /// All structures matching the filter when the command is applied.
+    Filter(tag::Filter),
+}
+
+/// The outcome of a [`BulkCommand`] on a single target.
+#[derive(Debug)]
+pub enum Outcome {
+    /// The action was applied.
+    Applied,
+    /// The action could not be applied to this target.
+    Rejected(error::Error),
+    /// The action was valid for this target,
+    /// but was not applied because another target was rejected.
+    Skipped,
+}
+
+/// Applies an action to every target atomically.
+///
+/// If the action is rejected for any target, it is not applied to any target.
+pub struct BulkCommand<A> {
+    /// The targets of the action.
+    pub targets:     Targets,
+    /// The action to apply.
+    pub action:      A,
+    /// The faction issuing the command.
+    ///
+    /// Targets that the faction is not [authorized](ownership::is_authorized) to command
+    /// are rejected. `None` skips the authorization check.
+    pub faction:     Option<Entity>,
+    /// Invoked with the outcome of each target.
+    pub on_complete: Box<dyn FnOnce(&mut World, Vec<(Entity, Outcome)>) + Send>,
+}
+
+impl<A: Action> Command for BulkCommand<A> {
+    fn apply(self, world: &mut World) {
+        let Self { targets, action, faction, on_complete } = self;
+
+        let targets = match targets {
+            Targets::Selection(entities) => entities,
+            Targets::Filter(filter) => filter.evaluate(world),
+        };
+
+        let validations: Vec<_> = targets
+            .into_iter()
+            .map(|entity| {
+                let result = match faction {
+                    Some(faction) if !ownership::is_authorized(world, faction, entity) => {
+                        let message =
+                            format!("{faction:?} is not authorized to command {entity:?}");
+                        Err(error::Error::permission("graph.ownership.unauthorized", message)
+                            .with_entity(faction)
+                            .with_entity(entity))
+                    }
+                    _ => action.validate(world, entity),
+                };
+                (entity, result)
+            })
+            .collect();
+
+        let all_valid = validations.iter().all(|(_, result)| result.is_ok());
+        let outcomes = validations
+            .into_iter()
+            .map(|(entity, result)| {
+                let outcome = match result {
+                    Err(err) => Outcome::Rejected(err),
+                    Ok(()) if all_valid => {
+                        action.apply(world, entity);
+                        Outcome::Applied
+                    }
+                    Ok(()) => Outcome::Skipped,
+                };
+                (entity, outcome)
+            })
+            .collect();
+
+        on_complete(world, outcomes);
+    }
+}
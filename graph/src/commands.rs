@@ -0,0 +1,70 @@
+use bevy::ecs::world::{Command, World};
+use bevy::math::{Quat, Vec3};
+use bevy::transform::components::Transform;
+use traffloat_base::{error, Ref};
+use traffloat_view::viewable;
+
+use crate::event::BuildingMoved;
+use crate::{bounds, building};
+
+#[cfg(test)]
+mod tests;
+
+/// A command to move a building to a new position.
+///
+/// The move is [rejected](error::reject) if the new position fails [`bounds::validate_placement`],
+/// e.g. if the building would overlap with another building
+/// or if any adjacent corridor would exceed [`crate::corridor::Limits::max_length`].
+pub struct MoveBuilding {
+    /// The building to move.
+    pub building:    Ref<building::Marker>,
+    /// The new position of the building center.
+    pub translation: Vec3,
+}
+
+impl Command for MoveBuilding {
+    fn apply(self, world: &mut World) {
+        let mut transform = match self.building.resolve(world) {
+            Ok(building) => *building.get::<Transform>().expect("building must have Transform"),
+            Err(err) => return error::reject(world, err),
+        };
+        transform.translation = self.translation;
+
+        let building = self.building.entity();
+        if let Err(err) = bounds::validate_placement(world, Some(building), &transform) {
+            error::reject(world, err.into_error(building));
+            return;
+        }
+
+        relocate(world, self.building, transform);
+    }
+}
+
+/// A command to change the orientation of a building.
+///
+/// Rotation does not affect the validity of the building position,
+/// since buildings are checked against their bounding spheres.
+pub struct RotateBuilding {
+    /// The building to rotate.
+    pub building: Ref<building::Marker>,
+    /// The new absolute rotation of the building.
+    pub rotation: Quat,
+}
+
+impl Command for RotateBuilding {
+    fn apply(self, world: &mut World) {
+        let mut transform = match self.building.resolve(world) {
+            Ok(building) => *building.get::<Transform>().expect("building must have Transform"),
+            Err(err) => return error::reject(world, err),
+        };
+        transform.rotation = self.rotation;
+
+        relocate(world, self.building, transform);
+    }
+}
+
+fn relocate(world: &mut World, building: Ref<building::Marker>, transform: Transform) {
+    let viewable = building.cast(world).expect("buildings must be stationary viewables");
+    viewable::Relocate { viewable, transform }.apply(world);
+    world.send_event(BuildingMoved { building: building.entity() });
+}
@@ -0,0 +1,102 @@
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::Command;
+use bevy::math::{Quat, Vec3};
+use bevy::transform::components::Transform;
+use traffloat_base::Ref;
+use traffloat_view::viewable;
+
+use super::{MoveBuilding, RotateBuilding};
+use crate::corridor::{self, Binary};
+use crate::event::BuildingMoved;
+use crate::test_util;
+
+fn translation(app: &App, building: Entity) -> Vec3 {
+    app.world().get::<Transform>(building).unwrap().translation
+}
+
+fn move_building(app: &mut App, building: Entity, translation: Vec3) {
+    MoveBuilding { building: Ref::new_unchecked(building), translation }.apply(app.world_mut());
+}
+
+#[test]
+fn move_to_free_position() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    test_util::spawn_building(&mut app, Vec3::new(10., 0., 0.));
+    app.update();
+
+    move_building(&mut app, building, Vec3::new(0., 5., 0.));
+
+    assert!(test_util::rejections(&app).is_empty());
+    assert_eq!(translation(&app, building), Vec3::new(0., 5., 0.));
+    let moved = test_util::events::<BuildingMoved>(&app);
+    assert_eq!(moved.iter().map(|event| event.building).collect::<Vec<_>>(), [building]);
+}
+
+#[test]
+fn reject_overlap() {
+    // before the first update the spatial index is not built yet
+    for build_index in [false, true] {
+        let mut app = test_util::new_app();
+        let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+        test_util::spawn_building(&mut app, Vec3::new(10., 0., 0.));
+        if build_index {
+            app.update();
+        }
+        let index = app.world().resource::<viewable::SpatialIndex>();
+        assert_eq!(index.within_radius(Vec3::ZERO, 1.).is_some(), build_index);
+
+        move_building(&mut app, building, Vec3::new(9., 0., 0.));
+
+        assert_eq!(test_util::rejections(&app), ["graph.placement.overlap"]);
+        assert_eq!(translation(&app, building), Vec3::ZERO);
+        assert!(test_util::events::<BuildingMoved>(&app).is_empty());
+    }
+}
+
+#[test]
+fn reject_overlap_with_large_building() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let large = test_util::spawn_scaled_building(&mut app, Vec3::new(40., 0., 0.), 10.);
+    app.update();
+
+    // the center of the large building is farther than the sum of unit radii
+    move_building(&mut app, building, Vec3::new(30.5, 0., 0.));
+
+    assert_eq!(test_util::rejections(&app), ["graph.placement.overlap"]);
+    assert_eq!(translation(&app, building), Vec3::ZERO);
+    assert_eq!(translation(&app, large), Vec3::new(40., 0., 0.));
+}
+
+#[test]
+fn reject_long_corridor() {
+    let mut app = test_util::new_app();
+    let [alpha, beta] =
+        [0., 10.].map(|x| test_util::spawn_building(&mut app, Vec3::new(x, 0., 0.)));
+    test_util::spawn_corridor(&mut app, Binary { alpha, beta });
+    app.insert_resource(corridor::Limits { max_length: 20. });
+    app.update();
+
+    move_building(&mut app, beta, Vec3::new(15., 0., 0.));
+    assert!(test_util::rejections(&app).is_empty());
+
+    move_building(&mut app, beta, Vec3::new(25., 0., 0.));
+    assert_eq!(test_util::rejections(&app), ["graph.placement.corridor_too_long"]);
+    assert_eq!(translation(&app, beta), Vec3::new(15., 0., 0.));
+}
+
+#[test]
+fn rotate_in_place() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let rotation = Quat::from_rotation_z(1.);
+
+    RotateBuilding { building: Ref::new_unchecked(building), rotation }.apply(app.world_mut());
+
+    let transform = app.world().get::<Transform>(building).unwrap();
+    assert_eq!(transform.rotation, rotation);
+    assert_eq!(transform.translation, Vec3::ZERO);
+    assert_eq!(test_util::events::<BuildingMoved>(&app).len(), 1);
+}
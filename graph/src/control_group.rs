@@ -0,0 +1,118 @@
+//! Persistence of viewer [control groups](control_group).
+//!
+//! Groups are saved with the [`Sid`](viewer::Sid) of their viewer
+//! and [restored](control_group::restore) to the viewer with the same ID after loading.
+//! Only buildings and facilities are saved as members;
+//! other viewables are omitted from the saved group.
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::system::Query;
+use bevy::ecs::world::World;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::save;
+use traffloat_view::viewer::{self, control_group};
+
+use crate::building::{self, facility};
+
+#[cfg(test)]
+mod tests;
+
+/// Persists control groups.
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) { save::add_def::<Save>(app); }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The ID of the viewer owning the group.
+    pub viewer:  u32,
+    /// The group number.
+    pub group:   u8,
+    /// The members of the group.
+    pub members: Vec<SaveMember>,
+}
+
+/// Member of a control group, used in saves.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum SaveMember {
+    /// The member is a building.
+    Building {
+        /// Save ID of the building.
+        id: save::Id<building::Save>,
+    },
+    /// The member is a facility.
+    Facility {
+        /// Save ID of the facility.
+        id: save::Id<facility::Save>,
+    },
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.ControlGroup";
+
+    type Runtime = (viewer::Sid, u8);
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (building_dep, facility_dep): (
+                save::StoreDepend<building::Save>,
+                save::StoreDepend<facility::Save>,
+            ),
+            query: Query<(&viewer::Sid, &control_group::Groups)>,
+        ) {
+            let save_member = |member: Entity| {
+                if let Some(id) = building_dep.get(member) {
+                    Some(SaveMember::Building { id })
+                } else {
+                    facility_dep.get(member).map(|id| SaveMember::Facility { id })
+                }
+            };
+
+            for (&viewer, groups) in &query {
+                for (&group, members) in &groups.groups {
+                    let members: Vec<_> =
+                        members.iter().filter_map(|&member| save_member(member)).collect();
+                    if !members.is_empty() {
+                        writer
+                            .write((viewer, group), Save { viewer: viewer.into(), group, members });
+                    }
+                }
+            }
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (building_dep, facility_dep): &(
+                save::LoadDepend<building::Save>,
+                save::LoadDepend<facility::Save>,
+            ),
+        ) -> anyhow::Result<(viewer::Sid, u8)> {
+            let members = def
+                .members
+                .into_iter()
+                .map(|member| match member {
+                    SaveMember::Building { id } => building_dep.get(id),
+                    SaveMember::Facility { id } => facility_dep.get(id),
+                })
+                .collect::<Result<Vec<Entity>, _>>()?;
+            let viewer = viewer::Sid::from(def.viewer);
+            control_group::restore(world, viewer, def.group, members);
+            Ok((viewer, def.group))
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::{Command, World};
+use bevy::math::Vec3;
+use bevy::transform::components::Transform;
+use traffloat_base::save;
+use traffloat_view::viewer::{self, control_group};
+
+use crate::test_util;
+
+fn spawn_viewer(world: &mut World) -> Entity {
+    let sid = viewer::next_sid(world);
+    world
+        .spawn(
+            viewer::Bundle::builder()
+                .id(sid)
+                .range(viewer::Range { distance: 100. })
+                .position(Transform::default())
+                .build(),
+        )
+        .id()
+}
+
+fn translations(world: &World, members: &[Entity]) -> Vec<Vec3> {
+    members.iter().map(|&member| world.get::<Transform>(member).unwrap().translation).collect()
+}
+
+#[test]
+fn save_and_load() {
+    let mut app = test_util::new_app();
+    let [first, second] =
+        [10., 20.].map(|x| test_util::spawn_building(&mut app, Vec3::new(x, 0., 0.)));
+    let unsaved = app.world_mut().spawn_empty().id();
+    let viewer = spawn_viewer(app.world_mut());
+    app.world_mut()
+        .get_mut::<control_group::Groups>(viewer)
+        .unwrap()
+        .groups
+        .extend([(1, vec![second, first]), (2, vec![unsaved])]);
+
+    let data = Arc::new(Mutex::new(None));
+    save::StoreCommand {
+        format:      save::Format::Json,
+        on_complete: Box::new({
+            let data = Arc::clone(&data);
+            move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+        }),
+    }
+    .apply(app.world_mut());
+    let data = data.lock().unwrap().take().expect("StoreCommand completes synchronously");
+
+    let mut app = test_util::new_app();
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(app.world_mut());
+
+    let viewer = spawn_viewer(app.world_mut());
+    let groups = &app.world().get::<control_group::Groups>(viewer).unwrap().groups;
+    assert_eq!(
+        groups.keys().copied().collect::<Vec<_>>(),
+        [1],
+        "groups without saved members are dropped"
+    );
+    assert_eq!(
+        translations(app.world(), &groups[&1]),
+        [Vec3::new(20., 0., 0.), Vec3::new(10., 0., 0.)]
+    );
+    assert!(app.world().resource::<control_group::Restored>().groups.is_empty());
+}
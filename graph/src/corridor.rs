@@ -5,7 +5,7 @@ use bevy::ecs::bundle;
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::query::With;
-use bevy::ecs::system::Query;
+use bevy::ecs::system::{Query, Resource};
 use bevy::ecs::world::World;
 use bevy::hierarchy::BuildWorldChildren;
 use schemars::JsonSchema;
@@ -19,6 +19,7 @@ mod endpoint;
 pub use endpoint::{Binary, Endpoint};
 
 pub mod duct;
+pub mod reshape;
 
 /// Maintain corridors.
 pub struct Plugin;
@@ -27,6 +28,9 @@ impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         save::add_def::<Save>(app);
         save::add_def::<duct::Save>(app);
+        app.add_plugins(reshape::Plugin);
+        app.init_resource::<Limits>();
+        debug::invariant::require::<Marker, (Endpoints, DuctList)>(app);
     }
 }
 
@@ -46,6 +50,9 @@ pub struct Bundle {
 pub struct Marker;
 
 /// The endpoint buildings of a corridor.
+///
+/// Use [`SetEndpoints`](crate::event::SetEndpoints) to change the endpoints
+/// instead of mutating this component directly.
 #[derive(Component)]
 pub struct Endpoints {
     /// Endpoint buildings.
@@ -63,6 +70,17 @@ pub struct DuctList {
     pub ambient: Entity,
 }
 
+/// Geometric constraints on corridors.
+#[derive(Resource)]
+pub struct Limits {
+    /// The maximum distance between the endpoint building centers of a corridor.
+    pub max_length: f32,
+}
+
+impl Default for Limits {
+    fn default() -> Self { Self { max_length: f32::INFINITY } }
+}
+
 /// Save schema.
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Save {
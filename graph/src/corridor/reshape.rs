@@ -0,0 +1,357 @@
+//! Splitting and merging corridors.
+//!
+//! Both operations are performed by commands that update the graph atomically.
+//! Modules maintaining components that depend on ducts
+//! should add systems to the [`OnSplit`] and [`OnMerge`] schedules,
+//! which are executed synchronously within the command
+//! after the new entities are spawned and before the obsolete entities are despawned.
+
+use std::iter;
+
+use bevy::app::{self, App};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy::{BuildWorldChildren, DespawnRecursiveExt};
+use bevy::math::Quat;
+use bevy::transform::components::Transform;
+use traffloat_base::error;
+use traffloat_view::{appearance, viewable};
+use typed_builder::TypedBuilder;
+
+use super::{duct, Binary, Bundle, DuctList, Endpoints, Marker};
+use crate::building::{self, facility};
+use crate::event::SetEndpoints;
+
+/// The default value of [`Merge::max_deviation`].
+pub const DEFAULT_MAX_DEVIATION: f32 = 0.01;
+
+#[cfg(test)]
+mod tests;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_schedule(OnSplit);
+        app.init_schedule(OnMerge);
+        app.init_resource::<CurrentSplit>();
+        app.init_resource::<CurrentMerge>();
+    }
+}
+
+/// Splits a corridor into two by inserting a new junction building.
+///
+/// The original corridor keeps its alpha endpoint and is reconnected to the junction.
+/// A new corridor is spawned from the junction to the original beta endpoint,
+/// with a new duct corresponding to each duct in the original corridor.
+///
+/// The junction is created without any storages,
+/// so the ducts on both sides are disconnected at the junction
+/// until facilities are constructed there.
+///
+/// The split is [rejected](error::reject) if `ratio` is not within `[0, 1]`.
+#[derive(TypedBuilder)]
+pub struct Split {
+    /// The corridor to split.
+    pub corridor:   Entity,
+    /// The position of the junction along the corridor,
+    /// where 0 is the alpha endpoint and 1 is the beta endpoint.
+    pub ratio:      f32,
+    /// Appearance of the junction building.
+    pub appearance: appearance::Appearance,
+}
+
+impl Command for Split {
+    fn apply(self, world: &mut World) {
+        if !(0. ..=1.).contains(&self.ratio) {
+            let message = format!("cannot split {:?} at ratio {}", self.corridor, self.ratio);
+            let err = error::Error::validation("graph.split.ratio", message);
+            error::reject(world, err.with_entity(self.corridor));
+            return;
+        }
+
+        let endpoints = world
+            .get::<Endpoints>(self.corridor)
+            .expect("Split.corridor must be a corridor entity")
+            .endpoints;
+        let endpoint_tfs = endpoints.map(|building| {
+            *world.get::<Transform>(building).expect("corridor endpoints must be buildings")
+        });
+        let junction = spawn_junction(
+            world,
+            Transform {
+                translation: endpoint_tfs
+                    .alpha
+                    .translation
+                    .lerp(endpoint_tfs.beta.translation, self.ratio),
+                rotation:    Quat::IDENTITY,
+                scale:       endpoint_tfs.alpha.scale.lerp(endpoint_tfs.beta.scale, self.ratio),
+            },
+            self.appearance,
+        );
+
+        let (original_ambient, original_non_ambient) = {
+            let list = world.get::<DuctList>(self.corridor).expect("corridor must have DuctList");
+            (list.ambient, list.duct_list.clone())
+        };
+
+        let split_ambient = world.spawn(duct::Bundle::builder().build()).id();
+        let split_non_ambient: Vec<Entity> = original_non_ambient
+            .iter()
+            .map(|_| world.spawn(duct::Bundle::builder().build()).id())
+            .collect();
+
+        let split_off = {
+            let mut corridor = world.spawn(
+                Bundle::builder()
+                    .endpoints(Endpoints {
+                        endpoints: Binary { alpha: junction, beta: endpoints.beta },
+                    })
+                    .duct_list(DuctList {
+                        duct_list: split_non_ambient.clone(),
+                        ambient:   split_ambient,
+                    })
+                    .build(),
+            );
+            corridor.add_child(split_ambient);
+            corridor.push_children(&split_non_ambient);
+            corridor.id()
+        };
+
+        SetEndpoints {
+            corridor:  self.corridor,
+            endpoints: Binary { alpha: endpoints.alpha, beta: junction },
+        }
+        .apply(world);
+
+        world.resource_mut::<CurrentSplit>().0 = Some(Splitting {
+            original: self.corridor,
+            split_off,
+            junction,
+            far_endpoint: endpoints.beta,
+            ratio: self.ratio,
+            ducts: iter::once((original_ambient, split_ambient))
+                .chain(iter::zip(original_non_ambient, split_non_ambient))
+                .collect(),
+        });
+        world.run_schedule(OnSplit);
+        world.resource_mut::<CurrentSplit>().0 = None;
+    }
+}
+
+fn spawn_junction(
+    world: &mut World,
+    transform: Transform,
+    appearance: appearance::Appearance,
+) -> Entity {
+    let ambient_sid = viewable::next_sid(world);
+    let ambient = world
+        .spawn(
+            facility::Bundle::builder()
+                .viewable(
+                    viewable::StationaryChildBundle::builder()
+                        .base(
+                            viewable::BaseBundle::builder()
+                                .sid(ambient_sid)
+                                .appearance(appearance::Appearance::null())
+                                .build(),
+                        )
+                        .inner_transform(Transform::IDENTITY)
+                        .build(),
+                )
+                .build(),
+        )
+        .id();
+
+    let sid = viewable::next_sid(world);
+    let mut building = world.spawn(
+        building::Bundle::builder()
+            .viewable(
+                viewable::StationaryBundle::builder()
+                    .base(viewable::BaseBundle::builder().sid(sid).appearance(appearance).build())
+                    .transform(transform)
+                    .build(),
+            )
+            .facility_list(building::FacilityList { ambient, non_ambient: Vec::new() })
+            .build(),
+    );
+    building.add_child(ambient);
+    building.id()
+}
+
+/// Merges the two corridors connected to a junction building,
+/// despawning the junction building.
+///
+/// The junction must be connected to exactly two corridors,
+/// which must be collinear and contain the same number of ducts.
+/// Ducts are paired by their order in the [`DuctList`].
+///
+/// The corridor listed first is kept and reconnected to the other endpoint,
+/// while the other corridor is despawned.
+#[derive(TypedBuilder)]
+pub struct Merge {
+    /// The junction building between the two corridors.
+    pub junction:      Entity,
+    /// The maximum angle in radians between the two corridors
+    /// for them to be considered collinear.
+    #[builder(default = DEFAULT_MAX_DEVIATION)]
+    pub max_deviation: f32,
+}
+
+impl Command for Merge {
+    fn apply(self, world: &mut World) {
+        let corridors: Vec<(Entity, Binary<Entity>)> = world
+            .query_filtered::<(Entity, &Endpoints), With<Marker>>()
+            .iter(world)
+            .filter(|(_, endpoints)| endpoints.endpoints.find(&self.junction).is_some())
+            .map(|(corridor, endpoints)| (corridor, endpoints.endpoints))
+            .collect();
+        let [(kept, kept_endpoints), (removed, removed_endpoints)] = corridors[..] else {
+            let message = format!(
+                "cannot merge at {:?} connected to {} corridors",
+                self.junction,
+                corridors.len()
+            );
+            let err = error::Error::validation("graph.merge.corridor_count", message);
+            error::reject(world, err.with_entity(self.junction));
+            return;
+        };
+
+        let kept_side = kept_endpoints.find(&self.junction).expect("filtered in query");
+        let removed_side = removed_endpoints.find(&self.junction).expect("filtered in query");
+        let kept_far = *kept_endpoints.as_endpoint(!kept_side);
+        let removed_far = *removed_endpoints.as_endpoint(!removed_side);
+        if kept_far == self.junction || removed_far == self.junction || kept_far == removed_far {
+            let message = format!("cannot merge at {:?} with degenerate corridors", self.junction);
+            let err = error::Error::validation("graph.merge.degenerate", message);
+            error::reject(world, err.with_entity(self.junction));
+            return;
+        }
+
+        let [junction_pos, kept_far_pos, removed_far_pos] = [self.junction, kept_far, removed_far]
+            .map(|building| {
+                world
+                    .get::<Transform>(building)
+                    .expect("corridor endpoints must be buildings")
+                    .translation
+            });
+        let deviation = (kept_far_pos - junction_pos).angle_between(junction_pos - removed_far_pos);
+        if deviation.is_nan() || deviation > self.max_deviation {
+            let message = format!(
+                "cannot merge non-collinear corridors at {:?} (deviation {deviation})",
+                self.junction
+            );
+            let err = error::Error::validation("graph.merge.non_collinear", message);
+            error::reject(world, err.with_entity(self.junction));
+            return;
+        }
+
+        let ducts = {
+            let [kept_list, removed_list] = [kept, removed].map(|corridor| {
+                world.get::<DuctList>(corridor).expect("corridor must have DuctList")
+            });
+            if kept_list.duct_list.len() != removed_list.duct_list.len() {
+                let message = format!(
+                    "cannot merge corridors at {:?} with different number of ducts",
+                    self.junction
+                );
+                let err = error::Error::validation("graph.merge.duct_count", message)
+                    .with_entity(self.junction)
+                    .with_entity(kept)
+                    .with_entity(removed);
+                error::reject(world, err);
+                return;
+            }
+
+            iter::once((kept_list.ambient, removed_list.ambient))
+                .chain(iter::zip(
+                    kept_list.duct_list.iter().copied(),
+                    removed_list.duct_list.iter().copied(),
+                ))
+                .collect()
+        };
+
+        let mut new_endpoints = kept_endpoints;
+        *new_endpoints.as_endpoint_mut(kept_side) = removed_far;
+        SetEndpoints { corridor: kept, endpoints: new_endpoints }.apply(world);
+
+        world.resource_mut::<CurrentMerge>().0 =
+            Some(Merging { kept, removed, junction: self.junction, ducts });
+        world.run_schedule(OnMerge);
+        world.resource_mut::<CurrentMerge>().0 = None;
+
+        world.entity_mut(removed).despawn_recursive();
+        world.entity_mut(self.junction).despawn_recursive();
+    }
+}
+
+/// Systems in the schedule are called in non-deterministic order
+/// immediately after a corridor is split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
+pub struct OnSplit;
+
+/// Systems in the schedule are called in non-deterministic order
+/// immediately before the merged corridor and the junction are despawned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
+pub struct OnMerge;
+
+/// Describes a corridor split.
+#[derive(Debug, Clone)]
+pub struct Splitting {
+    /// The original corridor, now connecting the alpha endpoint and the junction.
+    pub original:     Entity,
+    /// The new corridor connecting the junction and the far endpoint.
+    pub split_off:    Entity,
+    /// The new junction building.
+    pub junction:     Entity,
+    /// The original beta endpoint, now connected to `split_off`.
+    pub far_endpoint: Entity,
+    /// The position of the junction along the original corridor.
+    pub ratio:        f32,
+    /// Pairs of `(original, split_off)` ducts, starting with the ambient ducts.
+    pub ducts:        Vec<(Entity, Entity)>,
+}
+
+/// Describes a corridor merge.
+#[derive(Debug, Clone)]
+pub struct Merging {
+    /// The corridor that is kept after merging.
+    pub kept:     Entity,
+    /// The corridor to be despawned after merging.
+    pub removed:  Entity,
+    /// The junction building to be despawned after merging.
+    pub junction: Entity,
+    /// Pairs of `(kept, removed)` ducts, starting with the ambient ducts.
+    pub ducts:    Vec<(Entity, Entity)>,
+}
+
+/// The split being performed.
+///
+/// Only valid in the [`OnSplit`] schedule.
+#[derive(Default, Resource)]
+pub struct CurrentSplit(Option<Splitting>);
+
+impl CurrentSplit {
+    /// Gets the split that triggered the [`OnSplit`] schedule.
+    #[must_use]
+    pub fn get(&self) -> &Splitting {
+        self.0.as_ref().expect("CurrentSplit can only be used from OnSplit systems")
+    }
+}
+
+/// The merge being performed.
+///
+/// Only valid in the [`OnMerge`] schedule.
+#[derive(Default, Resource)]
+pub struct CurrentMerge(Option<Merging>);
+
+impl CurrentMerge {
+    /// Gets the merge that triggered the [`OnMerge`] schedule.
+    #[must_use]
+    pub fn get(&self) -> &Merging {
+        self.0.as_ref().expect("CurrentMerge can only be used from OnMerge systems")
+    }
+}
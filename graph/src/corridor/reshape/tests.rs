@@ -0,0 +1,107 @@
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::With;
+use bevy::ecs::world::Command;
+use bevy::math::Vec3;
+use bevy::transform::components::Transform;
+use traffloat_view::appearance::Appearance;
+
+use super::{Merge, Split};
+use crate::corridor::{Binary, DuctList, Endpoints, Marker};
+use crate::{building, test_util};
+
+fn setup() -> (App, Binary<Entity>, Entity) {
+    let mut app = test_util::new_app();
+    let alpha = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let beta = test_util::spawn_building(&mut app, Vec3::new(40., 0., 0.));
+    let endpoints = Binary { alpha, beta };
+    let corridor = test_util::spawn_corridor(&mut app, endpoints);
+    (app, endpoints, corridor)
+}
+
+fn split(app: &mut App, corridor: Entity, ratio: f32) {
+    Split::builder()
+        .corridor(corridor)
+        .ratio(ratio)
+        .appearance(Appearance::null())
+        .build()
+        .apply(app.world_mut());
+}
+
+fn corridors(app: &mut App) -> Vec<(Entity, Binary<Entity>)> {
+    app.world_mut()
+        .query_filtered::<(Entity, &Endpoints), With<Marker>>()
+        .iter(app.world())
+        .map(|(corridor, endpoints)| (corridor, endpoints.endpoints))
+        .collect()
+}
+
+fn building_count(app: &mut App) -> usize {
+    app.world_mut().query_filtered::<(), With<building::Marker>>().iter(app.world()).count()
+}
+
+#[test]
+fn split_and_merge() {
+    let (mut app, endpoints, corridor) = setup();
+    split(&mut app, corridor, 0.25);
+
+    let corridors_after_split = corridors(&mut app);
+    assert_eq!(corridors_after_split.len(), 2);
+    let junction = app.world().get::<Endpoints>(corridor).unwrap().endpoints.beta;
+    assert_eq!(app.world().get::<Endpoints>(corridor).unwrap().endpoints.alpha, endpoints.alpha);
+    assert_vec_eq(
+        app.world().get::<Transform>(junction).unwrap().translation,
+        Vec3::new(10., 0., 0.),
+    );
+
+    let &(split_off, split_off_endpoints) =
+        corridors_after_split.iter().find(|&&(other, _)| other != corridor).unwrap();
+    assert_eq!((split_off_endpoints.alpha, split_off_endpoints.beta), (junction, endpoints.beta));
+    let split_off_ambient = app.world().get::<DuctList>(split_off).unwrap().ambient;
+    assert!(app.world().get::<super::duct::Marker>(split_off_ambient).is_some());
+
+    Merge::builder().junction(junction).build().apply(app.world_mut());
+
+    let corridors_after_merge = corridors(&mut app);
+    assert_eq!(corridors_after_merge.len(), 1);
+    let (kept, kept_endpoints) = corridors_after_merge[0];
+    assert_eq!(kept, corridor);
+    assert_eq!((kept_endpoints.alpha, kept_endpoints.beta), (endpoints.alpha, endpoints.beta));
+    assert_eq!(building_count(&mut app), 2);
+    assert!(app.world().get_entity(split_off).is_none());
+    assert!(app.world().get_entity(split_off_ambient).is_none());
+    assert!(test_util::rejections(&app).is_empty());
+}
+
+#[test]
+fn reject_invalid_ratio() {
+    for ratio in [-0.5, 1.5, f32::NAN] {
+        let (mut app, endpoints, corridor) = setup();
+        split(&mut app, corridor, ratio);
+
+        assert_eq!(test_util::rejections(&app), ["graph.split.ratio"]);
+        assert_eq!(corridors(&mut app).len(), 1);
+        assert_eq!(building_count(&mut app), 2);
+        let current = app.world().get::<Endpoints>(corridor).unwrap().endpoints;
+        assert_eq!((current.alpha, current.beta), (endpoints.alpha, endpoints.beta));
+    }
+}
+
+#[test]
+fn reject_non_collinear_merge() {
+    let mut app = test_util::new_app();
+    let [alpha, junction, beta] = [Vec3::ZERO, Vec3::new(10., 0., 0.), Vec3::new(10., 10., 0.)]
+        .map(|translation| test_util::spawn_building(&mut app, translation));
+    test_util::spawn_corridor(&mut app, Binary { alpha, beta: junction });
+    test_util::spawn_corridor(&mut app, Binary { alpha: junction, beta });
+
+    Merge::builder().junction(junction).build().apply(app.world_mut());
+
+    assert_eq!(test_util::rejections(&app), ["graph.merge.non_collinear"]);
+    assert_eq!(corridors(&mut app).len(), 2);
+    assert_eq!(building_count(&mut app), 3);
+}
+
+fn assert_vec_eq(actual: Vec3, expected: Vec3) {
+    assert!(actual.abs_diff_eq(expected, 1e-4), "{actual} != {expected}");
+}
@@ -0,0 +1,157 @@
+//! Events for structural changes in the graph.
+//!
+//! Creation and removal events are sent from component hooks,
+//! so they are emitted regardless of whether the structure is spawned
+//! by a save loader, a command or a test fixture.
+//!
+//! The endpoints of a corridor must only be changed through [`SetEndpoints`],
+//! which emits [`EndpointsChanged`] for other modules to update dependent components.
+//! Similarly, buildings must only be moved through
+//! [`MoveBuilding`](crate::MoveBuilding) or [`RotateBuilding`](crate::RotateBuilding),
+//! which emit [`BuildingMoved`].
+
+use bevy::app::{self, App};
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Event;
+use bevy::ecs::world::{Command, DeferredWorld, World};
+use bevy::transform::components::Transform;
+use traffloat_base::partition::AppExt;
+
+use crate::corridor::{self, Binary};
+use crate::{bounds, building};
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<BuildingCreated>();
+        app.add_partitioned_event::<BuildingRemoved>();
+        app.add_partitioned_event::<BuildingMoved>();
+        app.add_partitioned_event::<CorridorCreated>();
+        app.add_partitioned_event::<CorridorRemoved>();
+        app.add_partitioned_event::<EndpointsChanged>();
+
+        app.world_mut()
+            .register_component_hooks::<building::Marker>()
+            .on_add(building_created_hook)
+            .on_remove(building_removed_hook);
+        app.world_mut()
+            .register_component_hooks::<corridor::Marker>()
+            .on_add(corridor_created_hook)
+            .on_remove(corridor_removed_hook);
+    }
+}
+
+/// A building has been created.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct BuildingCreated {
+    /// The new building entity.
+    pub building: Entity,
+}
+
+/// A building is getting removed.
+///
+/// The building entity is no longer valid when this event is read.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct BuildingRemoved {
+    /// The removed building entity.
+    pub building: Entity,
+}
+
+/// The transform of a building has changed.
+///
+/// Systems that derive corridor geometry from endpoint positions
+/// should recompute it for the corridors adjacent to the building.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct BuildingMoved {
+    /// The moved building entity.
+    pub building: Entity,
+}
+
+/// A corridor has been created.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CorridorCreated {
+    /// The new corridor entity.
+    pub corridor:  Entity,
+    /// The endpoint buildings of the new corridor.
+    pub endpoints: Binary<Entity>,
+}
+
+/// A corridor is getting removed.
+///
+/// The corridor entity is no longer valid when this event is read,
+/// but the endpoint buildings may still be valid.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CorridorRemoved {
+    /// The removed corridor entity.
+    pub corridor:  Entity,
+    /// The endpoint buildings of the removed corridor.
+    pub endpoints: Binary<Entity>,
+}
+
+/// The endpoint buildings of a corridor have changed.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct EndpointsChanged {
+    /// The corridor entity.
+    pub corridor: Entity,
+    /// The endpoint buildings before the change.
+    pub previous: Binary<Entity>,
+    /// The endpoint buildings after the change.
+    pub current:  Binary<Entity>,
+}
+
+/// Replaces the endpoint buildings of a corridor.
+pub struct SetEndpoints {
+    /// The corridor entity.
+    pub corridor:  Entity,
+    /// The new endpoint buildings.
+    pub endpoints: Binary<Entity>,
+}
+
+impl Command for SetEndpoints {
+    fn apply(self, world: &mut World) {
+        let mut endpoints = world
+            .get_mut::<corridor::Endpoints>(self.corridor)
+            .expect("SetEndpoints.corridor must be a corridor entity");
+        let previous = endpoints.endpoints;
+        endpoints.endpoints = self.endpoints;
+
+        world.send_event(EndpointsChanged {
+            corridor: self.corridor,
+            previous,
+            current: self.endpoints,
+        });
+    }
+}
+
+fn building_created_hook(mut world: DeferredWorld, building: Entity, _: ComponentId) {
+    let &transform = world
+        .get::<Transform>(building)
+        .expect("building::Marker must be inserted together with Transform");
+    world.resource_mut::<bounds::MaxBoundingRadius>().include(&transform);
+    world.send_event(BuildingCreated { building });
+}
+
+fn building_removed_hook(mut world: DeferredWorld, building: Entity, _: ComponentId) {
+    world.send_event(BuildingRemoved { building });
+}
+
+fn corridor_created_hook(mut world: DeferredWorld, corridor: Entity, _: ComponentId) {
+    let endpoints = world
+        .get::<corridor::Endpoints>(corridor)
+        .expect("corridor::Marker must be inserted together with corridor::Endpoints")
+        .endpoints;
+    world.send_event(CorridorCreated { corridor, endpoints });
+}
+
+fn corridor_removed_hook(mut world: DeferredWorld, corridor: Entity, _: ComponentId) {
+    let endpoints = world
+        .get::<corridor::Endpoints>(corridor)
+        .expect("corridor::Marker must be removed together with corridor::Endpoints")
+        .endpoints;
+    world.send_event(CorridorRemoved { corridor, endpoints });
+}
@@ -0,0 +1,65 @@
+use bevy::ecs::world::Command;
+use bevy::hierarchy::DespawnRecursiveExt;
+use bevy::math::Vec3;
+
+use super::{
+    BuildingCreated, BuildingRemoved, CorridorCreated, CorridorRemoved, EndpointsChanged,
+    SetEndpoints,
+};
+use crate::corridor::{self, Binary};
+use crate::test_util;
+
+#[test]
+fn building_lifecycle() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+
+    let created = test_util::events::<BuildingCreated>(&app);
+    assert_eq!(created.iter().map(|event| event.building).collect::<Vec<_>>(), [building]);
+    assert!(test_util::events::<BuildingRemoved>(&app).is_empty());
+
+    app.world_mut().entity_mut(building).despawn_recursive();
+
+    let removed = test_util::events::<BuildingRemoved>(&app);
+    assert_eq!(removed.iter().map(|event| event.building).collect::<Vec<_>>(), [building]);
+}
+
+#[test]
+fn corridor_lifecycle() {
+    let mut app = test_util::new_app();
+    let alpha = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let beta = test_util::spawn_building(&mut app, Vec3::new(10., 0., 0.));
+    let corridor = test_util::spawn_corridor(&mut app, Binary { alpha, beta });
+
+    let created = test_util::events::<CorridorCreated>(&app);
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].corridor, corridor);
+    assert_eq!((created[0].endpoints.alpha, created[0].endpoints.beta), (alpha, beta));
+
+    app.world_mut().entity_mut(corridor).despawn_recursive();
+
+    let removed = test_util::events::<CorridorRemoved>(&app);
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].corridor, corridor);
+    assert_eq!((removed[0].endpoints.alpha, removed[0].endpoints.beta), (alpha, beta));
+    assert!(test_util::events::<BuildingRemoved>(&app).is_empty());
+}
+
+#[test]
+fn set_endpoints() {
+    let mut app = test_util::new_app();
+    let [alpha, beta, gamma] =
+        [0., 10., 20.].map(|x| test_util::spawn_building(&mut app, Vec3::new(x, 0., 0.)));
+    let corridor = test_util::spawn_corridor(&mut app, Binary { alpha, beta });
+
+    SetEndpoints { corridor, endpoints: Binary { alpha, beta: gamma } }.apply(app.world_mut());
+
+    let endpoints = app.world().get::<corridor::Endpoints>(corridor).unwrap().endpoints;
+    assert_eq!((endpoints.alpha, endpoints.beta), (alpha, gamma));
+
+    let changed = test_util::events::<EndpointsChanged>(&app);
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].corridor, corridor);
+    assert_eq!((changed[0].previous.alpha, changed[0].previous.beta), (alpha, beta));
+    assert_eq!((changed[0].current.alpha, changed[0].current.beta), (alpha, gamma));
+}
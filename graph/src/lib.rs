@@ -3,12 +3,34 @@
 
 use bevy::app::{self, App};
 
+pub mod bounds;
 pub mod building;
+pub mod bulk;
+pub mod control_group;
 pub mod corridor;
+pub mod event;
+pub mod ownership;
+pub mod tag;
+
+mod commands;
+pub use commands::*;
+
+#[cfg(test)]
+mod test_util;
 
 /// Maintains graph components.
 pub struct Plugin;
 
 impl app::Plugin for Plugin {
-    fn build(&self, app: &mut App) { app.add_plugins((building::Plugin, corridor::Plugin)); }
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            bounds::Plugin,
+            building::Plugin,
+            control_group::Plugin,
+            corridor::Plugin,
+            event::Plugin,
+            ownership::Plugin,
+            tag::Plugin,
+        ));
+    }
 }
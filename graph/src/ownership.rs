@@ -0,0 +1,243 @@
+//! Ownership of graph structures by player factions.
+//!
+//! A faction is an entity with [`faction::Bundle`].
+//! Buildings and corridors may have an [`Owner`] component referencing a faction;
+//! facilities, ducts and anything else parented under them inherit the owner,
+//! which can be resolved with [`owner_of`].
+//! Unowned structures are neutral and can be commanded by any faction.
+//! Owners can share command authority with other factions
+//! through [access lists](access::Access).
+//!
+//! Viewers with a [`Member`] component automatically see all buildings
+//! owned by their own faction and its allies, regardless of distance.
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::query::With;
+use bevy::ecs::system::Query;
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{error, save, Ref};
+use traffloat_view::{viewable, viewer};
+
+use crate::{bounds, building, corridor};
+
+pub mod access;
+pub mod faction;
+
+#[cfg(test)]
+mod tests;
+
+/// Maintains ownership of structures.
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        save::add_def::<faction::Save>(app);
+        save::add_def::<faction::AllianceSave>(app);
+        save::add_def::<Save>(app);
+        save::add_def::<access::Save>(app);
+        viewable::add_relevance_provider(app, shared_vision_system);
+    }
+}
+
+/// The faction owning a building or corridor.
+#[derive(Component)]
+pub struct Owner {
+    /// The owner faction entity.
+    pub faction: Entity,
+}
+
+/// The faction that a viewer plays as.
+#[derive(Component)]
+pub struct Member {
+    /// The faction entity.
+    pub faction: Entity,
+}
+
+/// Resolves the owner faction of an entity,
+/// searching up the hierarchy for the nearest [`Owner`].
+#[must_use]
+pub fn owner_of(world: &World, mut entity: Entity) -> Option<Entity> {
+    loop {
+        if let Some(owner) = world.get::<Owner>(entity) {
+            return Some(owner.faction);
+        }
+        entity = world.get::<hierarchy::Parent>(entity)?.get();
+    }
+}
+
+/// Checks whether `faction` is allowed to issue commands on `entity`.
+///
+/// An owned structure can be commanded by the owner faction
+/// and the factions granted by its [access list](access::access_of).
+/// Alliances grant shared vision but not command authority.
+#[must_use]
+pub fn is_authorized(world: &World, faction: Entity, entity: Entity) -> bool {
+    match owner_of(world, entity) {
+        None => true,
+        Some(owner) if owner == faction => true,
+        Some(_) => access::access_of(world, entity).is_some_and(|access| access.grants(faction)),
+    }
+}
+
+/// Transfers a building or corridor to another faction,
+/// or makes it neutral if `faction` is `None`.
+///
+/// The transfer is [rejected](error::reject) if the structure or the faction does not exist,
+/// or if the new owner of a building is at its [build limit](bounds::BuildLimits).
+/// Access lists of the structure and its children are cleared
+/// unless the owner is unchanged.
+pub struct TransferOwnership {
+    /// The building or corridor entity.
+    pub entity:  Entity,
+    /// The new owner faction.
+    pub faction: Option<Entity>,
+}
+
+impl Command for TransferOwnership {
+    fn apply(self, world: &mut World) {
+        let Some((is_building, previous)) = world.get_entity(self.entity).and_then(|entity| {
+            let is_building = entity.contains::<building::Marker>();
+            (is_building || entity.contains::<corridor::Marker>())
+                .then(|| (is_building, entity.get::<Owner>().map(|owner| owner.faction)))
+        }) else {
+            let message = format!("{:?} is not a building or corridor", self.entity);
+            let err = error::Error::not_found("graph.structure.not_found", message)
+                .with_entity(self.entity);
+            error::reject(world, err);
+            return;
+        };
+
+        if let Some(faction) = self.faction {
+            if let Err(err) = Ref::<faction::Marker>::new_unchecked(faction).resolve(world) {
+                error::reject(world, err);
+                return;
+            }
+
+            if is_building && previous != Some(faction) {
+                if let Err(err) = bounds::validate_ownership(world, faction) {
+                    error::reject(world, err.into_error(self.entity));
+                    return;
+                }
+            }
+        }
+
+        if previous != self.faction {
+            access::clear(world, self.entity);
+        }
+
+        let mut entity = world.entity_mut(self.entity);
+        match self.faction {
+            Some(faction) => {
+                entity.insert(Owner { faction });
+            }
+            None => {
+                entity.remove::<Owner>();
+            }
+        }
+    }
+}
+
+fn shared_vision_system(
+    mut viewer_query: Query<(&Member, &mut viewer::RelevantViewables)>,
+    faction_query: Query<&faction::Allies, With<faction::Marker>>,
+    building_query: Query<(Entity, &Owner), With<building::Marker>>,
+) {
+    for (member, mut relevant) in &mut viewer_query {
+        let mut visible_factions = EntityHashSet::default();
+        visible_factions.insert(member.faction);
+        if let Ok(allies) = faction_query.get(member.faction) {
+            visible_factions.extend(allies.factions.iter().copied());
+        }
+
+        relevant.set.extend(
+            building_query
+                .iter()
+                .filter(|(_, owner)| visible_factions.contains(&owner.faction))
+                .map(|(building, _)| building),
+        );
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The owned structure.
+    pub subject: SaveSubject,
+    /// The owner faction.
+    pub faction: save::Id<faction::Save>,
+}
+
+/// Owned structure, used in saves.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum SaveSubject {
+    /// The owned structure is a building.
+    Building {
+        /// Save ID of the building.
+        id: save::Id<building::Save>,
+    },
+    /// The owned structure is a corridor.
+    Corridor {
+        /// Save ID of the corridor.
+        id: save::Id<corridor::Save>,
+    },
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.Ownership";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (faction_dep, building_dep, corridor_dep): (
+                save::StoreDepend<faction::Save>,
+                save::StoreDepend<building::Save>,
+                save::StoreDepend<corridor::Save>,
+            ),
+            query: Query<(Entity, &Owner, Option<&building::Marker>, Option<&corridor::Marker>)>,
+        ) {
+            writer.write_all(query.iter().filter_map(|(entity, owner, building, corridor)| {
+                let subject = match (building, corridor) {
+                    (Some(_), None) => SaveSubject::Building { id: building_dep.must_get(entity) },
+                    (None, Some(_)) => SaveSubject::Corridor { id: corridor_dep.must_get(entity) },
+                    // only buildings and corridors are owned directly;
+                    // owners inserted elsewhere are not part of the graph
+                    _ => return None,
+                };
+                Some((entity, Save { subject, faction: faction_dep.must_get(owner.faction) }))
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (faction_dep, building_dep, corridor_dep): &(
+                save::LoadDepend<faction::Save>,
+                save::LoadDepend<building::Save>,
+                save::LoadDepend<corridor::Save>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let faction = faction_dep.get(def.faction)?;
+            let subject = match def.subject {
+                SaveSubject::Building { id } => building_dep.get(id)?,
+                SaveSubject::Corridor { id } => corridor_dep.get(id)?,
+            };
+            world.entity_mut(subject).insert(Owner { faction });
+            Ok(subject)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,262 @@
+//! Access control lists sharing command authority over owned structures.
+//!
+//! An [`Access`] component on a building, corridor, facility or duct
+//! authorizes factions other than the [owner](super::Owner) to command it.
+//! Like ownership, access is inherited down the hierarchy,
+//! but the nearest [`Access`] wins,
+//! so a facility with its own list can be kept private in a shared building
+//! or shared in a private building.
+//! Lists are edited by the owner through [`SetAccess`]
+//! and are cleared when the ownership of the structure is transferred.
+
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::system::Query;
+use bevy::ecs::world::{Command, EntityRef, World};
+use bevy::hierarchy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{error, save};
+
+use super::{faction, owner_of};
+use crate::building::{self, facility};
+use crate::corridor::{self, duct};
+
+/// Factions authorized to command a structure in addition to its owner.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Access {
+    /// Whether every faction is authorized.
+    pub public: bool,
+    /// Authorized factions.
+    pub shared: EntityHashSet,
+}
+
+impl Access {
+    /// Whether the list authorizes `faction`.
+    #[must_use]
+    pub fn grants(&self, faction: Entity) -> bool { self.public || self.shared.contains(&faction) }
+}
+
+/// Resolves the access list of an entity,
+/// searching up the hierarchy for the nearest [`Access`].
+#[must_use]
+pub fn access_of(world: &World, mut entity: Entity) -> Option<&Access> {
+    loop {
+        if let Some(access) = world.get::<Access>(entity) {
+            return Some(access);
+        }
+        entity = world.get::<hierarchy::Parent>(entity)?.get();
+    }
+}
+
+/// Removes the access lists of a structure and everything parented under it.
+pub(super) fn clear(world: &mut World, entity: Entity) {
+    let children: Vec<_> =
+        world.get::<hierarchy::Children>(entity).into_iter().flatten().copied().collect();
+    world.entity_mut(entity).remove::<Access>();
+    for child in children {
+        clear(world, child);
+    }
+}
+
+fn is_structure(entity: EntityRef) -> bool {
+    entity.contains::<building::Marker>()
+        || entity.contains::<corridor::Marker>()
+        || entity.contains::<facility::Marker>()
+        || entity.contains::<duct::Marker>()
+}
+
+/// A modification to the access list of a structure.
+#[derive(Debug, Clone, Copy)]
+pub enum Change {
+    /// Authorizes or deauthorizes a faction.
+    Share {
+        /// The faction to authorize or deauthorize.
+        faction: Entity,
+        /// Whether the faction should be authorized.
+        shared:  bool,
+    },
+    /// Authorizes or deauthorizes all factions not explicitly shared with.
+    Public(bool),
+    /// Removes the list of the structure so that it inherits the list of its parent.
+    Inherit,
+}
+
+/// Edits the access list of a building, corridor, facility or duct.
+///
+/// A structure without its own list starts from the list it inherits,
+/// so sharing a facility does not revoke access already granted on its building.
+/// The command is [rejected](error::reject) if the issuer is not the owner of the structure.
+pub struct SetAccess {
+    /// The structure entity.
+    pub entity: Entity,
+    /// The faction issuing the command.
+    /// `None` skips the authorization check.
+    pub issuer: Option<Entity>,
+    /// The modification to apply.
+    pub change: Change,
+}
+
+impl Command for SetAccess {
+    fn apply(self, world: &mut World) {
+        if !world.get_entity(self.entity).is_some_and(is_structure) {
+            let message =
+                format!("{:?} is not a building, corridor, facility or duct", self.entity);
+            let err = error::Error::not_found("graph.structure.not_found", message)
+                .with_entity(self.entity);
+            error::reject(world, err);
+            return;
+        }
+
+        if let Some(issuer) = self.issuer {
+            if owner_of(world, self.entity) != Some(issuer) {
+                let message = format!("{issuer:?} does not own {:?}", self.entity);
+                let err = error::Error::permission("graph.access.not_owner", message)
+                    .with_entity(issuer)
+                    .with_entity(self.entity);
+                error::reject(world, err);
+                return;
+            }
+        }
+
+        if let Change::Inherit = self.change {
+            world.entity_mut(self.entity).remove::<Access>();
+            return;
+        }
+
+        let mut access = access_of(world, self.entity).cloned().unwrap_or_default();
+        match self.change {
+            Change::Share { faction, shared: true } => {
+                access.shared.insert(faction);
+            }
+            Change::Share { faction, shared: false } => {
+                access.shared.remove(&faction);
+            }
+            Change::Public(public) => access.public = public,
+            Change::Inherit => unreachable!("handled above"),
+        }
+        world.entity_mut(self.entity).insert(access);
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The structure with the access list.
+    pub subject: SaveSubject,
+    /// Whether every faction is authorized.
+    #[serde(default)]
+    pub public:  bool,
+    /// Authorized factions.
+    #[serde(default)]
+    pub shared:  Vec<save::Id<faction::Save>>,
+}
+
+/// Structure with an access list, used in saves.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum SaveSubject {
+    /// The structure is a building.
+    Building {
+        /// Save ID of the building.
+        id: save::Id<building::Save>,
+    },
+    /// The structure is a corridor.
+    Corridor {
+        /// Save ID of the corridor.
+        id: save::Id<corridor::Save>,
+    },
+    /// The structure is a facility.
+    Facility {
+        /// Save ID of the facility.
+        id: save::Id<facility::Save>,
+    },
+    /// The structure is a duct.
+    Duct {
+        /// Save ID of the duct.
+        id: save::Id<duct::Save>,
+    },
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.Access";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (faction_dep, building_dep, corridor_dep, facility_dep, duct_dep): (
+                save::StoreDepend<faction::Save>,
+                save::StoreDepend<building::Save>,
+                save::StoreDepend<corridor::Save>,
+                save::StoreDepend<facility::Save>,
+                save::StoreDepend<duct::Save>,
+            ),
+            query: Query<(
+                Entity,
+                &Access,
+                Option<&building::Marker>,
+                Option<&corridor::Marker>,
+                Option<&facility::Marker>,
+                Option<&duct::Marker>,
+            )>,
+        ) {
+            writer.write_all(query.iter().map(
+                |(entity, access, building, corridor, facility, duct)| {
+                    let subject = match (building, corridor, facility, duct) {
+                        (Some(_), None, None, None) => {
+                            SaveSubject::Building { id: building_dep.must_get(entity) }
+                        }
+                        (None, Some(_), None, None) => {
+                            SaveSubject::Corridor { id: corridor_dep.must_get(entity) }
+                        }
+                        (None, None, Some(_), None) => {
+                            SaveSubject::Facility { id: facility_dep.must_get(entity) }
+                        }
+                        (None, None, None, Some(_)) => {
+                            SaveSubject::Duct { id: duct_dep.must_get(entity) }
+                        }
+                        _ => panic!("Access must be on a building, corridor, facility or duct"),
+                    };
+                    let shared = access
+                        .shared
+                        .iter()
+                        .map(|&faction| faction_dep.must_get(faction))
+                        .collect();
+                    (entity, Save { subject, public: access.public, shared })
+                },
+            ));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (faction_dep, building_dep, corridor_dep, facility_dep, duct_dep): &(
+                save::LoadDepend<faction::Save>,
+                save::LoadDepend<building::Save>,
+                save::LoadDepend<corridor::Save>,
+                save::LoadDepend<facility::Save>,
+                save::LoadDepend<duct::Save>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let subject = match def.subject {
+                SaveSubject::Building { id } => building_dep.get(id)?,
+                SaveSubject::Corridor { id } => corridor_dep.get(id)?,
+                SaveSubject::Facility { id } => facility_dep.get(id)?,
+                SaveSubject::Duct { id } => duct_dep.get(id)?,
+            };
+            let shared =
+                def.shared.into_iter().map(|id| faction_dep.get(id)).collect::<Result<_, _>>()?;
+            world.entity_mut(subject).insert(Access { public: def.public, shared });
+            Ok(subject)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,176 @@
+//! A faction is a group of players sharing ownership of structures.
+
+use bevy::ecs::bundle;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::query::With;
+use bevy::ecs::system::Query;
+use bevy::ecs::world::{Command, World};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{debug, error, save, Ref};
+use traffloat_view::DisplayText;
+use typed_builder::TypedBuilder;
+
+/// Components for a faction.
+#[derive(bundle::Bundle, TypedBuilder)]
+pub struct Bundle {
+    label:   Label,
+    #[builder(default)]
+    allies:  Allies,
+    #[builder(default, setter(skip))]
+    _marker: Marker,
+    #[builder(default = debug::Bundle::new("Faction"))]
+    _debug:  debug::Bundle,
+}
+
+/// Marks an entity as a faction.
+#[derive(Component, Default)]
+pub struct Marker;
+
+/// The display name of a faction.
+#[derive(Component)]
+pub struct Label {
+    /// Display name value.
+    pub label: DisplayText,
+}
+
+/// Factions allied with this faction.
+///
+/// Alliances are symmetric and should only be changed through [`SetAlliance`].
+#[derive(Component, Default)]
+pub struct Allies {
+    /// Allied faction entities.
+    pub factions: EntityHashSet,
+}
+
+/// Forms or breaks an alliance between two factions.
+///
+/// The change is [rejected](error::reject)
+/// if either entity is not a faction or both are the same faction.
+pub struct SetAlliance {
+    /// The two factions.
+    pub factions: [Entity; 2],
+    /// Whether the factions should be allied.
+    pub allied:   bool,
+}
+
+impl Command for SetAlliance {
+    fn apply(self, world: &mut World) {
+        let [first, second] = self.factions;
+        if first == second {
+            let message = format!("{first:?} cannot ally with itself");
+            let err = error::Error::validation("graph.alliance.self", message).with_entity(first);
+            error::reject(world, err);
+            return;
+        }
+        for faction in self.factions {
+            if let Err(err) = Ref::<Allies>::new_unchecked(faction).resolve(world) {
+                error::reject(world, err);
+                return;
+            }
+        }
+
+        for (this, other) in [(first, second), (second, first)] {
+            let mut allies = world.get_mut::<Allies>(this).expect("checked above");
+            if self.allied {
+                allies.factions.insert(other);
+            } else {
+                allies.factions.remove(&other);
+            }
+        }
+    }
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// Display name of the faction.
+    pub label: DisplayText,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.Faction";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (): (),
+            query: Query<(Entity, &Label), With<Marker>>,
+        ) {
+            writer.write_all(
+                query.iter().map(|(entity, label)| (entity, Save { label: label.label.clone() })),
+            );
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<Entity> {
+            let faction = world.spawn(Bundle::builder().label(Label { label: def.label }).build());
+            Ok(faction.id())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for an alliance between two factions.
+///
+/// Stored separately from [`Save`] since factions cannot depend on each other.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct AllianceSave {
+    /// The allied factions.
+    pub factions: [save::Id<Save>; 2],
+}
+
+impl save::Def for AllianceSave {
+    const TYPE: &'static str = "traffloat.save.Alliance";
+
+    type Runtime = (Entity, Entity);
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<AllianceSave>,
+            (faction_dep,): (save::StoreDepend<Save>,),
+            query: Query<(Entity, &Allies), With<Marker>>,
+        ) {
+            writer.write_all(query.iter().flat_map(|(entity, allies)| {
+                let faction_dep = &faction_dep;
+                // each alliance is stored once from the smaller entity
+                allies.factions.iter().filter(move |&&ally| entity < ally).map(move |&ally| {
+                    (
+                        (entity, ally),
+                        AllianceSave {
+                            factions: [faction_dep.must_get(entity), faction_dep.must_get(ally)],
+                        },
+                    )
+                })
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: AllianceSave,
+            (faction_dep,): &(save::LoadDepend<Save>,),
+        ) -> anyhow::Result<(Entity, Entity)> {
+            let [first, second] = def.factions;
+            let factions = [faction_dep.get(first)?, faction_dep.get(second)?];
+            anyhow::ensure!(factions[0] != factions[1], "a faction cannot ally with itself");
+
+            SetAlliance { factions, allied: true }.apply(world);
+            Ok((factions[0], factions[1]))
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::world::Command;
+use bevy::math::Vec3;
+use traffloat_base::save;
+use traffloat_view::DisplayText;
+
+use super::access::{Change, SetAccess};
+use super::faction::{self, SetAlliance};
+use super::{is_authorized, owner_of, Owner, TransferOwnership};
+use crate::corridor::{Binary, DuctList};
+use crate::{bounds, test_util};
+
+fn spawn_faction(app: &mut App) -> Entity {
+    app.world_mut()
+        .spawn(
+            faction::Bundle::builder()
+                .label(faction::Label { label: DisplayText::default() })
+                .build(),
+        )
+        .id()
+}
+
+fn transfer(app: &mut App, entity: Entity, faction: Option<Entity>) {
+    TransferOwnership { entity, faction }.apply(app.world_mut());
+}
+
+fn set_access(app: &mut App, entity: Entity, issuer: Entity, change: Change) {
+    SetAccess { entity, issuer: Some(issuer), change }.apply(app.world_mut());
+}
+
+#[test]
+fn neutral_structures_are_shared() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let faction = spawn_faction(&mut app);
+
+    assert_eq!(owner_of(app.world(), building), None);
+    assert!(is_authorized(app.world(), faction, building));
+}
+
+#[test]
+fn only_owner_is_authorized() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let [owner, other] = [(); 2].map(|()| spawn_faction(&mut app));
+
+    transfer(&mut app, building, Some(owner));
+
+    assert!(test_util::rejections(&app).is_empty());
+    assert_eq!(owner_of(app.world(), building), Some(owner));
+    assert!(is_authorized(app.world(), owner, building));
+    assert!(!is_authorized(app.world(), other, building));
+
+    transfer(&mut app, building, None);
+    assert!(is_authorized(app.world(), other, building));
+}
+
+#[test]
+fn allies_are_not_authorized() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let [owner, ally] = [(); 2].map(|()| spawn_faction(&mut app));
+    transfer(&mut app, building, Some(owner));
+
+    SetAlliance { factions: [owner, ally], allied: true }.apply(app.world_mut());
+
+    assert!(!is_authorized(app.world(), ally, building));
+}
+
+#[test]
+fn reject_invalid_alliance() {
+    let mut app = test_util::new_app();
+    let faction = spawn_faction(&mut app);
+    let non_faction = test_util::spawn_building(&mut app, Vec3::ZERO);
+
+    SetAlliance { factions: [faction, faction], allied: true }.apply(app.world_mut());
+    SetAlliance { factions: [faction, non_faction], allied: true }.apply(app.world_mut());
+
+    assert_eq!(test_util::rejections(&app), ["graph.alliance.self", "base.ref.not_found"]);
+    assert!(app.world().get::<faction::Allies>(faction).unwrap().factions.is_empty());
+}
+
+#[test]
+fn access_list_authorizes_factions() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let [owner, shared, other] = [(); 3].map(|()| spawn_faction(&mut app));
+    transfer(&mut app, building, Some(owner));
+
+    set_access(&mut app, building, owner, Change::Share { faction: shared, shared: true });
+    assert!(is_authorized(app.world(), shared, building));
+    assert!(!is_authorized(app.world(), other, building));
+
+    set_access(&mut app, building, owner, Change::Public(true));
+    assert!(is_authorized(app.world(), other, building));
+
+    // transferring the building revokes the access list
+    transfer(&mut app, building, Some(shared));
+    assert!(!is_authorized(app.world(), owner, building));
+    assert!(!is_authorized(app.world(), other, building));
+}
+
+#[test]
+fn only_owner_edits_access() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let [owner, other] = [(); 2].map(|()| spawn_faction(&mut app));
+    transfer(&mut app, building, Some(owner));
+
+    set_access(&mut app, building, other, Change::Public(true));
+
+    assert_eq!(test_util::rejections(&app), ["graph.access.not_owner"]);
+    assert!(!is_authorized(app.world(), other, building));
+}
+
+#[test]
+fn children_inherit_owner_and_access() {
+    let mut app = test_util::new_app();
+    let [alpha, beta] =
+        [0., 10.].map(|x| test_util::spawn_building(&mut app, Vec3::new(x, 0., 0.)));
+    let corridor = test_util::spawn_corridor(&mut app, Binary { alpha, beta });
+    let duct = app.world().get::<DuctList>(corridor).unwrap().ambient;
+    let [owner, other] = [(); 2].map(|()| spawn_faction(&mut app));
+    transfer(&mut app, corridor, Some(owner));
+
+    assert_eq!(owner_of(app.world(), duct), Some(owner));
+    assert!(!is_authorized(app.world(), other, duct));
+
+    // the nearest access list wins
+    set_access(&mut app, corridor, owner, Change::Public(true));
+    set_access(&mut app, duct, owner, Change::Public(false));
+    assert!(is_authorized(app.world(), other, corridor));
+    assert!(!is_authorized(app.world(), other, duct));
+}
+
+#[test]
+fn reject_missing_structure_or_faction() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let faction = spawn_faction(&mut app);
+    let despawned = app.world_mut().spawn_empty().id();
+    app.world_mut().despawn(despawned);
+
+    transfer(&mut app, despawned, Some(faction));
+    transfer(&mut app, faction, Some(faction));
+    transfer(&mut app, building, Some(despawned));
+
+    assert_eq!(
+        test_util::rejections(&app),
+        ["graph.structure.not_found", "graph.structure.not_found", "base.ref.not_found"]
+    );
+    assert!(app.world().get::<Owner>(building).is_none());
+}
+
+#[test]
+fn reject_faction_at_build_limit() {
+    let mut app = test_util::new_app();
+    let [first, second] =
+        [0., 10.].map(|x| test_util::spawn_building(&mut app, Vec3::new(x, 0., 0.)));
+    let faction = spawn_faction(&mut app);
+    app.insert_resource(bounds::BuildLimits { max_buildings_per_faction: Some(1) });
+
+    transfer(&mut app, first, Some(faction));
+    transfer(&mut app, first, Some(faction));
+    transfer(&mut app, second, Some(faction));
+
+    assert_eq!(test_util::rejections(&app), ["graph.placement.faction_limit"]);
+    assert_eq!(owner_of(app.world(), second), None);
+}
+
+#[test]
+fn store_skips_foreign_owners() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+    let faction = spawn_faction(&mut app);
+    transfer(&mut app, building, Some(faction));
+    app.world_mut().spawn(Owner { faction });
+
+    let data = Arc::new(Mutex::new(None));
+    save::StoreCommand {
+        format:      save::Format::Json,
+        on_complete: Box::new({
+            let data = Arc::clone(&data);
+            move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+        }),
+    }
+    .apply(app.world_mut());
+    let data = data.lock().unwrap().take().expect("StoreCommand completes synchronously");
+
+    let mut app = test_util::new_app();
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(app.world_mut());
+
+    let owners: Vec<_> =
+        app.world_mut().query::<&Owner>().iter(app.world()).map(|owner| owner.faction).collect();
+    assert_eq!(owners.len(), 1);
+}
@@ -0,0 +1,413 @@
+//! Free-form tags on buildings and corridors, and filters over them.
+//!
+//! Tags are assigned with [`AddTag`] and [`RemoveTag`],
+//! which keep the [`Tags`] component and the reverse [`Index`] in sync.
+//! A [`Filter`] combines tag, structure kind and metric predicates,
+//! and is [evaluated](Filter::evaluate) starting from the smallest indexed tag set.
+//! Filters can be stored by name in [`SavedFilters`]
+//! for reuse in overlays and bulk operations,
+//! and are saved with the metric types of their predicates identified by [`metrics::Sid`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Context;
+use bevy::app::{self, App};
+use bevy::ecs::component::{Component, ComponentHooks, StorageType};
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::query::{Or, With};
+use bevy::ecs::system::{Query, Res, Resource};
+use bevy::ecs::world::{Command, World};
+use bevy::utils::HashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{error, save};
+use traffloat_view::metrics;
+
+use crate::ownership::SaveSubject;
+use crate::{building, corridor};
+
+#[cfg(test)]
+mod tests;
+
+/// Maintains tags and saved filters.
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Index>();
+        app.init_resource::<SavedFilters>();
+        save::add_def::<Save>(app);
+        save::add_def::<SaveFilter>(app);
+    }
+}
+
+/// A free-form label assigned by players.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(transparent)]
+pub struct Tag(pub String);
+
+/// The tags of a building or corridor.
+///
+/// This component is maintained by [`AddTag`] and [`RemoveTag`]
+/// and should not be modified directly.
+pub struct Tags {
+    /// The assigned tags.
+    pub tags: BTreeSet<Tag>,
+}
+
+impl Component for Tags {
+    const STORAGE_TYPE: StorageType = StorageType::Table;
+
+    fn register_component_hooks(hooks: &mut ComponentHooks) {
+        hooks.on_remove(|mut world, entity, _comp_id| {
+            let tags = world.get::<Tags>(entity).expect("hook is called on removal").tags.clone();
+            let mut index = world.resource_mut::<Index>();
+            for tag in &tags {
+                index.remove(tag, entity);
+            }
+        });
+    }
+}
+
+/// Reverse index from tags to tagged entities.
+#[derive(Default, Resource)]
+pub struct Index {
+    by_tag: HashMap<Tag, EntityHashSet>,
+}
+
+impl Index {
+    /// Returns the entities with the tag.
+    #[must_use]
+    pub fn get(&self, tag: &Tag) -> Option<&EntityHashSet> { self.by_tag.get(tag) }
+
+    /// Iterates over all tags in use.
+    pub fn tags(&self) -> impl Iterator<Item = &Tag> { self.by_tag.keys() }
+
+    fn remove(&mut self, tag: &Tag, entity: Entity) {
+        if let Some(set) = self.by_tag.get_mut(tag) {
+            set.remove(&entity);
+            if set.is_empty() {
+                self.by_tag.remove(tag);
+            }
+        }
+    }
+}
+
+/// Assigns a tag to a building or corridor.
+///
+/// The change is [rejected](error::reject) if the entity is not a building or corridor.
+pub struct AddTag {
+    /// The building or corridor entity.
+    pub entity: Entity,
+    /// The tag to assign.
+    pub tag:    Tag,
+}
+
+impl Command for AddTag {
+    fn apply(self, world: &mut World) {
+        let Some(mut entity) = world.get_entity_mut(self.entity).filter(|entity| {
+            entity.contains::<building::Marker>() || entity.contains::<corridor::Marker>()
+        }) else {
+            let message = format!("{:?} is not a building or corridor", self.entity);
+            let err = error::Error::not_found("graph.structure.not_found", message)
+                .with_entity(self.entity);
+            error::reject(world, err);
+            return;
+        };
+
+        match entity.get_mut::<Tags>() {
+            Some(mut tags) => {
+                tags.tags.insert(self.tag.clone());
+            }
+            None => {
+                entity.insert(Tags { tags: BTreeSet::from([self.tag.clone()]) });
+            }
+        }
+
+        world.resource_mut::<Index>().by_tag.entry(self.tag).or_default().insert(self.entity);
+    }
+}
+
+/// Removes a tag from a building or corridor.
+pub struct RemoveTag {
+    /// The building or corridor entity.
+    pub entity: Entity,
+    /// The tag to remove.
+    pub tag:    Tag,
+}
+
+impl Command for RemoveTag {
+    fn apply(self, world: &mut World) {
+        let Some(mut tags) = world.get_mut::<Tags>(self.entity) else { return };
+        tags.tags.remove(&self.tag);
+        let is_empty = tags.tags.is_empty();
+
+        world.resource_mut::<Index>().remove(&self.tag, self.entity);
+        if is_empty {
+            world.entity_mut(self.entity).remove::<Tags>();
+        }
+    }
+}
+
+/// The kind of structure matched by a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Kind {
+    /// Buildings only.
+    Building,
+    /// Corridors only.
+    Corridor,
+}
+
+/// A comparison between a metric value and a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Comparison {
+    /// The metric value is strictly less than the threshold.
+    Less,
+    /// The metric value is strictly greater than the threshold.
+    Greater,
+}
+
+/// A predicate on the current value of a metric.
+#[derive(Debug, Clone)]
+pub struct MetricPredicate {
+    /// The metric type.
+    pub ty:         metrics::Type,
+    /// The comparison to apply.
+    pub comparison: Comparison,
+    /// The threshold to compare against.
+    pub threshold:  f32,
+}
+
+impl MetricPredicate {
+    /// Tests the predicate on an entity.
+    ///
+    /// Entities without a value for the metric never match.
+    #[must_use]
+    pub fn test(&self, world: &World, entity: Entity) -> bool {
+        metrics::value(world, self.ty, entity).is_some_and(|value| match self.comparison {
+            Comparison::Less => value < self.threshold,
+            Comparison::Greater => value > self.threshold,
+        })
+    }
+}
+
+/// Selects buildings and corridors matching all of its predicates.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Tags that a matching entity must all have.
+    pub tags:    Vec<Tag>,
+    /// The kind of structure to match, or `None` to match both.
+    pub kind:    Option<Kind>,
+    /// Metric predicates that a matching entity must all satisfy.
+    pub metrics: Vec<MetricPredicate>,
+}
+
+impl Filter {
+    /// Returns all entities matching the filter.
+    ///
+    /// If the filter has tags, only the entities with the rarest tag are tested.
+    /// Otherwise, every building and corridor is tested.
+    ///
+    /// Metric predicates only see metrics that are currently fed,
+    /// i.e. metric types with at least one subscriber.
+    #[must_use]
+    pub fn evaluate(&self, world: &mut World) -> Vec<Entity> {
+        let candidates: Vec<Entity> = if self.tags.is_empty() {
+            world
+                .query_filtered::<Entity, Or<(With<building::Marker>, With<corridor::Marker>)>>()
+                .iter(world)
+                .collect()
+        } else {
+            let index = world.resource::<Index>();
+            let sets: Option<Vec<_>> = self.tags.iter().map(|tag| index.get(tag)).collect();
+            let Some(sets) = sets else { return Vec::new() };
+            let smallest = sets.iter().min_by_key(|set| set.len()).expect("tags is non-empty");
+            smallest
+                .iter()
+                .copied()
+                .filter(|entity| sets.iter().all(|set| set.contains(entity)))
+                .collect()
+        };
+
+        candidates.into_iter().filter(|&entity| self.test_untagged(world, entity)).collect()
+    }
+
+    /// Tests whether an entity matches the filter.
+    #[must_use]
+    pub fn test(&self, world: &World, entity: Entity) -> bool {
+        let tags = world.get::<Tags>(entity);
+        self.tags.iter().all(|tag| tags.is_some_and(|tags| tags.tags.contains(tag)))
+            && self.test_untagged(world, entity)
+    }
+
+    /// Tests the predicates of the filter other than tags.
+    fn test_untagged(&self, world: &World, entity: Entity) -> bool {
+        let kind_matches = match self.kind {
+            None => true,
+            Some(Kind::Building) => world.get::<building::Marker>(entity).is_some(),
+            Some(Kind::Corridor) => world.get::<corridor::Marker>(entity).is_some(),
+        };
+        kind_matches && self.metrics.iter().all(|predicate| predicate.test(world, entity))
+    }
+}
+
+/// Filters stored by name.
+#[derive(Default, Resource)]
+pub struct SavedFilters {
+    /// The stored filters.
+    pub filters: BTreeMap<String, Filter>,
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The tagged structure.
+    pub subject: SaveSubject,
+    /// The assigned tags.
+    pub tags:    BTreeSet<Tag>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.Tags";
+
+    type Runtime = Entity;
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (building_dep, corridor_dep): (
+                save::StoreDepend<building::Save>,
+                save::StoreDepend<corridor::Save>,
+            ),
+            query: Query<(Entity, &Tags, Option<&building::Marker>, Option<&corridor::Marker>)>,
+        ) {
+            writer.write_all(query.iter().map(|(entity, tags, building, corridor)| {
+                let subject = match (building, corridor) {
+                    (Some(_), None) => SaveSubject::Building { id: building_dep.must_get(entity) },
+                    (None, Some(_)) => SaveSubject::Corridor { id: corridor_dep.must_get(entity) },
+                    _ => panic!("Tags must be on a building or a corridor"),
+                };
+                (entity, Save { subject, tags: tags.tags.clone() })
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref, clippy::unnecessary_wraps)]
+        fn loader(
+            world: &mut World,
+            def: Save,
+            (building_dep, corridor_dep): &(
+                save::LoadDepend<building::Save>,
+                save::LoadDepend<corridor::Save>,
+            ),
+        ) -> anyhow::Result<Entity> {
+            let subject = match def.subject {
+                SaveSubject::Building { id } => building_dep.get(id)?,
+                SaveSubject::Corridor { id } => corridor_dep.get(id)?,
+            };
+            for tag in def.tags {
+                AddTag { entity: subject, tag }.apply(world);
+            }
+            Ok(subject)
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
+
+/// Save schema for a named filter in [`SavedFilters`].
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveFilter {
+    /// The name of the filter.
+    pub name:    String,
+    /// See [`Filter::tags`].
+    pub tags:    Vec<Tag>,
+    /// See [`Filter::kind`].
+    pub kind:    Option<Kind>,
+    /// See [`Filter::metrics`].
+    pub metrics: Vec<SaveMetricPredicate>,
+}
+
+/// Save schema for [`MetricPredicate`].
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SaveMetricPredicate {
+    /// The [`metrics::Sid`] of the metric type.
+    ///
+    /// Metric types are not saved,
+    /// so this is only stable for the same plugins and scenario.
+    pub ty:         u32,
+    /// See [`MetricPredicate::comparison`].
+    pub comparison: Comparison,
+    /// See [`MetricPredicate::threshold`].
+    pub threshold:  f32,
+}
+
+impl save::Def for SaveFilter {
+    const TYPE: &'static str = "traffloat.save.Filter";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<SaveFilter>,
+            (): (),
+            (filters, sid_query): (Res<SavedFilters>, Query<&metrics::Sid>),
+        ) {
+            writer.write_all(filters.filters.iter().map(|(name, filter)| {
+                let metrics = filter
+                    .metrics
+                    .iter()
+                    .map(|predicate| SaveMetricPredicate {
+                        ty:         sid_query
+                            .get(predicate.ty.0)
+                            .copied()
+                            .expect("metric predicate must refer to a metric type")
+                            .into(),
+                        comparison: predicate.comparison,
+                        threshold:  predicate.threshold,
+                    })
+                    .collect();
+                let def = SaveFilter {
+                    name: name.clone(),
+                    tags: filter.tags.clone(),
+                    kind: filter.kind,
+                    metrics,
+                };
+                ((), def)
+            }));
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(world: &mut World, def: SaveFilter, (): &()) -> anyhow::Result<()> {
+            let metrics = def
+                .metrics
+                .into_iter()
+                .map(|predicate| {
+                    let entity = world
+                        .resource::<metrics::SidIndex>()
+                        .get(metrics::Sid::from(predicate.ty))
+                        .with_context(|| format!("unknown metric type {}", predicate.ty))?;
+                    Ok(MetricPredicate {
+                        ty:         metrics::Type(entity),
+                        comparison: predicate.comparison,
+                        threshold:  predicate.threshold,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            let filter = Filter { tags: def.tags, kind: def.kind, metrics };
+            world.resource_mut::<SavedFilters>().filters.insert(def.name, filter);
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::app::App;
+use bevy::ecs::entity::EntityHashSet;
+use bevy::ecs::world::Command;
+use bevy::math::Vec3;
+use bevy::transform::components::Transform;
+use traffloat_base::save;
+use traffloat_view::{metrics, DisplayText};
+
+use super::{
+    AddTag, Comparison, Filter, Index, Kind, MetricPredicate, RemoveTag, SavedFilters, Tag, Tags,
+};
+use crate::corridor::Binary;
+use crate::{building, corridor, test_util};
+
+fn tag(name: &str) -> Tag { Tag(name.into()) }
+
+fn create_metric_type(app: &mut App) -> metrics::Type {
+    let ty = metrics::create_type(
+        &mut app.world_mut().commands(),
+        metrics::TypeDef {
+            update_frequency: Duration::from_secs(1),
+            display_label:    DisplayText::Custom { value: "Fullness".into() },
+            aggregation:      metrics::Aggregation::Sum,
+        },
+    );
+    app.world_mut().flush();
+    ty
+}
+
+#[test]
+fn add_and_remove() {
+    let mut app = test_util::new_app();
+    let building = test_util::spawn_building(&mut app, Vec3::ZERO);
+
+    AddTag { entity: building, tag: tag("mine") }.apply(app.world_mut());
+    AddTag { entity: building, tag: tag("north") }.apply(app.world_mut());
+    assert_eq!(app.world().resource::<Index>().get(&tag("mine")).map(EntityHashSet::len), Some(1));
+
+    RemoveTag { entity: building, tag: tag("mine") }.apply(app.world_mut());
+    assert!(app.world().resource::<Index>().get(&tag("mine")).is_none());
+    RemoveTag { entity: building, tag: tag("north") }.apply(app.world_mut());
+    assert!(app.world().get::<Tags>(building).is_none());
+    assert_eq!(app.world().resource::<Index>().tags().count(), 0);
+}
+
+#[test]
+fn reject_invalid_subject() {
+    let mut app = test_util::new_app();
+    let non_structure = app.world_mut().spawn_empty().id();
+    let despawned = app.world_mut().spawn_empty().id();
+    app.world_mut().despawn(despawned);
+
+    AddTag { entity: non_structure, tag: tag("mine") }.apply(app.world_mut());
+    AddTag { entity: despawned, tag: tag("mine") }.apply(app.world_mut());
+
+    assert_eq!(test_util::rejections(&app), ["graph.structure.not_found"; 2]);
+    assert!(app.world().get::<Tags>(non_structure).is_none());
+    assert!(app.world().resource::<Index>().get(&tag("mine")).is_none());
+}
+
+#[test]
+fn save_and_load() {
+    let mut app = test_util::new_app();
+    let ty = create_metric_type(&mut app);
+    let buildings = Binary {
+        alpha: test_util::spawn_building(&mut app, Vec3::ZERO),
+        beta:  test_util::spawn_building(&mut app, Vec3::new(10., 0., 0.)),
+    };
+    let corridor = test_util::spawn_corridor(&mut app, buildings);
+    AddTag { entity: buildings.beta, tag: tag("mine") }.apply(app.world_mut());
+    AddTag { entity: corridor, tag: tag("mine") }.apply(app.world_mut());
+    app.world_mut().resource_mut::<SavedFilters>().filters.insert(
+        "full mines".into(),
+        Filter {
+            tags:    vec![tag("mine")],
+            kind:    Some(Kind::Building),
+            metrics: vec![MetricPredicate { ty, comparison: Comparison::Greater, threshold: 0.5 }],
+        },
+    );
+
+    let data = Arc::new(Mutex::new(None));
+    save::StoreCommand {
+        format:      save::Format::Json,
+        on_complete: Box::new({
+            let data = Arc::clone(&data);
+            move |_, result| *data.lock().unwrap() = Some(result.unwrap())
+        }),
+    }
+    .apply(app.world_mut());
+    let data = data.lock().unwrap().take().expect("StoreCommand completes synchronously");
+
+    let mut app = test_util::new_app();
+    let ty = create_metric_type(&mut app);
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(app.world_mut());
+
+    let tagged = app.world().resource::<Index>().get(&tag("mine")).cloned().unwrap_or_default();
+    assert_eq!(tagged.len(), 2);
+    let building = tagged
+        .iter()
+        .copied()
+        .find(|&entity| app.world().get::<building::Marker>(entity).is_some())
+        .expect("tagged building should be loaded");
+    assert_eq!(
+        app.world().get::<Transform>(building).map(|transform| transform.translation),
+        Some(Vec3::new(10., 0., 0.))
+    );
+    assert!(tagged.iter().any(|&entity| app.world().get::<corridor::Marker>(entity).is_some()));
+
+    let filters = &app.world().resource::<SavedFilters>().filters;
+    let filter = &filters["full mines"];
+    assert_eq!(filter.tags, [tag("mine")]);
+    assert_eq!(filter.kind, Some(Kind::Building));
+    let [predicate] = &filter.metrics[..] else { panic!("expected one metric predicate") };
+    assert_eq!(predicate.ty, ty);
+    assert_eq!(predicate.comparison, Comparison::Greater);
+    assert!((predicate.threshold - 0.5).abs() < f32::EPSILON);
+}
@@ -0,0 +1,89 @@
+//! Shared setup for tests of graph behavior.
+
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, Events};
+use bevy::hierarchy::BuildWorldChildren;
+use bevy::math::Vec3;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimePlugin;
+use bevy::transform::components::Transform;
+use traffloat_base::{error, save};
+use traffloat_view::appearance::Appearance;
+use traffloat_view::viewable;
+
+use crate::corridor::{duct, Binary};
+use crate::{building, corridor};
+
+/// Creates an app with the graph plugins.
+pub(crate) fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        StatesPlugin,
+        error::Plugin,
+        save::Plugin,
+        traffloat_view::Plugin,
+        crate::Plugin,
+    ));
+    app
+}
+
+/// Spawns a building of unit scale at `translation` with an empty ambient facility.
+pub(crate) fn spawn_building(app: &mut App, translation: Vec3) -> Entity {
+    spawn_scaled_building(app, translation, 1.)
+}
+
+/// Spawns a building at `translation` with an empty ambient facility.
+pub(crate) fn spawn_scaled_building(app: &mut App, translation: Vec3, scale: f32) -> Entity {
+    let world = app.world_mut();
+    let ambient = world.spawn_empty().id();
+    let sid = viewable::next_sid(world);
+    world
+        .spawn(
+            building::Bundle::builder()
+                .viewable(
+                    viewable::StationaryBundle::builder()
+                        .base(
+                            viewable::BaseBundle::builder()
+                                .sid(sid)
+                                .appearance(Appearance::null())
+                                .build(),
+                        )
+                        .transform(
+                            Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
+                        )
+                        .build(),
+                )
+                .facility_list(building::FacilityList { ambient, non_ambient: Vec::new() })
+                .build(),
+        )
+        .id()
+}
+
+/// Spawns a corridor between two buildings with only an ambient duct.
+pub(crate) fn spawn_corridor(app: &mut App, endpoints: Binary<Entity>) -> Entity {
+    let world = app.world_mut();
+    let ambient = world.spawn(duct::Bundle::builder().build()).id();
+    world
+        .spawn(
+            corridor::Bundle::builder()
+                .endpoints(corridor::Endpoints { endpoints })
+                .duct_list(corridor::DuctList { duct_list: Vec::new(), ambient })
+                .build(),
+        )
+        .add_child(ambient)
+        .id()
+}
+
+/// Returns all events of type `E` that have not been dropped yet.
+pub(crate) fn events<E: Event + Clone>(app: &App) -> Vec<E> {
+    let events = app.world().resource::<Events<E>>();
+    events.get_reader().read(events).cloned().collect()
+}
+
+/// Returns the i18n keys of all rejected commands.
+pub(crate) fn rejections(app: &App) -> Vec<String> {
+    let events = app.world().resource::<Events<error::RejectedEvent>>();
+    events.get_reader().read(events).map(|event| event.error.detail().key.to_string()).collect()
+}
@@ -0,0 +1,226 @@
+//! Imports saves from the legion-based engine.
+#![doc = include_str!("../README.md")]
+
+use std::collections::HashMap;
+use std::io;
+
+use bevy::math::Vec3;
+use bevy::transform::components::Transform;
+use traffloat_base::save;
+use traffloat_fluid::{config, container, units};
+use traffloat_graph::building::{self, facility};
+use traffloat_graph::corridor::{self, duct, Binary};
+use traffloat_view::appearance::Appearance;
+use traffloat_view::DisplayText;
+
+pub mod schema;
+
+#[cfg(test)]
+mod tests;
+
+/// Specific volume of imported gases, taken as that of air.
+const GAS_SPECIFIC_VOLUME: f32 = 22400. / 28.96;
+/// Specific volume of imported liquids, taken as that of water.
+const LIQUID_SPECIFIC_VOLUME: f32 = 1.;
+/// Pressure limit of imported containers.
+const MAX_PRESSURE: f32 = 100.;
+
+/// Whether `buf` is a legacy save file.
+#[must_use]
+pub fn is_legacy(buf: &[u8]) -> bool { buf.starts_with(save::LEGACY_TFSAVE_HEADER) }
+
+/// Converts a legacy save file into a JSON save file.
+///
+/// # Errors
+/// Returns an error if the file is not a valid legacy save.
+pub fn import(buf: &[u8]) -> Result<Vec<u8>, Error> { convert(&decode(buf)?) }
+
+/// Decodes a legacy save file.
+///
+/// # Errors
+/// Returns an error if the file is not a valid legacy save.
+pub fn decode(buf: &[u8]) -> Result<schema::TfsaveFile, Error> {
+    let compressed = buf.strip_prefix(save::LEGACY_TFSAVE_HEADER).ok_or(Error::NotLegacy)?;
+    let mut decoder = flate2::read::DeflateDecoder::new(compressed);
+    let mut decompressed = Vec::new();
+    io::Read::read_to_end(&mut decoder, &mut decompressed).map_err(Error::Deflate)?;
+    rmp_serde::from_slice(&decompressed).map_err(Error::Decode)
+}
+
+/// Encodes a legacy save file.
+///
+/// Only used to produce test fixtures, since the current engine never writes legacy saves.
+///
+/// # Errors
+/// Returns an error if the file cannot be encoded.
+pub fn encode(file: &schema::TfsaveFile) -> Result<Vec<u8>, Error> {
+    let msgpack = rmp_serde::to_vec_named(file).map_err(Error::Encode)?;
+    let mut buf = save::LEGACY_TFSAVE_HEADER.to_vec();
+    let mut encoder = flate2::write::DeflateEncoder::new(&mut buf, flate2::Compression::default());
+    io::Write::write_all(&mut encoder, &msgpack).map_err(Error::Deflate)?;
+    encoder.finish().map_err(Error::Deflate)?;
+    Ok(buf)
+}
+
+/// Converts a decoded legacy save file into a JSON save file.
+///
+/// # Errors
+/// Returns an error if the file references undefined nodes or types.
+pub fn convert(file: &schema::TfsaveFile) -> Result<Vec<u8>, Error> {
+    let mut builder = save::JsonBuilder::default();
+
+    let mut gases = HashMap::new();
+    for def in &file.def.gas {
+        gases.insert(def.id.as_str(), builder.add(fluid_type(&def.name, GAS_SPECIFIC_VOLUME))?);
+    }
+    let mut liquids = HashMap::new();
+    for def in &file.def.liquid {
+        liquids
+            .insert(def.id.as_str(), builder.add(fluid_type(&def.name, LIQUID_SPECIFIC_VOLUME))?);
+    }
+    let building_defs: HashMap<_, _> =
+        file.def.building.iter().map(|def| (def.id.as_str(), def)).collect();
+
+    let mut buildings = HashMap::new();
+    for node in &file.state.nodes {
+        let def = building_defs
+            .get(node.building.as_str())
+            .ok_or_else(|| Error::UnknownBuilding(node.building.clone()))?;
+        let name = node.name.as_deref().unwrap_or(&def.name);
+
+        #[allow(clippy::cast_possible_truncation)] // legacy positions are far below f32::MAX
+        let translation = Vec3::from(node.position.map(|coord| coord as f32));
+        let building = builder.add(building::Save {
+            transform:  Transform::from_translation(translation).into(),
+            appearance: labeled(name),
+        })?;
+        buildings.insert(node.id, building);
+
+        let ambient = write_facility(&mut builder, building, true)?;
+        let container = write_container(&mut builder, ambient, def.storage.gas)?;
+        for (ty, &mass) in &node.gas {
+            let ty = *gases.get(ty.as_str()).ok_or_else(|| Error::UnknownFluid(ty.clone()))?;
+            write_element(&mut builder, container, ty, mass)?;
+        }
+
+        if !node.liquid.is_empty() {
+            let facility = write_facility(&mut builder, building, false)?;
+            for (index, storage) in node.liquid.iter().enumerate() {
+                let &capacity = def
+                    .storage
+                    .liquid
+                    .get(index)
+                    .ok_or(Error::MissingLiquidStorage { node: node.id, index })?;
+                let container = write_container(&mut builder, facility, capacity)?;
+                let ty = *liquids
+                    .get(storage.ty.as_str())
+                    .ok_or_else(|| Error::UnknownFluid(storage.ty.clone()))?;
+                write_element(&mut builder, container, ty, storage.mass)?;
+            }
+        }
+    }
+
+    for edge in &file.state.edges {
+        let endpoints = Binary { alpha: edge.from, beta: edge.to }
+            .try_map(|node| buildings.get(&node).copied().ok_or(Error::UnknownNode(node)))?;
+        let corridor = builder.add(corridor::Save { endpoints })?;
+        builder.add(duct::Save { parent: corridor, is_ambient: true })?;
+    }
+
+    Ok(builder.build()?)
+}
+
+fn labeled(label: &str) -> Appearance {
+    Appearance { label: DisplayText::Custom { value: label.into() }, ..Appearance::null() }
+}
+
+fn fluid_type(label: &str, specific_volume: f32) -> config::SaveType {
+    config::SaveType {
+        def: config::TypeDef {
+            display_label:          DisplayText::Custom { value: label.into() },
+            viscosity:              units::Viscosity::from(1.),
+            vacuum_specific_volume: units::SpecificVolume::from(specific_volume),
+            critical_pressure:      units::Pressure::from(MAX_PRESSURE),
+            saturation_gamma:       100.,
+            immiscible:             false,
+            color:                  None,
+            haze:                   None,
+        },
+    }
+}
+
+fn write_facility(
+    builder: &mut save::JsonBuilder,
+    parent: save::Id<building::Save>,
+    is_ambient: bool,
+) -> Result<save::Id<facility::Save>, Error> {
+    Ok(builder.add(facility::Save {
+        parent,
+        inner: Transform::IDENTITY.into(),
+        appearance: Appearance::null(),
+        is_ambient,
+    })?)
+}
+
+fn write_container(
+    builder: &mut save::JsonBuilder,
+    facility: save::Id<facility::Save>,
+    max_volume: f64,
+) -> Result<save::Id<container::Save>, Error> {
+    #[allow(clippy::cast_possible_truncation)] // legacy capacities are far below f32::MAX
+    let max_volume = max_volume as f32;
+    Ok(builder.add(container::Save {
+        owner:        container::SaveOwner::Facility { id: facility },
+        max_volume:   units::Volume::from(max_volume),
+        max_pressure: units::Pressure::from(MAX_PRESSURE),
+    })?)
+}
+
+fn write_element(
+    builder: &mut save::JsonBuilder,
+    parent: save::Id<container::Save>,
+    ty: save::Id<config::SaveType>,
+    mass: f64,
+) -> Result<(), Error> {
+    #[allow(clippy::cast_possible_truncation)] // legacy masses are far below f32::MAX
+    let mass = mass as f32;
+    builder.add(container::element::Save { parent, ty, mass: mass.into() })?;
+    Ok(())
+}
+
+/// Errors during import.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The file does not start with the legacy header.
+    #[error("file does not start with the legacy save header")]
+    NotLegacy,
+    /// The DEFLATE stream could not be decompressed or compressed.
+    #[error("legacy save DEFLATE stream: {0}")]
+    Deflate(io::Error),
+    /// The Msgpack contents do not match the legacy schema.
+    #[error("decoding legacy save: {0}")]
+    Decode(rmp_serde::decode::Error),
+    /// The legacy file could not be encoded.
+    #[error("encoding legacy save: {0}")]
+    Encode(rmp_serde::encode::Error),
+    /// A node references an undefined building type.
+    #[error("node references undefined building type {0:?}")]
+    UnknownBuilding(String),
+    /// A node references an undefined gas or liquid type.
+    #[error("node references undefined fluid type {0:?}")]
+    UnknownFluid(String),
+    /// An edge references an undefined node.
+    #[error("edge references undefined node {0}")]
+    UnknownNode(u32),
+    /// A node has more liquid storages than its building type defines.
+    #[error("node {node} has liquid storage {index} not defined by its building type")]
+    MissingLiquidStorage {
+        /// The node ID.
+        node:  u32,
+        /// The index of the liquid storage.
+        index: usize,
+    },
+    /// The converted save could not be encoded.
+    #[error("encoding converted save: {0}")]
+    Convert(#[from] serde_json::Error),
+}
@@ -0,0 +1,111 @@
+//! The subset of the legacy `TfsaveFile` schema read by the importer.
+//!
+//! Fields not listed here are ignored when decoding.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A legacy save file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TfsaveFile {
+    /// Game definitions.
+    pub def:   Def,
+    /// Game state.
+    pub state: State,
+}
+
+/// Game definitions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Def {
+    /// Liquid types.
+    #[serde(default)]
+    pub liquid:   Vec<FluidDef>,
+    /// Gas types.
+    #[serde(default)]
+    pub gas:      Vec<FluidDef>,
+    /// Building types.
+    #[serde(default)]
+    pub building: Vec<BuildingDef>,
+}
+
+/// A liquid or gas type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FluidDef {
+    /// The string ID referenced by nodes.
+    pub id:   String,
+    /// The display name.
+    pub name: String,
+}
+
+/// A building type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildingDef {
+    /// The string ID referenced by nodes.
+    pub id:      String,
+    /// The display name.
+    pub name:    String,
+    /// Storage capacities.
+    #[serde(default)]
+    pub storage: BuildingStorage,
+}
+
+/// Storage capacities of a building type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildingStorage {
+    /// Total volume of gas storage.
+    #[serde(default)]
+    pub gas:    f64,
+    /// Volume of each liquid storage.
+    #[serde(default)]
+    pub liquid: Vec<f64>,
+}
+
+/// Game state.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct State {
+    /// Buildings.
+    #[serde(default)]
+    pub nodes: Vec<Node>,
+    /// Links between buildings.
+    #[serde(default)]
+    pub edges: Vec<Edge>,
+}
+
+/// A building.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    /// The node ID referenced by edges.
+    pub id:       u32,
+    /// The building type ID.
+    pub building: String,
+    /// The custom name of the node, if any.
+    #[serde(default)]
+    pub name:     Option<String>,
+    /// The node position.
+    pub position: [f64; 3],
+    /// Gas masses by gas type ID.
+    #[serde(default)]
+    pub gas:      BTreeMap<String, f64>,
+    /// Liquid storages in the order of [`BuildingStorage::liquid`].
+    #[serde(default)]
+    pub liquid:   Vec<LiquidStorage>,
+}
+
+/// The contents of a liquid storage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidStorage {
+    /// The liquid type ID.
+    pub ty:   String,
+    /// The liquid mass.
+    pub mass: f64,
+}
+
+/// A link between two buildings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Edge {
+    /// The source node ID.
+    pub from: u32,
+    /// The destination node ID.
+    pub to:   u32,
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use bevy::app::App;
+use bevy::ecs::query::With;
+use bevy::ecs::world::Command;
+use bevy::state::app::{AppExtStates, StatesPlugin};
+use bevy::time::TimePlugin;
+use bevy::transform::components::Transform;
+use traffloat_base::{save, EmptyState};
+use traffloat_graph::{building, corridor};
+use traffloat_view::appearance::Appearance;
+
+use crate::schema::{
+    BuildingDef, BuildingStorage, Def, Edge, FluidDef, LiquidStorage, Node, State, TfsaveFile,
+};
+use crate::{decode, encode, import, is_legacy, Error};
+
+fn fixture() -> TfsaveFile {
+    let node = |id: u32, name: Option<&str>, x: f64| Node {
+        id,
+        building: "core".into(),
+        name: name.map(Into::into),
+        position: [x, 0., 0.],
+        gas: BTreeMap::from([("oxygen".into(), 100.)]),
+        liquid: vec![LiquidStorage { ty: "water".into(), mass: 5. }],
+    };
+
+    TfsaveFile {
+        def:   Def {
+            liquid:   vec![FluidDef { id: "water".into(), name: "Water".into() }],
+            gas:      vec![FluidDef { id: "oxygen".into(), name: "Oxygen".into() }],
+            building: vec![BuildingDef {
+                id:      "core".into(),
+                name:    "Core".into(),
+                storage: BuildingStorage { gas: 1000., liquid: vec![10.] },
+            }],
+        },
+        state: State {
+            nodes: vec![node(3, None, 0.), node(7, Some("Outpost"), 20.)],
+            edges: vec![Edge { from: 3, to: 7 }],
+        },
+    }
+}
+
+#[test]
+fn round_trip_encoding() {
+    let buf = encode(&fixture()).unwrap();
+    assert!(is_legacy(&buf));
+
+    let file = decode(&buf).unwrap();
+    assert_eq!(file.state.nodes.len(), 2);
+    assert_eq!(file.state.edges.len(), 1);
+    assert!(matches!(decode(b"{}"), Err(Error::NotLegacy)));
+}
+
+#[test]
+fn import_and_load() {
+    let data = import(&encode(&fixture()).unwrap()).unwrap();
+
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        StatesPlugin,
+        save::Plugin,
+        traffloat_view::Plugin,
+        traffloat_graph::Plugin,
+        traffloat_fluid::Plugin(EmptyState),
+    ));
+    app.init_state::<EmptyState>();
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(app.world_mut());
+
+    let world = app.world_mut();
+    let mut buildings: Vec<_> = world
+        .query_filtered::<(&Transform, &Appearance), With<building::Marker>>()
+        .iter(world)
+        .map(|(transform, appearance)| {
+            (transform.translation.x, appearance.label.render_to_string())
+        })
+        .collect();
+    buildings.sort_by(|(x1, _), (x2, _)| x1.total_cmp(x2));
+    assert_eq!(buildings, [(0., "Core".into()), (20., "Outpost".into())]);
+    assert_eq!(world.query_filtered::<(), With<corridor::Marker>>().iter(world).count(), 1);
+}
+
+#[test]
+fn reject_undefined_references() {
+    let mut file = fixture();
+    file.state.edges.push(Edge { from: 3, to: 4 });
+    assert!(matches!(import(&encode(&file).unwrap()), Err(Error::UnknownNode(4))));
+
+    let mut file = fixture();
+    file.state.nodes[0].gas.insert("argon".into(), 1.);
+    assert!(
+        matches!(import(&encode(&file).unwrap()), Err(Error::UnknownFluid(ty)) if ty == "argon")
+    );
+}
@@ -0,0 +1,262 @@
+//! Procedural generation of new worlds.
+#![doc = include_str!("../README.md")]
+
+use std::ops::Range;
+
+use bevy::math::{Quat, Vec3};
+use bevy::transform::components::Transform;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use traffloat_base::save;
+use traffloat_fluid::{config, container, mining, units};
+use traffloat_graph::building::{self, facility};
+use traffloat_view::appearance::Appearance;
+use traffloat_view::DisplayText;
+use typed_builder::TypedBuilder;
+
+/// Number of attempts to place an asteroid before skipping it.
+const MAX_PLACEMENT_ATTEMPTS: usize = 16;
+
+/// Tunable parameters for map generation.
+#[derive(TypedBuilder)]
+pub struct Params {
+    /// Seed for the random number generator.
+    #[builder(default)]
+    pub seed:                  u64,
+    /// Number of asteroid clusters.
+    #[builder(default = 6)]
+    pub clusters:              usize,
+    /// Number of asteroids attempted in each cluster.
+    #[builder(default = 8)]
+    pub asteroids_per_cluster: usize,
+    /// Maximum distance of a cluster center from the core station.
+    #[builder(default = 150.)]
+    pub world_radius:          f32,
+    /// Minimum distance of a cluster center from the core station.
+    #[builder(default = 40.)]
+    pub min_cluster_distance:  f32,
+    /// Maximum distance of an asteroid from its cluster center.
+    #[builder(default = 20.)]
+    pub cluster_radius:        f32,
+    /// Range of the bounding radius of an asteroid.
+    #[builder(default = 1. .. 4.)]
+    pub asteroid_radius:       Range<f32>,
+    /// Probability that an asteroid contains a resource deposit.
+    #[builder(default = 0.3)]
+    pub deposit_chance:        f64,
+    /// Range of the initial fluid mass in a resource deposit.
+    #[builder(default = 1000. .. 10000.)]
+    pub deposit_mass:          Range<f32>,
+    /// Appearances of generated structures.
+    #[builder(default)]
+    pub appearances:           Appearances,
+}
+
+/// Appearances of generated structures.
+///
+/// The generator has no access to mesh assets,
+/// so the default appearances only contain labels with null layers.
+pub struct Appearances {
+    /// The core station building.
+    pub core:     Appearance,
+    /// An asteroid building.
+    pub asteroid: Appearance,
+}
+
+impl Default for Appearances {
+    fn default() -> Self { Self { core: labeled("Core"), asteroid: labeled("Asteroid") } }
+}
+
+fn labeled(label: &str) -> Appearance {
+    Appearance { label: DisplayText::Custom { value: label.into() }, ..Appearance::null() }
+}
+
+/// Generates a new world as a JSON save file.
+///
+/// # Errors
+/// Returns an error if the save file cannot be encoded.
+pub fn generate(params: &Params) -> Result<Vec<u8>, serde_json::Error> {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(params.seed);
+    let mut builder = save::JsonBuilder::default();
+    builder.add(save::seed::Save { seed: params.seed })?;
+    let fluids = Fluids::write(&mut builder)?;
+
+    let core = Transform::from_scale(Vec3::splat(2.));
+    let core_id = write_building(&mut builder, core, params.appearances.core.clone())?;
+    let core_ambient =
+        write_facility(&mut builder, core_id, Transform::IDENTITY, true, Appearance::null())?;
+    fluids.write_ambient_container(&mut builder, core_ambient, 10000., 100.)?;
+
+    let mut placed = vec![(core.translation, core.scale.max_element())];
+
+    for _ in 0..params.clusters {
+        let center =
+            random_in_ball(&mut rng) * params.world_radius.max(params.min_cluster_distance);
+        let center = if center.length() < params.min_cluster_distance {
+            center.try_normalize().unwrap_or(Vec3::X) * params.min_cluster_distance
+        } else {
+            center
+        };
+
+        for _ in 0..params.asteroids_per_cluster {
+            let Some(transform) = place_asteroid(&mut rng, params, center, &placed) else {
+                continue;
+            };
+            placed.push((transform.translation, transform.scale.max_element()));
+
+            let asteroid =
+                write_building(&mut builder, transform, params.appearances.asteroid.clone())?;
+            write_facility(&mut builder, asteroid, Transform::IDENTITY, true, Appearance::null())?;
+
+            if rng.gen_bool(params.deposit_chance) {
+                let mass = rng.gen_range(params.deposit_mass.clone());
+                builder.add(mining::SaveDeposit {
+                    building:  asteroid,
+                    ty:        fluids.water,
+                    remaining: mass.into(),
+                    surveyed:  false,
+                })?;
+            }
+        }
+    }
+
+    builder.build()
+}
+
+/// Uniformly samples a point in the unit ball.
+fn random_in_ball(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let point =
+            Vec3::new(rng.gen_range(-1. ..1.), rng.gen_range(-1. ..1.), rng.gen_range(-1. ..1.));
+        if point.length_squared() <= 1. {
+            return point;
+        }
+    }
+}
+
+/// Finds a position for an asteroid that does not overlap with placed structures.
+fn place_asteroid(
+    rng: &mut impl Rng,
+    params: &Params,
+    center: Vec3,
+    placed: &[(Vec3, f32)],
+) -> Option<Transform> {
+    (0..MAX_PLACEMENT_ATTEMPTS).find_map(|_| {
+        let position = center + random_in_ball(rng) * params.cluster_radius;
+        let radius = rng.gen_range(params.asteroid_radius.clone());
+        let overlaps = placed
+            .iter()
+            .any(|&(other, other_radius)| other.distance(position) < radius + other_radius);
+        (!overlaps).then(|| {
+            let rotation = Quat::from_euler(
+                bevy::math::EulerRot::XYZ,
+                rng.gen_range(0. ..std::f32::consts::TAU),
+                rng.gen_range(0. ..std::f32::consts::TAU),
+                rng.gen_range(0. ..std::f32::consts::TAU),
+            );
+            Transform { translation: position, rotation, scale: Vec3::splat(radius) }
+        })
+    })
+}
+
+fn write_building(
+    builder: &mut save::JsonBuilder,
+    transform: Transform,
+    appearance: Appearance,
+) -> Result<save::Id<building::Save>, serde_json::Error> {
+    builder.add(building::Save { transform: transform.into(), appearance })
+}
+
+fn write_facility(
+    builder: &mut save::JsonBuilder,
+    parent: save::Id<building::Save>,
+    inner: Transform,
+    is_ambient: bool,
+    appearance: Appearance,
+) -> Result<save::Id<facility::Save>, serde_json::Error> {
+    builder.add(facility::Save { parent, inner: inner.into(), appearance, is_ambient })
+}
+
+/// Standard fluid types in generated worlds.
+struct Fluids {
+    nitrogen: save::Id<config::SaveType>,
+    oxygen:   save::Id<config::SaveType>,
+    co2:      save::Id<config::SaveType>,
+    water:    save::Id<config::SaveType>,
+}
+
+impl Fluids {
+    fn write(builder: &mut save::JsonBuilder) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            nitrogen: builder.add(gas_like("Nitrogen", 28.02))?,
+            oxygen:   builder.add(gas_like("Oxygen", 31.99))?,
+            co2:      builder.add(gas_like("CO2", 44.01))?,
+            water:    builder.add(aqueous("Water", 18.02))?,
+        })
+    }
+
+    /// Writes a container with the standard atmospheric composition.
+    fn write_ambient_container(
+        &self,
+        builder: &mut save::JsonBuilder,
+        facility: save::Id<facility::Save>,
+        max_volume: f32,
+        max_pressure: f32,
+    ) -> Result<(), serde_json::Error> {
+        let container = write_container(builder, facility, max_volume, max_pressure)?;
+        for (ty, mass) in [
+            (self.nitrogen, 22400. / 14. * max_volume * 0.78),
+            (self.oxygen, 22400. / 16. * max_volume * 0.21),
+            (self.co2, 22400. / 16. * max_volume * 0.01),
+        ] {
+            builder.add(container::element::Save { parent: container, ty, mass: mass.into() })?;
+        }
+        Ok(())
+    }
+}
+
+fn write_container(
+    builder: &mut save::JsonBuilder,
+    facility: save::Id<facility::Save>,
+    max_volume: f32,
+    max_pressure: f32,
+) -> Result<save::Id<container::Save>, serde_json::Error> {
+    builder.add(container::Save {
+        owner:        container::SaveOwner::Facility { id: facility },
+        max_volume:   units::Volume::from(max_volume),
+        max_pressure: units::Pressure::from(max_pressure),
+    })
+}
+
+fn gas_like(label: &str, molar_mass: f32) -> config::SaveType {
+    config::SaveType {
+        def: config::TypeDef {
+            display_label:          DisplayText::Custom { value: label.into() },
+            viscosity:              units::Viscosity::from(0.1),
+            vacuum_specific_volume: units::SpecificVolume::from(22400. / molar_mass),
+            critical_pressure:      units::Pressure::from(1000.),
+            saturation_gamma:       100.,
+            immiscible:             false,
+            color:                  None,
+            haze:                   None,
+        },
+    }
+}
+
+fn aqueous(label: &str, molar_mass: f32) -> config::SaveType {
+    config::SaveType {
+        def: config::TypeDef {
+            display_label:          DisplayText::Custom { value: label.into() },
+            viscosity:              units::Viscosity::from(2.),
+            vacuum_specific_volume: units::SpecificVolume::from(18. / molar_mass),
+            critical_pressure:      units::Pressure::from(1.2),
+            saturation_gamma:       100.,
+            immiscible:             false,
+            color:                  None,
+            haze:                   None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests;
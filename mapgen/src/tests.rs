@@ -0,0 +1,47 @@
+use bevy::app::App;
+use bevy::ecs::query::With;
+use bevy::ecs::world::Command;
+use bevy::state::app::{AppExtStates, StatesPlugin};
+use bevy::time::TimePlugin;
+use traffloat_base::save::seed::WorldSeed;
+use traffloat_base::{save, EmptyState};
+use traffloat_graph::building;
+
+use super::{generate, Params};
+
+#[test]
+fn same_seed_same_world() {
+    let params = Params::builder().seed(42).build();
+    assert_eq!(generate(&params).unwrap(), generate(&params).unwrap());
+}
+
+#[test]
+fn different_seed_different_world() {
+    let first = generate(&Params::builder().seed(1).build()).unwrap();
+    let second = generate(&Params::builder().seed(2).build()).unwrap();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn load_generated_world() {
+    let params = Params::builder().seed(42).clusters(2).asteroids_per_cluster(3).build();
+    let data = generate(&params).unwrap();
+
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        StatesPlugin,
+        save::Plugin,
+        traffloat_view::Plugin,
+        traffloat_graph::Plugin,
+        traffloat_fluid::Plugin(EmptyState),
+    ));
+    app.init_state::<EmptyState>();
+    save::LoadCommand { data, on_complete: Box::new(|_, result| result.unwrap()) }
+        .apply(app.world_mut());
+
+    let world = app.world_mut();
+    let buildings = world.query_filtered::<(), With<building::Marker>>().iter(world).count();
+    assert_eq!(buildings, 7, "the core station and all six asteroids should be placed");
+    assert_eq!(world.resource::<WorldSeed>().seed, Some(42));
+}
@@ -66,6 +66,7 @@ def core(ctx: Context, position: Position, rotation: Rotation = Rotation.identit
         rotation=rotation,
         scale=Scale.splat(2.0),
         label=CustomDisplayText("Core"),
+        tags=["core"],
         layers=Layers(
             distal=PbrLayer(mesh=sphere.Mesh(), material=common_materials.Glass()),
             proximal=PbrLayer(
@@ -84,6 +85,7 @@ def garden(ctx: Context, position: Position, rotation: Rotation = Rotation.ident
         position=position,
         rotation=rotation,
         label=CustomDisplayText("Garden"),
+        tags=["garden"],
         layers=Layers(
             distal=PbrLayer(mesh=sphere.Mesh(), material=common_materials.Glass()),
             proximal=PbrLayer(mesh=sphere.Mesh(), material=common_materials.Glass()),
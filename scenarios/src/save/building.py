@@ -3,6 +3,7 @@ from typing import Optional, Self
 
 from . import Def, Id, Writer
 from .facility import Facility
+from .fluid.pipe import Pipe as FluidPipe
 from .types import DisplayText, Layers, Position, Rotation, Scale
 
 
@@ -19,6 +20,8 @@ class Building(Def):
 
     ambient_facility: Facility
     other_facilities: list[Facility] = field(default_factory=list)
+    openings: list["Opening"] = field(default_factory=list)
+    tags: list[str] = field(default_factory=list)
 
     id: Optional[Id[Self]] = None
 
@@ -39,6 +42,7 @@ class Building(Def):
                     "distal": self.layers.distal.as_dict(writer),
                     "proximal": self.layers.proximal.as_dict(writer),
                     "interior": self.layers.interior.as_dict(writer),
+                    "tank": self.layers.tank_dict(writer),
                 },
             },
         )
@@ -48,4 +52,54 @@ class Building(Def):
         for facility in self.other_facilities:
             facility.write(writer=writer, parent=self.id, is_ambient=False)
 
+        for opening in self.openings:
+            opening.write(writer)
+
+        if self.tags:
+            Tags(building=self.id, tags=self.tags).write(writer)
+
         return self.id
+
+
+@dataclass
+class Tags(Def):
+    """Free-form tags of a building, e.g. to address it in scenario tests."""
+
+    _: KW_ONLY
+
+    building: Id[Building]
+    tags: list[str]
+
+    def save_id() -> str:
+        return "traffloat.save.Tags"
+
+    def write(self, writer: Writer) -> Id[Self]:
+        return writer.write(
+            Tags,
+            {
+                "subject": {"type": "Building", "id": self.building.id},
+                "tags": sorted(set(self.tags)),
+            },
+        )
+
+
+@dataclass
+class Opening:
+    """An internal opening between two rooms of a building.
+
+    Each room is a facility of the building,
+    and the opening connects the first fluid container of each room,
+    allowing gases to diffuse between the rooms.
+    """
+
+    _: KW_ONLY
+
+    between: tuple[Facility, Facility]
+    resistance: float
+
+    def write(self, writer: Writer):
+        alpha, beta = self.between
+        FluidPipe(
+            containers=(alpha.fluid_containers[0], beta.fluid_containers[0]),
+            shape_resistance=self.resistance,
+        ).write(writer)
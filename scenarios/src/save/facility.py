@@ -45,6 +45,7 @@ class Facility(Def):
                     "distal": self.layers.distal.as_dict(writer),
                     "proximal": self.layers.proximal.as_dict(writer),
                     "interior": self.layers.interior.as_dict(writer),
+                    "tank": self.layers.tank_dict(writer),
                 },
             },
         )
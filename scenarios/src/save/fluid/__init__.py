@@ -1,5 +1,5 @@
 from dataclasses import dataclass, KW_ONLY
-from typing import Self
+from typing import Optional, Self
 
 from .. import Def, Id, Writer
 from ..types import CustomDisplayText, DisplayText
@@ -14,6 +14,9 @@ class Type(Def):
     vacuum_specific_volume: float
     critical_pressure: float
     saturation_gamma: float
+    immiscible: bool = False
+    color: Optional[tuple[float, float, float]] = None
+    haze: Optional["Haze"] = None
 
     def aqueous(display_label: str, molar_mass: float) -> Self:
         return Type(
@@ -45,7 +48,21 @@ class Type(Def):
                 "vacuum_specific_volume": self.vacuum_specific_volume,
                 "critical_pressure": self.critical_pressure,
                 "saturation_gamma": self.saturation_gamma,
+                "immiscible": self.immiscible,
+                "color": self.color,
+                "haze": self.haze and self.haze.as_dict(),
             },
         )
 
         return self.id
+
+
+@dataclass
+class Haze:
+    """Renders a fluid as haze, with densities in mass per unit of container capacity."""
+
+    threshold: float
+    opaque: float
+
+    def as_dict(self) -> dict:
+        return {"threshold": self.threshold, "opaque": self.opaque}
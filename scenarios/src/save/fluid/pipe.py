@@ -0,0 +1,30 @@
+from dataclasses import dataclass, field, KW_ONLY
+from typing import Optional, Self
+
+from .. import Def, Id, Writer
+from .container import Container
+
+
+@dataclass
+class Pipe(Def):
+    _: KW_ONLY
+
+    containers: tuple[Container, Container]
+    shape_resistance: float
+    ports: tuple[Optional[float], Optional[float]] = (None, None)
+
+    def save_id() -> str:
+        return "traffloat.save.fluid.Pipe"
+
+    def write(self, writer: Writer) -> Id[Self]:
+        alpha, beta = self.containers
+        self.id = writer.write(
+            Pipe,
+            {
+                "containers": {"alpha": alpha.id.id, "beta": beta.id.id},
+                "shape_resistance": self.shape_resistance,
+                "ports": {"alpha": self.ports[0], "beta": self.ports[1]},
+            },
+        )
+
+        return self.id
@@ -0,0 +1,73 @@
+from dataclasses import dataclass, field, KW_ONLY
+from typing import Any, Self
+
+from . import Def, Id, Writer
+from .types import DisplayText
+
+
+@dataclass
+class Trigger(Def):
+    """Fires actions when a condition is met.
+
+    Conditions and actions are written as tagged dicts,
+    built with the helper functions in this module.
+    """
+
+    _: KW_ONLY
+
+    condition: dict[str, Any]
+    actions: list[dict[str, Any]] = field(default_factory=list)
+    repeat: bool = False
+
+    def save_id() -> str:
+        return "traffloat.save.Trigger"
+
+    def write(self, writer: Writer) -> Id[Self]:
+        return writer.write(
+            Trigger,
+            {
+                "condition": self.condition,
+                "actions": self.actions,
+                "repeat": "Repeat" if self.repeat else "Once",
+            },
+        )
+
+
+def at_cycle(at: int) -> dict[str, Any]:
+    return {"type": "Cycle", "at": at}
+
+
+def fluid_below(ty: Id, threshold: float, hysteresis: float = 0.0) -> dict[str, Any]:
+    return {
+        "type": "FluidMass",
+        "ty": ty.id,
+        "comparison": "Below",
+        "threshold": threshold,
+        "hysteresis": hysteresis,
+    }
+
+
+def fluid_above(ty: Id, threshold: float, hysteresis: float = 0.0) -> dict[str, Any]:
+    return {
+        "type": "FluidMass",
+        "ty": ty.id,
+        "comparison": "Above",
+        "threshold": threshold,
+        "hysteresis": hysteresis,
+    }
+
+
+def building_removed(building: Id) -> dict[str, Any]:
+    return {"type": "BuildingRemoved", "building": building.id}
+
+
+def announce(message: DisplayText) -> dict[str, Any]:
+    return {"type": "Announce", "message": message.as_dict()}
+
+
+def signal(name: str) -> dict[str, Any]:
+    return {"type": "Signal", "name": name}
+
+
+def ignite(container: Id) -> dict[str, Any]:
+    return {"type": "Ignite", "container": container.id}
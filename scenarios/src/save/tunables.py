@@ -0,0 +1,15 @@
+from dataclasses import dataclass, field
+from typing import Self
+
+from . import Def, Id, Writer
+
+
+@dataclass
+class Tunables(Def):
+    values: dict[str, float] = field(default_factory=dict)
+
+    def save_id() -> str:
+        return "traffloat.save.Tunables"
+
+    def write(self, writer: Writer) -> Id[Self]:
+        return writer.write(Tunables, {"values": self.values})
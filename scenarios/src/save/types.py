@@ -1,6 +1,6 @@
 from abc import abstractmethod
 from dataclasses import dataclass, field
-from typing import Self
+from typing import Optional, Self
 
 from ..assets import Material, Mesh
 from . import Writer
@@ -83,15 +83,34 @@ class PbrLayer:
         }
 
 
+@dataclass
+class Tank:
+    mesh: Mesh
+    bottom: float = -1.0
+    top: float = 1.0
+
+    def as_dict(self, writer: Writer):
+        return {
+            "mesh": self.mesh.use(writer.pool),
+            "bottom": self.bottom,
+            "top": self.top,
+        }
+
+
 @dataclass
 class Layers:
     distal: Layer = field(default_factory=NullLayer)
     proximal: Layer = field(default_factory=NullLayer)
     interior: Layer = field(default_factory=NullLayer)
+    tank: Optional[Tank] = None
+
+    def tank_dict(self, writer: Writer):
+        return self.tank.as_dict(writer) if self.tank is not None else None
 
     def as_dict(self, writer: Writer):
         return {
             "distal": self.distal.as_dict(writer),
             "proximal": self.proximal.as_dict(writer),
             "interior": self.interior.as_dict(writer),
+            "tank": self.tank_dict(writer),
         }
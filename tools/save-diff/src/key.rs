@@ -0,0 +1,293 @@
+//! Stable keys of definitions.
+//!
+//! The key of a definition is built from the [identity](Identity) fields of its type,
+//! e.g. the position of a building or the parent and fluid type of a container element.
+//! References to other definitions are replaced by the type and key of the referenced definition,
+//! both in the key and in the compared value.
+//! Definitions of the same type with equal keys are numbered by their order in the save,
+//! e.g. `[/owner=...]#1` for the second container of the same facility.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::Defs;
+
+/// How to identify definitions of a type.
+enum Identity {
+    /// The type has at most one definition.
+    Singleton,
+    /// The definition is identified by the fields at these JSON pointers.
+    Fields(&'static [&'static str]),
+    /// The definition is identified by its whole content.
+    Content,
+}
+
+/// The type of a referenced definition.
+#[derive(Clone, Copy)]
+enum Target {
+    /// A definition of this type.
+    Type(&'static str),
+    /// A tagged structure reference with `type` and `id` fields,
+    /// as in the subject and owner enums.
+    Structure,
+}
+
+/// Structure names used in tagged references.
+const STRUCTURES: &[(&str, &str)] = &[
+    ("Building", "traffloat.save.Building"),
+    ("Corridor", "traffloat.save.Corridor"),
+    ("Facility", "traffloat.save.Facility"),
+    ("Duct", "traffloat.save.Duct"),
+];
+
+const BUILDING: Target = Target::Type("traffloat.save.Building");
+const CORRIDOR: Target = Target::Type("traffloat.save.Corridor");
+const FACTION: Target = Target::Type("traffloat.save.Faction");
+const CONTAINER: Target = Target::Type("traffloat.save.fluid.Container");
+const FLUID_TYPE: Target = Target::Type("traffloat.save.fluid.Type");
+const PIPE: Target = Target::Type("traffloat.save.fluid.Pipe");
+
+/// Matching rules of known definition types.
+///
+/// References are JSON pointers where `*` matches every array index.
+/// Types not listed here are identified by their content.
+const RULES: &[(&str, Identity, &[(&str, Target)])] = &[
+    ("traffloat.save.Building", Identity::Fields(&["/transform/position"]), &[]),
+    (
+        "traffloat.save.Facility",
+        Identity::Fields(&["/parent", "/is_ambient", "/inner/position"]),
+        &[("/parent", BUILDING)],
+    ),
+    (
+        "traffloat.save.Corridor",
+        Identity::Fields(&["/endpoints"]),
+        &[("/endpoints/alpha", BUILDING), ("/endpoints/beta", BUILDING)],
+    ),
+    (
+        "traffloat.save.Duct",
+        Identity::Fields(&["/parent", "/is_ambient"]),
+        &[("/parent", CORRIDOR)],
+    ),
+    ("traffloat.save.Faction", Identity::Fields(&["/label"]), &[]),
+    ("traffloat.save.Alliance", Identity::Fields(&["/factions"]), &[("/factions/*", FACTION)]),
+    (
+        "traffloat.save.Ownership",
+        Identity::Fields(&["/subject"]),
+        &[("/subject", Target::Structure), ("/faction", FACTION)],
+    ),
+    (
+        "traffloat.save.Access",
+        Identity::Fields(&["/subject"]),
+        &[("/subject", Target::Structure), ("/shared/*", FACTION)],
+    ),
+    ("traffloat.save.Tags", Identity::Fields(&["/subject"]), &[("/subject", Target::Structure)]),
+    ("traffloat.save.Filter", Identity::Fields(&["/name"]), &[]),
+    ("traffloat.save.OperatingMode", Identity::Fields(&["/building"]), &[("/building", BUILDING)]),
+    (
+        "traffloat.save.ControlGroup",
+        Identity::Fields(&["/viewer", "/group"]),
+        &[("/members/*", Target::Structure)],
+    ),
+    ("traffloat.save.fluid.Type", Identity::Fields(&["/def/display_label"]), &[]),
+    (
+        "traffloat.save.fluid.Container",
+        Identity::Fields(&["/owner"]),
+        &[("/owner", Target::Structure)],
+    ),
+    (
+        "traffloat.save.fluid.ContainerElement",
+        Identity::Fields(&["/parent", "/ty"]),
+        &[("/parent", CONTAINER), ("/ty", FLUID_TYPE)],
+    ),
+    (
+        "traffloat.save.fluid.Pipe",
+        Identity::Fields(&["/containers"]),
+        &[("/containers/alpha", CONTAINER), ("/containers/beta", CONTAINER)],
+    ),
+    ("traffloat.save.fluid.Pump", Identity::Fields(&["/pipe"]), &[("/pipe", PIPE)]),
+    (
+        "traffloat.save.fluid.LiquidPump",
+        Identity::Fields(&["/pipe"]),
+        &[("/pipe", PIPE), ("/ty", FLUID_TYPE), ("/source", CONTAINER)],
+    ),
+    (
+        "traffloat.save.fluid.Farm",
+        Identity::Fields(&["/container"]),
+        &[
+            ("/container", CONTAINER),
+            ("/inputs/*/ty", FLUID_TYPE),
+            ("/byproducts/*/ty", FLUID_TYPE),
+            ("/harvest/*/ty", FLUID_TYPE),
+        ],
+    ),
+    (
+        "traffloat.save.fluid.Recycler",
+        Identity::Fields(&["/container"]),
+        &[("/container", CONTAINER), ("/input", FLUID_TYPE), ("/output", FLUID_TYPE)],
+    ),
+    ("traffloat.save.fluid.Fire", Identity::Fields(&["/container"]), &[("/container", CONTAINER)]),
+    ("traffloat.save.fluid.Miner", Identity::Fields(&["/container"]), &[("/container", CONTAINER)]),
+    (
+        "traffloat.save.fluid.Reaction",
+        Identity::Fields(&["/container"]),
+        &[("/container", CONTAINER), ("/inputs/*/ty", FLUID_TYPE), ("/outputs/*/ty", FLUID_TYPE)],
+    ),
+    (
+        "traffloat.save.fluid.Combustion",
+        Identity::Fields(&["/oxidizer/ty", "/fuel/ty"]),
+        &[("/oxidizer/ty", FLUID_TYPE), ("/fuel/ty", FLUID_TYPE), ("/products/*/ty", FLUID_TYPE)],
+    ),
+    (
+        "traffloat.save.fluid.Deposit",
+        Identity::Fields(&["/building"]),
+        &[("/building", BUILDING), ("/ty", FLUID_TYPE)],
+    ),
+    (
+        "traffloat.save.fluid.MiningArm",
+        Identity::Fields(&["/corridor"]),
+        &[("/corridor", CORRIDOR)],
+    ),
+    ("traffloat.save.fluid.Waste", Identity::Fields(&["/ty"]), &[("/ty", FLUID_TYPE)]),
+    (
+        "traffloat.save.Trigger",
+        Identity::Content,
+        &[
+            ("/condition/ty", FLUID_TYPE),
+            ("/condition/building", BUILDING),
+            ("/actions/*/container", CONTAINER),
+        ],
+    ),
+    ("traffloat.save.AssetPacks", Identity::Singleton, &[]),
+    ("traffloat.save.SimRng", Identity::Singleton, &[]),
+    ("traffloat.save.StartingCamera", Identity::Singleton, &[]),
+    ("traffloat.save.TriggerClock", Identity::Singleton, &[]),
+    ("traffloat.save.Tunables", Identity::Singleton, &[]),
+    ("traffloat.save.WorldBounds", Identity::Singleton, &[]),
+    ("traffloat.save.WorldSeed", Identity::Singleton, &[]),
+    ("traffloat.save.fluid.PhysicsConfig", Identity::Singleton, &[]),
+    ("traffloat.save.fluid.ScalarConfig", Identity::Singleton, &[]),
+];
+
+/// Resolves the definitions of a save into their keys and values with resolved references.
+pub(crate) fn resolve(defs: &Defs) -> BTreeMap<String, BTreeMap<String, Value>> {
+    let mut resolver = Resolver { defs, resolved: HashMap::new(), resolving: HashSet::new() };
+    for ty in defs.keys() {
+        resolver.resolve_type(ty);
+    }
+
+    resolver
+        .resolved
+        .into_iter()
+        .map(|(ty, defs)| (ty.to_string(), defs.into_iter().collect()))
+        .collect()
+}
+
+struct Resolver<'a> {
+    defs:      &'a Defs,
+    /// Keys and resolved values of each type, in the order of save IDs.
+    resolved:  HashMap<&'a str, Vec<(String, Value)>>,
+    /// Types being resolved, to break reference cycles.
+    resolving: HashSet<&'a str>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Returns the full key of a referenced definition,
+    /// or `None` if it cannot be resolved, in which case the save ID is kept.
+    fn reference(&mut self, ty: &'a str, id: &Value) -> Option<String> {
+        let id = usize::try_from(id.as_u64()?).ok()?;
+        self.resolve_type(ty);
+        let (key, _) = self.resolved.get(ty)?.get(id)?;
+        Some(format!("{ty}{key}"))
+    }
+
+    fn resolve_type(&mut self, ty: &str) {
+        let Some((ty, defs)) = self.defs.get_key_value(ty) else { return };
+        let ty = ty.as_str();
+        if self.resolved.contains_key(ty) || !self.resolving.insert(ty) {
+            return;
+        }
+
+        let (identity, refs) = RULES
+            .iter()
+            .find(|&&(rule_ty, _, _)| rule_ty == ty)
+            .map_or((&Identity::Content, &[][..]), |(_, identity, refs)| (identity, refs));
+
+        let mut counts = HashMap::<String, usize>::new();
+        let resolved = defs
+            .iter()
+            .map(|def| {
+                let mut value = def.clone();
+                for &(pointer, target) in refs {
+                    self.resolve_refs(&mut value, pointer, target);
+                }
+
+                let mut key = match identity {
+                    Identity::Singleton => String::new(),
+                    Identity::Fields(pointers) => {
+                        let fields: Vec<_> = pointers
+                            .iter()
+                            .map(|&pointer| {
+                                let field = value.pointer(pointer).map_or_else(
+                                    || "(absent)".to_string(),
+                                    |field| match field {
+                                        Value::String(string) => string.clone(),
+                                        field => field.to_string(),
+                                    },
+                                );
+                                format!("{pointer}={field}")
+                            })
+                            .collect();
+                        format!("[{}]", fields.join(", "))
+                    }
+                    Identity::Content => format!("[{value}]"),
+                };
+
+                let count = counts.entry(key.clone()).or_default();
+                if *count > 0 {
+                    key = format!("{key}#{count}");
+                }
+                *count += 1;
+
+                (key, value)
+            })
+            .collect();
+
+        self.resolving.remove(ty);
+        self.resolved.insert(ty, resolved);
+    }
+
+    /// Replaces the save IDs at `pointer` with the keys of the referenced definitions.
+    fn resolve_refs(&mut self, value: &mut Value, pointer: &str, target: Target) {
+        let mut matches = vec![value];
+        for segment in pointer.split('/').skip(1) {
+            matches = matches
+                .into_iter()
+                .flat_map(|parent| match (segment, parent) {
+                    ("*", Value::Array(items)) => items.iter_mut().collect(),
+                    (segment, parent) => parent.get_mut(segment).into_iter().collect::<Vec<_>>(),
+                })
+                .collect();
+        }
+
+        for matched in matches {
+            let (ty, id) = match target {
+                Target::Type(ty) => (ty, matched),
+                Target::Structure => {
+                    let Some(&(_, ty)) =
+                        matched.get("type").and_then(Value::as_str).and_then(|name| {
+                            STRUCTURES.iter().find(|&&(structure, _)| structure == name)
+                        })
+                    else {
+                        continue;
+                    };
+                    let Some(id) = matched.get_mut("id") else { continue };
+                    (ty, id)
+                }
+            };
+            if let Some(key) = self.reference(ty, id) {
+                *id = Value::String(key);
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Prints a semantic diff between two saves.
+//!
+//! Definitions are matched by type and a [key](key) built from their identity fields,
+//! so that inserting or removing a definition does not shift the others.
+//! References to other definitions are compared by the key of the referenced definition
+//! instead of its save ID.
+//! Changed definitions are reported field by field with JSON pointer paths.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::Context;
+use clap::Parser as _;
+use serde::Serialize;
+use serde_json::Value;
+use traffloat_base::save;
+
+mod key;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(clap::Parser)]
+#[command(name = "traffloat-save-diff", version = traffloat_version::VERSION, about)]
+struct Options {
+    /// The original save.
+    old:    PathBuf,
+    /// The changed save.
+    new:    PathBuf,
+    /// Output format.
+    #[clap(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    /// One line per change.
+    Text,
+    /// A JSON array of changes.
+    Json,
+}
+
+/// A difference between the two saves.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Change {
+    /// A definition only present in the new save.
+    Added { r#type: String, key: String, value: Value },
+    /// A definition only present in the old save.
+    Removed { r#type: String, key: String, value: Value },
+    /// A field that differs between the two saves.
+    Modified {
+        r#type: String,
+        key:    String,
+        /// JSON pointer to the field within the definition.
+        path:   String,
+        /// The old value, absent if the field was added.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        old:    Option<Value>,
+        /// The new value, absent if the field was removed.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new:    Option<Value>,
+    },
+}
+
+type Defs = BTreeMap<String, Vec<Value>>;
+
+fn main() -> anyhow::Result<ExitCode> {
+    let options = Options::parse();
+
+    let old = read(&options.old)?;
+    let new = read(&options.new)?;
+    let changes = diff(&old, &new);
+
+    match options.format {
+        OutputFormat::Text => {
+            for change in &changes {
+                print_text(change);
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&changes).context("encode changes")?);
+        }
+    }
+
+    Ok(if changes.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+fn diff(old: &Defs, new: &Defs) -> Vec<Change> {
+    let old = key::resolve(old);
+    let new = key::resolve(new);
+
+    let mut changes = Vec::new();
+    let types: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for ty in types {
+        let empty = BTreeMap::new();
+        let old_defs = old.get(ty).unwrap_or(&empty);
+        let new_defs = new.get(ty).unwrap_or(&empty);
+
+        let keys: BTreeSet<&String> = old_defs.keys().chain(new_defs.keys()).collect();
+        for key in keys {
+            match (old_defs.get(key), new_defs.get(key)) {
+                (Some(old), Some(new)) => {
+                    diff_value(&mut String::new(), Some(old), Some(new), &mut |path, old, new| {
+                        changes.push(Change::Modified {
+                            r#type: ty.clone(),
+                            key:    key.clone(),
+                            path:   path.to_string(),
+                            old:    old.cloned(),
+                            new:    new.cloned(),
+                        });
+                    });
+                }
+                (Some(old), None) => changes.push(Change::Removed {
+                    r#type: ty.clone(),
+                    key:    key.clone(),
+                    value:  old.clone(),
+                }),
+                (None, Some(new)) => changes.push(Change::Added {
+                    r#type: ty.clone(),
+                    key:    key.clone(),
+                    value:  new.clone(),
+                }),
+                (None, None) => unreachable!("key is from either save"),
+            }
+        }
+    }
+    changes
+}
+
+fn read(path: &Path) -> anyhow::Result<Defs> {
+    let buf = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    save::decode_untyped(&buf).with_context(|| format!("decode {}", path.display()))
+}
+
+/// Reports each differing leaf between `old` and `new` with its JSON pointer.
+///
+/// Objects are compared by key and arrays by index;
+/// all other values are compared as a whole.
+fn diff_value(
+    path: &mut String,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    report: &mut impl FnMut(&str, Option<&Value>, Option<&Value>),
+) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                diff_value(path, old.get(key), new.get(key), report);
+                path.truncate(len);
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for index in 0..old.len().max(new.len()) {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&index.to_string());
+                diff_value(path, old.get(index), new.get(index), report);
+                path.truncate(len);
+            }
+        }
+        (old, new) if old != new => report(path, old, new),
+        _ => {}
+    }
+}
+
+fn print_text(change: &Change) {
+    match change {
+        Change::Added { r#type: ty, key, value } => println!("+ {ty}{key} {value}"),
+        Change::Removed { r#type: ty, key, value } => println!("- {ty}{key} {value}"),
+        Change::Modified { r#type: ty, key, path, old, new } => {
+            let show = |value: &Option<Value>| {
+                value.as_ref().map_or_else(|| "(absent)".to_string(), Value::to_string)
+            };
+            println!("~ {ty}{key} {path}: {} -> {}", show(old), show(new));
+        }
+    }
+}
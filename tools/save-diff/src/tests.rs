@@ -0,0 +1,95 @@
+use serde_json::{json, Value};
+
+use super::{diff, Change, Defs};
+
+const BUILDING: &str = "traffloat.save.Building";
+const FACILITY: &str = "traffloat.save.Facility";
+
+fn building(x: f32, label: &str) -> Value {
+    json!({
+        "transform": {"position": {"x": x, "y": 0., "z": 0.}},
+        "appearance": {"label": label},
+    })
+}
+
+fn facility(parent: usize) -> Value {
+    json!({
+        "parent": parent,
+        "inner": {"position": {"x": 0., "y": 0., "z": 0.}},
+        "appearance": {"label": "ambient"},
+        "is_ambient": true,
+    })
+}
+
+fn save(buildings: Vec<Value>, facilities: Vec<Value>) -> Defs {
+    [(BUILDING.into(), buildings), (FACILITY.into(), facilities)].into()
+}
+
+fn base() -> Defs {
+    save(vec![building(0., "Core"), building(10., "Outpost")], vec![facility(0), facility(1)])
+}
+
+#[test]
+fn unchanged() {
+    assert_eq!(diff(&base(), &base()), []);
+}
+
+#[test]
+fn insert_before_existing() {
+    let new = save(
+        vec![building(-10., "Depot"), building(0., "Core"), building(10., "Outpost")],
+        vec![facility(0), facility(1), facility(2)],
+    );
+
+    let changes = diff(&base(), &new);
+    let added: Vec<_> = changes
+        .iter()
+        .map(|change| match change {
+            Change::Added { r#type, key, .. } => (r#type.as_str(), key.as_str()),
+            change => panic!("unexpected change {change:?}"),
+        })
+        .collect();
+    assert_eq!(added.len(), 2, "{changes:?}");
+    assert_eq!(added[0].0, BUILDING);
+    assert!(added[0].1.contains("\"x\":-10.0"), "{changes:?}");
+    assert_eq!(added[1].0, FACILITY);
+    assert!(added[1].1.contains("\"x\":-10.0"), "facility key should name its parent: {changes:?}");
+}
+
+#[test]
+fn remove_existing() {
+    let new = save(vec![building(10., "Outpost")], vec![facility(0)]);
+
+    let changes = diff(&base(), &new);
+    assert_eq!(changes.len(), 2, "{changes:?}");
+    for change in &changes {
+        let Change::Removed { key, .. } = change else { panic!("unexpected change {change:?}") };
+        assert!(key.contains("\"x\":0.0"), "{changes:?}");
+    }
+}
+
+#[test]
+fn modify_field() {
+    let new =
+        save(vec![building(0., "Core"), building(10., "Mine")], vec![facility(0), facility(1)]);
+
+    let [Change::Modified { r#type, key, path, old, new }] = &diff(&base(), &new)[..] else {
+        panic!("expected a single modification");
+    };
+    assert_eq!(r#type, BUILDING);
+    assert!(key.contains("\"x\":10.0"), "{key}");
+    assert_eq!(path, "/appearance/label");
+    assert_eq!(old, &Some(json!("Outpost")));
+    assert_eq!(new, &Some(json!("Mine")));
+}
+
+#[test]
+fn number_duplicate_keys() {
+    let old = save(vec![building(0., "Core")], vec![facility(0), facility(0)]);
+    let new = save(vec![building(0., "Core")], vec![facility(0)]);
+
+    let [Change::Removed { key, .. }] = &diff(&old, &new)[..] else {
+        panic!("expected a single removal");
+    };
+    assert!(key.ends_with("#1"), "{key}");
+}
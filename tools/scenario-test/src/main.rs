@@ -0,0 +1,273 @@
+//! Runs scenarios headlessly and checks expected outcomes.
+//!
+//! Each test file is a TOML document naming a scenario and a list of expectations:
+//!
+//! ```toml
+//! scenario = "../assets/basic.tfsave"
+//!
+//! [[expect]]
+//! after    = 1000
+//! building = "core"
+//! quantity = "pressure"
+//! min      = 90.0
+//! max      = 110.0
+//! ```
+//!
+//! The scenario path is relative to the test file.
+//! Containers are addressed by a [tag](traffloat_graph::tag) of their building
+//! and the index of the facility, where 0 (the default) is the ambient facility.
+//! `after` is the number of simulation ticks since the scenario was loaded.
+//! The process exits with failure if any expectation is not met.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{fmt, fs};
+
+use anyhow::Context;
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::{Command, World};
+use bevy::hierarchy::Children;
+use bevy::state::app::{AppExtStates, StatesPlugin};
+use clap::Parser as _;
+use serde::Deserialize;
+use traffloat_base::{save, EmptyState};
+use traffloat_fluid::{config, container, hazard};
+use traffloat_graph::{building, tag};
+
+#[derive(clap::Parser)]
+#[command(name = "traffloat-scenario-test", version = traffloat_version::VERSION, about)]
+struct Options {
+    /// The test files to run.
+    #[clap(required = true)]
+    tests: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestFile {
+    /// Path to the scenario, relative to the test file.
+    scenario: PathBuf,
+    #[serde(default)]
+    expect:   Vec<Expectation>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expectation {
+    /// Number of ticks since the scenario was loaded.
+    after:    u32,
+    /// Tag of the building.
+    building: String,
+    /// Index of the facility in the building, where 0 is the ambient facility.
+    #[serde(default)]
+    facility: usize,
+    quantity: Quantity,
+    /// Display label of the fluid type to measure the mass of.
+    ///
+    /// Only applicable to [`Quantity::Mass`].
+    /// The total mass of all fluids is measured if unspecified.
+    fluid:    Option<String>,
+    /// Inclusive lower bound.
+    min:      Option<f32>,
+    /// Inclusive upper bound.
+    max:      Option<f32>,
+}
+
+impl Expectation {
+    fn accepts(&self, value: f32) -> bool {
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.quantity)?;
+        if let Some(fluid) = &self.fluid {
+            write!(f, " of {fluid}")?;
+        }
+        write!(f, " in {}#{} after {} ticks", self.building, self.facility, self.after)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Quantity {
+    /// Overall pressure of the container.
+    Pressure,
+    /// Volume occupied by fluids in the container.
+    Volume,
+    /// Mass of fluids in the container.
+    Mass,
+    /// [Hazard score](hazard::Hazard::score) of the container.
+    Hazard,
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pressure => "pressure",
+            Self::Volume => "volume",
+            Self::Mass => "mass",
+            Self::Hazard => "hazard",
+        })
+    }
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let options = Options::parse();
+
+    let mut failures = 0;
+    for path in &options.tests {
+        failures += run_file(path)?;
+    }
+
+    if failures > 0 {
+        eprintln!("{failures} expectations failed");
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Runs a test file, returning the number of failed expectations.
+fn run_file(path: &Path) -> anyhow::Result<usize> {
+    let source = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut test: TestFile =
+        toml::from_str(&source).with_context(|| format!("parse {}", path.display()))?;
+
+    let scenario = path.parent().unwrap_or(Path::new(".")).join(&test.scenario);
+    let data = fs::read(&scenario).with_context(|| format!("read {}", scenario.display()))?;
+
+    let mut app = new_app();
+    load(&mut app, data).with_context(|| format!("load {}", scenario.display()))?;
+
+    test.expect.sort_by_key(|expectation| expectation.after);
+
+    let mut tick = 0;
+    let mut failures = 0;
+    for expectation in &test.expect {
+        while tick < expectation.after {
+            app.update();
+            tick += 1;
+        }
+
+        match measure(app.world(), expectation) {
+            Ok(value) if expectation.accepts(value) => {
+                println!("PASS {}: {expectation} = {value}", path.display());
+            }
+            Ok(value) => {
+                let min = expectation.min.map_or_else(|| "-inf".into(), |min| min.to_string());
+                let max = expectation.max.map_or_else(|| "inf".into(), |max| max.to_string());
+                println!(
+                    "FAIL {}: {expectation} = {value}, expected [{min}, {max}]",
+                    path.display()
+                );
+                failures += 1;
+            }
+            Err(err) => {
+                println!("FAIL {}: {expectation}: {err:#}", path.display());
+                failures += 1;
+            }
+        }
+    }
+
+    Ok(failures)
+}
+
+/// Creates an app with the simulation plugins but without rendering or input.
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        bevy::MinimalPlugins,
+        StatesPlugin,
+        traffloat_base::save::Plugin,
+        traffloat_view::Plugin,
+        traffloat_graph::Plugin,
+        traffloat_fluid::Plugin(EmptyState),
+    ));
+    app.init_state::<EmptyState>();
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[derive(Resource)]
+struct LoadResultSlot(save::LoadResult);
+
+fn load(app: &mut App, data: Vec<u8>) -> anyhow::Result<()> {
+    save::LoadCommand {
+        data,
+        on_complete: Box::new(|world, result| world.insert_resource(LoadResultSlot(result))),
+    }
+    .apply(app.world_mut());
+    let LoadResultSlot(result) =
+        app.world_mut().remove_resource().expect("LoadCommand always invokes on_complete");
+    Ok(result?)
+}
+
+fn measure(world: &World, expectation: &Expectation) -> anyhow::Result<f32> {
+    if expectation.fluid.is_some() && !matches!(expectation.quantity, Quantity::Mass) {
+        anyhow::bail!("fluid is only applicable to mass");
+    }
+
+    let facility = find_facility(world, &expectation.building, expectation.facility)?;
+    anyhow::ensure!(
+        world.get::<container::Marker>(facility).is_some(),
+        "facility has no fluid container"
+    );
+
+    Ok(match expectation.quantity {
+        Quantity::Pressure => {
+            world.get::<container::CurrentPressure>(facility).context("pressure")?.pressure.quantity
+        }
+        Quantity::Volume => {
+            world.get::<container::CurrentVolume>(facility).context("volume")?.volume.quantity
+        }
+        Quantity::Hazard => world.get::<hazard::Hazard>(facility).context("hazard")?.score,
+        Quantity::Mass => {
+            let mut total = 0.;
+            for &element in world.get::<Children>(facility).into_iter().flatten() {
+                let (Some(ty), Some(mass)) = (
+                    world.get::<config::Type>(element),
+                    world.get::<container::element::Mass>(element),
+                ) else {
+                    continue;
+                };
+                if let Some(fluid) = &expectation.fluid {
+                    let mut label = String::new();
+                    world
+                        .get::<config::TypeDef>(ty.0)
+                        .context("dangling fluid type")?
+                        .display_label
+                        .render(&mut label);
+                    if label != *fluid {
+                        continue;
+                    }
+                }
+                total += mass.mass.quantity;
+            }
+            total
+        }
+    })
+}
+
+/// Finds the facility at `index` of the only building tagged `building_tag`.
+fn find_facility(world: &World, building_tag: &str, index: usize) -> anyhow::Result<Entity> {
+    let tagged = world.resource::<tag::Index>().get(&tag::Tag(building_tag.to_owned()));
+    let mut buildings = tagged
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&entity| world.get::<building::Marker>(entity).is_some());
+
+    let building =
+        buildings.next().with_context(|| format!("no building is tagged {building_tag:?}"))?;
+    anyhow::ensure!(buildings.next().is_none(), "multiple buildings are tagged {building_tag:?}");
+
+    let facilities =
+        world.get::<building::FacilityList>(building).context("building has no facility list")?;
+    let count = facilities.iter().count();
+    facilities.iter().nth(index).with_context(|| format!("building only has {count} facilities"))
+}
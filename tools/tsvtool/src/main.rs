@@ -0,0 +1,180 @@
+//! Inspects saves and scenarios without launching the game.
+//!
+//! Definitions are addressed as `TYPE#ID`,
+//! the same notation printed by `traffloat-save-diff`.
+//! Saves from the legion-based engine are [imported](traffloat_legacy)
+//! and inspected as the definitions they are converted into.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser as _;
+use serde_json::Value;
+use traffloat_base::save;
+
+#[derive(clap::Parser)]
+#[command(name = "tsvtool", version = traffloat_version::VERSION, about)]
+struct Options {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Prints the format, definition counts and a state summary.
+    Info {
+        /// The save file.
+        file: PathBuf,
+    },
+    /// Lists definitions with a one-line preview.
+    ListDefs {
+        /// The save file.
+        file: PathBuf,
+        /// Only lists types with this full name, prefix or dot-separated segment, e.g. `fluid`.
+        #[clap(long = "type")]
+        ty:   Option<String>,
+    },
+    /// Prints a single definition as pretty JSON.
+    ExtractDef {
+        /// The save file.
+        file: PathBuf,
+        /// The definition to print, as `TYPE#ID`.
+        def:  String,
+    },
+}
+
+/// Maximum length of a definition preview in `list-defs`.
+const PREVIEW_LEN: usize = 100;
+
+#[cfg(test)]
+mod tests;
+
+fn main() -> anyhow::Result<()> {
+    let options = Options::parse();
+
+    let (Command::Info { file }
+    | Command::ListDefs { file, .. }
+    | Command::ExtractDef { file, .. }) = &options.command;
+    let buf = fs::read(file).with_context(|| format!("read {}", file.display()))?;
+    let mut out = io::stdout().lock();
+
+    match &options.command {
+        Command::Info { .. } => {
+            writeln!(out, "File: {}", file.display())?;
+            info(&buf, &mut out)
+        }
+        Command::ListDefs { ty, .. } => list_defs(&buf, ty.as_deref(), &mut out),
+        Command::ExtractDef { def, .. } => extract_def(&buf, def, &mut out),
+    }
+    .with_context(|| format!("inspect {}", file.display()))
+}
+
+fn info(buf: &[u8], out: &mut impl Write) -> anyhow::Result<()> {
+    writeln!(out, "Size: {} bytes", buf.len())?;
+    writeln!(out, "Format: {}", describe_format(buf))?;
+
+    let types = decode(buf)?;
+    let total: usize = types.values().map(Vec::len).sum();
+    writeln!(out, "Definitions: {total} in {} types", types.len())?;
+    for (ty, defs) in &types {
+        writeln!(out, "  {ty}: {}", defs.len())?;
+    }
+
+    let mut namespaces = BTreeMap::<&str, usize>::new();
+    for (ty, defs) in &types {
+        let namespace = ty.rsplit_once('.').map_or(ty.as_str(), |(namespace, _)| namespace);
+        *namespaces.entry(namespace).or_default() += defs.len();
+    }
+    writeln!(out, "Summary:")?;
+    for (namespace, count) in namespaces {
+        writeln!(out, "  {namespace}: {count} definitions")?;
+    }
+    for def in types.get("traffloat.save.Tunables").into_iter().flatten() {
+        let overrides =
+            def.get("values").and_then(Value::as_object).map_or(0, serde_json::Map::len);
+        writeln!(out, "  tunable overrides: {overrides}")?;
+    }
+    for def in types.get("traffloat.save.AssetPacks").into_iter().flatten() {
+        for pack in def.get("packs").and_then(Value::as_array).into_iter().flatten() {
+            let field =
+                |key: &str| pack.get(key).and_then(Value::as_str).unwrap_or("?").to_string();
+            writeln!(out, "  asset pack: {} ({})", field("name"), field("sha256"))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn list_defs(buf: &[u8], filter: Option<&str>, out: &mut impl Write) -> anyhow::Result<()> {
+    let types = decode(buf)?;
+
+    for (ty, defs) in &types {
+        if filter.is_some_and(|filter| !type_matches(ty, filter)) {
+            continue;
+        }
+
+        for (id, def) in defs.iter().enumerate() {
+            let mut preview = def.to_string();
+            if preview.len() > PREVIEW_LEN {
+                let mut end = PREVIEW_LEN;
+                while !preview.is_char_boundary(end) {
+                    end -= 1;
+                }
+                preview.truncate(end);
+                preview.push('…');
+            }
+            writeln!(out, "{ty}#{id} {preview}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn extract_def(buf: &[u8], def: &str, out: &mut impl Write) -> anyhow::Result<()> {
+    let (ty, id) = def.rsplit_once('#').context("definition must be written as TYPE#ID")?;
+    let id: usize = id.parse().with_context(|| format!("invalid definition ID {id:?}"))?;
+
+    let types = decode(buf)?;
+    let defs = types.get(ty).with_context(|| format!("save has no definitions of type {ty}"))?;
+    let value =
+        defs.get(id).with_context(|| format!("{ty} only has {} definitions", defs.len()))?;
+    writeln!(out, "{}", serde_json::to_string_pretty(value).context("encode definition")?)?;
+
+    Ok(())
+}
+
+/// Whether `ty` is `filter`, starts with `filter` or has `filter` as a dot-separated segment.
+fn type_matches(ty: &str, filter: &str) -> bool {
+    ty.starts_with(filter) || ty.split('.').any(|segment| segment == filter)
+}
+
+fn describe_format(buf: &[u8]) -> String {
+    if buf.starts_with(save::LEGACY_TFSAVE_HEADER) {
+        "legacy (legion-based engine), shown as imported".into()
+    } else if let Some(tagged) = buf.strip_prefix(save::MSGPACK_COMPRESSED_HEADER) {
+        // tags as written by `save::Compression`
+        let compression = match tagged.first() {
+            Some(0) => "uncompressed".into(),
+            Some(1) => "DEFLATE".into(),
+            Some(2) => "Zstandard".into(),
+            Some(tag) => format!("unknown compression {tag}"),
+            None => "missing compression".into(),
+        };
+        format!("Msgpack, {compression}")
+    } else if buf.starts_with(save::MSGPACK_HEADER) {
+        "Msgpack v1, DEFLATE".into()
+    } else {
+        "JSON".into()
+    }
+}
+
+fn decode(buf: &[u8]) -> anyhow::Result<BTreeMap<String, Vec<Value>>> {
+    if traffloat_legacy::is_legacy(buf) {
+        let imported = traffloat_legacy::import(buf).context("import legacy save")?;
+        return save::decode_untyped(&imported).context("decode imported legacy save");
+    }
+    save::decode_untyped(buf).context("decode save")
+}
@@ -0,0 +1,76 @@
+use traffloat_base::save;
+use traffloat_legacy::schema;
+
+use super::{extract_def, info, list_defs};
+
+fn json_save() -> Vec<u8> {
+    let mut builder = save::JsonBuilder::default();
+    builder.add(save::seed::Save { seed: 42 }).unwrap();
+    builder.build().unwrap()
+}
+
+fn legacy_save() -> Vec<u8> {
+    traffloat_legacy::encode(&schema::TfsaveFile {
+        def:   schema::Def {
+            gas: vec![schema::FluidDef { id: "oxygen".into(), name: "Oxygen".into() }],
+            building: vec![schema::BuildingDef {
+                id:      "core".into(),
+                name:    "Core".into(),
+                storage: schema::BuildingStorage { gas: 1000., liquid: Vec::new() },
+            }],
+            ..schema::Def::default()
+        },
+        state: schema::State {
+            nodes: vec![schema::Node {
+                id:       1,
+                building: "core".into(),
+                name:     None,
+                position: [0., 0., 0.],
+                gas:      [("oxygen".into(), 100.)].into(),
+                liquid:   Vec::new(),
+            }],
+            edges: Vec::new(),
+        },
+    })
+    .unwrap()
+}
+
+fn output(f: impl FnOnce(&mut Vec<u8>) -> anyhow::Result<()>) -> String {
+    let mut out = Vec::new();
+    f(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn info_json() {
+    let out = output(|out| info(&json_save(), out));
+    assert!(out.contains("Format: JSON\n"), "{out}");
+    assert!(out.contains("  traffloat.save.WorldSeed: 1\n"), "{out}");
+}
+
+#[test]
+fn info_legacy() {
+    let out = output(|out| info(&legacy_save(), out));
+    assert!(out.contains("Format: legacy (legion-based engine), shown as imported\n"), "{out}");
+    assert!(out.contains("  traffloat.save.Building: 1\n"), "{out}");
+    assert!(out.contains("  traffloat.save.fluid.ContainerElement: 1\n"), "{out}");
+}
+
+#[test]
+fn list_legacy_defs_by_type() {
+    let out = output(|out| list_defs(&legacy_save(), Some("Building"), out));
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 1, "{out}");
+    assert!(lines[0].starts_with("traffloat.save.Building#0 "), "{out}");
+}
+
+#[test]
+fn extract_defs() {
+    let out = output(|out| extract_def(&json_save(), "traffloat.save.WorldSeed#0", out));
+    assert_eq!(out, "{\n  \"seed\": 42\n}\n");
+
+    let out = output(|out| extract_def(&legacy_save(), "traffloat.save.Building#0", out));
+    assert!(out.contains("\"Core\""), "{out}");
+
+    assert!(extract_def(&json_save(), "traffloat.save.WorldSeed#1", &mut Vec::new()).is_err());
+}
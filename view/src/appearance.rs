@@ -26,6 +26,10 @@ pub struct Appearance {
     /// The appearance of the viewable when the viewport camera
     /// is within the bounds of the object.
     pub interior: Layer,
+
+    /// The visible fluid tank inside the viewable, if any.
+    #[serde(default)]
+    pub tank: Option<Tank>,
 }
 
 impl Appearance {
@@ -37,6 +41,7 @@ impl Appearance {
             distal:   Layer::Null,
             proximal: Layer::Null,
             interior: Layer::Null,
+            tank:     None,
         }
     }
 }
@@ -63,6 +68,22 @@ pub enum Layer {
     // },
 }
 
+/// A fluid tank rendered with its fill level.
+///
+/// The tank mesh represents the fluid when the container is full.
+/// It is scaled along the Y axis from `bottom` to `top` by the fill level of the container,
+/// and colored by the dominant fluid in the container.
+/// Only effective on viewables that are also fluid containers, e.g. storage facilities.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub struct Tank {
+    /// The fluid mesh at full level.
+    pub mesh:   GlbMeshRef,
+    /// The Y coordinate of the tank floor in model space.
+    pub bottom: f32,
+    /// The Y coordinate of the tank ceiling in model space.
+    pub top:    f32,
+}
+
 /// Reference to a image file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Serialize, Deserialize, JsonSchema)]
 pub struct ImageRef {
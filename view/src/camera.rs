@@ -0,0 +1,88 @@
+//! Scenario-defined starting camera and intro.
+//!
+//! Clients position their camera at [`StartingCamera::transform`] when a save is loaded,
+//! then play the [intro keyframes](StartingCamera::intro)
+//! before handing camera control to the player.
+
+use bevy::app::{self, App};
+use bevy::ecs::system::{Res, Resource};
+use bevy::ecs::world::World;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use traffloat_base::{proto, save};
+
+use crate::DisplayText;
+
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) { save::add_def::<Save>(app); }
+}
+
+/// The initial camera of the loaded scenario.
+///
+/// Absent if the scenario does not specify a starting camera.
+#[derive(Debug, Clone, Resource)]
+pub struct StartingCamera {
+    /// The camera transform before the intro.
+    pub transform: proto::Transform,
+    /// Keyframes of the intro, played in order.
+    pub intro:     Vec<Keyframe>,
+}
+
+/// A step of the intro.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Keyframe {
+    /// The camera transform at the end of this step.
+    pub transform: proto::Transform,
+    /// Seconds taken to move from the previous keyframe to this keyframe.
+    pub duration:  f32,
+    /// Caption displayed during this step.
+    #[serde(default)]
+    pub caption:   Option<DisplayText>,
+}
+
+/// Save schema.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Save {
+    /// The camera transform before the intro.
+    pub transform: proto::Transform,
+    /// Keyframes of the intro, played in order.
+    #[serde(default)]
+    pub intro:     Vec<Keyframe>,
+}
+
+impl save::Def for Save {
+    const TYPE: &'static str = "traffloat.save.StartingCamera";
+
+    type Runtime = ();
+
+    fn store_system() -> impl save::StoreSystem<Def = Self> {
+        fn store_system(
+            mut writer: save::Writer<Save>,
+            (): (),
+            camera: Option<Res<StartingCamera>>,
+        ) {
+            if let Some(camera) = camera {
+                writer.write((), Save { transform: camera.transform, intro: camera.intro.clone() });
+            }
+        }
+
+        save::StoreSystemFn::new(store_system)
+    }
+
+    fn loader() -> impl save::LoadOnce<Def = Self> {
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn loader(world: &mut World, def: Save, (): &()) -> anyhow::Result<()> {
+            anyhow::ensure!(
+                def.intro.iter().all(|keyframe| keyframe.duration >= 0.),
+                "intro keyframe duration must not be negative"
+            );
+            world
+                .insert_resource(StartingCamera { transform: def.transform, intro: def.intro });
+            Ok(())
+        }
+
+        save::LoadFn::new(loader)
+    }
+}
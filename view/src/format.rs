@@ -0,0 +1,207 @@
+//! Formatting of metric values for display.
+//!
+//! A metric type declares the [`Unit`] of its values under the [`UNIT_METADATA`] key.
+//! Clients render values through the [`Formatter`] resource,
+//! which applies SI prefixes, unit symbols and locale-specific separators,
+//! so that every UI element shows the same quantity in the same way.
+
+use std::fmt::Write;
+
+use bevy::ecs::system::Resource;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::metrics::MetadataKey;
+
+#[cfg(test)]
+mod tests;
+
+/// Metadata key of the [`Unit`] of a metric type.
+pub const UNIT_METADATA: MetadataKey = MetadataKey::new("traffloat.unit");
+
+/// The physical unit of a metric value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Unit {
+    /// A dimensionless number.
+    #[default]
+    Scalar,
+    /// Mass in kilograms.
+    Mass,
+    /// Volume in cubic meters.
+    Volume,
+    /// Pressure in pascals.
+    Pressure,
+    /// A proportion where 1 is 100%.
+    Ratio,
+    /// Duration in seconds.
+    Duration,
+}
+
+impl Unit {
+    /// Returns the metadata entry declaring this unit.
+    #[must_use]
+    pub fn metadata(self) -> (MetadataKey, JsonValue) {
+        (UNIT_METADATA, serde_json::to_value(self).expect("unit is always serializable"))
+    }
+
+    /// Reads the unit declared in the metadata of a metric type.
+    ///
+    /// Returns [`Unit::Scalar`] if the metadata does not declare a valid unit.
+    #[must_use]
+    pub fn from_metadata(metadata: &HashMap<MetadataKey, JsonValue>) -> Self {
+        metadata
+            .get(&UNIT_METADATA)
+            .and_then(|value| Self::deserialize(value).ok())
+            .unwrap_or_default()
+    }
+
+    /// The symbol that SI prefixes are applied to,
+    /// and the factor converting a value into that symbol.
+    fn symbol(self) -> (&'static str, f64) {
+        match self {
+            Self::Scalar | Self::Ratio | Self::Duration => ("", 1.),
+            Self::Mass => ("g", 1e3),
+            Self::Volume => ("L", 1e3),
+            Self::Pressure => ("Pa", 1.),
+        }
+    }
+}
+
+/// Separators used when formatting numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    /// Separates the integer and fractional parts.
+    pub decimal_separator: char,
+    /// Separates groups of three digits in the integer part.
+    pub group_separator:   Option<char>,
+}
+
+impl Locale {
+    /// `1,234.5`
+    pub const ENGLISH: Self = Self { decimal_separator: '.', group_separator: Some(',') };
+    /// `1.234,5`
+    pub const CONTINENTAL: Self = Self { decimal_separator: ',', group_separator: Some('.') };
+    /// `1 234,5` with a narrow no-break space.
+    pub const FRENCH: Self = Self { decimal_separator: ',', group_separator: Some('\u{202f}') };
+
+    /// Selects the separators for a language tag such as `en-US` or `de_DE.UTF-8`.
+    ///
+    /// Returns `None` for unknown languages.
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_', '.']).next().unwrap_or_default().to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "ja" | "ko" | "zh" | "c" | "posix" => Some(Self::ENGLISH),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" => Some(Self::CONTINENTAL),
+            "fr" | "pl" | "ru" | "sv" | "cs" | "fi" | "nb" | "uk" => Some(Self::FRENCH),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self { Self::ENGLISH }
+}
+
+const SI_PREFIXES: [(i32, &str); 7] =
+    [(12, "T"), (9, "G"), (6, "M"), (3, "k"), (0, ""), (-3, "m"), (-6, "µ")];
+
+/// Formats metric values for display.
+#[derive(Debug, Clone, Resource)]
+pub struct Formatter {
+    /// Separators for numbers.
+    pub locale:   Locale,
+    /// Number of fractional digits after applying SI prefixes.
+    pub decimals: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Self { Self { locale: Locale::default(), decimals: 2 } }
+}
+
+impl Formatter {
+    /// Formats a value with its unit.
+    #[must_use]
+    pub fn format(&self, value: f32, unit: Unit) -> String {
+        let value = f64::from(value);
+        match unit {
+            Unit::Ratio => format!("{}%", self.format_number(value * 100., self.decimals)),
+            Unit::Duration => self.format_duration(value),
+            _ => {
+                let (symbol, factor) = unit.symbol();
+                let (scaled, prefix) = with_si_prefix(value * factor);
+                let number = self.format_number(scaled, self.decimals);
+                if symbol.is_empty() && prefix.is_empty() {
+                    number
+                } else {
+                    format!("{number} {prefix}{symbol}")
+                }
+            }
+        }
+    }
+
+    /// Formats a number with a fixed number of fractional digits and locale separators.
+    #[must_use]
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        let digits = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+        let mut output = String::new();
+        if value < 0. && digits.chars().any(|ch| ch.is_ascii_digit() && ch != '0') {
+            output.push('-');
+        }
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                if let Some(separator) = self.locale.group_separator {
+                    output.push(separator);
+                }
+            }
+            output.push(digit);
+        }
+        if !fraction.is_empty() {
+            output.push(self.locale.decimal_separator);
+            output.push_str(fraction);
+        }
+        output
+    }
+
+    fn format_duration(&self, seconds: f64) -> String {
+        if seconds.abs() < 60. {
+            return format!("{} s", self.format_number(seconds, self.decimals.min(1)));
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // rounded absolute value
+        let total = seconds.abs().round() as u64;
+        let (hours, minutes, seconds_part) = (total / 3600, total / 60 % 60, total % 60);
+
+        let mut output = String::new();
+        if seconds < 0. {
+            output.push('-');
+        }
+        if hours > 0 {
+            _ = write!(output, "{hours} h {minutes} min");
+        } else {
+            _ = write!(output, "{minutes} min {seconds_part} s");
+        }
+        output
+    }
+}
+
+fn with_si_prefix(value: f64) -> (f64, &'static str) {
+    let magnitude = value.abs();
+    if magnitude == 0. || !magnitude.is_finite() {
+        return (value, "");
+    }
+
+    let &(exponent, prefix) = SI_PREFIXES
+        .iter()
+        .find(|&&(exponent, _)| magnitude >= 10f64.powi(exponent))
+        .unwrap_or(&SI_PREFIXES[SI_PREFIXES.len() - 1]);
+    (value / 10f64.powi(exponent), prefix)
+}
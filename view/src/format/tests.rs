@@ -0,0 +1,48 @@
+use bevy::utils::HashMap;
+
+use super::{Formatter, Locale, Unit};
+
+#[test]
+fn number_grouping_follows_locale() {
+    let english = Formatter::default();
+    assert_eq!(english.format_number(1_234_567.891, 2), "1,234,567.89");
+    assert_eq!(english.format_number(-999.7, 0), "-1,000");
+    assert_eq!(english.format_number(-0.001, 2), "0.00");
+
+    let continental = Formatter { locale: Locale::CONTINENTAL, ..Default::default() };
+    assert_eq!(continental.format_number(1234.5, 1), "1.234,5");
+}
+
+#[test]
+fn si_prefixes_are_applied_to_unit_symbols() {
+    let formatter = Formatter::default();
+    assert_eq!(formatter.format(1500., Unit::Pressure), "1.50 kPa");
+    assert_eq!(formatter.format(2.5, Unit::Mass), "2.50 kg");
+    assert_eq!(formatter.format(0.004, Unit::Mass), "4.00 g");
+    assert_eq!(formatter.format(0., Unit::Volume), "0.00 L");
+    assert_eq!(formatter.format(12., Unit::Scalar), "12.00");
+    assert_eq!(formatter.format(0.456, Unit::Ratio), "45.60%");
+}
+
+#[test]
+fn durations_use_clock_units() {
+    let formatter = Formatter::default();
+    assert_eq!(formatter.format(12.34, Unit::Duration), "12.3 s");
+    assert_eq!(formatter.format(200., Unit::Duration), "3 min 20 s");
+    assert_eq!(formatter.format(7500., Unit::Duration), "2 h 5 min");
+}
+
+#[test]
+fn locale_tags() {
+    assert_eq!(Locale::from_tag("en-US"), Some(Locale::ENGLISH));
+    assert_eq!(Locale::from_tag("de_DE.UTF-8"), Some(Locale::CONTINENTAL));
+    assert_eq!(Locale::from_tag("fr"), Some(Locale::FRENCH));
+    assert_eq!(Locale::from_tag("xx"), None);
+}
+
+#[test]
+fn unit_metadata_round_trip() {
+    let metadata: HashMap<_, _> = [Unit::Pressure.metadata()].into_iter().collect();
+    assert_eq!(Unit::from_metadata(&metadata), Unit::Pressure);
+    assert_eq!(Unit::from_metadata(&HashMap::new()), Unit::Scalar);
+}
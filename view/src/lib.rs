@@ -11,9 +11,12 @@ mod sid;
 pub use sid::Index as SidIndex;
 
 pub mod appearance;
+pub mod camera;
+pub mod format;
 mod text;
 pub use text::DisplayText;
 pub mod metrics;
+pub mod search;
 pub mod viewable;
 pub mod viewer;
 
@@ -22,6 +25,6 @@ pub struct Plugin;
 
 impl app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((viewable::Plugin, viewer::Plugin, metrics::Plugin));
+        app.add_plugins((viewable::Plugin, viewer::Plugin, metrics::Plugin, camera::Plugin));
     }
 }
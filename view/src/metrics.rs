@@ -9,12 +9,13 @@ use bevy::app::{self, App};
 use bevy::ecs::component::{Component, ComponentDescriptor, ComponentId, StorageType};
 use bevy::ecs::entity::Entity;
 use bevy::ecs::event::{Event, EventReader, EventWriter};
-use bevy::ecs::query::{QueryData, QueryFilter};
+use bevy::ecs::query::{Added, QueryData, QueryFilter};
 use bevy::ecs::schedule::{IntoSystemConfigs, ScheduleLabel, Schedules, SystemConfigs, SystemSet};
 use bevy::ecs::system::{
-    Commands, EntityCommand, Query, Res, StaticSystemParam, SystemBuilder, SystemParam,
+    Commands, EntityCommand, Query, Res, Resource, StaticSystemParam, SystemBuilder, SystemParam,
 };
 use bevy::ecs::world::{Command, FilteredEntityMut, World};
+use bevy::hierarchy;
 use bevy::ptr::OwningPtr;
 use bevy::time::{Time, Timer, TimerMode};
 use bevy::utils::HashMap;
@@ -22,7 +23,7 @@ use rand::{thread_rng, Rng};
 use rand_distr::StandardNormal;
 use serde_json::Value as JsonValue;
 use traffloat_base::debug;
-use traffloat_base::partition::AppExt;
+use traffloat_base::partition::{self, AppExt};
 
 use crate::{viewable, viewer, DisplayText};
 
@@ -40,7 +41,13 @@ impl app::Plugin for Plugin {
         app.add_partitioned_event::<NewTypeEvent>();
         app.add_partitioned_event::<RequestSubscribeEvent>();
         app.init_schedule(BroadcastSchedule);
+        app.init_resource::<AnnouncedTypes>();
         app.add_systems(app::Update, admit_subscription_system);
+        app.add_systems(
+            app::Update,
+            announce_types_system
+                .in_set(partition::EventWriterSystemSet::<NewTypeEvent>::default()),
+        );
         app.add_systems(app::PostUpdate, |world: &mut World| world.run_schedule(BroadcastSchedule));
     }
 }
@@ -108,13 +115,25 @@ pub struct TypeDef {
     pub update_frequency: Duration,
     /// The display name of this metric type.
     pub display_label:    DisplayText,
+    /// How values are combined for [clustered](viewable::cluster) viewables.
+    pub aggregation:      Aggregation,
+}
+
+/// How values of a metric type are combined over the members of a [cluster](viewable::cluster).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Adds up the member values, e.g. for masses.
+    #[default]
+    Sum,
+    /// Averages the member values, e.g. for proportions.
+    Mean,
 }
 
 /// A [`SystemParam`] to access the registered metric types.
 #[derive(SystemParam)]
 pub struct Types<'w, 's>(Query<'w, 's, (Entity, &'static TypeDef)>);
 
-impl<'w, 's> Types<'w, 's> {
+impl Types<'_, '_> {
     /// Get a fluid type definition by type ID.
     #[must_use]
     pub fn get(&self, ty: Type) -> &TypeDef {
@@ -133,6 +152,38 @@ pub struct Value {
     pub magnitude: f32,
 }
 
+/// Returns the last fed magnitude of a metric type for an entity.
+///
+/// Returns `None` if the metric has never been fed for the entity,
+/// which happens when the metric type has no subscribers.
+///
+/// # Panics
+/// Panics if the type is not initialized yet.
+#[must_use]
+pub fn value(world: &World, ty: Type, entity: Entity) -> Option<f32> {
+    let &ValueComponentId(value_comp_id) = world
+        .get::<ValueComponentId>(ty.0)
+        .expect("metrics::Type refers to a non-metric or uninitialized entity");
+    let ptr = world.get_by_id(entity, value_comp_id)?;
+    // Safety: Value components must have type Value
+    Some(unsafe { ptr.deref::<Value>() }.magnitude)
+}
+
+/// Returns the subscription of a viewer to a metric type,
+/// or `None` if the viewer is not subscribed.
+///
+/// # Panics
+/// Panics if the type is not initialized yet.
+#[must_use]
+pub fn subscription(world: &World, ty: Type, viewer: Entity) -> Option<&Subscription> {
+    let &SubscriberComponentId(subscriber_comp_id) = world
+        .get::<SubscriberComponentId>(ty.0)
+        .expect("metrics::Type refers to a non-metric or uninitialized entity");
+    let ptr = world.get_by_id(viewer, subscriber_comp_id)?;
+    // Safety: subscription components must have type Subscription
+    Some(unsafe { ptr.deref::<Subscription>() })
+}
+
 /// The dynamic component type attached to viewers to indicate that
 /// the viewer should receive metrics of this type.
 pub struct Subscription {
@@ -345,6 +396,64 @@ where
         .in_set(ValueFeederSystemSet(ty))
 }
 
+/// Adds a metric type that exists for the lifetime of the app.
+///
+/// The type is created on startup with a [value feeder](make_value_feeder_system)
+/// for each entity matching `Query<OtherComps, Filter>`,
+/// and is announced to every new viewer with `metadata`.
+pub fn add_fed_type<OtherComps, Filter, OtherSystemParams, FeederFn>(
+    app: &mut App,
+    def: TypeDef,
+    metadata: impl IntoIterator<Item = (MetadataKey, JsonValue)>,
+    feeder: FeederFn,
+) where
+    OtherComps: QueryData + 'static,
+    Filter: QueryFilter + 'static,
+    OtherSystemParams: SystemParam + 'static,
+    FeederFn: Fn(&mut FilteredEntityMut<'_>, &OtherSystemParams::Item<'_, '_>) -> f32,
+    FeederFn: Send + Sync + 'static,
+{
+    let mut init = Some((def, metadata.into_iter().collect::<HashMap<_, _>>(), feeder));
+    app.add_systems(app::Startup, move |world: &mut World| {
+        let (def, metadata, feeder) = init.take().expect("startup systems only run once");
+        let ty = create_type(&mut world.commands(), def);
+        world.flush();
+
+        let feeder =
+            make_value_feeder_system::<OtherComps, Filter, OtherSystemParams, _>(world, feeder, ty);
+        world.resource_mut::<Schedules>().add_systems(BroadcastSchedule, feeder);
+        world.resource_mut::<AnnouncedTypes>().types.push((ty, metadata));
+    });
+}
+
+/// Types added by [`add_fed_type`], with the metadata announced to new viewers.
+#[derive(Default, Resource)]
+struct AnnouncedTypes {
+    types: Vec<(Type, HashMap<MetadataKey, JsonValue>)>,
+}
+
+fn announce_types_system(
+    types: Res<AnnouncedTypes>,
+    viewer_query: Query<&viewer::Sid, Added<viewer::Sid>>,
+    metric_type_query: Query<(&TypeDef, &Sid)>,
+    mut writer: EventWriter<NewTypeEvent>,
+) {
+    for &viewer in &viewer_query {
+        writer.send_batch(types.types.iter().map(|(ty, metadata)| {
+            let (ty_def, &ty_sid) =
+                metric_type_query.get(ty.0).expect("AnnouncedTypes refers to a metric type");
+            NewTypeEvent {
+                viewer,
+                ty: ty_sid,
+                data: ClientTypeData {
+                    display_label: ty_def.display_label.clone(),
+                    metadata:      metadata.clone(),
+                },
+            }
+        }));
+    }
+}
+
 /// Creates a system that updates the magnitude of a metric type from another entity.
 ///
 /// Metrics are generated from "source entities",
@@ -474,11 +583,15 @@ fn make_value_broadcast_system(
         .builder::<Query<FilteredEntityMut>>(|builder| {
             builder.ref_id(subscriber_comp_id);
             builder.data::<&viewer::Sid>();
+            builder.data::<&viewer::CoarseViewableList>();
         })
         .builder::<Query<FilteredEntityMut>>(|builder| {
             builder.ref_id(value_comp_id);
             builder.data::<&viewable::Viewers>();
             builder.data::<&viewable::Sid>();
+            builder.optional(|builder| {
+                builder.data::<&hierarchy::Parent>();
+            });
         })
         .build(
             move |time: Res<Time>,
@@ -500,8 +613,17 @@ fn make_value_broadcast_system(
 
                     let viewable_viewers =
                         viewable_fem.get::<viewable::Viewers>().expect("requested in query");
+                    let stationary_entity = viewable_fem
+                        .get::<hierarchy::Parent>()
+                        .map_or(viewable_fem.id(), hierarchy::Parent::get);
                     viewable_viewers.iter().filter_map(move |viewer_entity| {
                         let viewer_fem = viewers_query.get(viewer_entity).ok()?;
+                        let coarse = viewer_fem
+                            .get::<viewer::CoarseViewableList>()
+                            .expect("requested in query");
+                        if coarse.set.contains(&stationary_entity) {
+                            return None; // coarse viewables do not receive metrics
+                        }
                         let sub_ptr =
                             viewer_fem.get_by_id(subscriber_comp_id).expect("requested in query");
                         let viewer_sid =
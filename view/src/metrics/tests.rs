@@ -9,10 +9,11 @@ use bevy::math::Vec3;
 use bevy::time::Time;
 use bevy::transform::components::Transform;
 use bevy::utils::HashMap;
+use traffloat_base::save;
 
 use super::{
-    create_type, make_value_feeder_system, SubscribeCommand, Subscription, Type, TypeDef,
-    UpdateMetricEvent,
+    add_fed_type, create_type, make_value_feeder_system, value, Aggregation, MetadataKey,
+    NewTypeEvent, SubscribeCommand, Subscription, Type, TypeDef, UpdateMetricEvent,
 };
 use crate::viewable::{self, ShowEvent};
 use crate::{appearance, viewer, DisplayText};
@@ -20,7 +21,7 @@ use crate::{appearance, viewer, DisplayText};
 #[test]
 fn report() {
     let mut app = App::new();
-    app.add_plugins(crate::Plugin);
+    app.add_plugins((save::Plugin, crate::Plugin));
     let setup = setup_world(&mut app);
 
     let mut show_event_reader = event_reader::<ShowEvent>(app.world());
@@ -63,6 +64,7 @@ fn setup_world(app: &mut App) -> WorldSetup {
         TypeDef {
             update_frequency: Duration::from_secs(5),
             display_label:    DisplayText::default(),
+            aggregation:      Aggregation::Sum,
         },
     );
     let ty2 = create_type(
@@ -70,6 +72,7 @@ fn setup_world(app: &mut App) -> WorldSetup {
         TypeDef {
             update_frequency: Duration::from_secs(2),
             display_label:    DisplayText::default(),
+            aggregation:      Aggregation::Sum,
         },
     );
 
@@ -205,6 +208,52 @@ impl ValueGenerator {
     fn generate(&self, pos_x: f32, multiplier: f32) -> f32 { (pos_x * 100. + self.0) * multiplier }
 }
 
+#[test]
+fn announce_fed_type_to_new_viewers() {
+    const KEY: MetadataKey = MetadataKey::new("test.key");
+
+    let mut app = App::new();
+    app.add_plugins((save::Plugin, crate::Plugin));
+    app.insert_resource(Time::<()>::default());
+    add_fed_type::<&Transform, (), (), _>(
+        &mut app,
+        TypeDef {
+            update_frequency: Duration::from_secs(1),
+            display_label:    DisplayText::Custom { value: "Height".into() },
+            aggregation:      Aggregation::Mean,
+        },
+        [(KEY, serde_json::Value::Bool(true))],
+        |entity, ()| entity.get::<Transform>().unwrap().translation.y,
+    );
+    let mut new_type_reader = event_reader::<NewTypeEvent>(app.world());
+    app.update();
+
+    let viewer_id = viewer::next_sid(app.world_mut());
+    let viewer = app
+        .world_mut()
+        .spawn(
+            viewer::Bundle::builder()
+                .id(viewer_id)
+                .range(viewer::Range { distance: 100. })
+                .position(Transform::from_xyz(0., 3., 0.))
+                .build(),
+        )
+        .id();
+    app.update();
+
+    let events: Vec<_> = get_events(app.world(), &mut new_type_reader).collect();
+    let [event] = &events[..] else { panic!("expected one new type event, got {events:?}") };
+    assert_eq!(event.viewer, viewer_id);
+    assert_eq!(event.data.metadata.get(&KEY), Some(&serde_json::Value::Bool(true)));
+
+    let metric_sid = event.ty;
+    let ty = Type(app.world().resource::<super::SidIndex>().get(metric_sid).unwrap());
+    SubscribeCommand { viewer, ty, subscription: Subscription { noise_sd: 0. } }
+        .apply(app.world_mut());
+    app.update();
+    assert_eq!(value(app.world(), ty, viewer), Some(3.));
+}
+
 fn event_reader<E: Event>(world: &World) -> ManualEventReader<E> {
     world.resource::<Events<E>>().get_reader()
 }
@@ -0,0 +1,118 @@
+//! Fuzzy search over labelled targets.
+//!
+//! An [`Index`] maps display labels to arbitrary jump targets,
+//! e.g. the entity to focus when a search result is selected.
+//! Queries match labels as case-insensitive subsequences
+//! and results are ranked by [`score`].
+
+#[cfg(test)]
+mod tests;
+
+/// Score bonus for every matched character.
+const MATCH_SCORE: u32 = 1;
+/// Score bonus for a matched character immediately following the previous match.
+const CONSECUTIVE_BONUS: u32 = 4;
+/// Score bonus for a matched character at the start of a word.
+const WORD_START_BONUS: u32 = 3;
+
+/// Scores how well `query` matches `candidate`.
+///
+/// Returns `None` if the characters of `query` do not appear in `candidate` in order.
+/// Consecutive matches and matches at word boundaries score higher.
+/// Every occurrence of the first query character is tried as the start of the match,
+/// and the best resulting score is returned.
+#[must_use]
+pub fn score(query: &str, candidate: &str) -> Option<u32> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+
+    let Some(&first) = query.first() else { return Some(0) };
+    (0..candidate.len())
+        .filter(|&start| candidate[start] == first)
+        .filter_map(|start| score_from(&query, &candidate, start))
+        .max()
+}
+
+/// Greedily matches `query` against `candidate` starting from `start`.
+fn score_from(query: &[char], candidate: &[char], start: usize) -> Option<u32> {
+    let mut query = query.iter().peekable();
+    let mut total = 0;
+    let mut prev_matched = false;
+
+    for (index, &ch) in candidate.iter().enumerate().skip(start) {
+        let Some(&&expect) = query.peek() else { break };
+        let matched = ch == expect;
+
+        if matched {
+            query.next();
+            total += MATCH_SCORE;
+            if prev_matched {
+                total += CONSECUTIVE_BONUS;
+            }
+            if index == 0 || !candidate[index - 1].is_alphanumeric() {
+                total += WORD_START_BONUS;
+            }
+        }
+
+        prev_matched = matched;
+    }
+
+    query.peek().is_none().then_some(total)
+}
+
+/// A searchable collection of labelled targets.
+#[derive(Debug, Clone)]
+pub struct Index<T> {
+    entries: Vec<(String, T)>,
+}
+
+impl<T> Default for Index<T> {
+    fn default() -> Self { Self { entries: Vec::new() } }
+}
+
+impl<T> Index<T> {
+    /// Adds a labelled target to the index.
+    pub fn insert(&mut self, label: impl Into<String>, target: T) {
+        self.entries.push((label.into(), target));
+    }
+
+    /// Removes all entries from the index.
+    pub fn clear(&mut self) { self.entries.clear(); }
+
+    /// Returns the number of entries in the index.
+    #[must_use]
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    /// Returns whether the index has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Returns up to `limit` entries matching `query`, best match first.
+    ///
+    /// Ties are broken by shorter labels first, then by insertion order.
+    /// An empty query matches every entry with a score of zero.
+    #[must_use]
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Match<'_, T>> {
+        let mut matches: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|(label, target)| {
+                Some(Match { label: label.as_str(), target, score: score(query, label)? })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then(a.label.len().cmp(&b.label.len())));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// A search result.
+#[derive(Debug)]
+pub struct Match<'a, T> {
+    /// The label of the matched entry.
+    pub label:  &'a str,
+    /// The jump target of the matched entry.
+    pub target: &'a T,
+    /// The match score; higher is better.
+    pub score:  u32,
+}
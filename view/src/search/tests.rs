@@ -0,0 +1,35 @@
+use super::{score, Index};
+
+#[test]
+fn score_requires_subsequence() {
+    assert!(score("crx", "Core").is_none());
+    assert!(score("cre", "Core reactor").is_some());
+    assert_eq!(score("", "anything"), Some(0));
+}
+
+#[test]
+fn score_is_case_insensitive() {
+    assert_eq!(score("CORE", "core"), score("core", "Core"));
+}
+
+#[test]
+fn search_ranks_consecutive_and_word_start_matches() {
+    let mut index = Index::default();
+    index.insert("Ice cluster", 1);
+    index.insert("Cluster core", 2);
+    index.insert("Chlorine tank", 3);
+
+    let results: Vec<_> = index.search("cl", 10).into_iter().map(|m| *m.target).collect();
+    // both clusters match "cl" consecutively at a word start; the shorter label wins the tie
+    assert_eq!(results, [1, 2, 3]);
+}
+
+#[test]
+fn search_respects_limit() {
+    let mut index = Index::default();
+    for i in 0..10 {
+        index.insert(format!("Asteroid {i}"), i);
+    }
+    assert_eq!(index.search("ast", 3).len(), 3);
+    assert!(index.search("xyz", 3).is_empty());
+}
@@ -51,7 +51,7 @@ impl DisplayText {
     pub fn short_debug(&self) -> impl fmt::Display + '_ {
         struct Wrapper<'a>(&'a DisplayText);
 
-        impl<'a> fmt::Display for Wrapper<'a> {
+        impl fmt::Display for Wrapper<'_> {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 match self.0 {
                     DisplayText::Custom { value } => write!(f, "{value}"),
@@ -1,5 +1,6 @@
 //! A viewable entity can be subscribed by a viewer.
 
+use std::time::Duration;
 use std::{iter, mem};
 
 use bevy::app::{self, App};
@@ -8,24 +9,30 @@ use bevy::ecs::component::{Component, ComponentId};
 use bevy::ecs::entity::{Entity, EntityHashSet};
 use bevy::ecs::event::{Event, EventReader, EventWriter, Events};
 use bevy::ecs::query::With;
-use bevy::ecs::schedule::IntoSystemConfigs;
-use bevy::ecs::system::{Query, Res, ResMut, Resource};
-use bevy::ecs::world::DeferredWorld;
+use bevy::ecs::schedule::{IntoSystemConfigs, SystemSet};
+use bevy::ecs::system::{Local, Query, Res, ResMut, Resource};
+use bevy::ecs::world::{Command, DeferredWorld, World};
 use bevy::hierarchy;
 use bevy::math::bounding::Aabb3d;
-use bevy::math::Vec3A;
+use bevy::math::{Vec3, Vec3A};
+use bevy::time::{Time, Timer, TimerMode};
 use bevy::transform::components::Transform;
 use bevy::utils::HashSet;
 use either::Either;
 use kd_tree::KdTree3;
 use traffloat_base::partition::{AppExt, EventReaderSystemSet, EventWriterSystemSet};
-use traffloat_base::proto;
+use traffloat_base::{proto, Ref};
 use typed_builder::TypedBuilder;
 
 use crate::{appearance, viewer};
 
 sid_alias!("viewable");
 
+pub mod cluster;
+
+#[cfg(test)]
+mod tests;
+
 pub(crate) struct Plugin;
 
 impl app::Plugin for Plugin {
@@ -36,14 +43,20 @@ impl app::Plugin for Plugin {
         app.add_partitioned_event::<ShowStationaryEvent>();
         app.add_partitioned_event::<HideEvent>();
         app.add_partitioned_event::<HideStationaryEvent>();
+        app.add_partitioned_event::<MoveEvent>();
 
         app.insert_resource(SpatialIndex { kdtree: None });
+        app.init_resource::<LodPolicy>();
+        app.init_resource::<CoarseSync>();
+        app.add_plugins(cluster::Plugin);
         app.add_systems(
             app::Update,
             (
                 update_spatial_index_system,
+                clear_relevance_system.before(RelevanceSystemSet),
                 update_stationary_viewers_system
                     .after(update_spatial_index_system)
+                    .after(RelevanceSystemSet)
                     .in_set(EventWriterSystemSet::<ShowEvent>::default())
                     .in_set(EventWriterSystemSet::<ShowStationaryEvent>::default())
                     .in_set(EventWriterSystemSet::<HideEvent>::default())
@@ -57,6 +70,12 @@ impl app::Plugin for Plugin {
                         .in_set(EventReaderSystemSet::<HideStationaryEvent>::default()),
                 )
                     .after(update_stationary_viewers_system),
+                update_lod_system
+                    .after(update_stationary_viewers_system)
+                    .in_set(EventWriterSystemSet::<MoveEvent>::default()),
+                flush_coarse_moves_system
+                    .after(update_lod_system)
+                    .in_set(EventWriterSystemSet::<MoveEvent>::default()),
             ),
         );
         app.world_mut()
@@ -110,6 +129,74 @@ pub struct HideEvent {
     pub viewable: Sid,
 }
 
+/// The client should update the transform of a displayed viewable.
+#[derive(Debug, Event)]
+pub struct MoveEvent {
+    /// The viewer to update.
+    pub viewer:    viewer::Sid,
+    /// The moved viewable.
+    pub viewable:  Sid,
+    /// The new transform for the viewable model, relative to parent or world origin.
+    pub transform: proto::Transform,
+}
+
+/// Updates the transform of a [stationary](Stationary) viewable.
+///
+/// The spatial index is rebuilt in the next update,
+/// and current viewers are notified through [`MoveEvent`].
+/// Viewers displaying the viewable at coarse level of detail
+/// are notified at the reduced frequency specified in [`LodPolicy`].
+/// Viewers for which the viewable moves in or out of range
+/// receive [`ShowEvent`] or [`HideEvent`] after the spatial index is rebuilt.
+/// The command is ignored with a warning if the viewable has been despawned.
+pub struct Relocate {
+    /// The stationary viewable entity.
+    pub viewable:  Ref<Stationary>,
+    /// The new absolute transform of the viewable.
+    pub transform: Transform,
+}
+
+impl Command for Relocate {
+    fn apply(self, world: &mut World) {
+        let Ok((mut transform, &viewable_sid, viewers)) = world
+            .query_filtered::<(&mut Transform, &Sid, &Viewers), With<Stationary>>()
+            .get_mut(world, self.viewable.entity())
+        else {
+            bevy::log::warn!(
+                "cannot relocate non-stationary viewable {:?}",
+                self.viewable.entity()
+            );
+            return;
+        };
+        *transform = self.transform;
+        let viewers: Vec<Entity> = viewers.iter().collect();
+
+        let mut move_events = Vec::new();
+        for viewer in viewers {
+            let viewer_ref = world.entity(viewer);
+            let &viewer_sid = viewer_ref
+                .get::<viewer::Sid>()
+                .expect("viewer list must reference valid viewer with viewer::Sid");
+            if viewer_ref
+                .get::<viewer::CoarseViewableList>()
+                .is_some_and(|list| list.set.contains(&self.viewable.entity()))
+            {
+                world.resource_mut::<CoarseSync>().pending.insert((viewer, self.viewable.entity()));
+                continue;
+            }
+
+            move_events.push(MoveEvent {
+                viewer:    viewer_sid,
+                viewable:  viewable_sid,
+                transform: self.transform.into(),
+            });
+        }
+        world.resource_mut::<Events<MoveEvent>>().send_batch(move_events);
+
+        world.resource_mut::<SpatialIndex>().kdtree = None;
+    }
+}
+
 /// Common components to construct a viewable entity.
 ///
 /// Entities should be initialized through [`StationaryBundle`] or [`StationaryChildBundle`] instead.
@@ -201,7 +288,7 @@ impl Viewers {
     #[must_use]
     pub fn contains(&self, entity: Entity) -> bool {
         match self.0 {
-            ViewersInner::Array(ref array) => array.iter().any(|item| *item == Some(entity)),
+            ViewersInner::Array(ref array) => array.contains(&Some(entity)),
             ViewersInner::HashSet(ref set) => set.contains(&entity),
         }
     }
@@ -279,12 +366,34 @@ fn clean_viewers_hook(mut world: DeferredWorld, entity: Entity, _comp_id: Compon
     world.resource_mut::<Events<HideEvent>>().send_batch(hide_events);
 }
 
+/// Spatial index of stationary viewables by position.
+///
+/// The index is invalidated when a stationary viewable is spawned or relocated,
+/// and rebuilt in the next update.
 #[derive(Resource)]
-struct SpatialIndex {
+pub struct SpatialIndex {
     /// Position => viewable entity
     kdtree: Option<KdTree3<([f32; 3], Entity)>>,
 }
 
+impl SpatialIndex {
+    /// Lists the stationary viewables positioned within `radius` from `center`,
+    /// or returns `None` if the index is outdated.
+    ///
+    /// The list may contain viewables that have been despawned since the index was built.
+    #[must_use]
+    pub fn within_radius(&self, center: Vec3, radius: f32) -> Option<Vec<Entity>> {
+        let kdtree = self.kdtree.as_ref()?;
+        Some(
+            kdtree
+                .within_radius(&center.to_array(), radius)
+                .into_iter()
+                .map(|&(_, viewable)| viewable)
+                .collect(),
+        )
+    }
+}
+
 /// A marker component to indicate that
 /// the viewer list of the viewable entity is controlled by the view module
 /// and the viewable entity has a stationary position.
@@ -304,13 +413,41 @@ pub struct Stationary;
 #[derive(Component, Default)]
 pub struct StationaryChild;
 
+/// Systems that populate [`viewer::RelevantViewables`].
+///
+/// See [`add_relevance_provider`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct RelevanceSystemSet;
+
+/// Registers a system that marks stationary viewables as relevant to viewers.
+///
+/// Relevance providers allow plugins to extend the viewable list of a viewer
+/// beyond the spatial range, e.g. to always display buildings owned by the viewer.
+/// The system should insert entities into [`viewer::RelevantViewables`],
+/// which is cleared before the providers run in every update.
+/// Entities that are not stationary viewables are ignored.
+pub fn add_relevance_provider<M>(app: &mut App, system: impl IntoSystemConfigs<M>) {
+    app.add_systems(app::Update, system.in_set(RelevanceSystemSet));
+}
+
+fn clear_relevance_system(mut query: Query<&mut viewer::RelevantViewables>) {
+    for mut relevant in &mut query {
+        relevant.set.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)] // bevy system parameters
 fn update_stationary_viewers_system(
+    // scratch buffers reused across viewers and updates
+    mut next_viewable_vec: Local<Vec<Entity>>,
+    mut next_viewable_set: Local<HashSet<Entity>>,
     tree: Res<SpatialIndex>,
     mut viewer_query: Query<(
         Entity,
         &viewer::Sid,
         &Transform,
         &viewer::Range,
+        &viewer::RelevantViewables,
         &mut viewer::ViewableList,
     )>,
     mut viewable_query: Query<
@@ -332,15 +469,30 @@ fn update_stationary_viewers_system(
             &viewer_sid,
             &Transform { translation: new_pos, .. },
             &viewer::Range { distance },
+            relevant,
             mut prev_viewables,
         )| {
             let visible_aabb = Aabb3d::new(new_pos, Vec3A::splat(distance));
-            let next_viewable_vec =
-                kdtree.within(&[visible_aabb.min.to_array(), visible_aabb.max.to_array()]);
-
-            let mut next_viewable_set = HashSet::with_capacity(next_viewable_vec.len());
-            for &(_, viewable) in next_viewable_vec {
-                next_viewable_set.insert(viewable);
+            next_viewable_vec.clear();
+            next_viewable_vec.extend(
+                kdtree
+                    .within(&[visible_aabb.min.to_array(), visible_aabb.max.to_array()])
+                    .into_iter()
+                    .map(|&(_, viewable)| viewable)
+                    .chain(
+                        relevant
+                            .set
+                            .iter()
+                            .copied()
+                            .filter(|&viewable| viewable_query.contains(viewable)),
+                    ),
+            );
+
+            next_viewable_set.clear();
+            for &viewable in &*next_viewable_vec {
+                if !next_viewable_set.insert(viewable) {
+                    continue; // both spatially visible and relevant
+                }
 
                 if prev_viewables.set.contains(&viewable) {
                     continue;
@@ -387,7 +539,7 @@ fn update_stationary_viewers_system(
                 hide_stationary_events.send(HideStationaryEvent { viewer, viewable: *viewable });
             }
 
-            prev_viewables.set = next_viewable_set;
+            mem::swap(&mut prev_viewables.set, &mut *next_viewable_set);
         },
     );
 }
@@ -454,3 +606,129 @@ fn hide_stationary_children_system(
 
     hide_events.send_batch(events);
 }
+
+/// Level-of-detail policy for stationary viewables.
+///
+/// A viewable is displayed to a viewer at coarse level of detail
+/// if it is farther than `coarse_distance` from the viewer.
+/// Coarse viewables receive position updates at a reduced frequency
+/// and do not receive metric updates.
+///
+/// To avoid flapping at the boundary,
+/// a viewable only switches to coarse level of detail beyond `coarse_distance + hysteresis`
+/// and switches back within `coarse_distance - hysteresis`.
+///
+/// The default policy never displays viewables at coarse level of detail.
+#[derive(Resource)]
+pub struct LodPolicy {
+    /// The distance beyond which viewables are displayed at coarse level of detail.
+    pub coarse_distance:    f32,
+    /// The margin around `coarse_distance` within which the level of detail is unchanged.
+    pub hysteresis:         f32,
+    /// The period between position updates for coarse viewables.
+    pub coarse_move_period: Duration,
+}
+
+impl Default for LodPolicy {
+    fn default() -> Self {
+        Self {
+            coarse_distance:    f32::INFINITY,
+            hysteresis:         5.,
+            coarse_move_period: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Position updates deferred for coarse viewables.
+#[derive(Resource)]
+struct CoarseSync {
+    timer:   Timer,
+    /// `(viewer, viewable)` pairs with a pending [`MoveEvent`].
+    pending: HashSet<(Entity, Entity)>,
+}
+
+impl Default for CoarseSync {
+    fn default() -> Self {
+        Self {
+            timer:   Timer::new(LodPolicy::default().coarse_move_period, TimerMode::Repeating),
+            pending: HashSet::new(),
+        }
+    }
+}
+
+fn update_lod_system(
+    policy: Res<LodPolicy>,
+    mut sync: ResMut<CoarseSync>,
+    mut viewer_query: Query<(
+        Entity,
+        &viewer::Sid,
+        &Transform,
+        &viewer::ViewableList,
+        &mut viewer::CoarseViewableList,
+    )>,
+    viewable_query: Query<(&Sid, &Transform), With<Stationary>>,
+    mut move_events: EventWriter<MoveEvent>,
+) {
+    let enter_distance = policy.coarse_distance + policy.hysteresis;
+    let exit_distance = policy.coarse_distance - policy.hysteresis;
+
+    for (viewer, &viewer_sid, viewer_tf, viewables, mut coarse) in &mut viewer_query {
+        coarse.set.retain(|viewable| viewables.set.contains(viewable));
+
+        for &viewable in &viewables.set {
+            let Ok((&viewable_sid, viewable_tf)) = viewable_query.get(viewable) else { continue };
+            let distance = viewer_tf.translation.distance(viewable_tf.translation);
+
+            if coarse.set.contains(&viewable) {
+                if distance < exit_distance {
+                    coarse.set.remove(&viewable);
+                    if sync.pending.remove(&(viewer, viewable)) {
+                        move_events.send(MoveEvent {
+                            viewer:    viewer_sid,
+                            viewable:  viewable_sid,
+                            transform: (*viewable_tf).into(),
+                        });
+                    }
+                }
+            } else if distance > enter_distance {
+                coarse.set.insert(viewable);
+            }
+        }
+    }
+}
+
+fn flush_coarse_moves_system(
+    time: Res<Time>,
+    policy: Res<LodPolicy>,
+    mut sync: ResMut<CoarseSync>,
+    viewer_query: Query<(&viewer::Sid, &viewer::ViewableList, &cluster::Clusters)>,
+    viewable_query: Query<(&Sid, &Transform), With<Stationary>>,
+    mut move_events: EventWriter<MoveEvent>,
+) {
+    if sync.timer.duration() != policy.coarse_move_period {
+        sync.timer.set_duration(policy.coarse_move_period);
+    }
+    sync.timer.tick(time.delta());
+    if !sync.timer.finished() {
+        return;
+    }
+
+    let events: Vec<_> = sync
+        .pending
+        .drain()
+        .filter_map(|(viewer, viewable)| {
+            let (&viewer_sid, viewables, clusters) = viewer_query.get(viewer).ok()?;
+            // hidden viewables get the latest transform when they are shown again
+            if !viewables.set.contains(&viewable) || clusters.contains(viewable) {
+                return None;
+            }
+            let (&viewable_sid, &transform) = viewable_query.get(viewable).ok()?;
+            Some(MoveEvent {
+                viewer:    viewer_sid,
+                viewable:  viewable_sid,
+                transform: transform.into(),
+            })
+        })
+        .collect();
+    move_events.send_batch(events);
+}
@@ -0,0 +1,331 @@
+//! Aggregation of dense groups of coarse viewables.
+//!
+//! The [coarse](viewer::CoarseViewableList) viewables of each viewer
+//! are bucketed into cubic cells of [`ClusterPolicy::cell_size`].
+//! A cell with at least [`ClusterPolicy::min_members`] coarse viewables is collapsed:
+//! its members are hidden from the viewer
+//! and replaced by a synthetic cluster viewable at their centroid,
+//! labelled with the member count and the most common member label.
+//! Subscribed metrics of the members are [aggregated](metrics::Aggregation)
+//! and broadcast for the cluster.
+//!
+//! A cluster is dissolved as soon as its membership changes,
+//! e.g. when the viewer moves closer and members leave coarse level of detail,
+//! and the members are shown again.
+//! A cell with changed membership that still exceeds the threshold gets a new cluster.
+//!
+//! Cluster viewables only exist on the wire.
+//! Their SIDs are allocated from [`SidIndex`] but do not resolve to any entity.
+//! The SIDs of dissolved clusters are reused for later clusters of the same viewer,
+//! starting from the next update so that the client has seen the cluster hidden.
+
+use std::iter;
+use std::time::Duration;
+
+use bevy::app::{self, App};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::{Entity, EntityHashSet};
+use bevy::ecs::event::EventWriter;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{Query, Res, Resource};
+use bevy::ecs::world::World;
+use bevy::hierarchy;
+use bevy::math::{IVec3, Vec3};
+use bevy::time::{Time, Timer, TimerMode};
+use bevy::transform::components::Transform;
+use bevy::utils::HashMap;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use traffloat_base::partition::EventWriterSystemSet;
+use traffloat_base::save::rng::SimRng;
+
+use super::{
+    flush_coarse_moves_system, update_lod_system, HideEvent, MoveEvent, ShowEvent, Sid, SidIndex,
+    Stationary,
+};
+use crate::{appearance, metrics, viewer, DisplayText};
+
+#[cfg(test)]
+mod tests;
+
+pub(super) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClusterPolicy>();
+        app.init_resource::<MetricSync>();
+        app.add_systems(
+            app::Update,
+            update_clusters_system
+                .after(update_lod_system)
+                .before(flush_coarse_moves_system)
+                .in_set(EventWriterSystemSet::<ShowEvent>::default())
+                .in_set(EventWriterSystemSet::<HideEvent>::default())
+                .in_set(EventWriterSystemSet::<MoveEvent>::default()),
+        );
+        app.add_systems(metrics::BroadcastSchedule, broadcast_cluster_metrics_system);
+    }
+}
+
+/// Clustering policy for coarse viewables.
+#[derive(Resource)]
+pub struct ClusterPolicy {
+    /// The edge length of the cubic cells that viewables are bucketed into.
+    pub cell_size:     f32,
+    /// The minimum number of coarse viewables in a cell to form a cluster.
+    pub min_members:   usize,
+    /// The period between broadcasts of aggregated metrics.
+    pub metric_period: Duration,
+}
+
+impl Default for ClusterPolicy {
+    fn default() -> Self {
+        Self { cell_size: 25., min_members: 8, metric_period: Duration::from_secs(2) }
+    }
+}
+
+/// Clusters displayed to a viewer in place of their members.
+#[derive(Component, Default)]
+pub struct Clusters {
+    cells:     HashMap<IVec3, Cluster>,
+    /// Union of the members of all clusters.
+    clustered: EntityHashSet,
+    /// SIDs of dissolved clusters available for reuse.
+    free_sids: Vec<Sid>,
+}
+
+struct Cluster {
+    sid:      Sid,
+    members:  EntityHashSet,
+    centroid: Vec3,
+}
+
+impl Clusters {
+    /// Whether the viewable is hidden from the viewer in favor of a cluster.
+    #[must_use]
+    pub fn contains(&self, viewable: Entity) -> bool { self.clustered.contains(&viewable) }
+
+    /// Iterates over the SIDs of the cluster viewables and their members.
+    pub fn iter(&self) -> impl Iterator<Item = (Sid, &EntityHashSet)> {
+        self.cells.values().map(|cluster| (cluster.sid, &cluster.members))
+    }
+}
+
+fn update_clusters_system(
+    policy: Res<ClusterPolicy>,
+    sid_index: Res<SidIndex>,
+    mut viewer_query: Query<(
+        &viewer::Sid,
+        &viewer::ViewableList,
+        &viewer::CoarseViewableList,
+        &mut Clusters,
+    )>,
+    viewable_query: Query<(&Sid, &appearance::Appearance, &Transform), With<Stationary>>,
+    mut show_events: EventWriter<ShowEvent>,
+    mut hide_events: EventWriter<HideEvent>,
+    mut move_events: EventWriter<MoveEvent>,
+) {
+    for (&viewer_sid, viewables, coarse, mut clusters) in &mut viewer_query {
+        let mut cells: HashMap<IVec3, EntityHashSet> = HashMap::new();
+        for &viewable in &coarse.set {
+            let Ok((_, _, transform)) = viewable_query.get(viewable) else { continue };
+            let cell = (transform.translation / policy.cell_size).floor().as_ivec3();
+            cells.entry(cell).or_default().insert(viewable);
+        }
+        cells.retain(|_, members| members.len() >= policy.min_members);
+
+        let clusters = &mut *clusters;
+
+        let mut dissolved = Vec::new();
+        clusters.cells.retain(|cell, cluster| {
+            if cells.get(cell) == Some(&cluster.members) {
+                return true;
+            }
+            hide_events.send(HideEvent { viewer: viewer_sid, viewable: cluster.sid });
+            dissolved.push(cluster.sid);
+            false
+        });
+
+        let next_clustered: EntityHashSet = cells.values().flatten().copied().collect();
+        for &viewable in clusters.clustered.difference(&next_clustered) {
+            if !viewables.set.contains(&viewable) {
+                continue; // already hidden for leaving the range
+            }
+            let Ok((&sid, appearance, &transform)) = viewable_query.get(viewable) else {
+                continue;
+            };
+            show_events.send(ShowEvent {
+                viewer:     viewer_sid,
+                viewable:   sid,
+                parent:     None,
+                appearance: appearance.clone(),
+                transform:  transform.into(),
+            });
+        }
+        for &viewable in next_clustered.difference(&clusters.clustered) {
+            let Ok((&sid, _, _)) = viewable_query.get(viewable) else { continue };
+            hide_events.send(HideEvent { viewer: viewer_sid, viewable: sid });
+        }
+        clusters.clustered = next_clustered;
+
+        for (cell, members) in cells {
+            let centroid = centroid(&members, &viewable_query);
+            let transform = Transform::from_translation(centroid);
+
+            if let Some(cluster) = clusters.cells.get_mut(&cell) {
+                if cluster.centroid != centroid {
+                    cluster.centroid = centroid;
+                    move_events.send(MoveEvent {
+                        viewer:    viewer_sid,
+                        viewable:  cluster.sid,
+                        transform: transform.into(),
+                    });
+                }
+                continue;
+            }
+
+            let sid = clusters.free_sids.pop().unwrap_or_else(|| sid_index.next_id());
+            show_events.send(ShowEvent {
+                viewer:     viewer_sid,
+                viewable:   sid,
+                parent:     None,
+                appearance: cluster_appearance(
+                    members
+                        .iter()
+                        .filter_map(|&member| viewable_query.get(member).ok())
+                        .map(|(_, appearance, _)| appearance),
+                ),
+                transform:  transform.into(),
+            });
+            clusters.cells.insert(cell, Cluster { sid, members, centroid });
+        }
+
+        clusters.free_sids.extend(dissolved);
+    }
+}
+
+fn centroid(
+    members: &EntityHashSet,
+    viewable_query: &Query<(&Sid, &appearance::Appearance, &Transform), With<Stationary>>,
+) -> Vec3 {
+    let (sum, count) = members
+        .iter()
+        .filter_map(|&member| viewable_query.get(member).ok())
+        .fold((Vec3::ZERO, 0_u32), |(sum, count), (_, _, transform)| {
+            (sum + transform.translation, count + 1)
+        });
+    #[allow(clippy::cast_precision_loss)] // member counts are far below 2^24
+    let centroid = sum / count.max(1) as f32;
+    centroid
+}
+
+/// Builds the appearance of a cluster from the most common appearance among its members.
+fn cluster_appearance<'a>(
+    members: impl Iterator<Item = &'a appearance::Appearance>,
+) -> appearance::Appearance {
+    let mut counts: HashMap<String, (usize, &appearance::Appearance)> = HashMap::new();
+    let mut total = 0;
+    for member in members {
+        counts.entry(member.label.render_to_string()).or_insert((0, member)).0 += 1;
+        total += 1;
+    }
+
+    let Some((_, &(_, dominant))) =
+        counts.iter().max_by(|(label1, (count1, _)), (label2, (count2, _))| {
+            count1.cmp(count2).then_with(|| label2.cmp(label1))
+        })
+    else {
+        return appearance::Appearance::null();
+    };
+
+    appearance::Appearance {
+        label: DisplayText::Concat {
+            children: vec![
+                DisplayText::Custom { value: format!("{total} × ") },
+                dominant.label.clone(),
+            ],
+        },
+        tank: None,
+        ..dominant.clone()
+    }
+}
+
+/// Timer for [`broadcast_cluster_metrics_system`].
+#[derive(Resource)]
+struct MetricSync {
+    timer: Timer,
+}
+
+impl Default for MetricSync {
+    fn default() -> Self {
+        Self { timer: Timer::new(ClusterPolicy::default().metric_period, TimerMode::Repeating) }
+    }
+}
+
+fn broadcast_cluster_metrics_system(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let period = world.resource::<ClusterPolicy>().metric_period;
+    let mut sync = world.resource_mut::<MetricSync>();
+    if sync.timer.duration() != period {
+        sync.timer.set_duration(period);
+    }
+    sync.timer.tick(delta);
+    if !sync.timer.finished() {
+        return;
+    }
+
+    let types: Vec<_> = world
+        .query::<(Entity, &metrics::TypeDef, &metrics::Sid)>()
+        .iter(world)
+        .map(|(entity, def, &sid)| (metrics::Type(entity), sid, def.aggregation))
+        .collect();
+
+    let mut samples = Vec::new();
+    let mut viewer_query = world.query::<(Entity, &viewer::Sid, &Clusters)>();
+    for (viewer, &viewer_sid, clusters) in viewer_query.iter(world) {
+        for &(ty, metric_sid, aggregation) in &types {
+            let Some(&metrics::Subscription { noise_sd }) =
+                metrics::subscription(world, ty, viewer)
+            else {
+                continue;
+            };
+
+            for (cluster_sid, members) in clusters.iter() {
+                let (sum, count) = members
+                    .iter()
+                    .flat_map(|&member| {
+                        iter::once(member).chain(
+                            world.get::<hierarchy::Children>(member).into_iter().flatten().copied(),
+                        )
+                    })
+                    .filter_map(|entity| metrics::value(world, ty, entity))
+                    .fold((0., 0_u32), |(sum, count), value| (sum + value, count + 1));
+                if count == 0 {
+                    continue;
+                }
+
+                #[allow(clippy::cast_precision_loss)] // member counts are far below 2^24
+                let magnitude = match aggregation {
+                    metrics::Aggregation::Sum => sum,
+                    metrics::Aggregation::Mean => sum / count as f32,
+                };
+                samples.push((viewer_sid, cluster_sid, metric_sid, magnitude, noise_sd));
+            }
+        }
+    }
+
+    if samples.is_empty() {
+        return;
+    }
+    let mut rng = world.resource_mut::<SimRng>();
+    let mut rng = rng.event("view.cluster.noise");
+    let events: Vec<_> = samples
+        .into_iter()
+        .map(|(viewer, viewable, ty, magnitude, noise_sd)| {
+            let z: f32 = rng.sample(StandardNormal);
+            metrics::UpdateMetricEvent { viewer, viewable, ty, magnitude: magnitude + z * noise_sd }
+        })
+        .collect();
+    world.send_event_batch(events);
+}
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::{Event, Events, ManualEventReader};
+use bevy::ecs::world::World;
+use bevy::math::Vec3;
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use traffloat_base::save;
+
+use crate::viewable::{self, HideEvent, LodPolicy, ShowEvent};
+use crate::{appearance, viewer, DisplayText};
+
+const MEMBER_COUNT: u16 = 10;
+
+#[test]
+fn collapse_and_expand() {
+    let mut app = App::new();
+    app.add_plugins((save::Plugin, crate::Plugin));
+    app.insert_resource(Time::<()>::default());
+    app.insert_resource(LodPolicy { coarse_distance: 50., ..LodPolicy::default() });
+
+    let (viewer, members) = setup_world(app.world_mut());
+
+    let mut show_reader = event_reader::<ShowEvent>(app.world());
+    let mut hide_reader = event_reader::<HideEvent>(app.world());
+
+    app.update();
+
+    let shown: Vec<_> = get_events(app.world(), &mut show_reader)
+        .map(|event| (event.viewable, event.appearance.label.render_to_string()))
+        .collect();
+    let cluster_sid = shown
+        .iter()
+        .find(|(_, label)| *label == format!("{MEMBER_COUNT} × Dome"))
+        .map(|&(sid, _)| sid)
+        .expect("cluster should be shown");
+
+    let mut hidden: Vec<_> =
+        get_events(app.world(), &mut hide_reader).map(|event| event.viewable).collect();
+    hidden.sort();
+    assert_eq!(hidden, members);
+
+    app.world_mut().get_mut::<Transform>(viewer).unwrap().translation = Vec3::new(210., 0., 0.);
+    app.update();
+
+    let hidden: Vec<_> =
+        get_events(app.world(), &mut hide_reader).map(|event| event.viewable).collect();
+    assert_eq!(hidden, [cluster_sid]);
+
+    let mut shown: Vec<_> =
+        get_events(app.world(), &mut show_reader).map(|event| event.viewable).collect();
+    shown.sort();
+    assert_eq!(shown, members);
+
+    app.world_mut().get_mut::<Transform>(viewer).unwrap().translation = Vec3::ZERO;
+    app.update();
+
+    let shown: Vec<_> =
+        get_events(app.world(), &mut show_reader).map(|event| event.viewable).collect();
+    assert_eq!(shown, [cluster_sid], "the SID of the dissolved cluster should be reused");
+}
+
+fn setup_world(world: &mut World) -> (Entity, Vec<viewable::Sid>) {
+    world.resource_mut::<Time>().advance_by(Duration::from_millis(100));
+
+    let viewer_id = viewer::next_sid(world);
+    let viewer = world
+        .spawn(
+            viewer::Bundle::builder()
+                .id(viewer_id)
+                .range(viewer::Range { distance: 1000. })
+                .position(Transform::IDENTITY)
+                .build(),
+        )
+        .id();
+
+    let members = (0..MEMBER_COUNT)
+        .map(|index| {
+            let sid = viewable::next_sid(world);
+            world.spawn(
+                viewable::StationaryBundle::builder()
+                    .base(
+                        viewable::BaseBundle::builder()
+                            .sid(sid)
+                            .appearance(appearance::Appearance {
+                                label: DisplayText::Custom { value: "Dome".into() },
+                                ..appearance::Appearance::null()
+                            })
+                            .build(),
+                    )
+                    .transform(Transform::from_xyz(200. + f32::from(index), 0., 0.))
+                    .build(),
+            );
+            sid
+        })
+        .collect();
+
+    (viewer, members)
+}
+
+fn event_reader<E: Event>(world: &World) -> ManualEventReader<E> {
+    world.resource::<Events<E>>().get_reader()
+}
+
+fn get_events<'a, E: Event>(
+    world: &'a World,
+    reader: &'a mut ManualEventReader<E>,
+) -> impl Iterator<Item = &'a E> {
+    reader.read(world.resource::<Events<E>>())
+}
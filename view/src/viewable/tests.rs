@@ -0,0 +1,93 @@
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Events;
+use bevy::ecs::world::Command;
+use bevy::math::Vec3;
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use traffloat_base::{save, Ref};
+
+use super::{LodPolicy, MoveEvent, Relocate};
+use crate::{appearance, viewable, viewer};
+
+fn setup(distance: f32) -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((save::Plugin, crate::Plugin));
+    app.insert_resource(Time::<()>::default());
+
+    let viewer_id = viewer::next_sid(app.world_mut());
+    let viewer = app
+        .world_mut()
+        .spawn(
+            viewer::Bundle::builder()
+                .id(viewer_id)
+                .range(viewer::Range { distance: distance * 2. })
+                .position(Transform::default())
+                .build(),
+        )
+        .id();
+
+    let sid = viewable::next_sid(app.world_mut());
+    let viewable = app
+        .world_mut()
+        .spawn(
+            viewable::StationaryBundle::builder()
+                .base(
+                    viewable::BaseBundle::builder()
+                        .sid(sid)
+                        .appearance(appearance::Appearance::null())
+                        .build(),
+                )
+                .transform(Transform::from_translation(Vec3::new(distance, 0., 0.)))
+                .build(),
+        )
+        .id();
+
+    (app, viewer, viewable)
+}
+
+fn is_coarse(app: &App, viewer: Entity, viewable: Entity) -> bool {
+    let viewer = app.world().entity(viewer);
+    assert!(viewer.get::<viewer::ViewableList>().unwrap().set.contains(&viewable));
+    viewer.get::<viewer::CoarseViewableList>().unwrap().set.contains(&viewable)
+}
+
+#[test]
+fn default_policy_keeps_full_detail() {
+    let (mut app, viewer, viewable) = setup(1e6);
+    app.update();
+    assert!(!is_coarse(&app, viewer, viewable));
+}
+
+#[test]
+fn coarsen_beyond_configured_distance() {
+    let (mut app, viewer, viewable) = setup(100.);
+    app.insert_resource(LodPolicy { coarse_distance: 50., ..LodPolicy::default() });
+    app.update();
+    assert!(is_coarse(&app, viewer, viewable));
+}
+
+#[test]
+fn relocate_notifies_viewers() {
+    let (mut app, _, viewable) = setup(10.);
+    app.update();
+
+    let transform = Transform::from_translation(Vec3::new(5., 0., 0.));
+    Relocate { viewable: Ref::new_unchecked(viewable), transform }.apply(app.world_mut());
+
+    assert_eq!(app.world().get::<Transform>(viewable), Some(&transform));
+    let moves: Vec<_> = app.world_mut().resource_mut::<Events<MoveEvent>>().drain().collect();
+    assert_eq!(moves.len(), 1);
+}
+
+#[test]
+fn ignore_relocate_of_despawned_viewable() {
+    let (mut app, _, viewable) = setup(10.);
+    app.update();
+    app.world_mut().despawn(viewable);
+
+    Relocate { viewable: Ref::new_unchecked(viewable), transform: Transform::default() }
+        .apply(app.world_mut());
+
+    assert_eq!(app.world_mut().resource_mut::<Events<MoveEvent>>().drain().count(), 0);
+}
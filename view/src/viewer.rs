@@ -9,24 +9,39 @@ use bevy::utils::HashSet;
 use traffloat_base::debug;
 use typed_builder::TypedBuilder;
 
+use crate::viewable;
+
 sid_alias!("viewer");
 
+pub mod control_group;
+
 pub(crate) struct Plugin;
 
 impl app::Plugin for Plugin {
-    fn build(&self, app: &mut App) { SidIndex::init(app.world_mut()); }
+    fn build(&self, app: &mut App) {
+        SidIndex::init(app.world_mut());
+        app.add_plugins(control_group::Plugin);
+    }
 }
 
 /// Components for a viewer.
 #[derive(bundle::Bundle, TypedBuilder)]
 pub struct Bundle {
-    position:      Transform,
-    range:         Range,
-    id:            Sid,
+    position:       Transform,
+    range:          Range,
+    id:             Sid,
+    #[builder(default, setter(skip))]
+    last_viewable:  ViewableList,
+    #[builder(default, setter(skip))]
+    coarse:         CoarseViewableList,
+    #[builder(default, setter(skip))]
+    relevant:       RelevantViewables,
     #[builder(default, setter(skip))]
-    last_viewable: ViewableList,
+    clusters:       viewable::cluster::Clusters,
+    #[builder(default, setter(skip))]
+    control_groups: control_group::Groups,
     #[builder(default = debug::Bundle::new("Viewer"))]
-    _debug:        debug::Bundle,
+    _debug:         debug::Bundle,
 }
 
 /// List of viewables displayed to the viewer.
@@ -36,6 +51,25 @@ pub struct ViewableList {
     pub set: HashSet<Entity>,
 }
 
+/// Stationary viewables displayed to the viewer regardless of [`Range`].
+///
+/// This component is repopulated by
+/// [relevance providers](crate::viewable::add_relevance_provider) in every update.
+#[derive(Component, Default)]
+pub struct RelevantViewables {
+    /// Set of viewable entities.
+    pub set: HashSet<Entity>,
+}
+
+/// Subset of [`ViewableList`] displayed to the viewer at coarse level of detail.
+///
+/// See [`LodPolicy`](crate::viewable::LodPolicy) for details.
+#[derive(Component, Default)]
+pub struct CoarseViewableList {
+    /// Set of viewable entities.
+    pub set: HashSet<Entity>,
+}
+
 /// The maximum distance a viewer can observe.
 ///
 /// Due to optimization concerns, the distance is interpreted as max-norm instead of 2-norm.
@@ -0,0 +1,249 @@
+//! Control groups are numbered selections of viewables that a viewer can recall.
+//!
+//! Groups are assigned and recalled through commands,
+//! so that keybindings and other frontends share the same validation.
+//! A viewer can only assign viewables it can currently see to a group.
+//!
+//! Viewers are not saved, so saved groups are [restored](restore)
+//! to the viewer with the same [`Sid`](super::Sid) when it is spawned.
+
+use std::collections::BTreeMap;
+
+use bevy::app::{self, App};
+use bevy::ecs::component::{Component, ComponentId};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Event;
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::Resource;
+use bevy::ecs::world::{Command, DeferredWorld, World};
+use bevy::hierarchy;
+use bevy::utils::HashMap;
+use traffloat_base::partition::{self, AppExt};
+
+use super::ViewableList;
+use crate::viewable;
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) struct Plugin;
+
+impl app::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_partitioned_event::<RecallEvent>();
+        app.init_resource::<Restored>();
+        app.init_resource::<PendingRecalls>();
+        app.add_systems(
+            app::Update,
+            recall_system.in_set(partition::EventWriterSystemSet::<RecallEvent>::default()),
+        );
+        app.world_mut().register_component_hooks::<Groups>().on_add(restore_groups_hook);
+    }
+}
+
+/// The control groups of a viewer.
+#[derive(Component, Default)]
+pub struct Groups {
+    /// Viewable entities in each group.
+    pub groups: BTreeMap<u8, Vec<Entity>>,
+}
+
+/// The client should select the viewables in a recalled control group.
+#[derive(Debug, Event)]
+pub struct RecallEvent {
+    /// The viewer that recalled the group.
+    pub viewer:    super::Sid,
+    /// The recalled group number.
+    pub group:     u8,
+    /// The members of the group that are currently visible to the viewer.
+    pub viewables: Vec<viewable::Sid>,
+}
+
+/// Replaces the members of a control group.
+///
+/// The assignment is rejected with a warning
+/// if any of the viewables is unknown or not visible to the viewer.
+pub struct Assign {
+    /// The viewer entity owning the group.
+    pub viewer:    Entity,
+    /// The group number.
+    pub group:     u8,
+    /// The new members of the group.
+    pub viewables: Vec<viewable::Sid>,
+}
+
+impl Command for Assign {
+    fn apply(self, world: &mut World) {
+        let Some(entities) = resolve_visible(world, self.viewer, self.viewables) else { return };
+
+        let mut groups = world.get_mut::<Groups>(self.viewer).expect("checked in resolve_visible");
+        if entities.is_empty() {
+            groups.groups.remove(&self.group);
+        } else {
+            groups.groups.insert(self.group, entities);
+        }
+    }
+}
+
+/// Adds members to a control group, creating the group if it does not exist.
+///
+/// Viewables already in the group are not added again.
+/// The addition is rejected with a warning
+/// if any of the viewables is unknown or not visible to the viewer.
+pub struct Extend {
+    /// The viewer entity owning the group.
+    pub viewer:    Entity,
+    /// The group number.
+    pub group:     u8,
+    /// The members to add.
+    pub viewables: Vec<viewable::Sid>,
+}
+
+impl Command for Extend {
+    fn apply(self, world: &mut World) {
+        let Some(entities) = resolve_visible(world, self.viewer, self.viewables) else { return };
+        if entities.is_empty() {
+            return;
+        }
+
+        let mut groups = world.get_mut::<Groups>(self.viewer).expect("checked in resolve_visible");
+        let members = groups.groups.entry(self.group).or_default();
+        for entity in entities {
+            if !members.contains(&entity) {
+                members.push(entity);
+            }
+        }
+    }
+}
+
+/// Resolves viewable IDs that the viewer can currently see,
+/// or returns `None` after logging a warning
+/// if the viewer does not exist or any of the viewables cannot be assigned.
+fn resolve_visible(
+    world: &World,
+    viewer: Entity,
+    viewables: Vec<viewable::Sid>,
+) -> Option<Vec<Entity>> {
+    if world.get::<Groups>(viewer).is_none() {
+        bevy::log::warn!("cannot assign control group of non-viewer {viewer:?}");
+        return None;
+    }
+
+    let mut entities = Vec::with_capacity(viewables.len());
+    for sid in viewables {
+        let Some(entity) = world.resource::<viewable::SidIndex>().get(sid) else {
+            bevy::log::warn!("cannot assign unknown viewable {sid:?} to control group");
+            return None;
+        };
+        if !is_visible(world, viewer, entity) {
+            bevy::log::warn!(
+                "cannot assign viewable {sid:?} invisible to {viewer:?} to control group"
+            );
+            return None;
+        }
+        entities.push(entity);
+    }
+    Some(entities)
+}
+
+/// Recalls a control group, emitting a [`RecallEvent`] in the next update.
+///
+/// Members that are no longer visible are omitted from the event,
+/// but remain in the group.
+/// Despawned members are removed from the group.
+/// The recall is ignored with a warning if the viewer does not exist.
+pub struct Recall {
+    /// The viewer entity owning the group.
+    pub viewer: Entity,
+    /// The group number.
+    pub group:  u8,
+}
+
+impl Command for Recall {
+    fn apply(self, world: &mut World) {
+        if world.get::<Groups>(self.viewer).is_none() {
+            bevy::log::warn!("cannot recall control group of non-viewer {:?}", self.viewer);
+            return;
+        }
+
+        world.resource_mut::<PendingRecalls>().recalls.push(self);
+    }
+}
+
+/// Recalls waiting to be emitted by [`recall_system`].
+#[derive(Default, Resource)]
+struct PendingRecalls {
+    recalls: Vec<Recall>,
+}
+
+fn recall_system(world: &mut World) {
+    let recalls = std::mem::take(&mut world.resource_mut::<PendingRecalls>().recalls);
+    for Recall { viewer, group } in recalls {
+        // the viewer may have been despawned after the recall was queued
+        let Some(&viewer_sid) = world.get::<super::Sid>(viewer) else { continue };
+        let Some(members) = world.get::<Groups>(viewer).map(|groups| groups.groups.get(&group))
+        else {
+            continue;
+        };
+        let members: Vec<Entity> = members
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|&entity| world.get_entity(entity).is_some())
+            .collect();
+        let viewables = members
+            .iter()
+            .filter(|&&entity| is_visible(world, viewer, entity))
+            .filter_map(|&entity| world.get::<viewable::Sid>(entity).copied())
+            .collect();
+
+        if let Some(members_mut) =
+            world.get_mut::<Groups>(viewer).expect("checked above").groups.get_mut(&group)
+        {
+            *members_mut = members;
+        }
+
+        world.send_event(RecallEvent { viewer: viewer_sid, group, viewables });
+    }
+}
+
+/// Checks whether a stationary viewable or a child of one is visible to the viewer.
+fn is_visible(world: &World, viewer: Entity, viewable: Entity) -> bool {
+    let Some(list) = world.get::<ViewableList>(viewer) else { return false };
+    if list.set.contains(&viewable) {
+        return true;
+    }
+
+    world.get::<viewable::StationaryChild>(viewable).is_some()
+        && world
+            .get::<hierarchy::Parent>(viewable)
+            .is_some_and(|parent| list.set.contains(&parent.get()))
+}
+
+/// Saved control groups waiting for their viewer to be spawned.
+#[derive(Default, Resource)]
+pub struct Restored {
+    /// Members of each group, by the viewer owning the group.
+    pub groups: HashMap<super::Sid, BTreeMap<u8, Vec<Entity>>>,
+}
+
+/// Restores a saved control group of the viewer identified by `viewer`.
+///
+/// The group is assigned immediately if the viewer exists,
+/// otherwise it is kept in [`Restored`] until the viewer is spawned.
+/// Members are not checked for visibility,
+/// since the viewer may not have observed the world yet.
+pub fn restore(world: &mut World, viewer: super::Sid, group: u8, members: Vec<Entity>) {
+    let viewer_entity = world.resource::<super::SidIndex>().get(viewer);
+    if let Some(mut groups) = viewer_entity.and_then(|entity| world.get_mut::<Groups>(entity)) {
+        groups.groups.insert(group, members);
+    } else {
+        world.resource_mut::<Restored>().groups.entry(viewer).or_default().insert(group, members);
+    }
+}
+
+fn restore_groups_hook(mut world: DeferredWorld, viewer: Entity, _: ComponentId) {
+    let Some(&sid) = world.get::<super::Sid>(viewer) else { return };
+    let Some(restored) = world.resource_mut::<Restored>().groups.remove(&sid) else { return };
+    world.get_mut::<Groups>(viewer).expect("subject of component hook").groups.extend(restored);
+}
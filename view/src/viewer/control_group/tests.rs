@@ -0,0 +1,165 @@
+use bevy::app::App;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::event::Events;
+use bevy::ecs::world::Command;
+use bevy::math::Vec3;
+use bevy::time::Time;
+use bevy::transform::components::Transform;
+use traffloat_base::save;
+
+use super::{restore, Assign, Extend, Groups, Recall, RecallEvent};
+use crate::{appearance, viewable, viewer};
+
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((save::Plugin, crate::Plugin));
+    app.insert_resource(Time::<()>::default());
+    app
+}
+
+fn spawn_viewer(app: &mut App) -> Entity {
+    let sid = viewer::next_sid(app.world_mut());
+    app.world_mut()
+        .spawn(
+            viewer::Bundle::builder()
+                .id(sid)
+                .range(viewer::Range { distance: 100. })
+                .position(Transform::default())
+                .build(),
+        )
+        .id()
+}
+
+fn spawn_viewable(app: &mut App, x: f32) -> (Entity, viewable::Sid) {
+    let sid = viewable::next_sid(app.world_mut());
+    let entity = app
+        .world_mut()
+        .spawn(
+            viewable::StationaryBundle::builder()
+                .base(
+                    viewable::BaseBundle::builder()
+                        .sid(sid)
+                        .appearance(appearance::Appearance::null())
+                        .build(),
+                )
+                .transform(Transform::from_translation(Vec3::new(x, 0., 0.)))
+                .build(),
+        )
+        .id();
+    (entity, sid)
+}
+
+fn group(app: &App, viewer: Entity, group: u8) -> Option<Vec<Entity>> {
+    app.world().get::<Groups>(viewer).unwrap().groups.get(&group).cloned()
+}
+
+fn recall(app: &mut App, viewer: Entity, group: u8) -> Vec<viewable::Sid> {
+    Recall { viewer, group }.apply(app.world_mut());
+    app.update();
+    let mut events = app.world_mut().resource_mut::<Events<RecallEvent>>();
+    let event = events.drain().last().expect("Recall emits an event");
+    assert_eq!(event.group, group);
+    event.viewables
+}
+
+#[test]
+fn assign_and_recall() {
+    let mut app = new_app();
+    let viewer = spawn_viewer(&mut app);
+    let [(first, first_sid), (second, second_sid)] =
+        [10., 20.].map(|x| spawn_viewable(&mut app, x));
+    app.update();
+
+    Assign { viewer, group: 1, viewables: vec![first_sid, second_sid] }.apply(app.world_mut());
+    assert_eq!(group(&app, viewer, 1), Some(vec![first, second]));
+    assert_eq!(recall(&mut app, viewer, 1), [first_sid, second_sid]);
+
+    Assign { viewer, group: 1, viewables: Vec::new() }.apply(app.world_mut());
+    assert_eq!(group(&app, viewer, 1), None);
+    assert!(recall(&mut app, viewer, 1).is_empty());
+}
+
+#[test]
+fn extend_without_duplicates() {
+    let mut app = new_app();
+    let viewer = spawn_viewer(&mut app);
+    let [(first, first_sid), (second, second_sid)] =
+        [10., 20.].map(|x| spawn_viewable(&mut app, x));
+    app.update();
+
+    Extend { viewer, group: 2, viewables: vec![first_sid] }.apply(app.world_mut());
+    Extend { viewer, group: 2, viewables: vec![second_sid, first_sid] }.apply(app.world_mut());
+    assert_eq!(group(&app, viewer, 2), Some(vec![first, second]));
+}
+
+#[test]
+fn reject_invisible_viewable() {
+    let mut app = new_app();
+    let viewer = spawn_viewer(&mut app);
+    let (near, near_sid) = spawn_viewable(&mut app, 10.);
+    let (_, far_sid) = spawn_viewable(&mut app, 1000.);
+    app.update();
+
+    Assign { viewer, group: 3, viewables: vec![near_sid] }.apply(app.world_mut());
+    Assign { viewer, group: 3, viewables: vec![near_sid, far_sid] }.apply(app.world_mut());
+    Extend { viewer, group: 3, viewables: vec![far_sid] }.apply(app.world_mut());
+    assert_eq!(group(&app, viewer, 3), Some(vec![near]));
+}
+
+#[test]
+fn recall_prunes_despawned_members() {
+    let mut app = new_app();
+    let viewer = spawn_viewer(&mut app);
+    let [(first, first_sid), (second, second_sid)] =
+        [10., 20.].map(|x| spawn_viewable(&mut app, x));
+    app.update();
+
+    Assign { viewer, group: 4, viewables: vec![first_sid, second_sid] }.apply(app.world_mut());
+    app.world_mut().despawn(first);
+    assert_eq!(recall(&mut app, viewer, 4), [second_sid]);
+    assert_eq!(group(&app, viewer, 4), Some(vec![second]));
+}
+
+#[test]
+fn restore_to_viewer() {
+    let mut app = new_app();
+    let (member, _) = spawn_viewable(&mut app, 10.);
+
+    // the first viewer spawned in a fresh world always gets the same sid
+    let viewer_sid = viewer::Sid::from(0);
+    restore(app.world_mut(), viewer_sid, 5, vec![member]);
+    let viewer = spawn_viewer(&mut app);
+    assert_eq!(app.world().get::<viewer::Sid>(viewer), Some(&viewer_sid));
+    assert_eq!(group(&app, viewer, 5), Some(vec![member]));
+
+    restore(app.world_mut(), viewer_sid, 6, vec![member]);
+    assert_eq!(group(&app, viewer, 6), Some(vec![member]));
+}
+
+#[test]
+fn ignore_non_viewer() {
+    let mut app = new_app();
+    let (viewable, viewable_sid) = spawn_viewable(&mut app, 10.);
+    app.update();
+
+    Assign { viewer: viewable, group: 7, viewables: vec![viewable_sid] }.apply(app.world_mut());
+    Extend { viewer: viewable, group: 7, viewables: vec![viewable_sid] }.apply(app.world_mut());
+    Recall { viewer: viewable, group: 7 }.apply(app.world_mut());
+    app.update();
+
+    assert!(app.world().get::<Groups>(viewable).is_none());
+    assert_eq!(app.world_mut().resource_mut::<Events<RecallEvent>>().drain().count(), 0);
+}
+
+#[test]
+fn ignore_recall_of_despawned_viewer() {
+    let mut app = new_app();
+    let viewer = spawn_viewer(&mut app);
+    app.update();
+
+    Recall { viewer, group: 8 }.apply(app.world_mut());
+    app.world_mut().despawn(viewer);
+    app.update();
+
+    assert_eq!(app.world_mut().resource_mut::<Events<RecallEvent>>().drain().count(), 0);
+}